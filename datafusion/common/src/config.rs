@@ -309,6 +309,25 @@ config_namespace! {
         /// batches and merged.
         pub sort_in_place_threshold_bytes: usize, default = 1024 * 1024
 
+        /// Number of batches buffered ahead of the merge for each input
+        /// partition of a `SortPreservingMergeExec`. Larger values can improve
+        /// throughput for inputs with high latency, such as remote shards, at
+        /// the cost of memory for the buffered batches. Must be at least 1
+        pub sort_preserving_merge_buffer_size: usize, default = 1
+
+        /// Number of batches a `SortPreservingMergeExec` prefetches from each
+        /// input partition before the merge needs them, for example to hide
+        /// the latency of object store scans. Each input buffers the larger
+        /// of this and `sort_preserving_merge_buffer_size` batches. Must be at
+        /// least 1
+        pub sort_merge_prefetch_batches: usize, default = 1
+
+        /// Largest `k`, the `LIMIT` of a sort, for which a TopK may allocate
+        /// the space for all k rows up front, if configured to do so. For a
+        /// larger k this space grows as rows enter the top k instead, so a
+        /// large `LIMIT` does not allocate memory before any input arrives
+        pub topk_eager_allocation_limit: usize, default = 100_000
+
         /// Number of files to read in parallel when inferring schema and statistics
         pub meta_fetch_concurrency: usize, default = 32
 
@@ -369,6 +388,54 @@ config_namespace! {
         /// in joins can reduce memory usage when joining large
         /// tables with a highly-selective join filter, but is also slightly slower.
         pub enforce_batch_size_in_joins: bool, default = false
+
+        /// Should DataFusion normalize floating point group by keys, so that
+        /// all `NaN` values form a single group and `-0.0` groups together
+        /// with `+0.0`. By default, float group keys are grouped by their
+        /// exact bit pattern.
+        pub normalize_float_group_keys: bool, default = false
+
+        /// Compression codec used for the files written when sorts, aggregations
+        /// and sort merge joins spill to disk. Valid values are `zstd`,
+        /// `lz4_frame` and `uncompressed`. Compressing spill files reduces disk
+        /// usage and IO at the cost of CPU time
+        pub spill_compression: SpillCompression, default = SpillCompression::Uncompressed
+
+        /// Should DataFusion cast `Utf8View` / `BinaryView` group by keys to
+        /// `LargeUtf8` / `LargeBinary` internally, so they are stored with the
+        /// offset based group values builders instead of the view builders.
+        /// The output keeps the view types. This is an escape hatch in case of
+        /// problems with the view builders and is slower than the default.
+        pub cast_view_group_keys_to_offsets: bool, default = false
+
+        /// Should DataFusion cast `LargeUtf8` / `LargeBinary` group by keys to
+        /// `Utf8View` / `BinaryView` internally, so they are stored with the
+        /// view based group values builders instead of the offset builders.
+        /// The output keeps the large types. This can be faster for many
+        /// long group keys, at the cost of converting them on input and output.
+        pub cast_large_group_keys_to_views: bool, default = false
+
+        /// Maximum number of distinct groups a single hash aggregation may
+        /// hold at once. If a new group would exceed this limit, the query
+        /// fails with an error instead of growing the hash table further, to
+        /// guard against accidentally grouping by a high cardinality column.
+        /// By default, the number of groups is only limited by memory
+        pub max_distinct_groups: Option<usize>, default = None
+
+        /// Number of completed data blocks after which each new block storing
+        /// `Utf8View` / `BinaryView` group by keys is twice as large as the
+        /// previous one, up to this number of times 2MB. This bounds the number
+        /// of blocks for inputs with many large keys, at the cost of larger
+        /// allocations. By default, all blocks are 2MB
+        pub max_group_key_view_blocks: Option<usize>, default = None
+
+        /// Maximum number of rows of an input batch a hash aggregation
+        /// aggregates at once. Larger batches are aggregated in chunks of at
+        /// most this many rows, and the memory reservation grows after each
+        /// chunk, so the aggregation fails as soon as it exceeds the memory
+        /// limit rather than after the whole batch. Must be at least 1. By
+        /// default, batches are aggregated whole
+        pub max_aggregate_chunk_rows: Option<usize>, default = None
     }
 }
 
@@ -1117,6 +1184,56 @@ impl ConfigField for CompressionTypeVariant {
     }
 }
 
+/// Compression codec used for the Arrow IPC files written when an operator
+/// spills to disk
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SpillCompression {
+    /// Zstandard compression
+    Zstd,
+    /// LZ4 frame compression
+    Lz4Frame,
+    /// Spill files are written uncompressed
+    #[default]
+    Uncompressed,
+}
+
+impl FromStr for SpillCompression {
+    type Err = DataFusionError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "zstd" => Ok(Self::Zstd),
+            "lz4_frame" => Ok(Self::Lz4Frame),
+            "" | "uncompressed" => Ok(Self::Uncompressed),
+            other => _config_err!(
+                "Invalid spill compression: {other}. Expected one of: zstd, lz4_frame, uncompressed"
+            ),
+        }
+    }
+}
+
+impl Display for SpillCompression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = match self {
+            Self::Zstd => "zstd",
+            Self::Lz4Frame => "lz4_frame",
+            Self::Uncompressed => "uncompressed",
+        };
+        write!(f, "{str}")
+    }
+}
+
+impl ConfigField for SpillCompression {
+    fn visit<V: Visit>(&self, v: &mut V, key: &str, description: &'static str) {
+        v.some(key, self, description)
+    }
+
+    fn set(&mut self, _: &str, value: &str) -> Result<()> {
+        *self = SpillCompression::from_str(value)?;
+        Ok(())
+    }
+}
+
 /// An implementation trait used to recursively walk configuration
 pub trait Visit {
     fn some<V: Display>(&mut self, key: &str, value: V, description: &'static str);
@@ -1775,7 +1775,13 @@ impl FunctionRegistry for SessionState {
     }
 
     fn udf(&self, name: &str) -> datafusion_common::Result<Arc<ScalarUDF>> {
-        let result = self.scalar_functions.get(name);
+        // functions are registered under their name and aliases, which are
+        // lowercase for the built-in functions, so resolve `MAKE_ARRAY` like
+        // `make_array` unless a function is registered with that exact name
+        let result = self
+            .scalar_functions
+            .get(name)
+            .or_else(|| self.scalar_functions.get(&name.to_lowercase()));
 
         result.cloned().ok_or_else(|| {
             plan_datafusion_err!("There is no UDF named \"{name}\" in the registry")
@@ -1783,7 +1789,11 @@ impl FunctionRegistry for SessionState {
     }
 
     fn udaf(&self, name: &str) -> datafusion_common::Result<Arc<AggregateUDF>> {
-        let result = self.aggregate_functions.get(name);
+        // see `udf`
+        let result = self
+            .aggregate_functions
+            .get(name)
+            .or_else(|| self.aggregate_functions.get(&name.to_lowercase()));
 
         result.cloned().ok_or_else(|| {
             plan_datafusion_err!("There is no UDAF named \"{name}\" in the registry")
@@ -1791,7 +1801,11 @@ impl FunctionRegistry for SessionState {
     }
 
     fn udwf(&self, name: &str) -> datafusion_common::Result<Arc<WindowUDF>> {
-        let result = self.window_functions.get(name);
+        // see `udf`
+        let result = self
+            .window_functions
+            .get(name)
+            .or_else(|| self.window_functions.get(&name.to_lowercase()));
 
         result.cloned().ok_or_else(|| {
             plan_datafusion_err!("There is no UDWF named \"{name}\" in the registry")
@@ -1989,10 +2003,12 @@ mod tests {
     use crate::execution::context::SessionState;
     use arrow_array::{ArrayRef, Int32Array, RecordBatch, StringArray};
     use arrow_schema::{DataType, Field, Schema};
+    use datafusion_common::not_impl_err;
     use datafusion_common::DFSchema;
     use datafusion_common::Result;
     use datafusion_execution::config::SessionConfig;
-    use datafusion_expr::Expr;
+    use datafusion_expr::registry::FunctionRegistry;
+    use datafusion_expr::{create_udf, Expr, Volatility};
     use datafusion_optimizer::optimizer::OptimizerRule;
     use datafusion_optimizer::Optimizer;
     use datafusion_sql::planner::{PlannerContext, SqlToRel};
@@ -2028,6 +2044,102 @@ mod tests {
         assert!(sql_to_expr(&state).is_err())
     }
 
+    #[test]
+    fn test_udf_case_insensitive() -> Result<()> {
+        let state = SessionStateBuilder::new().with_default_features().build();
+
+        let make_array = state.udf("make_array")?;
+        assert_eq!(make_array.name(), "make_array");
+        assert_eq!(make_array.aliases(), ["make_list"]);
+        for name in ["make_list", "MAKE_ARRAY", "Make_List"] {
+            assert!(Arc::ptr_eq(&state.udf(name)?, &make_array), "{name}");
+        }
+
+        // every function resolves by its name and aliases, in any case
+        for udf in state.scalar_functions().values() {
+            for name in std::iter::once(udf.name())
+                .chain(udf.aliases().iter().map(|a| a.as_str()))
+            {
+                assert_eq!(state.udf(name)?.name(), udf.name(), "{name}");
+                assert_eq!(
+                    state.udf(&name.to_uppercase())?.name(),
+                    udf.name(),
+                    "{name}"
+                );
+            }
+        }
+
+        // a function registered with a mixed case name is resolved by its
+        // exact name first
+        let mut state = state;
+        let udf = |name: &str| {
+            Arc::new(create_udf(
+                name,
+                vec![],
+                DataType::Int32,
+                Volatility::Immutable,
+                Arc::new(|_| not_impl_err!("not called")),
+            ))
+        };
+        state.register_udf(udf("MyFunc"))?;
+        state.register_udf(udf("myfunc"))?;
+        assert_eq!(state.udf("MyFunc")?.name(), "MyFunc");
+        assert_eq!(state.udf("MYFUNC")?.name(), "myfunc");
+        Ok(())
+    }
+
+    #[test]
+    fn test_udaf_case_insensitive() -> Result<()> {
+        let state = SessionStateBuilder::new().with_default_features().build();
+
+        let count = state.udaf("count")?;
+        for name in ["COUNT", "Count"] {
+            assert!(Arc::ptr_eq(&state.udaf(name)?, &count), "{name}");
+        }
+
+        // every function resolves by its name and aliases, in any case
+        for udaf in state.aggregate_functions().values() {
+            for name in std::iter::once(udaf.name())
+                .chain(udaf.aliases().iter().map(|a| a.as_str()))
+            {
+                assert_eq!(state.udaf(name)?.name(), udaf.name(), "{name}");
+                assert_eq!(
+                    state.udaf(&name.to_uppercase())?.name(),
+                    udaf.name(),
+                    "{name}"
+                );
+            }
+        }
+        assert!(state.udaf("NO_SUCH_AGGREGATE").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_udwf_case_insensitive() -> Result<()> {
+        let state = SessionStateBuilder::new().with_default_features().build();
+
+        let row_number = state.udwf("row_number")?;
+        for name in ["ROW_NUMBER", "Row_Number"] {
+            assert!(Arc::ptr_eq(&state.udwf(name)?, &row_number), "{name}");
+        }
+
+        // every function resolves by its name and aliases, in any case
+        for udwf in state.window_functions().values() {
+            for name in std::iter::once(udwf.name())
+                .chain(udwf.aliases().iter().map(|a| a.as_str()))
+            {
+                assert_eq!(state.udwf(name)?.name(), udwf.name(), "{name}");
+                assert_eq!(
+                    state.udwf(&name.to_uppercase())?.name(),
+                    udwf.name(),
+                    "{name}"
+                );
+            }
+        }
+        assert!(state.udwf("NO_SUCH_WINDOW").is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_from_existing() -> Result<()> {
         fn employee_batch() -> RecordBatch {
@@ -272,9 +272,15 @@ fn parallelize_sorts(
     } else if (is_sort(&requirements.plan)
         || is_sort_preserving_merge(&requirements.plan))
         && requirements.plan.output_partitioning().partition_count() <= 1
+        // the source partitions of a merge change when it is parallelized
+        && !requirements
+            .plan
+            .as_any()
+            .downcast_ref::<SortPreservingMergeExec>()
+            .is_some_and(|spm| spm.source_partition_column().is_some())
     {
         // Take the initial sort expressions and requirements
-        let (sort_exprs, fetch) = get_sort_exprs(&requirements.plan)?;
+        let (sort_exprs, fetch, offset) = get_sort_exprs(&requirements.plan)?;
         let sort_reqs = LexRequirement::from(sort_exprs.clone());
         let sort_exprs = sort_exprs.clone();
 
@@ -288,13 +294,18 @@ fn parallelize_sorts(
         // deals with the children and their children and so on.
         requirements = requirements.children.swap_remove(0);
 
-        requirements = add_sort_above_with_check(requirements, sort_reqs, fetch);
+        // The sort must also produce the rows skipped by the merge
+        requirements = add_sort_above_with_check(
+            requirements,
+            sort_reqs,
+            fetch.map(|fetch| fetch + offset),
+        );
 
         let spm =
             SortPreservingMergeExec::new(sort_exprs, Arc::clone(&requirements.plan));
         Ok(Transformed::yes(
             PlanWithCorrespondingCoalescePartitions::new(
-                Arc::new(spm.with_fetch(fetch)),
+                Arc::new(spm.with_fetch(fetch).with_offset(offset)),
                 false,
                 vec![requirements],
             ),
@@ -640,15 +651,16 @@ fn remove_corresponding_sort_from_sub_plan(
     Ok(node)
 }
 
-/// Converts an [ExecutionPlan] trait object to a [LexOrdering] reference when possible.
+/// Converts an [ExecutionPlan] trait object to a [LexOrdering] reference when possible,
+/// together with its fetch and offset.
 fn get_sort_exprs(
     sort_any: &Arc<dyn ExecutionPlan>,
-) -> Result<(&LexOrdering, Option<usize>)> {
+) -> Result<(&LexOrdering, Option<usize>, usize)> {
     if let Some(sort_exec) = sort_any.as_any().downcast_ref::<SortExec>() {
-        Ok((sort_exec.expr(), sort_exec.fetch()))
+        Ok((sort_exec.expr(), sort_exec.fetch(), 0))
     } else if let Some(spm) = sort_any.as_any().downcast_ref::<SortPreservingMergeExec>()
     {
-        Ok((spm.expr(), spm.fetch()))
+        Ok((spm.expr(), spm.fetch(), spm.offset()))
     } else {
         plan_err!("Given ExecutionPlan is not a SortExec or a SortPreservingMergeExec")
     }
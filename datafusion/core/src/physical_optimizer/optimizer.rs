@@ -24,6 +24,8 @@ use super::projection_pushdown::ProjectionPushdown;
 use super::update_aggr_exprs::OptimizeAggregateOrder;
 use crate::physical_optimizer::aggregate_statistics::AggregateStatistics;
 use crate::physical_optimizer::coalesce_batches::CoalesceBatches;
+use crate::physical_optimizer::coalesce_ordered_merge::CoalesceOrderedMerge;
+use crate::physical_optimizer::collapse_merges::CollapseMerges;
 use crate::physical_optimizer::combine_partial_final_agg::CombinePartialFinalAggregate;
 use crate::physical_optimizer::enforce_distribution::EnforceDistribution;
 use crate::physical_optimizer::enforce_sorting::EnforceSorting;
@@ -32,6 +34,7 @@ use crate::physical_optimizer::limit_pushdown::LimitPushdown;
 use crate::physical_optimizer::limited_distinct_aggregation::LimitedDistinctAggregation;
 use crate::physical_optimizer::output_requirements::OutputRequirements;
 use crate::physical_optimizer::sanity_checker::SanityCheckPlan;
+use crate::physical_optimizer::sort_fetch_pushdown::SortFetchPushdown;
 use crate::physical_optimizer::topk_aggregation::TopKAggregation;
 
 /// A rule-based physical optimizer.
@@ -103,6 +106,18 @@ impl PhysicalOptimizer {
             // replacing operators with fetching variants, or adding limits
             // past operators that support limit pushdown.
             Arc::new(LimitPushdown::new()),
+            // The CollapseMerges rule removes a SortPreservingMergeExec
+            // whose input is another SortPreservingMergeExec that already
+            // produces the required ordering, combining their limits.
+            Arc::new(CollapseMerges::new()),
+            // The SortFetchPushdown rule turns the sorts of the partitions
+            // merged by a SortPreservingMergeExec with a fetch into TopKs of
+            // the same size, as no partition can contribute more rows.
+            Arc::new(SortFetchPushdown::new()),
+            // The CoalesceOrderedMerge rule replaces a SortPreservingMergeExec
+            // with a CoalescePartitionsExec if its input partitions are
+            // already ordered across partitions.
+            Arc::new(CoalesceOrderedMerge::new()),
             // The SanityCheckPlan rule checks whether the order and
             // distribution requirements of each node in the plan
             // is satisfied. It will also reject non-runnable query
@@ -46,6 +46,7 @@ use datafusion_common::tree_node::{
     Transformed, TransformedResult, TreeNode, TreeNodeRecursion,
 };
 use datafusion_common::{internal_err, JoinSide};
+use datafusion_physical_expr::equivalence::ProjectionMapping;
 use datafusion_physical_expr::expressions::{Column, Literal};
 use datafusion_physical_expr::{
     utils::collect_columns, Partitioning, PhysicalExpr, PhysicalExprRef,
@@ -496,6 +497,10 @@ fn try_swapping_with_sort_preserving_merge(
     if projection.expr().len() >= projection.input().schema().fields().len() {
         return Ok(None);
     }
+    // The projection may refer to the column appended by the merge
+    if spm.source_partition_column().is_some() {
+        return Ok(None);
+    }
 
     let mut updated_exprs = LexOrdering::default();
     for sort in spm.expr() {
@@ -514,7 +519,8 @@ fn try_swapping_with_sort_preserving_merge(
             updated_exprs,
             make_with_child(projection, spm.input())?,
         )
-        .with_fetch(spm.fetch()),
+        .with_fetch(spm.fetch())
+        .with_offset(spm.offset()),
     )))
 }
 
@@ -927,18 +933,13 @@ fn try_swapping_with_sym_hash_join(
     )?)))
 }
 
-/// Compare the inputs and outputs of the projection. All expressions must be
-/// columns without alias, and projection does not change the order of fields.
-/// For example, if the input schema is `a, b`, `SELECT a, b` is removable,
-/// but `SELECT b, a` and `SELECT a+1, b` and `SELECT a AS c, b` are not.
+/// Compare the inputs and outputs of the projection, which is removable if
+/// its [`ProjectionMapping`] is the identity (see
+/// [`ProjectionMapping::is_identity`]).
 fn is_projection_removable(projection: &ProjectionExec) -> bool {
-    let exprs = projection.expr();
-    exprs.iter().enumerate().all(|(idx, (expr, alias))| {
-        let Some(col) = expr.as_any().downcast_ref::<Column>() else {
-            return false;
-        };
-        col.name() == alias && col.index() == idx
-    }) && exprs.len() == projection.input().schema().fields().len()
+    let input_schema = projection.input().schema();
+    ProjectionMapping::try_new(projection.expr(), &input_schema)
+        .is_ok_and(|mapping| mapping.is_identity(&input_schema))
 }
 
 /// Given the expression set of a projection, checks if the projection causes
@@ -0,0 +1,149 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Tests for the CoalesceOrderedMerge optimizer rule
+
+use std::sync::Arc;
+
+use arrow::array::Int32Array;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use arrow_schema::SortOptions;
+use datafusion::prelude::SessionContext;
+use datafusion_common::config::ConfigOptions;
+use datafusion_common::{assert_batches_sorted_eq, Result};
+use datafusion_expr::Operator;
+use datafusion_physical_expr::expressions::{binary, col, lit};
+use datafusion_physical_expr::PhysicalSortExpr;
+use datafusion_physical_expr_common::sort_expr::LexOrdering;
+use datafusion_physical_optimizer::coalesce_ordered_merge::CoalesceOrderedMerge;
+use datafusion_physical_optimizer::PhysicalOptimizerRule;
+use datafusion_physical_plan::filter::FilterExec;
+use datafusion_physical_plan::memory::MemoryExec;
+use datafusion_physical_plan::sorts::sort::SortExec;
+use datafusion_physical_plan::sorts::sort_preserving_merge::SortPreservingMergeExec;
+use datafusion_physical_plan::{collect, get_plan_string, ExecutionPlan};
+
+/// Returns `SortExec <- FilterExec(a = 1)` over three partitions, sorted by
+/// the columns in `sort_columns` within each partition
+fn sorted_partitions_with_constant_a(
+    sort_columns: &[&str],
+) -> Result<(Arc<dyn ExecutionPlan>, LexOrdering)> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int32, false),
+        Field::new("b", DataType::Int32, false),
+    ]));
+    let partition = |a: Vec<i32>, b: Vec<i32>| {
+        RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int32Array::from(a)), Arc::new(Int32Array::from(b))],
+        )
+        .map(|batch| vec![batch])
+    };
+    let partitions = vec![
+        partition(vec![1, 2, 1], vec![7, 1, 3])?,
+        partition(vec![1, 1], vec![5, 2])?,
+        partition(vec![3, 1, 1], vec![4, 6, 1])?,
+    ];
+    let input = MemoryExec::try_new(&partitions, Arc::clone(&schema), None)?;
+
+    // `a` has the same value in every partition after the filter
+    let predicate = binary(col("a", &schema)?, Operator::Eq, lit(1), &schema)?;
+    let filter = FilterExec::try_new(predicate, Arc::new(input))?;
+
+    let ordering = sort_columns
+        .iter()
+        .map(|name| {
+            Ok(PhysicalSortExpr {
+                expr: col(name, &schema)?,
+                options: SortOptions::default(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let ordering = LexOrdering::new(ordering);
+    let sort = SortExec::new(ordering.clone(), Arc::new(filter))
+        .with_preserve_partitioning(true);
+    Ok((Arc::new(sort), ordering))
+}
+
+#[tokio::test]
+async fn replaces_merge_of_globally_ordered_partitions() -> Result<()> {
+    let (input, ordering) = sorted_partitions_with_constant_a(&["a"])?;
+    let plan: Arc<dyn ExecutionPlan> =
+        Arc::new(SortPreservingMergeExec::new(ordering, input));
+
+    let optimized =
+        CoalesceOrderedMerge::new().optimize(Arc::clone(&plan), &ConfigOptions::new())?;
+    let expected_optimized = [
+        "CoalescePartitionsExec",
+        "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
+        "    FilterExec: a@0 = 1",
+        "      MemoryExec: partitions=3, partition_sizes=[1, 1, 1]",
+    ];
+    assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+    let expected = [
+        "+---+---+",
+        "| a | b |",
+        "+---+---+",
+        "| 1 | 1 |",
+        "| 1 | 2 |",
+        "| 1 | 3 |",
+        "| 1 | 5 |",
+        "| 1 | 6 |",
+        "| 1 | 7 |",
+        "+---+---+",
+    ];
+    let task_ctx = SessionContext::new().task_ctx();
+    assert_batches_sorted_eq!(expected, &collect(plan, Arc::clone(&task_ctx)).await?);
+    assert_batches_sorted_eq!(expected, &collect(optimized, task_ctx).await?);
+    Ok(())
+}
+
+#[test]
+fn replaces_merge_with_fetch_by_limit() -> Result<()> {
+    let (input, ordering) = sorted_partitions_with_constant_a(&["a"])?;
+    let plan =
+        Arc::new(SortPreservingMergeExec::new(ordering, input).with_fetch(Some(2)));
+
+    let optimized = CoalesceOrderedMerge::new().optimize(plan, &ConfigOptions::new())?;
+    let expected = [
+        "GlobalLimitExec: skip=0, fetch=2",
+        "  CoalescePartitionsExec",
+        "    SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
+        "      FilterExec: a@0 = 1",
+        "        MemoryExec: partitions=3, partition_sizes=[1, 1, 1]",
+    ];
+    assert_eq!(get_plan_string(&optimized), expected);
+    Ok(())
+}
+
+#[test]
+fn keeps_merge_of_partitions_ordered_within_partition() -> Result<()> {
+    // `b` is only sorted within each partition
+    let (input, ordering) = sorted_partitions_with_constant_a(&["a", "b"])?;
+    let plan = Arc::new(SortPreservingMergeExec::new(ordering, input));
+
+    let optimized = CoalesceOrderedMerge::new().optimize(plan, &ConfigOptions::new())?;
+    let expected = [
+        "SortPreservingMergeExec: [a@0 ASC, b@1 ASC]",
+        "  SortExec: expr=[a@0 ASC, b@1 ASC], preserve_partitioning=[true]",
+        "    FilterExec: a@0 = 1",
+        "      MemoryExec: partitions=3, partition_sizes=[1, 1, 1]",
+    ];
+    assert_eq!(get_plan_string(&optimized), expected);
+    Ok(())
+}
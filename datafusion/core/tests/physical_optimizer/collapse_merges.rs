@@ -0,0 +1,165 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Tests for the CollapseMerges optimizer rule
+
+use std::sync::Arc;
+
+use arrow::array::Int32Array;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use arrow_schema::SortOptions;
+use datafusion::prelude::SessionContext;
+use datafusion_common::config::ConfigOptions;
+use datafusion_common::{assert_batches_eq, Result};
+use datafusion_physical_expr::expressions::col;
+use datafusion_physical_expr::PhysicalSortExpr;
+use datafusion_physical_expr_common::sort_expr::LexOrdering;
+use datafusion_physical_optimizer::collapse_merges::CollapseMerges;
+use datafusion_physical_optimizer::PhysicalOptimizerRule;
+use datafusion_physical_plan::memory::MemoryExec;
+use datafusion_physical_plan::sorts::sort::SortExec;
+use datafusion_physical_plan::sorts::sort_preserving_merge::SortPreservingMergeExec;
+use datafusion_physical_plan::{collect, get_plan_string, ExecutionPlan};
+
+/// Returns `SortExec` over three partitions, sorted by the columns in
+/// `sort_columns` within each partition
+fn sorted_partitions(
+    sort_columns: &[&str],
+) -> Result<(Arc<dyn ExecutionPlan>, LexOrdering)> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int32, false),
+        Field::new("b", DataType::Int32, false),
+    ]));
+    let partition = |a: Vec<i32>, b: Vec<i32>| {
+        RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int32Array::from(a)), Arc::new(Int32Array::from(b))],
+        )
+        .map(|batch| vec![batch])
+    };
+    let partitions = vec![
+        partition(vec![5, 2, 8], vec![1, 1, 2])?,
+        partition(vec![4, 1], vec![2, 1])?,
+        partition(vec![7, 3, 6], vec![1, 2, 2])?,
+    ];
+    let input = MemoryExec::try_new(&partitions, Arc::clone(&schema), None)?;
+
+    let ordering = sort_columns
+        .iter()
+        .map(|name| {
+            Ok(PhysicalSortExpr {
+                expr: col(name, &schema)?,
+                options: SortOptions::default(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let ordering = LexOrdering::new(ordering);
+    let sort =
+        SortExec::new(ordering.clone(), Arc::new(input)).with_preserve_partitioning(true);
+    Ok((Arc::new(sort), ordering))
+}
+
+#[tokio::test]
+async fn collapses_stacked_merges() -> Result<()> {
+    let (input, ordering) = sorted_partitions(&["a"])?;
+    let inner = SortPreservingMergeExec::new(ordering.clone(), input).with_fetch(Some(5));
+    let plan: Arc<dyn ExecutionPlan> = Arc::new(
+        SortPreservingMergeExec::new(ordering, Arc::new(inner)).with_fetch(Some(3)),
+    );
+
+    let optimized =
+        CollapseMerges::new().optimize(Arc::clone(&plan), &ConfigOptions::new())?;
+    let expected_optimized = [
+        "SortPreservingMergeExec: [a@0 ASC], fetch=3",
+        "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
+        "    MemoryExec: partitions=3, partition_sizes=[1, 1, 1]",
+    ];
+    assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+    let expected = [
+        "+---+---+",
+        "| a | b |",
+        "+---+---+",
+        "| 1 | 1 |",
+        "| 2 | 1 |",
+        "| 3 | 2 |",
+        "+---+---+",
+    ];
+    let task_ctx = SessionContext::new().task_ctx();
+    assert_batches_eq!(expected, &collect(plan, Arc::clone(&task_ctx)).await?);
+    assert_batches_eq!(expected, &collect(optimized, task_ctx).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn combines_fetches_and_offsets() -> Result<()> {
+    let (input, ordering) = sorted_partitions(&["a"])?;
+    let inner = SortPreservingMergeExec::new(ordering.clone(), input)
+        .with_offset(1)
+        .with_fetch(Some(5));
+    let outer = SortPreservingMergeExec::new(ordering.clone(), Arc::new(inner))
+        .with_offset(2)
+        .with_fetch(Some(10));
+    let plan: Arc<dyn ExecutionPlan> = Arc::new(
+        SortPreservingMergeExec::new(ordering, Arc::new(outer)).with_fetch(Some(10)),
+    );
+
+    // the inner merges produce rows 1..6, of which the outer merges skip 2
+    let optimized =
+        CollapseMerges::new().optimize(Arc::clone(&plan), &ConfigOptions::new())?;
+    let expected_optimized = [
+        "SortPreservingMergeExec: [a@0 ASC], offset=3, fetch=3",
+        "  SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
+        "    MemoryExec: partitions=3, partition_sizes=[1, 1, 1]",
+    ];
+    assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+    let expected = [
+        "+---+---+",
+        "| a | b |",
+        "+---+---+",
+        "| 4 | 2 |",
+        "| 5 | 1 |",
+        "| 6 | 2 |",
+        "+---+---+",
+    ];
+    let task_ctx = SessionContext::new().task_ctx();
+    assert_batches_eq!(expected, &collect(plan, Arc::clone(&task_ctx)).await?);
+    assert_batches_eq!(expected, &collect(optimized, task_ctx).await?);
+    Ok(())
+}
+
+#[test]
+fn keeps_merge_with_different_ordering() -> Result<()> {
+    let (input, ordering) = sorted_partitions(&["a"])?;
+    let (_, outer_ordering) = sorted_partitions(&["b"])?;
+    let inner = SortPreservingMergeExec::new(ordering, input);
+    let plan = Arc::new(SortPreservingMergeExec::new(
+        outer_ordering,
+        Arc::new(inner),
+    ));
+
+    let optimized = CollapseMerges::new().optimize(plan, &ConfigOptions::new())?;
+    let expected = [
+        "SortPreservingMergeExec: [b@1 ASC]",
+        "  SortPreservingMergeExec: [a@0 ASC]",
+        "    SortExec: expr=[a@0 ASC], preserve_partitioning=[true]",
+        "      MemoryExec: partitions=3, partition_sizes=[1, 1, 1]",
+    ];
+    assert_eq!(get_plan_string(&optimized), expected);
+    Ok(())
+}
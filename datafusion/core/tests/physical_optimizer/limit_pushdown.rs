@@ -251,6 +251,52 @@ fn pushes_global_limit_into_multiple_fetch_plans() -> datafusion_common::Result<
     Ok(())
 }
 
+#[test]
+fn pushes_global_limit_into_sort_preserving_merge_with_offset(
+) -> datafusion_common::Result<()> {
+    let schema = create_schema();
+    let streaming_table = streaming_table_exec(schema.clone())?;
+    let repartition = repartition_exec(streaming_table)?;
+    let sort = sort_exec(
+        vec![PhysicalSortExpr {
+            expr: col("c1", &schema)?,
+            options: SortOptions::default(),
+        }],
+        repartition,
+    );
+    let spm = Arc::new(
+        SortPreservingMergeExec::new(sort.output_ordering().unwrap().clone(), sort)
+            .with_offset(3),
+    );
+    let global_limit = global_limit_exec(spm, 2, Some(5));
+
+    let initial = get_plan_string(&global_limit);
+    let expected_initial = [
+        "GlobalLimitExec: skip=2, fetch=5",
+        "  SortPreservingMergeExec: [c1@0 ASC], offset=3",
+        "    SortExec: expr=[c1@0 ASC], preserve_partitioning=[false]",
+        "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+        "        StreamingTableExec: partition_sizes=1, projection=[c1, c2, c3], infinite_source=true"
+    ];
+    assert_eq!(initial, expected_initial);
+
+    let after_optimize =
+        LimitPushdown::new().optimize(global_limit, &ConfigOptions::new())?;
+
+    // the fetch of the merge applies after its offset, and is not pushed
+    // to its input, which must produce the skipped rows as well
+    let expected = [
+        "GlobalLimitExec: skip=2, fetch=5",
+        "  SortPreservingMergeExec: [c1@0 ASC], offset=3, fetch=7",
+        "    SortExec: expr=[c1@0 ASC], preserve_partitioning=[false]",
+        "      RepartitionExec: partitioning=RoundRobinBatch(8), input_partitions=1",
+        "        StreamingTableExec: partition_sizes=1, projection=[c1, c2, c3], infinite_source=true"
+    ];
+    assert_eq!(get_plan_string(&after_optimize), expected);
+
+    Ok(())
+}
+
 #[test]
 fn keeps_pushed_local_limit_exec_when_there_are_multiple_input_partitions(
 ) -> datafusion_common::Result<()> {
@@ -15,7 +15,10 @@
 // specific language governing permissions and limitations
 // under the License.
 
+mod coalesce_ordered_merge;
+mod collapse_merges;
 mod combine_partial_final_agg;
 mod limit_pushdown;
 mod limited_distinct_aggregation;
+mod sort_fetch_pushdown;
 mod test_util;
@@ -0,0 +1,137 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Tests for the SortFetchPushdown optimizer rule
+
+use std::sync::Arc;
+
+use arrow::array::Int32Array;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use arrow_schema::SortOptions;
+use datafusion::prelude::SessionContext;
+use datafusion_common::config::ConfigOptions;
+use datafusion_common::{assert_batches_eq, Result};
+use datafusion_physical_expr::expressions::col;
+use datafusion_physical_expr::PhysicalSortExpr;
+use datafusion_physical_expr_common::sort_expr::LexOrdering;
+use datafusion_physical_optimizer::sort_fetch_pushdown::SortFetchPushdown;
+use datafusion_physical_optimizer::PhysicalOptimizerRule;
+use datafusion_physical_plan::memory::MemoryExec;
+use datafusion_physical_plan::sorts::sort::SortExec;
+use datafusion_physical_plan::sorts::sort_preserving_merge::SortPreservingMergeExec;
+use datafusion_physical_plan::{collect, get_plan_string, ExecutionPlan};
+
+/// Returns `SortPreservingMergeExec(fetch) <- SortExec(sort_fetch)` over
+/// three partitions, sorted by `a` descending
+fn merge_of_sorts(
+    fetch: Option<usize>,
+    sort_fetch: Option<usize>,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int32, false),
+        Field::new("b", DataType::Int32, false),
+    ]));
+    let partition = |a: Vec<i32>| {
+        let b = a.iter().map(|a| a * 10).collect::<Vec<_>>();
+        RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int32Array::from(a)), Arc::new(Int32Array::from(b))],
+        )
+        .map(|batch| vec![batch])
+    };
+    let partitions = vec![
+        partition(vec![1, 9, 4, 7])?,
+        partition(vec![8, 2, 6])?,
+        partition(vec![3, 5, 10, 0])?,
+    ];
+    let input = MemoryExec::try_new(&partitions, Arc::clone(&schema), None)?;
+
+    let ordering = LexOrdering::new(vec![PhysicalSortExpr {
+        expr: col("a", &schema)?,
+        options: SortOptions {
+            descending: true,
+            nulls_first: false,
+        },
+    }]);
+    let sort = SortExec::new(ordering.clone(), Arc::new(input))
+        .with_preserve_partitioning(true)
+        .with_fetch(sort_fetch);
+    Ok(Arc::new(
+        SortPreservingMergeExec::new(ordering, Arc::new(sort)).with_fetch(fetch),
+    ))
+}
+
+#[tokio::test]
+async fn pushes_merge_fetch_into_partition_sorts() -> Result<()> {
+    let plan = merge_of_sorts(Some(3), None)?;
+    let expected_initial = [
+        "SortPreservingMergeExec: [a@0 DESC NULLS LAST], fetch=3",
+        "  SortExec: expr=[a@0 DESC NULLS LAST], preserve_partitioning=[true]",
+        "    MemoryExec: partitions=3, partition_sizes=[1, 1, 1]",
+    ];
+    assert_eq!(get_plan_string(&plan), expected_initial);
+
+    let optimized =
+        SortFetchPushdown::new().optimize(Arc::clone(&plan), &ConfigOptions::new())?;
+    let expected_optimized = [
+        "SortPreservingMergeExec: [a@0 DESC NULLS LAST], fetch=3",
+        "  SortExec: TopK(fetch=3), expr=[a@0 DESC NULLS LAST], preserve_partitioning=[true]",
+        "    MemoryExec: partitions=3, partition_sizes=[1, 1, 1]",
+    ];
+    assert_eq!(get_plan_string(&optimized), expected_optimized);
+
+    // the per-partition TopKs produce the same top 3
+    let expected = [
+        "+----+-----+",
+        "| a  | b   |",
+        "+----+-----+",
+        "| 10 | 100 |",
+        "| 9  | 90  |",
+        "| 8  | 80  |",
+        "+----+-----+",
+    ];
+    let task_ctx = SessionContext::new().task_ctx();
+    assert_batches_eq!(expected, &collect(plan, Arc::clone(&task_ctx)).await?);
+    assert_batches_eq!(expected, &collect(optimized, task_ctx).await?);
+    Ok(())
+}
+
+#[test]
+fn keeps_smaller_sort_fetch() -> Result<()> {
+    let expected = [
+        "SortPreservingMergeExec: [a@0 DESC NULLS LAST], fetch=3",
+        "  SortExec: TopK(fetch=2), expr=[a@0 DESC NULLS LAST], preserve_partitioning=[true]",
+        "    MemoryExec: partitions=3, partition_sizes=[1, 1, 1]",
+    ];
+    let optimized = SortFetchPushdown::new()
+        .optimize(merge_of_sorts(Some(3), Some(2))?, &ConfigOptions::new())?;
+    assert_eq!(get_plan_string(&optimized), expected);
+    Ok(())
+}
+
+#[test]
+fn ignores_merge_without_fetch() -> Result<()> {
+    let expected = [
+        "SortPreservingMergeExec: [a@0 DESC NULLS LAST]",
+        "  SortExec: expr=[a@0 DESC NULLS LAST], preserve_partitioning=[true]",
+        "    MemoryExec: partitions=3, partition_sizes=[1, 1, 1]",
+    ];
+    let optimized = SortFetchPushdown::new()
+        .optimize(merge_of_sorts(None, None)?, &ConfigOptions::new())?;
+    assert_eq!(get_plan_string(&optimized), expected);
+    Ok(())
+}
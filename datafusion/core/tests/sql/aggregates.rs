@@ -16,6 +16,7 @@
 // under the License.
 
 use super::*;
+use datafusion::physical_plan::aggregates::{AggregateExec, AggregateMode};
 use datafusion::scalar::ScalarValue;
 
 #[tokio::test]
@@ -321,3 +322,54 @@ async fn test_accumulator_row_accumulator() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn partial_aggregate_state_field_names() -> Result<()> {
+    let tmp_dir = TempDir::new()?;
+    let ctx = create_ctx_with_partition(&tmp_dir, 4).await?;
+    // both averages have the state fields `count` and `sum`, and the state
+    // of both `first_value` and `last_value` holds the ordering by `c2`
+    let sql = "SELECT c3, avg(c1), avg(c2), \
+        first_value(c2 ORDER BY c2 DESC), last_value(c2 ORDER BY c2 DESC) \
+        FROM test GROUP BY c3";
+    let plan = ctx.sql(sql).await?.create_physical_plan().await?;
+
+    fn partial_schema(plan: &Arc<dyn ExecutionPlan>) -> Option<SchemaRef> {
+        match plan.as_any().downcast_ref::<AggregateExec>() {
+            Some(aggregate) if *aggregate.mode() == AggregateMode::Partial => {
+                Some(aggregate.schema())
+            }
+            _ => plan.children().into_iter().find_map(partial_schema),
+        }
+    }
+    let schema = partial_schema(&plan).expect("partial aggregate");
+    let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "c3",
+            "avg(test.c1)[count]",
+            "avg(test.c1)[sum]",
+            "avg(test.c2)[count]",
+            "avg(test.c2)[sum]",
+            "first_value(test.c2) ORDER BY [test.c2 DESC NULLS FIRST][first_value]",
+            "first_value(test.c2) ORDER BY [test.c2 DESC NULLS FIRST][c2@1]",
+            "first_value(test.c2) ORDER BY [test.c2 DESC NULLS FIRST][is_set]",
+            "last_value(test.c2) ORDER BY [test.c2 DESC NULLS FIRST][last_value]",
+            "last_value(test.c2) ORDER BY [test.c2 DESC NULLS FIRST][c2@1]",
+            "last_value(test.c2) ORDER BY [test.c2 DESC NULLS FIRST][is_set]",
+        ]
+    );
+
+    let results = collect(plan, ctx.task_ctx()).await?;
+    let expected = [
+        "+-------+--------------+--------------+----------------------------------------------------------+---------------------------------------------------------+",
+        "| c3    | avg(test.c1) | avg(test.c2) | first_value(test.c2) ORDER BY [test.c2 DESC NULLS FIRST] | last_value(test.c2) ORDER BY [test.c2 DESC NULLS FIRST] |",
+        "+-------+--------------+--------------+----------------------------------------------------------+---------------------------------------------------------+",
+        "| false | 1.5          | 5.0          | 9                                                        | 1                                                       |",
+        "| true  | 1.5          | 6.0          | 10                                                       | 2                                                       |",
+        "+-------+--------------+--------------+----------------------------------------------------------+---------------------------------------------------------+",
+    ];
+    assert_batches_sorted_eq!(expected, &results);
+    Ok(())
+}
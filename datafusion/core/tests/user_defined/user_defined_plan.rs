@@ -64,7 +64,7 @@ use std::{any::Any, collections::BTreeMap, fmt, sync::Arc};
 
 use arrow::{
     array::{Int64Array, StringArray},
-    datatypes::SchemaRef,
+    datatypes::{DataType, Field, Schema, SchemaRef},
     record_batch::RecordBatch,
     util::pretty::pretty_format_batches,
 };
@@ -84,13 +84,15 @@ use datafusion::{
     optimizer::{OptimizerConfig, OptimizerRule},
     physical_expr::EquivalenceProperties,
     physical_plan::{
-        DisplayAs, DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
-        PlanProperties, RecordBatchStream, SendableRecordBatchStream, Statistics,
+        memory::MemoryExec, DisplayAs, DisplayFormatType, Distribution, ExecutionPlan,
+        Partitioning, PlanProperties, RecordBatchStream, SendableRecordBatchStream,
+        Statistics,
     },
     physical_planner::{DefaultPhysicalPlanner, ExtensionPlanner, PhysicalPlanner},
     prelude::{SessionConfig, SessionContext},
 };
 use datafusion_common::config::ConfigOptions;
+use datafusion_common::stats::Precision;
 use datafusion_common::tree_node::{Transformed, TransformedResult, TreeNode};
 use datafusion_common::ScalarValue;
 use datafusion_expr::{FetchType, Projection, SortExpr};
@@ -295,6 +297,31 @@ async fn topk_plan() -> Result<()> {
     Ok(())
 }
 
+#[test]
+// The output of TopKExec has at most k rows
+fn topk_statistics() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("customer_id", DataType::Utf8, false),
+        Field::new("revenue", DataType::Int64, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        Arc::clone(&schema),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "b", "c", "d", "e"])),
+            Arc::new(Int64Array::from(vec![10, 20, 30, 40, 50])),
+        ],
+    )?;
+    let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None)?);
+
+    for k in [0, 3, 5, 10] {
+        let topk = TopKExec::new(Arc::clone(&input) as _, k);
+        let statistics = topk.statistics()?;
+        assert_eq!(statistics.num_rows, Precision::Exact(k.min(5)), "k={k}");
+        assert_eq!(statistics.column_statistics.len(), 2);
+    }
+    Ok(())
+}
+
 fn make_topk_context() -> SessionContext {
     let config = SessionConfig::new().with_target_partitions(48);
     let runtime = Arc::new(RuntimeEnv::default());
@@ -569,9 +596,22 @@ impl ExecutionPlan for TopKExec {
     }
 
     fn statistics(&self) -> Result<Statistics> {
-        // to improve the optimizability of this plan
-        // better statistics inference could be provided
-        Ok(Statistics::new_unknown(&self.schema()))
+        let input_statistics = self.input.statistics()?;
+        let mut statistics =
+            input_statistics
+                .clone()
+                .with_fetch(self.schema(), Some(self.k), 0, 1)?;
+        // the output rows are a subset of the input rows, so the bounds
+        // of the input columns still hold
+        for (column, input_column) in statistics
+            .column_statistics
+            .iter_mut()
+            .zip(input_statistics.column_statistics)
+        {
+            column.min_value = input_column.min_value.to_inexact();
+            column.max_value = input_column.max_value.to_inexact();
+        }
+        Ok(statistics)
     }
 }
 
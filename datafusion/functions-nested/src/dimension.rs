@@ -17,16 +17,18 @@
 
 //! [`ScalarUDFImpl`] definitions for array_dims and array_ndims functions.
 
+use arrow::array::AsArray;
 use arrow::array::{
     Array, ArrayRef, GenericListArray, ListArray, OffsetSizeTrait, UInt64Array,
 };
 use arrow::datatypes::{DataType, UInt64Type};
 use std::any::Any;
+use std::ops::Range;
 
 use datafusion_common::cast::{as_large_list_array, as_list_array};
 use datafusion_common::{exec_err, plan_err, Result};
 
-use crate::utils::{compute_array_dims, make_scalar_function};
+use crate::utils::make_scalar_function;
 use arrow_schema::DataType::{FixedSizeList, LargeList, List, UInt64};
 use arrow_schema::Field;
 use datafusion_expr::{
@@ -45,7 +47,7 @@ make_udf_expr_and_func!(
 
 #[user_doc(
     doc_section(label = "Array Functions"),
-    description = "Returns an array of the array's dimensions.",
+    description = "Returns an array of the array's dimensions. Returns NULL if the sub-arrays at some nesting level have different lengths.",
     syntax_example = "array_dims(array)",
     sql_example = r#"```sql
 > select array_dims([[1, 2, 3], [4, 5, 6]]);
@@ -200,20 +202,8 @@ pub fn array_dims_inner(args: &[ArrayRef]) -> Result<ArrayRef> {
     }
 
     let data = match args[0].data_type() {
-        List(_) => {
-            let array = as_list_array(&args[0])?;
-            array
-                .iter()
-                .map(compute_array_dims)
-                .collect::<Result<Vec<_>>>()?
-        }
-        LargeList(_) => {
-            let array = as_large_list_array(&args[0])?;
-            array
-                .iter()
-                .map(compute_array_dims)
-                .collect::<Result<Vec<_>>>()?
-        }
+        List(_) => general_array_dims(as_list_array(&args[0])?),
+        LargeList(_) => general_array_dims(as_large_list_array(&args[0])?),
         array_type => {
             return exec_err!("array_dims does not support type '{array_type:?}'");
         }
@@ -224,6 +214,71 @@ pub fn array_dims_inner(args: &[ArrayRef]) -> Result<ArrayRef> {
     Ok(Arc::new(result) as ArrayRef)
 }
 
+/// Returns the dimensions of each row of `array`, or `None` for null rows,
+/// empty rows and rows whose sub-lists have different lengths
+fn general_array_dims<O: OffsetSizeTrait>(
+    array: &GenericListArray<O>,
+) -> Vec<Option<Vec<Option<u64>>>> {
+    (0..array.len())
+        .map(|row| {
+            if array.is_null(row) || array.value_length(row).as_usize() == 0 {
+                return None;
+            }
+            let mut dims = vec![];
+            list_dims(array, row..row + 1, &mut dims)?;
+            Some(dims)
+        })
+        .collect()
+}
+
+/// Appends to `dims` the length of the sub-lists `range` of `list`, and
+/// the dimensions of their values.
+///
+/// Returns `None` if the sub-lists at some nesting level have different
+/// lengths
+fn list_dims<O: OffsetSizeTrait>(
+    list: &GenericListArray<O>,
+    range: Range<usize>,
+    dims: &mut Vec<Option<u64>>,
+) -> Option<()> {
+    let offsets = list.value_offsets();
+    let len = offsets[range.start + 1] - offsets[range.start];
+    if range
+        .clone()
+        .any(|index| offsets[index + 1] - offsets[index] != len)
+    {
+        return None;
+    }
+    dims.push(Some(len.as_usize() as u64));
+
+    let values_range = offsets[range.start].as_usize()..offsets[range.end].as_usize();
+    values_dims(list.values(), values_range, dims)
+}
+
+/// Appends to `dims` the dimensions of `values[range]`, if `values` is a
+/// list array, see [`list_dims`]
+fn values_dims(
+    values: &ArrayRef,
+    range: Range<usize>,
+    dims: &mut Vec<Option<u64>>,
+) -> Option<()> {
+    // the dimensions of empty sub-lists are unknown below this level
+    if range.is_empty() {
+        return Some(());
+    }
+    match values.data_type() {
+        List(_) => list_dims(values.as_list::<i32>(), range, dims),
+        LargeList(_) => list_dims(values.as_list::<i64>(), range, dims),
+        FixedSizeList(_, size) => {
+            let size = *size as usize;
+            dims.push(Some(size as u64));
+            let list = values.as_fixed_size_list();
+            values_dims(list.values(), range.start * size..range.end * size, dims)
+        }
+        _ => Some(()),
+    }
+}
+
 /// Array_ndims SQL function
 pub fn array_ndims_inner(args: &[ArrayRef]) -> Result<ArrayRef> {
     if args.len() != 1 {
@@ -258,3 +313,69 @@ pub fn array_ndims_inner(args: &[ArrayRef]) -> Result<ArrayRef> {
         array_type => exec_err!("array_ndims does not support type {array_type:?}"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::array_dims_inner;
+    use arrow::array::{Array, ArrayRef, AsArray, LargeListArray, ListArray};
+    use arrow::buffer::{NullBuffer, OffsetBuffer};
+    use arrow::datatypes::{Int64Type, UInt64Type};
+    use arrow_schema::Field;
+    use std::sync::Arc;
+
+    fn dims(array: ArrayRef) -> Vec<Option<Vec<u64>>> {
+        let result = array_dims_inner(&[array]).unwrap();
+        result
+            .as_list::<i32>()
+            .iter()
+            .map(|dims| {
+                dims.map(|dims| dims.as_primitive::<UInt64Type>().values().to_vec())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_array_dims_flat() {
+        let array = LargeListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+            Some(vec![Some(1), Some(2), Some(3)]),
+            None,
+            Some(vec![]),
+            Some(vec![None]),
+        ]);
+        assert_eq!(
+            dims(Arc::new(array)),
+            vec![Some(vec![3]), None, None, Some(vec![1])]
+        );
+    }
+
+    #[test]
+    fn test_array_dims_nested() {
+        // [[1, 2], [3, 4]], [[5], [6, 7]], NULL, [[8, 9, 10]], [[], []]
+        let inner = LargeListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+            Some(vec![Some(1), Some(2)]),
+            Some(vec![Some(3), Some(4)]),
+            Some(vec![Some(5)]),
+            Some(vec![Some(6), Some(7)]),
+            Some(vec![Some(8), Some(9), Some(10)]),
+            Some(vec![]),
+            Some(vec![]),
+        ]);
+        let field = Arc::new(Field::new_list_field(inner.data_type().clone(), true));
+        let array = ListArray::new(
+            field,
+            OffsetBuffer::from_lengths([2, 2, 0, 1, 2]),
+            Arc::new(inner),
+            Some(NullBuffer::from(vec![true, true, false, true, true])),
+        );
+        assert_eq!(
+            dims(Arc::new(array)),
+            vec![
+                Some(vec![2, 2]),
+                None,
+                None,
+                Some(vec![1, 3]),
+                Some(vec![2, 0]),
+            ]
+        );
+    }
+}
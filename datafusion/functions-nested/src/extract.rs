@@ -32,9 +32,7 @@ use arrow_schema::Field;
 use datafusion_common::cast::as_int64_array;
 use datafusion_common::cast::as_large_list_array;
 use datafusion_common::cast::as_list_array;
-use datafusion_common::{
-    exec_err, internal_datafusion_err, plan_err, DataFusionError, Result,
-};
+use datafusion_common::{exec_err, internal_datafusion_err, plan_err, Result};
 use datafusion_expr::Expr;
 use datafusion_expr::{
     ColumnarValue, Documentation, ScalarUDFImpl, Signature, Volatility,
@@ -226,16 +224,12 @@ where
     let mut mutable =
         MutableArrayData::with_capacities(vec![&original_data], true, capacity);
 
-    fn adjusted_array_index<O: OffsetSizeTrait>(index: i64, len: O) -> Result<Option<O>>
+    fn adjusted_array_index<O: OffsetSizeTrait>(index: i64, len: O) -> Option<O>
     where
         i64: TryInto<O>,
     {
-        let index: O = index.try_into().map_err(|_| {
-            DataFusionError::Execution(format!(
-                "array_element got invalid index: {}",
-                index
-            ))
-        })?;
+        // an index not representable by the offsets is out of bounds of any list
+        let index: O = index.try_into().ok()?;
         // 0 ~ len - 1
         let adjusted_zero_index = if index < O::usize_as(0) {
             index + len
@@ -244,10 +238,10 @@ where
         };
 
         if O::usize_as(0) <= adjusted_zero_index && adjusted_zero_index < len {
-            Ok(Some(adjusted_zero_index))
+            Some(adjusted_zero_index)
         } else {
             // Out of bounds
-            Ok(None)
+            None
         }
     }
 
@@ -256,13 +250,15 @@ where
         let end = offset_window[1];
         let len = end - start;
 
-        // array is null
-        if len == O::usize_as(0) {
+        // array or index is null, or array is empty. A null list may still
+        // span values, so its validity is checked rather than its length
+        if array.is_null(row_index) || indexes.is_null(row_index) || len == O::usize_as(0)
+        {
             mutable.extend_nulls(1);
             continue;
         }
 
-        let index = adjusted_array_index::<O>(indexes.value(row_index), len)?;
+        let index = adjusted_array_index::<O>(indexes.value(row_index), len);
 
         if let Some(index) = index {
             let start = start.as_usize() + index.as_usize();
@@ -954,12 +950,16 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::array_element_udf;
+    use super::{array_element_inner, array_element_udf};
+    use arrow::array::{Array, ArrayRef, AsArray, Int64Array, ListArray};
+    use arrow::buffer::{NullBuffer, OffsetBuffer};
+    use arrow::datatypes::Int64Type;
     use arrow_schema::{DataType, Field};
     use datafusion_common::{Column, DFSchema, ScalarValue};
     use datafusion_expr::expr::ScalarFunction;
     use datafusion_expr::{cast, Expr, ExprSchemable};
     use std::collections::HashMap;
+    use std::sync::Arc;
 
     // Regression test for https://github.com/apache/datafusion/issues/13755
     #[test]
@@ -1033,4 +1033,77 @@ mod tests {
             fixed_size_list_type
         );
     }
+
+    #[test]
+    fn test_array_element_indexes() {
+        // [1, 2, 3], [4, 5], NULL (spanning [6, 7]), [], [8, 9, 10]
+        let values = Arc::new(Int64Array::from(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+        let list: ArrayRef = Arc::new(ListArray::new(
+            Arc::new(Field::new_list_field(DataType::Int64, true)),
+            OffsetBuffer::new(vec![0, 3, 5, 7, 7, 10].into()),
+            values,
+            Some(NullBuffer::from(vec![true, true, false, true, true])),
+        ));
+
+        let cases = vec![
+            (
+                vec![Some(1), Some(2), Some(1), Some(1), Some(3)],
+                vec![Some(1), Some(5), None, None, Some(10)],
+            ),
+            (
+                vec![Some(-1), Some(-2), Some(-1), Some(-1), Some(-3)],
+                vec![Some(3), Some(4), None, None, Some(8)],
+            ),
+            (
+                vec![Some(4), Some(-3), Some(1), Some(1), Some(i64::MAX)],
+                vec![None, None, None, None, None],
+            ),
+            (
+                vec![Some(0), Some(0), Some(0), Some(0), Some(0)],
+                vec![None, None, None, None, None],
+            ),
+            (
+                vec![None, Some(1), None, None, None],
+                vec![None, Some(4), None, None, None],
+            ),
+        ];
+        for (indexes, expected) in cases {
+            let indexes: ArrayRef = Arc::new(Int64Array::from(indexes));
+            let result = array_element_inner(&[Arc::clone(&list), indexes]).unwrap();
+            let result = result.as_primitive::<Int64Type>();
+            assert_eq!(result.iter().collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn test_array_element_nested() {
+        // [[1, 2], [3]], NULL, [[4], NULL]
+        let inner = ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+            Some(vec![Some(1), Some(2)]),
+            Some(vec![Some(3)]),
+            Some(vec![Some(4)]),
+            None,
+        ]);
+        let list: ArrayRef = Arc::new(ListArray::new(
+            Arc::new(Field::new_list_field(inner.data_type().clone(), true)),
+            OffsetBuffer::new(vec![0, 2, 2, 4].into()),
+            Arc::new(inner),
+            Some(NullBuffer::from(vec![true, false, true])),
+        ));
+        let indexes: ArrayRef = Arc::new(Int64Array::from(vec![-1, 1, 2]));
+
+        let result = array_element_inner(&[list, indexes]).unwrap();
+        let result = result.as_list::<i32>();
+        assert_eq!(result.len(), 3);
+        assert_eq!(
+            result
+                .value(0)
+                .as_primitive::<Int64Type>()
+                .values()
+                .to_vec(),
+            vec![3]
+        );
+        assert!(result.is_null(1));
+        assert!(result.is_null(2));
+    }
 }
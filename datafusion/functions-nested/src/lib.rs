@@ -105,6 +105,7 @@ pub mod expr_fn {
     pub use super::resize::array_resize;
     pub use super::reverse::array_reverse;
     pub use super::set_ops::array_distinct;
+    pub use super::set_ops::array_distinct_count;
     pub use super::set_ops::array_intersect;
     pub use super::set_ops::array_union;
     pub use super::sort::array_sort;
@@ -144,6 +145,7 @@ pub fn all_default_nested_functions() -> Vec<Arc<ScalarUDF>> {
         resize::array_resize_udf(),
         reverse::array_reverse_udf(),
         set_ops::array_distinct_udf(),
+        set_ops::array_distinct_count_udf(),
         set_ops::array_intersect_udf(),
         set_ops::array_union_udf(),
         position::array_position_udf(),
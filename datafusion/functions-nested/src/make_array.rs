@@ -18,17 +18,19 @@
 //! [`ScalarUDFImpl`] definitions for `make_array` function.
 
 use std::any::Any;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::sync::Arc;
 use std::vec;
 
 use crate::utils::make_scalar_function;
 use arrow::array::{ArrayData, Capacities, MutableArrayData};
+use arrow::compute::cast;
 use arrow_array::{
     new_null_array, Array, ArrayRef, GenericListArray, NullArray, OffsetSizeTrait,
 };
-use arrow_buffer::OffsetBuffer;
-use arrow_schema::DataType::{List, Null};
-use arrow_schema::{DataType, Field};
+use arrow_buffer::{NullBufferBuilder, OffsetBuffer};
+use arrow_schema::DataType::{List, Null, Struct};
+use arrow_schema::{DataType, Field, Fields};
 use datafusion_common::utils::SingleRowListArrayBuilder;
 use datafusion_common::{plan_err, Result};
 use datafusion_expr::binary::{
@@ -68,6 +70,21 @@ make_udf_expr_and_func!(
 pub struct MakeArray {
     signature: Signature,
     aliases: Vec<String>,
+    nulls: MakeArrayNulls,
+}
+
+/// Whether `make_array` produces null elements or a null list for null
+/// arguments
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MakeArrayNulls {
+    /// Each null argument is a null element of the list, so
+    /// `make_array(NULL, NULL)` is `[NULL, NULL]`
+    #[default]
+    NullElements,
+    /// A row where all arguments are null is a null list, so
+    /// `make_array(NULL, NULL)` is `NULL`. Rows with any non null argument
+    /// still contain null elements, such as `[NULL, 1]`
+    NullList,
 }
 
 impl Default for MakeArray {
@@ -84,8 +101,16 @@ impl MakeArray {
                 Volatility::Immutable,
             ),
             aliases: vec![String::from("make_list")],
+            nulls: MakeArrayNulls::default(),
         }
     }
+
+    /// Produce null elements or null lists for null arguments according to
+    /// `nulls`, see [`MakeArrayNulls`]
+    pub fn with_nulls(mut self, nulls: MakeArrayNulls) -> Self {
+        self.nulls = nulls;
+        self
+    }
 }
 
 impl ScalarUDFImpl for MakeArray {
@@ -105,11 +130,11 @@ impl ScalarUDFImpl for MakeArray {
         match arg_types.len() {
             0 => Ok(empty_array_type()),
             _ => {
-                // At this point, all the type in array should be coerced to the same one
-                Ok(List(Arc::new(Field::new_list_field(
-                    arg_types[0].to_owned(),
-                    true,
-                ))))
+                // At this point, all the type in array should be coerced to the
+                // same one, except for the nullability of struct fields
+                let element_type = common_element_type(arg_types)
+                    .unwrap_or_else(|| arg_types[0].to_owned());
+                Ok(List(Arc::new(Field::new_list_field(element_type, true))))
             }
         }
     }
@@ -119,7 +144,8 @@ impl ScalarUDFImpl for MakeArray {
         args: &[ColumnarValue],
         _number_rows: usize,
     ) -> Result<ColumnarValue> {
-        make_scalar_function(make_array_inner)(args)
+        let nulls = self.nulls;
+        make_scalar_function(move |arrays| make_array_with_nulls(arrays, nulls))(args)
     }
 
     fn aliases(&self) -> &[String] {
@@ -154,6 +180,23 @@ impl ScalarUDFImpl for MakeArray {
         }
     }
 
+    fn equals(&self, other: &dyn ScalarUDFImpl) -> bool {
+        let Some(other) = other.as_any().downcast_ref::<MakeArray>() else {
+            return false;
+        };
+        self.signature == other.signature
+            && self.aliases == other.aliases
+            && self.nulls == other.nulls
+    }
+
+    fn hash_value(&self) -> u64 {
+        let hasher = &mut DefaultHasher::new();
+        self.name().hash(hasher);
+        self.signature.hash(hasher);
+        self.nulls.hash(hasher);
+        hasher.finish()
+    }
+
     fn documentation(&self) -> Option<&Documentation> {
         self.doc()
     }
@@ -168,6 +211,12 @@ pub(super) fn empty_array_type() -> DataType {
 /// Constructs an array using the input `data` as `ArrayRef`.
 /// Returns a reference-counted `Array` instance result.
 pub(crate) fn make_array_inner(arrays: &[ArrayRef]) -> Result<ArrayRef> {
+    make_array_with_nulls(arrays, MakeArrayNulls::default())
+}
+
+/// Like [`make_array_inner`], producing null elements or null lists for
+/// null arguments according to `nulls`
+fn make_array_with_nulls(arrays: &[ArrayRef], nulls: MakeArrayNulls) -> Result<ArrayRef> {
     let mut data_type = Null;
     for arg in arrays {
         let arg_data_type = arg.data_type();
@@ -178,6 +227,10 @@ pub(crate) fn make_array_inner(arrays: &[ArrayRef]) -> Result<ArrayRef> {
     }
 
     match data_type {
+        // all arguments are null in every row
+        Null if nulls == MakeArrayNulls::NullList && !arrays.is_empty() => {
+            Ok(new_null_array(&empty_array_type(), arrays[0].len()))
+        }
         // Either an empty array or all nulls:
         Null => {
             let length = arrays.iter().map(|a| a.len()).sum();
@@ -187,7 +240,7 @@ pub(crate) fn make_array_inner(arrays: &[ArrayRef]) -> Result<ArrayRef> {
                 SingleRowListArrayBuilder::new(array).build_list_array(),
             ))
         }
-        _ => array_array::<i32>(arrays, data_type),
+        _ => array_array::<i32>(arrays, data_type, nulls),
     }
 }
 
@@ -234,12 +287,137 @@ pub(crate) fn make_array_inner(arrays: &[ArrayRef]) -> Result<ArrayRef> {
 fn array_array<O: OffsetSizeTrait>(
     args: &[ArrayRef],
     data_type: DataType,
+    nulls: MakeArrayNulls,
 ) -> Result<ArrayRef> {
     // do not accept 0 arguments.
     if args.is_empty() {
         return plan_err!("Array requires at least one argument");
     }
 
+    // the arguments may still differ in type, such as `List(Int32)` and
+    // `List(Int64)`, so unify them before combining their values
+    let (args, data_type) = coerce_to_common_type(args, data_type)?;
+    let args = args.as_slice();
+
+    // Fast path: a single argument already holds the list values, one
+    // element per row, so it can be wrapped without copying
+    if let [arg] = args {
+        if !arg.as_any().is::<NullArray>() {
+            // a row is a null list exactly where its only element is null
+            let list_nulls = match nulls {
+                MakeArrayNulls::NullElements => None,
+                MakeArrayNulls::NullList => arg.logical_nulls(),
+            };
+            return Ok(Arc::new(GenericListArray::<O>::try_new(
+                Arc::new(Field::new_list_field(data_type, true)),
+                OffsetBuffer::from_lengths(std::iter::repeat(1).take(arg.len())),
+                Arc::clone(arg),
+                list_nulls,
+            )?));
+        }
+    }
+
+    interleave_array_array::<O>(args, data_type, nulls)
+}
+
+/// Casts the non null `args` to their common supertype, as determined by
+/// [`common_element_type`], returning the cast arguments and that type.
+fn coerce_to_common_type(
+    args: &[ArrayRef],
+    data_type: DataType,
+) -> Result<(Vec<ArrayRef>, DataType)> {
+    let arg_types: Vec<DataType> = args
+        .iter()
+        .map(|arg| arg.data_type().clone())
+        .filter(|arg_type| !arg_type.equals_datatype(&Null))
+        .collect();
+    if arg_types.iter().all(|arg_type| arg_type == &data_type) {
+        return Ok((args.to_vec(), data_type));
+    }
+
+    let Some(common_type) = common_element_type(&arg_types) else {
+        return plan_err!(
+            "make_array received arguments of incompatible types {arg_types:?}"
+        );
+    };
+    let args = args
+        .iter()
+        .map(|arg| {
+            let arg_type = arg.data_type();
+            if arg_type.equals_datatype(&Null) || arg_type == &common_type {
+                Ok(Arc::clone(arg))
+            } else {
+                Ok(cast(arg, &common_type)?)
+            }
+        })
+        .collect::<Result<_>>()?;
+    Ok((args, common_type))
+}
+
+/// Returns the common supertype of the `arg_types` of the elements of an
+/// array.
+///
+/// Structs with the same field names are combined field by field, keeping
+/// the field names, as [`type_union_resolution`] would rename them. A
+/// field of the combined struct is nullable if it is nullable in any of
+/// the structs.
+fn common_element_type(arg_types: &[DataType]) -> Option<DataType> {
+    let struct_fields = arg_types
+        .iter()
+        .map(|arg_type| match arg_type {
+            Struct(fields) => Some(fields),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+    match struct_fields {
+        Some(struct_fields) if !struct_fields.is_empty() => {
+            common_struct_type(&struct_fields)
+                .or_else(|| type_union_resolution(arg_types))
+        }
+        _ => type_union_resolution(arg_types),
+    }
+}
+
+/// Returns the struct combining the `struct_fields` of several structs, if
+/// they all have the same field names
+fn common_struct_type(struct_fields: &[&Fields]) -> Option<DataType> {
+    let first = struct_fields[0];
+    let same_names = struct_fields.iter().all(|fields| {
+        fields.len() == first.len()
+            && fields
+                .iter()
+                .zip(first.iter())
+                .all(|(f, g)| f.name() == g.name())
+    });
+    if !same_names {
+        return None;
+    }
+
+    let fields = first
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| {
+            let child_types: Vec<_> = struct_fields
+                .iter()
+                .map(|fields| fields[idx].data_type().clone())
+                .collect();
+            let nullable = struct_fields.iter().any(|fields| fields[idx].is_nullable());
+            Some(
+                Field::new(field.name(), common_element_type(&child_types)?, nullable)
+                    .with_metadata(field.metadata().clone()),
+            )
+        })
+        .collect::<Option<Fields>>()?;
+    Some(Struct(fields))
+}
+
+/// Builds the lists of [`array_array`] by copying the value of each
+/// argument row by row.
+fn interleave_array_array<O: OffsetSizeTrait>(
+    args: &[ArrayRef],
+    data_type: DataType,
+    nulls: MakeArrayNulls,
+) -> Result<ArrayRef> {
     let mut data = vec![];
     let mut total_len = 0;
     for arg in args {
@@ -260,18 +438,20 @@ fn array_array<O: OffsetSizeTrait>(
     let mut mutable = MutableArrayData::with_capacities(data_ref, true, capacity);
 
     let num_rows = args[0].len();
+    let mut list_nulls = NullBufferBuilder::new(num_rows);
     for row_idx in 0..num_rows {
+        let mut any_valid = false;
         for (arr_idx, arg) in args.iter().enumerate() {
-            if !arg.as_any().is::<NullArray>()
-                && !arg.is_null(row_idx)
-                && arg.is_valid(row_idx)
-            {
+            // a `NullArray` has no validity buffer, so `is_valid` is true
+            if !arg.as_any().is::<NullArray>() && arg.is_valid(row_idx) {
                 mutable.extend(arr_idx, row_idx, row_idx + 1);
+                any_valid = true;
             } else {
                 mutable.extend_nulls(1);
             }
         }
         offsets.push(O::usize_as(mutable.len()));
+        list_nulls.append(any_valid || nulls == MakeArrayNulls::NullElements);
     }
     let data = mutable.freeze();
 
@@ -279,6 +459,239 @@ fn array_array<O: OffsetSizeTrait>(
         Arc::new(Field::new_list_field(data_type, true)),
         OffsetBuffer::new(offsets.into()),
         arrow_array::make_array(data),
-        None,
+        list_nulls.finish(),
     )?))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::types::{Int32Type, Int64Type};
+    use arrow_array::{Int32Array, Int64Array, ListArray, StringArray, StructArray};
+    use arrow_buffer::NullBuffer;
+
+    #[test]
+    fn test_make_array_single_argument() -> Result<()> {
+        let values: ArrayRef = Arc::new(Int64Array::from_iter((0..1_000_000).map(|v| {
+            if v % 7 == 0 {
+                None
+            } else {
+                Some(v)
+            }
+        })));
+
+        for nulls in [MakeArrayNulls::NullElements, MakeArrayNulls::NullList] {
+            let fast =
+                array_array::<i32>(&[Arc::clone(&values)], DataType::Int64, nulls)?;
+            let general = interleave_array_array::<i32>(
+                &[Arc::clone(&values)],
+                DataType::Int64,
+                nulls,
+            )?;
+            assert_eq!(fast.as_ref(), general.as_ref());
+        }
+
+        let fast = array_array::<i32>(
+            &[Arc::clone(&values)],
+            DataType::Int64,
+            MakeArrayNulls::NullElements,
+        )?;
+
+        // the fast path reuses the input as list values rather than copying it
+        let list = fast.as_any().downcast_ref::<ListArray>().unwrap();
+        assert_eq!(list.len(), 1_000_000);
+        assert!(list.offsets().windows(2).all(|w| w[1] - w[0] == 1));
+        assert_eq!(
+            list.values().to_data().buffers()[0].as_ptr(),
+            values.to_data().buffers()[0].as_ptr()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_array_coerces_list_element_types() -> Result<()> {
+        let int_list: ArrayRef =
+            Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+                Some(vec![Some(1), Some(2)]),
+                None,
+            ]));
+        let bigint_list: ArrayRef =
+            Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+                Some(vec![Some(3)]),
+                Some(vec![Some(4), None]),
+            ]));
+
+        let result = make_array_inner(&[int_list, bigint_list])?;
+        let bigint_list_type =
+            List(Arc::new(Field::new_list_field(DataType::Int64, true)));
+        assert_eq!(
+            result.data_type(),
+            &List(Arc::new(Field::new_list_field(bigint_list_type, true)))
+        );
+
+        let result = result.as_any().downcast_ref::<ListArray>().unwrap();
+        let first = result.value(0);
+        let first = first.as_any().downcast_ref::<ListArray>().unwrap();
+        assert_eq!(
+            first,
+            &ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+                Some(vec![Some(1), Some(2)]),
+                Some(vec![Some(3)]),
+            ])
+        );
+        let second = result.value(1);
+        let second = second.as_any().downcast_ref::<ListArray>().unwrap();
+        assert_eq!(
+            second,
+            &ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+                None,
+                Some(vec![Some(4), None]),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_array_structs() -> Result<()> {
+        let struct_array = |a_nullable: bool, a: Int32Array, b: StringArray, nulls| {
+            let fields = vec![
+                Field::new("a", DataType::Int32, a_nullable),
+                Field::new("b", DataType::Utf8, true),
+            ];
+            Arc::new(StructArray::new(
+                fields.into(),
+                vec![Arc::new(a), Arc::new(b)],
+                nulls,
+            )) as ArrayRef
+        };
+        let first = struct_array(
+            false,
+            Int32Array::from(vec![1, 2, 3]),
+            StringArray::from(vec![Some("x"), None, Some("z")]),
+            Some(NullBuffer::from(vec![true, false, true])),
+        );
+        let second = struct_array(
+            true,
+            Int32Array::from(vec![Some(4), None, Some(6)]),
+            StringArray::from(vec![Some("u"), Some("v"), None]),
+            None,
+        );
+
+        // the field names are kept, and `a` is nullable as it is nullable in
+        // one of the structs
+        let element_type = second.data_type().clone();
+        let list_type = List(Arc::new(Field::new_list_field(element_type, true)));
+        let arg_types = [first.data_type().clone(), second.data_type().clone()];
+        assert_eq!(MakeArray::new().return_type(&arg_types)?, list_type);
+
+        let result = make_array_inner(&[Arc::clone(&first), Arc::clone(&second)])?;
+        assert_eq!(result.data_type(), &list_type);
+        result.to_data().validate_full()?;
+
+        let result = result.as_any().downcast_ref::<ListArray>().unwrap();
+        let expected = [
+            struct_array(
+                true,
+                Int32Array::from(vec![1, 4]),
+                StringArray::from(vec!["x", "u"]),
+                None,
+            ),
+            struct_array(
+                true,
+                Int32Array::from(vec![Some(2), None]),
+                StringArray::from(vec![None, Some("v")]),
+                Some(NullBuffer::from(vec![false, true])),
+            ),
+            struct_array(
+                true,
+                Int32Array::from(vec![3, 6]),
+                StringArray::from(vec![Some("z"), None]),
+                None,
+            ),
+        ];
+        for (row, expected) in expected.iter().enumerate() {
+            assert_eq!(&result.value(row), expected, "row {row}");
+        }
+
+        // a null argument is a null struct element
+        let result =
+            make_array_inner(&[Arc::clone(&first), Arc::new(NullArray::new(3))])?;
+        assert_eq!(
+            result.data_type(),
+            &List(Arc::new(Field::new_list_field(
+                first.data_type().clone(),
+                true
+            )))
+        );
+        result.to_data().validate_full()?;
+        let result = result.as_any().downcast_ref::<ListArray>().unwrap();
+        let elements = result.value(0);
+        assert!(elements.is_valid(0) && elements.is_null(1));
+        assert!(result.value(1).is_null(0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_array_incompatible_types() {
+        let int_list: ArrayRef =
+            Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+                Some(vec![Some(1)]),
+            ]));
+        let strings: ArrayRef = Arc::new(StringArray::from(vec!["a"]));
+
+        let err = make_array_inner(&[int_list, strings])
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("make_array received arguments of incompatible types"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn test_make_array_nulls() -> Result<()> {
+        let first: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None, None]));
+        let second: ArrayRef = Arc::new(Int32Array::from(vec![Some(2), Some(3), None]));
+        let null_lists = MakeArrayNulls::NullList;
+
+        // a row of null arguments is a list of null elements by default
+        let result = make_array_inner(&[Arc::clone(&first), Arc::clone(&second)])?;
+        let expected = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2)]),
+            Some(vec![None, Some(3)]),
+            Some(vec![None, None]),
+        ]);
+        assert_eq!(result.as_ref(), &expected as &dyn Array);
+
+        // or a null list, while other rows still contain null elements
+        let result = make_array_with_nulls(&[Arc::clone(&first), second], null_lists)?;
+        result.to_data().validate_full()?;
+        let result = result.as_any().downcast_ref::<ListArray>().unwrap();
+        assert!(result.is_valid(0) && result.is_valid(1) && result.is_null(2));
+        assert_eq!(result.value(1).null_count(), 1);
+
+        // the same applies to a single argument
+        let result = make_array_with_nulls(&[Arc::clone(&first)], null_lists)?;
+        let expected = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1)]),
+            None,
+            None,
+        ]);
+        assert_eq!(result.as_ref(), &expected as &dyn Array);
+
+        // and to `NullArray` arguments
+        let result = make_array_with_nulls(
+            &[Arc::new(NullArray::new(2)), Arc::new(NullArray::new(2))],
+            null_lists,
+        )?;
+        assert_eq!(result.data_type(), &empty_array_type());
+        assert_eq!(result.len(), 2);
+        assert_eq!(result.null_count(), 2);
+
+        // UDFs with different null handling are not interchangeable
+        let udf = MakeArray::new();
+        assert!(udf.equals(&MakeArray::new()));
+        assert!(!udf.equals(&MakeArray::new().with_nulls(null_lists)));
+        Ok(())
+    }
+}
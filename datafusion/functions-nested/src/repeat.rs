@@ -19,12 +19,8 @@
 
 use crate::utils::make_scalar_function;
 use arrow::array::{Capacities, MutableArrayData};
-use arrow::compute;
-use arrow_array::{
-    new_null_array, Array, ArrayRef, GenericListArray, Int64Array, ListArray,
-    OffsetSizeTrait,
-};
-use arrow_buffer::OffsetBuffer;
+use arrow_array::{Array, ArrayRef, GenericListArray, Int64Array, OffsetSizeTrait};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
 use arrow_schema::DataType::{LargeList, List};
 use arrow_schema::{DataType, Field};
 use datafusion_common::cast::{as_int64_array, as_large_list_array, as_list_array};
@@ -46,7 +42,7 @@ make_udf_expr_and_func!(
 
 #[user_doc(
     doc_section(label = "Array Functions"),
-    description = "Returns an array containing element `count` times.",
+    description = "Returns an array containing element `count` times. The result is a `LargeList` for a `LargeList` element, and a `List` otherwise, which can hold at most 2147483647 repeated values.",
     syntax_example = "array_repeat(element, count)",
     sql_example = r#"```sql
 > select array_repeat(1, 3);
@@ -68,7 +64,7 @@ make_udf_expr_and_func!(
     ),
     argument(
         name = "count",
-        description = "Value of how many times to repeat the element."
+        description = "Value of how many times to repeat the element. A negative count gives an empty array, a null count a null array."
     )
 )]
 #[derive(Debug)]
@@ -100,10 +96,13 @@ impl ScalarUDFImpl for ArrayRepeat {
     }
 
     fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
-        Ok(List(Arc::new(Field::new_list_field(
-            arg_types[0].clone(),
-            true,
-        ))))
+        let field = Arc::new(Field::new_list_field(arg_types[0].clone(), true));
+        // the return type is fixed at planning, so a `List` result that
+        // would exceed the `i32` offsets is an error rather than a `LargeList`
+        Ok(match &arg_types[0] {
+            LargeList(_) => LargeList(field),
+            _ => List(field),
+        })
     }
 
     fn invoke_batch(
@@ -145,16 +144,37 @@ pub fn array_repeat_inner(args: &[ArrayRef]) -> Result<ArrayRef> {
     }
 }
 
+/// Returns how many times each row is repeated, zero for null and
+/// negative counts, along with the total length of the repeated values.
+///
+/// Returns an error if the total length does not fit in the offsets `O`.
+fn repeat_counts<O: OffsetSizeTrait>(
+    count_array: &Int64Array,
+) -> Result<(Vec<usize>, usize)> {
+    let counts = count_array
+        .iter()
+        .map(|count| count.map_or(0, |count| count.max(0) as usize))
+        .collect::<Vec<_>>();
+
+    let total = counts
+        .iter()
+        .try_fold(0_usize, |total, &count| total.checked_add(count))
+        .filter(|&total| O::from_usize(total).is_some());
+    match total {
+        Some(total) => Ok((counts, total)),
+        None => exec_err!("array_repeat result is too large for the list offsets"),
+    }
+}
+
 /// For each element of `array[i]` repeat `count_array[i]` times.
 ///
-/// Assumption for the input:
-///     1. `count[i] >= 0`
-///     2. `array.len() == count_array.len()`
+/// A null `count_array[i]` produces a null list, a negative one an empty
+/// list.
 ///
 /// For example,
 /// ```text
 /// array_repeat(
-///     [1, 2, 3], [2, 0, 1] => [[1, 1], [], [3]]
+///     [1, 2, 3, 4], [2, 0, NULL, -1] => [[1, 1], [], NULL, []]
 /// )
 /// ```
 fn general_repeat<O: OffsetSizeTrait>(
@@ -162,48 +182,31 @@ fn general_repeat<O: OffsetSizeTrait>(
     count_array: &Int64Array,
 ) -> Result<ArrayRef> {
     let data_type = array.data_type();
-    let mut new_values = vec![];
-
-    let count_vec = count_array
-        .values()
-        .to_vec()
-        .iter()
-        .map(|x| *x as usize)
-        .collect::<Vec<_>>();
-
-    for (row_index, &count) in count_vec.iter().enumerate() {
-        let repeated_array = if array.is_null(row_index) {
-            new_null_array(data_type, count)
-        } else {
-            let original_data = array.to_data();
-            let capacity = Capacities::Array(count);
-            let mut mutable =
-                MutableArrayData::with_capacities(vec![&original_data], false, capacity);
+    let (counts, total) = repeat_counts::<O>(count_array)?;
 
-            for _ in 0..count {
-                mutable.extend(0, row_index, row_index + 1);
-            }
-
-            let data = mutable.freeze();
-            arrow_array::make_array(data)
-        };
-        new_values.push(repeated_array);
+    let original_data = array.to_data();
+    let capacity = Capacities::Array(total);
+    let mut mutable =
+        MutableArrayData::with_capacities(vec![&original_data], true, capacity);
+    for (row_index, &count) in counts.iter().enumerate() {
+        for _ in 0..count {
+            mutable.extend(0, row_index, row_index + 1);
+        }
     }
-
-    let new_values: Vec<_> = new_values.iter().map(|a| a.as_ref()).collect();
-    let values = compute::concat(&new_values)?;
+    let values = arrow_array::make_array(mutable.freeze());
 
     Ok(Arc::new(GenericListArray::<O>::try_new(
         Arc::new(Field::new_list_field(data_type.to_owned(), true)),
-        OffsetBuffer::from_lengths(count_vec),
+        OffsetBuffer::from_lengths(counts),
         values,
-        None,
+        count_array.nulls().cloned(),
     )?))
 }
 
 /// Handle List version of `general_repeat`
 ///
-/// For each element of `list_array[i]` repeat `count_array[i]` times.
+/// For each element of `list_array[i]` repeat `count_array[i]` times. The
+/// result uses the offsets `O` of `list_array`.
 ///
 /// For example,
 /// ```text
@@ -217,54 +220,137 @@ fn general_list_repeat<O: OffsetSizeTrait>(
 ) -> Result<ArrayRef> {
     let data_type = list_array.data_type();
     let value_type = list_array.value_type();
-    let mut new_values = vec![];
+    let (counts, total) = repeat_counts::<O>(count_array)?;
 
-    let count_vec = count_array
-        .values()
-        .to_vec()
-        .iter()
-        .map(|x| *x as usize)
-        .collect::<Vec<_>>();
+    // the offsets and validity of the repeated lists
+    let mut inner_lengths = Vec::with_capacity(total);
+    let mut inner_validity = Vec::with_capacity(total);
+    // the values of the repeated lists
+    let values_data = list_array.values().to_data();
+    let mut values_len = 0_usize;
+    for (row_index, &count) in counts.iter().enumerate() {
+        let len = list_array.value_length(row_index).as_usize();
+        inner_lengths.extend(std::iter::repeat(len).take(count));
+        inner_validity
+            .extend(std::iter::repeat(list_array.is_valid(row_index)).take(count));
+        values_len += len * count;
+    }
+    if O::from_usize(values_len).is_none() {
+        return exec_err!("array_repeat result is too large for the list offsets");
+    }
+
+    let capacity = Capacities::Array(values_len);
+    let mut mutable =
+        MutableArrayData::with_capacities(vec![&values_data], true, capacity);
+    let offsets = list_array.value_offsets();
+    for (row_index, &count) in counts.iter().enumerate() {
+        let start = offsets[row_index].as_usize();
+        let end = offsets[row_index + 1].as_usize();
+        for _ in 0..count {
+            mutable.extend(0, start, end);
+        }
+    }
+    let values = arrow_array::make_array(mutable.freeze());
+
+    let repeated = GenericListArray::<O>::try_new(
+        Arc::new(Field::new_list_field(value_type, true)),
+        OffsetBuffer::<O>::from_lengths(inner_lengths),
+        values,
+        Some(NullBuffer::from(inner_validity)),
+    )?;
+
+    Ok(Arc::new(GenericListArray::<O>::try_new(
+        Arc::new(Field::new_list_field(data_type.to_owned(), true)),
+        OffsetBuffer::<O>::from_lengths(counts),
+        Arc::new(repeated),
+        count_array.nulls().cloned(),
+    )?))
+}
 
-    for (list_array_row, &count) in list_array.iter().zip(count_vec.iter()) {
-        let list_arr = match list_array_row {
-            Some(list_array_row) => {
-                let original_data = list_array_row.to_data();
-                let capacity = Capacities::Array(original_data.len() * count);
-                let mut mutable = MutableArrayData::with_capacities(
-                    vec![&original_data],
-                    false,
-                    capacity,
-                );
+#[cfg(test)]
+mod tests {
+    use super::array_repeat_inner;
+    use arrow::array::{ArrayRef, AsArray, Int64Array, LargeListArray, StringArray};
+    use arrow::datatypes::Int64Type;
+    use std::sync::Arc;
 
-                for _ in 0..count {
-                    mutable.extend(0, 0, original_data.len());
-                }
+    fn counts(counts: Vec<Option<i64>>) -> ArrayRef {
+        Arc::new(Int64Array::from(counts))
+    }
 
-                let data = mutable.freeze();
-                let repeated_array = arrow_array::make_array(data);
+    #[test]
+    fn test_array_repeat_counts() {
+        let element = Arc::new(StringArray::from(vec![
+            Some("a"),
+            Some("b"),
+            None,
+            Some("c"),
+            Some("d"),
+        ])) as ArrayRef;
+        let count = counts(vec![Some(2), Some(0), Some(3), None, Some(-1)]);
 
-                let list_arr = GenericListArray::<O>::try_new(
-                    Arc::new(Field::new_list_field(value_type.clone(), true)),
-                    OffsetBuffer::<O>::from_lengths(vec![original_data.len(); count]),
-                    repeated_array,
-                    None,
-                )?;
-                Arc::new(list_arr) as ArrayRef
-            }
-            None => new_null_array(data_type, count),
-        };
-        new_values.push(list_arr);
+        let result = array_repeat_inner(&[element, count]).unwrap();
+        let result = result.as_list::<i32>();
+        let rows = result
+            .iter()
+            .map(|row| {
+                row.map(|row| {
+                    row.as_string::<i32>()
+                        .iter()
+                        .map(|v| v.map(str::to_string))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                Some(vec![Some("a".to_string()), Some("a".to_string())]),
+                Some(vec![]),
+                Some(vec![None, None, None]),
+                None,
+                Some(vec![]),
+            ]
+        );
     }
 
-    let lengths = new_values.iter().map(|a| a.len()).collect::<Vec<_>>();
-    let new_values: Vec<_> = new_values.iter().map(|a| a.as_ref()).collect();
-    let values = compute::concat(&new_values)?;
+    #[test]
+    fn test_array_repeat_list() {
+        let element = Arc::new(LargeListArray::from_iter_primitive::<Int64Type, _, _>(
+            vec![
+                Some(vec![Some(1), Some(2)]),
+                None,
+                Some(vec![Some(3)]),
+                Some(vec![Some(4)]),
+            ],
+        )) as ArrayRef;
+        let count = counts(vec![Some(2), Some(2), Some(0), None]);
 
-    Ok(Arc::new(ListArray::try_new(
-        Arc::new(Field::new_list_field(data_type.to_owned(), true)),
-        OffsetBuffer::<i32>::from_lengths(lengths),
-        values,
-        None,
-    )?))
+        let result = array_repeat_inner(&[element, count]).unwrap();
+        let result = result.as_list::<i64>();
+        let rows = result
+            .iter()
+            .map(|row| {
+                row.map(|row| {
+                    row.as_list::<i64>()
+                        .iter()
+                        .map(|list| {
+                            list.map(|list| {
+                                list.as_primitive::<Int64Type>().values().to_vec()
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                Some(vec![Some(vec![1, 2]), Some(vec![1, 2])]),
+                Some(vec![None, None]),
+                Some(vec![]),
+                None,
+            ]
+        );
+    }
 }
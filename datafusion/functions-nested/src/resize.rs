@@ -174,12 +174,13 @@ fn general_list_resize<O: OffsetSizeTrait + TryInto<i64>>(
     let values = array.values();
     let original_data = values.to_data();
 
-    // create default element array
+    // create default element array, with the default element of each row
+    // at the row's index
     let default_element = if let Some(default_element) = default_element {
         default_element
     } else {
         let null_scalar = ScalarValue::try_from(&data_type)?;
-        null_scalar.to_array_of_size(original_data.len())?
+        null_scalar.to_array_of_size(array.len())?
     };
     let default_value_data = default_element.to_data();
 
@@ -15,16 +15,20 @@
 // specific language governing permissions and limitations
 // under the License.
 
-//! [`ScalarUDFImpl`] definitions for array_union, array_intersect and array_distinct functions.
+//! [`ScalarUDFImpl`] definitions for array_union, array_intersect, array_distinct
+//! and array_distinct_count functions.
 
 use crate::make_array::{empty_array_type, make_array_inner};
 use crate::utils::make_scalar_function;
-use arrow::array::{new_empty_array, Array, ArrayRef, GenericListArray, OffsetSizeTrait};
-use arrow::buffer::OffsetBuffer;
+use arrow::array::{
+    new_empty_array, new_null_array, Array, ArrayRef, GenericListArray, OffsetSizeTrait,
+    UInt64Array,
+};
+use arrow::buffer::{NullBuffer, OffsetBuffer};
 use arrow::compute;
 use arrow::datatypes::{DataType, Field, FieldRef};
-use arrow::row::{RowConverter, SortField};
-use arrow_schema::DataType::{FixedSizeList, LargeList, List, Null};
+use arrow::row::{Row, RowConverter, Rows, SortField};
+use arrow_schema::DataType::{FixedSizeList, LargeList, List, Null, UInt64};
 use datafusion_common::cast::{as_large_list_array, as_list_array};
 use datafusion_common::{exec_err, internal_err, Result};
 use datafusion_expr::{
@@ -62,6 +66,14 @@ make_udf_expr_and_func!(
     array_distinct_udf
 );
 
+make_udf_expr_and_func!(
+    ArrayDistinctCount,
+    array_distinct_count,
+    array,
+    "returns the number of distinct values in the array.",
+    array_distinct_count_udf
+);
+
 #[user_doc(
     doc_section(label = "Array Functions"),
     description = "Returns an array of elements that are present in both arrays (all elements from both arrays) with out duplicates.",
@@ -299,6 +311,77 @@ impl ScalarUDFImpl for ArrayDistinct {
     }
 }
 
+#[user_doc(
+    doc_section(label = "Array Functions"),
+    description = "Returns the number of distinct values in the array. Multiple null elements are counted as a single distinct value.",
+    syntax_example = "array_distinct_count(array)",
+    sql_example = r#"```sql
+> select array_distinct_count([1, 3, 2, 3, 1, 2, 4]);
++---------------------------------------------+
+| array_distinct_count(List([1,3,2,3,1,2,4])) |
++---------------------------------------------+
+| 4                                           |
++---------------------------------------------+
+```"#,
+    argument(
+        name = "array",
+        description = "Array expression. Can be a constant, column, or function, and any combination of array operators."
+    )
+)]
+#[derive(Debug)]
+pub(super) struct ArrayDistinctCount {
+    signature: Signature,
+    aliases: Vec<String>,
+}
+
+impl ArrayDistinctCount {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::array(Volatility::Immutable),
+            aliases: vec!["list_distinct_count".to_string()],
+        }
+    }
+}
+
+impl ScalarUDFImpl for ArrayDistinctCount {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "array_distinct_count"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        match &arg_types[0] {
+            List(_) | LargeList(_) | FixedSizeList(_, _) | Null => Ok(UInt64),
+            _ => exec_err!(
+                "Not reachable, data_type should be List, LargeList or FixedSizeList"
+            ),
+        }
+    }
+
+    fn invoke_batch(
+        &self,
+        args: &[ColumnarValue],
+        _number_rows: usize,
+    ) -> Result<ColumnarValue> {
+        make_scalar_function(array_distinct_count_inner)(args)
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    fn documentation(&self) -> Option<&Documentation> {
+        self.doc()
+    }
+}
+
 /// array_distinct SQL function
 /// example: from list [1, 3, 2, 3, 1, 2, 4] to [1, 2, 3, 4]
 fn array_distinct_inner(args: &[ArrayRef]) -> Result<ArrayRef> {
@@ -325,6 +408,29 @@ fn array_distinct_inner(args: &[ArrayRef]) -> Result<ArrayRef> {
     }
 }
 
+/// array_distinct_count SQL function
+/// example: from list [1, 3, 2, 3, 1, 2, 4] to 4
+fn array_distinct_count_inner(args: &[ArrayRef]) -> Result<ArrayRef> {
+    if args.len() != 1 {
+        return exec_err!("array_distinct_count needs one argument");
+    }
+
+    match args[0].data_type() {
+        Null => Ok(new_null_array(&UInt64, args[0].len())),
+        List(_) => {
+            let array = as_list_array(&args[0])?;
+            general_array_distinct_count(array)
+        }
+        LargeList(_) => {
+            let array = as_large_list_array(&args[0])?;
+            general_array_distinct_count(array)
+        }
+        array_type => {
+            exec_err!("array_distinct_count does not support type '{array_type:?}'")
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum SetOp {
     Union,
@@ -354,10 +460,11 @@ fn generic_set_lists<OffsetSize: OffsetSizeTrait>(
         return general_array_distinct::<OffsetSize>(l, &field);
     }
 
-    // Handle empty array at rhs case
+    // Handle empty arrays at rhs case
     // array_union(arr, []) -> arr;
     // array_intersect(arr, []) -> [];
-    if r.value_length(0).is_zero() {
+    let r_offsets = r.value_offsets();
+    if r.null_count() == 0 && r_offsets.first() == r_offsets.last() {
         if set_op == SetOp::Union {
             return Ok(Arc::new(l.clone()) as ArrayRef);
         } else {
@@ -374,54 +481,67 @@ fn generic_set_lists<OffsetSize: OffsetSizeTrait>(
     let mut offsets = vec![OffsetSize::usize_as(0)];
     let mut new_arrays = vec![];
 
-    let converter = RowConverter::new(vec![SortField::new(dt)])?;
+    let converter = RowConverter::new(vec![SortField::new(dt.clone())])?;
     for (first_arr, second_arr) in l.iter().zip(r.iter()) {
-        if let (Some(first_arr), Some(second_arr)) = (first_arr, second_arr) {
-            let l_values = converter.convert_columns(&[first_arr])?;
-            let r_values = converter.convert_columns(&[second_arr])?;
-
-            let l_iter = l_values.iter().sorted().dedup();
-            let values_set: HashSet<_> = l_iter.clone().collect();
-            let mut rows = if set_op == SetOp::Union {
-                l_iter.collect::<Vec<_>>()
-            } else {
-                vec![]
-            };
-            for r_val in r_values.iter().sorted().dedup() {
-                match set_op {
-                    SetOp::Union => {
-                        if !values_set.contains(&r_val) {
-                            rows.push(r_val);
-                        }
+        // A null list is treated as an empty set, the result is only null
+        // if both lists are null
+        let first_arr = first_arr.unwrap_or_else(|| new_empty_array(&dt));
+        let second_arr = second_arr.unwrap_or_else(|| new_empty_array(&dt));
+
+        let last_offset = match offsets.last().copied() {
+            Some(offset) => offset,
+            None => return internal_err!("offsets should not be empty"),
+        };
+
+        let l_values = converter.convert_columns(&[first_arr])?;
+        let r_values = converter.convert_columns(&[second_arr])?;
+
+        let l_iter = l_values.iter().sorted().dedup();
+        let values_set: HashSet<_> = l_iter.clone().collect();
+        let mut rows = if set_op == SetOp::Union {
+            l_iter.collect::<Vec<_>>()
+        } else {
+            vec![]
+        };
+        for r_val in r_values.iter().sorted().dedup() {
+            match set_op {
+                SetOp::Union => {
+                    if !values_set.contains(&r_val) {
+                        rows.push(r_val);
                     }
-                    SetOp::Intersect => {
-                        if values_set.contains(&r_val) {
-                            rows.push(r_val);
-                        }
+                }
+                SetOp::Intersect => {
+                    if values_set.contains(&r_val) {
+                        rows.push(r_val);
                     }
                 }
             }
-
-            let last_offset = match offsets.last().copied() {
-                Some(offset) => offset,
-                None => return internal_err!("offsets should not be empty"),
-            };
-            offsets.push(last_offset + OffsetSize::usize_as(rows.len()));
-            let arrays = converter.convert_rows(rows)?;
-            let array = match arrays.first() {
-                Some(array) => Arc::clone(array),
-                None => {
-                    return internal_err!("{set_op}: failed to get array from rows");
-                }
-            };
-            new_arrays.push(array);
         }
+
+        offsets.push(last_offset + OffsetSize::usize_as(rows.len()));
+        let arrays = converter.convert_rows(rows)?;
+        let array = match arrays.first() {
+            Some(array) => Arc::clone(array),
+            None => {
+                return internal_err!("{set_op}: failed to get array from rows");
+            }
+        };
+        new_arrays.push(array);
     }
 
     let offsets = OffsetBuffer::new(offsets.into());
     let new_arrays_ref = new_arrays.iter().map(|v| v.as_ref()).collect::<Vec<_>>();
-    let values = compute::concat(&new_arrays_ref)?;
-    let arr = GenericListArray::<OffsetSize>::try_new(field, offsets, values, None)?;
+    let values = if new_arrays_ref.is_empty() {
+        new_empty_array(&dt)
+    } else {
+        compute::concat(&new_arrays_ref)?
+    };
+    let nulls = (l.null_count() > 0 && r.null_count() > 0).then(|| {
+        (0..l.len())
+            .map(|i| l.is_valid(i) || r.is_valid(i))
+            .collect::<NullBuffer>()
+    });
+    let arr = GenericListArray::<OffsetSize>::try_new(field, offsets, values, nulls)?;
     Ok(Arc::new(arr))
 }
 
@@ -503,6 +623,15 @@ fn array_intersect_inner(args: &[ArrayRef]) -> Result<ArrayRef> {
     general_set_op(array1, array2, SetOp::Intersect)
 }
 
+/// Returns the distinct `values` of a single list, in sorted order.
+///
+/// Null elements are converted to the same row, so that multiple nulls
+/// collapse to a single distinct value
+fn distinct_rows(values: &Rows) -> impl Iterator<Item = Row<'_>> + '_ {
+    // sort elements in list and remove duplicates
+    values.iter().sorted().dedup()
+}
+
 fn general_array_distinct<OffsetSize: OffsetSizeTrait>(
     array: &GenericListArray<OffsetSize>,
     field: &FieldRef,
@@ -524,8 +653,7 @@ fn general_array_distinct<OffsetSize: OffsetSizeTrait>(
             continue;
         };
         let values = converter.convert_columns(&[arr])?;
-        // sort elements in list and remove duplicates
-        let rows = values.iter().sorted().dedup().collect::<Vec<_>>();
+        let rows = distinct_rows(&values).collect::<Vec<_>>();
         offsets.push(last_offset + OffsetSize::usize_as(rows.len()));
         let arrays = converter.convert_rows(rows)?;
         let array = match arrays.first() {
@@ -547,3 +675,215 @@ fn general_array_distinct<OffsetSize: OffsetSizeTrait>(
         array.nulls().cloned(),
     )?))
 }
+
+fn general_array_distinct_count<OffsetSize: OffsetSizeTrait>(
+    array: &GenericListArray<OffsetSize>,
+) -> Result<ArrayRef> {
+    let converter = RowConverter::new(vec![SortField::new(array.value_type())])?;
+    // count the distinct values of each list in ListArray, keeping the
+    // list nulls
+    let counts = array
+        .iter()
+        .map(|arr| {
+            arr.map(|arr| {
+                let values = converter.convert_columns(&[arr])?;
+                Ok(distinct_rows(&values).count() as u64)
+            })
+            .transpose()
+        })
+        .collect::<Result<UInt64Array>>()?;
+    Ok(Arc::new(counts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow::array::{Int32Builder, LargeListArray, ListArray, ListBuilder};
+    use arrow::datatypes::Int32Type;
+
+    fn list(rows: Vec<Option<Vec<Option<i32>>>>) -> ArrayRef {
+        Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(rows))
+    }
+
+    fn large_list(rows: Vec<Option<Vec<Option<i32>>>>) -> ArrayRef {
+        Arc::new(LargeListArray::from_iter_primitive::<Int32Type, _, _>(rows))
+    }
+
+    /// Checks `array_union` and `array_intersect` of `l` and `r` for both
+    /// `List` and `LargeList`
+    fn check_set_ops(
+        l: Vec<Option<Vec<Option<i32>>>>,
+        r: Vec<Option<Vec<Option<i32>>>>,
+        union: Vec<Option<Vec<Option<i32>>>>,
+        intersect: Vec<Option<Vec<Option<i32>>>>,
+    ) {
+        let args = [list(l.clone()), list(r.clone())];
+        assert_eq!(&array_union_inner(&args).unwrap(), &list(union.clone()));
+        assert_eq!(
+            &array_intersect_inner(&args).unwrap(),
+            &list(intersect.clone())
+        );
+
+        let args = [large_list(l), large_list(r)];
+        assert_eq!(&array_union_inner(&args).unwrap(), &large_list(union));
+        assert_eq!(
+            &array_intersect_inner(&args).unwrap(),
+            &large_list(intersect)
+        );
+    }
+
+    #[test]
+    fn test_set_ops_overlapping() {
+        check_set_ops(
+            vec![Some(vec![Some(3), Some(1), Some(2), Some(2)])],
+            vec![Some(vec![Some(4), Some(2), Some(3)])],
+            vec![Some(vec![Some(1), Some(2), Some(3), Some(4)])],
+            vec![Some(vec![Some(2), Some(3)])],
+        );
+    }
+
+    #[test]
+    fn test_set_ops_disjoint() {
+        check_set_ops(
+            vec![Some(vec![Some(1), Some(2)]), Some(vec![Some(5)])],
+            vec![Some(vec![Some(3), Some(4)]), Some(vec![Some(6)])],
+            vec![
+                Some(vec![Some(1), Some(2), Some(3), Some(4)]),
+                Some(vec![Some(5), Some(6)]),
+            ],
+            vec![Some(vec![]), Some(vec![])],
+        );
+    }
+
+    #[test]
+    fn test_set_ops_nulls() {
+        // null elements are equal to each other
+        check_set_ops(
+            vec![Some(vec![Some(1), None, None])],
+            vec![Some(vec![None, Some(2)])],
+            vec![Some(vec![None, Some(1), Some(2)])],
+            vec![Some(vec![None])],
+        );
+        // a null list is treated as an empty set, and an empty list only
+        // applies to its own row
+        check_set_ops(
+            vec![
+                Some(vec![Some(1), Some(2)]),
+                None,
+                Some(vec![Some(5)]),
+                None,
+            ],
+            vec![
+                Some(vec![Some(2)]),
+                Some(vec![Some(3), Some(3)]),
+                None,
+                None,
+            ],
+            vec![
+                Some(vec![Some(1), Some(2)]),
+                Some(vec![Some(3)]),
+                Some(vec![Some(5)]),
+                None,
+            ],
+            vec![Some(vec![Some(2)]), Some(vec![]), Some(vec![]), None],
+        );
+        check_set_ops(
+            vec![Some(vec![Some(1)]), Some(vec![Some(1), Some(1)])],
+            vec![Some(vec![Some(1)]), Some(vec![])],
+            vec![Some(vec![Some(1)]), Some(vec![Some(1)])],
+            vec![Some(vec![Some(1)]), Some(vec![])],
+        );
+    }
+
+    #[test]
+    fn test_set_ops_nested() {
+        let nested = |rows: Vec<Vec<Vec<i32>>>| {
+            let mut builder = ListBuilder::new(ListBuilder::new(Int32Builder::new()));
+            for row in rows {
+                for inner in row {
+                    builder.values().values().append_slice(&inner);
+                    builder.values().append(true);
+                }
+                builder.append(true);
+            }
+            Arc::new(builder.finish()) as ArrayRef
+        };
+
+        let args = [
+            nested(vec![vec![vec![1, 2], vec![3]]]),
+            nested(vec![vec![vec![3], vec![2, 1], vec![1, 2]]]),
+        ];
+        assert_eq!(
+            &array_union_inner(&args).unwrap(),
+            &nested(vec![vec![vec![1, 2], vec![3], vec![2, 1]]])
+        );
+        assert_eq!(
+            &array_intersect_inner(&args).unwrap(),
+            &nested(vec![vec![vec![1, 2], vec![3]]])
+        );
+    }
+
+    #[test]
+    fn test_array_distinct_count() {
+        let rows = vec![
+            // duplicates
+            Some(vec![Some(1), Some(3), Some(2), Some(3), Some(1)]),
+            // all equal elements
+            Some(vec![Some(7), Some(7), Some(7)]),
+            // multiple nulls collapse to one distinct null
+            Some(vec![None, Some(1), None, Some(1), None]),
+            Some(vec![None, None]),
+            // empty list
+            Some(vec![]),
+            // null list
+            None,
+        ];
+        let expected: ArrayRef = Arc::new(UInt64Array::from(vec![
+            Some(3),
+            Some(1),
+            Some(2),
+            Some(1),
+            Some(0),
+            None,
+        ]));
+        assert_eq!(
+            &array_distinct_count_inner(&[list(rows.clone())]).unwrap(),
+            &expected
+        );
+        assert_eq!(
+            &array_distinct_count_inner(&[large_list(rows)]).unwrap(),
+            &expected
+        );
+    }
+
+    #[test]
+    fn test_array_distinct_count_nested() {
+        let mut builder = ListBuilder::new(ListBuilder::new(Int32Builder::new()));
+        for row in [
+            vec![vec![1, 2], vec![2, 1], vec![1, 2]],
+            vec![],
+            vec![vec![]],
+        ] {
+            for inner in row {
+                builder.values().values().append_slice(&inner);
+                builder.values().append(true);
+            }
+            builder.append(true);
+        }
+        builder.values().append_null();
+        builder.values().append_null();
+        builder.append(true);
+        builder.append_null();
+        let array = Arc::new(builder.finish()) as ArrayRef;
+
+        let expected: ArrayRef = Arc::new(UInt64Array::from(vec![
+            Some(2),
+            Some(0),
+            Some(1),
+            Some(1),
+            None,
+        ]));
+        assert_eq!(&array_distinct_count_inner(&[array]).unwrap(), &expected);
+    }
+}
@@ -38,6 +38,7 @@ pub mod utils {
 use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use datafusion_common::ScalarValue;
 use datafusion_common::{internal_err, not_impl_err, Result};
+use datafusion_expr::utils::format_state_name;
 use datafusion_expr::AggregateUDF;
 use datafusion_expr::ReversedUDAF;
 use datafusion_expr_common::accumulator::Accumulator;
@@ -277,7 +278,12 @@ impl AggregateFunctionExpr {
         self.fun.accumulator(acc_args)
     }
 
-    /// the field of the final result of this aggregation.
+    /// the fields of the intermediate state of this aggregation.
+    ///
+    /// The names of the fields are prefixed with the name of this
+    /// aggregation, see [`format_state_name`], unless the function already
+    /// did so. This keeps the state columns of different aggregations
+    /// apart, even if their functions use the same state field names.
     pub fn state_fields(&self) -> Result<Vec<Field>> {
         let args = StateFieldsArgs {
             name: &self.name,
@@ -287,7 +293,21 @@ impl AggregateFunctionExpr {
             is_distinct: self.is_distinct,
         };
 
-        self.fun.state_fields(args)
+        // the prefix added by `format_state_name`
+        let prefix = format!("{}[", self.name);
+        Ok(self
+            .fun
+            .state_fields(args)?
+            .into_iter()
+            .map(|field| {
+                if field.name().starts_with(&prefix) {
+                    field
+                } else {
+                    let name = format_state_name(&self.name, field.name());
+                    field.with_name(name)
+                }
+            })
+            .collect())
     }
 
     /// Order by requirements for the aggregate function
@@ -82,6 +82,30 @@ impl ProjectionMapping {
             .map(|map| Self { map })
     }
 
+    /// Returns true if this mapping is the identity for `input_schema`, that
+    /// is each column of `input_schema` is mapped, in order, to a target
+    /// column with the same name and index.
+    ///
+    /// A projection with such a mapping does not change its input, so it
+    /// can be removed. For example, if the input schema is `a, b`,
+    /// `SELECT a, b` is the identity, but `SELECT b, a`, `SELECT a + 1, b`
+    /// and `SELECT a AS c, b` are not.
+    pub fn is_identity(&self, input_schema: &SchemaRef) -> bool {
+        self.map.len() == input_schema.fields().len()
+            && self.map.iter().enumerate().all(|(idx, (source, target))| {
+                let (Some(source), Some(target)) = (
+                    source.as_any().downcast_ref::<Column>(),
+                    target.as_any().downcast_ref::<Column>(),
+                ) else {
+                    return false;
+                };
+                source.index() == idx
+                    && target.index() == idx
+                    && source.name() == input_schema.field(idx).name()
+                    && target.name() == source.name()
+            })
+    }
+
     /// Constructs a subset mapping using the provided indices.
     ///
     /// This is used when the output is a subset of the input without any
@@ -151,6 +175,50 @@ mod tests {
     use datafusion_common::DFSchema;
     use datafusion_expr::{Operator, ScalarUDF};
 
+    #[test]
+    fn is_identity() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        let col_a = col("a", &schema)?;
+        let col_b = col("b", &schema)?;
+        let mapping = |exprs: Vec<(&Arc<dyn PhysicalExpr>, &str)>| {
+            let exprs: Vec<_> = exprs
+                .into_iter()
+                .map(|(expr, name)| (Arc::clone(expr), name.to_string()))
+                .collect();
+            ProjectionMapping::try_new(&exprs, &schema)
+        };
+
+        // SELECT a, b
+        let identity = mapping(vec![(&col_a, "a"), (&col_b, "b")])?;
+        assert!(identity.is_identity(&schema));
+        assert!(ProjectionMapping::from_indices(&[0, 1], &schema)?.is_identity(&schema));
+
+        // SELECT b, a
+        let reordered = mapping(vec![(&col_b, "b"), (&col_a, "a")])?;
+        assert!(!reordered.is_identity(&schema));
+
+        // SELECT a AS c, b
+        let renamed = mapping(vec![(&col_a, "c"), (&col_b, "b")])?;
+        assert!(!renamed.is_identity(&schema));
+
+        // SELECT a
+        let subset = ProjectionMapping::from_indices(&[0], &schema)?;
+        assert!(!subset.is_identity(&schema));
+
+        // SELECT a + b AS a, b
+        let a_plus_b = Arc::new(BinaryExpr::new(
+            Arc::clone(&col_a),
+            Operator::Plus,
+            Arc::clone(&col_b),
+        )) as Arc<dyn PhysicalExpr>;
+        let computed = mapping(vec![(&a_plus_b, "a"), (&col_b, "b")])?;
+        assert!(!computed.is_identity(&schema));
+        Ok(())
+    }
+
     #[test]
     fn project_orderings() -> Result<()> {
         let schema = Arc::new(Schema::new(vec![
@@ -602,6 +670,34 @@ mod tests {
                     vec![("c_new", option_asc), ("b_new", option_desc)],
                 ],
             ),
+            // ------- TEST CASE 17 ----------
+            (
+                // orderings
+                vec![
+                    // [a ASC, b ASC]
+                    vec![(col_a, option_asc), (col_b, option_asc)],
+                    // [c ASC, d DESC]
+                    vec![(col_c, option_asc), (col_d, option_desc)],
+                    // [d ASC, e ASC]
+                    vec![(col_d, option_asc), (col_e, option_asc)],
+                ],
+                // proj exprs
+                vec![
+                    (col_a, "a_new".to_string()),
+                    (col_b, "b_new".to_string()),
+                    (col_c, "c_new".to_string()),
+                    (col_e, "e_new".to_string()),
+                ],
+                // expected
+                vec![
+                    // [a_new ASC, b_new ASC], fully covered by the projection
+                    vec![("a_new", option_asc), ("b_new", option_asc)],
+                    // [c_new ASC], truncated at the first unprojected expression
+                    vec![("c_new", option_asc)],
+                    // [d ASC, e ASC] does not survive since its leading
+                    // expression is not projected
+                ],
+            ),
         ];
 
         for (idx, (orderings, proj_exprs, expected)) in test_cases.into_iter().enumerate()
@@ -951,17 +951,23 @@ impl EquivalenceProperties {
             if self.is_expr_constant(source)
                 && !const_exprs_contains(&projected_constants, target)
             {
-                if self.is_expr_constant_accross_partitions(source) {
-                    projected_constants.push(
-                        ConstExpr::from(target)
-                            .with_across_partitions(self.get_expr_constant_value(source)),
-                    )
-                } else {
-                    projected_constants.push(
-                        ConstExpr::from(target)
-                            .with_across_partitions(AcrossPartitions::Heterogeneous),
-                    )
-                }
+                let across_partitions =
+                    if self.is_expr_constant_accross_partitions(source) {
+                        // An expression of constants that are the same in every
+                        // partition, such as `a + 1`, is the same in every
+                        // partition too, even if its value is not known
+                        match self.get_expr_constant_value(source) {
+                            AcrossPartitions::Heterogeneous => {
+                                AcrossPartitions::Uniform(None)
+                            }
+                            uniform => uniform,
+                        }
+                    } else {
+                        AcrossPartitions::Heterogeneous
+                    };
+                projected_constants.push(
+                    ConstExpr::from(target).with_across_partitions(across_partitions),
+                )
             }
         }
         projected_constants
@@ -2266,7 +2272,7 @@ mod tests {
         convert_to_orderings, convert_to_sort_exprs, convert_to_sort_reqs,
         create_test_params, create_test_schema, output_schema,
     };
-    use crate::expressions::{col, BinaryExpr, Column};
+    use crate::expressions::{col, BinaryExpr, Column, Literal};
     use crate::ScalarFunctionExpr;
 
     use arrow::datatypes::{DataType, Field, Schema};
@@ -2276,6 +2282,84 @@ mod tests {
 
     use datafusion_functions::string::concat;
 
+    #[test]
+    fn project_equivalence_properties_constants() -> Result<()> {
+        let input_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Int64, true),
+            Field::new("c", DataType::Int64, true),
+            Field::new("d", DataType::Int64, true),
+        ]));
+        let col_a = col("a", &input_schema)?;
+        let col_b = col("b", &input_schema)?;
+        let col_c = col("c", &input_schema)?;
+        let col_d = col("d", &input_schema)?;
+        let five = AcrossPartitions::Uniform(Some(ScalarValue::Int64(Some(5))));
+
+        // b = 5 in every partition, c is constant within each partition and
+        // a = b
+        let mut input_properties = EquivalenceProperties::new(Arc::clone(&input_schema))
+            .with_constants(vec![
+                ConstExpr::from(&col_b).with_across_partitions(five.clone()),
+                ConstExpr::from(&col_c),
+            ]);
+        input_properties.add_equal_conditions(&col_a, &col_b)?;
+
+        // b as b1, b as b2, a as a1, c as c1, b + d as bd, d as d1,
+        // b + 1 as b_plus_1
+        let proj_exprs = vec![
+            (Arc::clone(&col_b), "b1".to_string()),
+            (Arc::clone(&col_b), "b2".to_string()),
+            (Arc::clone(&col_a), "a1".to_string()),
+            (Arc::clone(&col_c), "c1".to_string()),
+            (
+                Arc::new(BinaryExpr::new(
+                    Arc::clone(&col_b),
+                    Operator::Plus,
+                    Arc::clone(&col_d),
+                )) as _,
+                "bd".to_string(),
+            ),
+            (Arc::clone(&col_d), "d1".to_string()),
+            (
+                Arc::new(BinaryExpr::new(
+                    Arc::clone(&col_b),
+                    Operator::Plus,
+                    Arc::new(Literal::new(ScalarValue::Int64(Some(1)))),
+                )) as _,
+                "b_plus_1".to_string(),
+            ),
+        ];
+        let projection_mapping = ProjectionMapping::try_new(&proj_exprs, &input_schema)?;
+        let out_schema = output_schema(&projection_mapping, &input_schema)?;
+        let out_properties =
+            input_properties.project(&projection_mapping, Arc::clone(&out_schema));
+
+        for name in ["b1", "b2", "a1"] {
+            let col = col(name, &out_schema)?;
+            assert!(out_properties.is_expr_constant(&col), "{name}");
+            assert_eq!(out_properties.get_expr_constant_value(&col), five, "{name}");
+        }
+        let col_c1 = col("c1", &out_schema)?;
+        assert!(out_properties.is_expr_constant(&col_c1));
+        assert_eq!(
+            out_properties.get_expr_constant_value(&col_c1),
+            AcrossPartitions::Heterogeneous
+        );
+        // `b + 1` is the same in every partition, but its value is not known
+        let col_b_plus_1 = col("b_plus_1", &out_schema)?;
+        assert!(out_properties.is_expr_constant_accross_partitions(&col_b_plus_1));
+        assert_eq!(
+            out_properties.get_expr_constant_value(&col_b_plus_1),
+            AcrossPartitions::Uniform(None)
+        );
+        for name in ["bd", "d1"] {
+            assert!(!out_properties.is_expr_constant(&col(name, &out_schema)?));
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn project_equivalence_properties_test() -> Result<()> {
         let input_schema = Arc::new(Schema::new(vec![
@@ -18,10 +18,13 @@
 //! [`LiteralGuarantee`] predicate analysis to determine if a column is a
 //! constant.
 
+use crate::expressions::{in_list, lit};
 use crate::utils::split_disjunction;
 use crate::{split_conjunction, PhysicalExpr};
-use datafusion_common::{Column, HashMap, ScalarValue};
+use arrow::datatypes::Schema;
+use datafusion_common::{Column, HashMap, Result, ScalarValue};
 use datafusion_expr::Operator;
+use std::borrow::Cow;
 use std::collections::HashSet;
 use std::fmt::{self, Display, Formatter};
 use std::sync::Arc;
@@ -123,103 +126,81 @@ impl LiteralGuarantee {
             .into_iter()
             // for an `AND` conjunction to be true, all terms individually must be true
             .fold(GuaranteeBuilder::new(), |builder, expr| {
-                if let Some(cel) = ColOpLit::try_new(expr) {
-                    builder.aggregate_conjunct(cel)
-                } else if let Some(inlist) = expr
-                    .as_any()
-                    .downcast_ref::<crate::expressions::InListExpr>()
-                {
-                    // Only support single-column inlist currently, multi-column inlist is not supported
-                    let col = inlist
-                        .expr()
-                        .as_any()
-                        .downcast_ref::<crate::expressions::Column>();
-                    let Some(col) = col else {
-                        return builder;
-                    };
-
-                    let literals = inlist
-                        .list()
-                        .iter()
-                        .map(|e| e.as_any().downcast_ref::<crate::expressions::Literal>())
-                        .collect::<Option<Vec<_>>>();
-                    let Some(literals) = literals else {
-                        return builder;
-                    };
-
-                    let guarantee = if inlist.negated() {
-                        Guarantee::NotIn
-                    } else {
-                        Guarantee::In
-                    };
-
-                    builder.aggregate_multi_conjunct(
-                        col,
-                        guarantee,
-                        literals.iter().map(|e| e.value()),
-                    )
-                } else {
-                    // split disjunction: <expr> OR <expr> OR ...
-                    let disjunctions = split_disjunction(expr);
-
-                    // We are trying to add a guarantee that a column must be
-                    // in/not in a particular set of values for the expression
-                    // to evaluate to true.
-                    //
-                    // A disjunction is true, if at least one of the terms is be
-                    // true.
-                    //
-                    // Thus, we can infer a guarantee if all terms are of the
-                    // form `(col <op> literal) OR (col <op> literal) OR ...`.
-                    //
-                    // For example, we can infer that `a = 1 OR a = 2 OR a = 3`
-                    // is guaranteed to be true ONLY if a is in (`1`, `2` or `3`).
-                    //
-                    // However, for something like  `a = 1 OR a = 2 OR a < 0` we
-                    // **can't** guarantee that the predicate is only true if a
-                    // is in (`1`, `2`), as it could also be true if `a` were less
-                    // than zero.
-                    let terms = disjunctions
-                        .iter()
-                        .filter_map(|expr| ColOpLit::try_new(expr))
-                        .collect::<Vec<_>>();
+                builder.aggregate_expr(expr)
+            })
+            .build()
+    }
 
-                    if terms.is_empty() {
-                        return builder;
-                    }
+    /// Like [`LiteralGuarantee::analyze`], but drops any guarantee with more
+    /// than `max_literals` literals.
+    ///
+    /// Predicates like `a IN (...)` with thousands of literals otherwise
+    /// result in guarantees holding equally large sets, which cost planning
+    /// memory while they rarely help pruning.
+    pub fn analyze_with_max_literals(
+        expr: &Arc<dyn PhysicalExpr>,
+        max_literals: usize,
+    ) -> Vec<LiteralGuarantee> {
+        let builder = GuaranteeBuilder {
+            max_literals: Some(max_literals),
+            ..Default::default()
+        };
+        split_conjunction(expr)
+            .into_iter()
+            .fold(builder, |builder, expr| builder.aggregate_expr(expr))
+            .build()
+    }
 
-                    // if not all terms are of the form (col <op> literal),
-                    // can't infer any guarantees
-                    if terms.len() != disjunctions.len() {
-                        return builder;
-                    }
+    /// Like [`LiteralGuarantee::analyze`], but also returns whether `expr`
+    /// can possibly evaluate to `true`.
+    ///
+    /// The returned `bool` is `false` if the guarantees contradict each
+    /// other, for example `a = 1 AND a = 2` or `a = 1 AND a != 1`. Such a
+    /// predicate is *provably* unsatisfiable: it always evaluates to `false`
+    /// or `null`, so a filter using it can be replaced by an empty relation.
+    ///
+    /// Analysis stops at the first contradiction, so in that case the
+    /// returned guarantees only cover the terms analyzed up to that point.
+    ///
+    /// A `true` result does **not** mean `expr` is satisfiable, only that no
+    /// contradiction was found.
+    pub fn analyze_with_satisfiability(
+        expr: &Arc<dyn PhysicalExpr>,
+    ) -> (Vec<LiteralGuarantee>, bool) {
+        let mut builder = GuaranteeBuilder::new();
+        // split conjunction: <expr> AND <expr> AND ...
+        for expr in split_conjunction(expr) {
+            builder = builder.aggregate_expr(expr);
+            if builder.unsatisfiable {
+                return (builder.build(), false);
+            }
+        }
+        (builder.build(), true)
+    }
 
-                    // if all terms are 'col <op> literal' with the same column
-                    // and operation we can infer any guarantees
-                    //
-                    // For those like (a != foo AND (a != bar OR a != baz)).
-                    // We can't combine the (a != bar OR a != baz) part, but
-                    // it also doesn't invalidate our knowledge that a !=
-                    // foo is required for the expression to be true.
-                    // So we can only create a multi value guarantee for `=`
-                    // (or a single value). (e.g. ignore `a != foo OR a != bar`)
-                    let first_term = &terms[0];
-                    if terms.iter().all(|term| {
-                        term.col.name() == first_term.col.name()
-                            && term.guarantee == Guarantee::In
-                    }) {
-                        builder.aggregate_multi_conjunct(
-                            first_term.col,
-                            Guarantee::In,
-                            terms.iter().map(|term| term.lit.value()),
-                        )
-                    } else {
-                        // can't infer anything
-                        builder
-                    }
-                }
-            })
-            .build()
+    /// Converts this guarantee back into a predicate over `schema`: a
+    /// `column IN (...)` or `column NOT IN (...)` [`InListExpr`].
+    ///
+    /// The column index is resolved from `schema`, and the literals are
+    /// listed in sorted order. Unless there are no literals, in which case a
+    /// boolean literal is returned, [`LiteralGuarantee::analyze`] on the
+    /// result returns a guarantee equal to `self`.
+    ///
+    /// [`InListExpr`]: crate::expressions::InListExpr
+    pub fn to_expr(&self, schema: &Schema) -> Result<Arc<dyn PhysicalExpr>> {
+        let negated = self.guarantee == Guarantee::NotIn;
+        // an empty list never (`IN`) or always (`NOT IN`) matches
+        if self.literals.is_empty() {
+            return Ok(lit(negated));
+        }
+
+        let index = schema.index_of(&self.column.name)?;
+        let column = Arc::new(crate::expressions::Column::new(&self.column.name, index));
+
+        let mut literals: Vec<_> = self.literals.iter().cloned().collect();
+        literals.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let list = literals.into_iter().map(lit).collect();
+        in_list(column, list, &negated, schema)
     }
 }
 
@@ -257,6 +238,15 @@ struct GuaranteeBuilder<'a> {
     /// Key is the (column name, guarantee type)
     /// Value is the index into `guarantees`
     map: HashMap<(&'a crate::expressions::Column, Guarantee), usize>,
+
+    /// Set once the conjuncts seen so far are known to contradict each
+    /// other, e.g. `a = foo AND a = bar`, so the expression can never
+    /// evaluate to `true`
+    unsatisfiable: bool,
+
+    /// Guarantees with more literals than this are dropped, see
+    /// [`LiteralGuarantee::analyze_with_max_literals`]
+    max_literals: Option<usize>,
 }
 
 impl<'a> GuaranteeBuilder<'a> {
@@ -264,6 +254,112 @@ impl<'a> GuaranteeBuilder<'a> {
         Default::default()
     }
 
+    /// Aggregate a new term of an `AND` conjunction to this builder, if
+    /// it offers any guarantees
+    fn aggregate_expr(self, expr: &'a Arc<dyn PhysicalExpr>) -> Self {
+        if let Some(cel) = ColOpLit::try_new(expr) {
+            self.aggregate_conjunct(cel)
+        } else if let Some(inlist) = expr
+            .as_any()
+            .downcast_ref::<crate::expressions::InListExpr>()
+        {
+            // Only support single-column inlist currently, multi-column inlist is not supported
+            let col = inlist
+                .expr()
+                .as_any()
+                .downcast_ref::<crate::expressions::Column>();
+            let Some(col) = col else {
+                return self;
+            };
+
+            let literals = inlist
+                .list()
+                .iter()
+                .map(|e| e.as_any().downcast_ref::<crate::expressions::Literal>())
+                .collect::<Option<Vec<_>>>();
+            let Some(literals) = literals else {
+                return self;
+            };
+
+            let guarantee = if inlist.negated() {
+                Guarantee::NotIn
+            } else {
+                Guarantee::In
+            };
+
+            self.aggregate_multi_conjunct(
+                col,
+                guarantee,
+                literals.iter().map(|e| e.value()),
+            )
+        } else {
+            // split disjunction: <expr> OR <expr> OR ...
+            // Structurally equal terms, like the second `a = 1` in
+            // `a = 1 OR a = 1`, are only considered once
+            let mut seen = HashSet::new();
+            let disjunctions = split_disjunction(expr)
+                .into_iter()
+                .filter(|disjunct| seen.insert(*disjunct))
+                .collect::<Vec<_>>();
+
+            // We are trying to add a guarantee that a column must be
+            // in/not in a particular set of values for the expression
+            // to evaluate to true.
+            //
+            // A disjunction is true, if at least one of the terms is be
+            // true.
+            //
+            // Thus, we can infer a guarantee if all terms are of the
+            // form `(col <op> literal) OR (col <op> literal) OR ...`.
+            //
+            // For example, we can infer that `a = 1 OR a = 2 OR a = 3`
+            // is guaranteed to be true ONLY if a is in (`1`, `2` or `3`).
+            //
+            // However, for something like  `a = 1 OR a = 2 OR a < 0` we
+            // **can't** guarantee that the predicate is only true if a
+            // is in (`1`, `2`), as it could also be true if `a` were less
+            // than zero.
+            let terms = disjunctions
+                .iter()
+                .filter_map(|expr| ColOpLit::try_new(expr))
+                .collect::<Vec<_>>();
+
+            if terms.is_empty() {
+                return self;
+            }
+
+            // if not all terms are of the form (col <op> literal),
+            // can't infer any guarantees
+            if terms.len() != disjunctions.len() {
+                return self;
+            }
+
+            // if all terms are 'col <op> literal' with the same column
+            // and operation we can infer any guarantees
+            //
+            // For those like (a != foo AND (a != bar OR a != baz)).
+            // We can't combine the (a != bar OR a != baz) part, but
+            // it also doesn't invalidate our knowledge that a !=
+            // foo is required for the expression to be true.
+            // So we can only create a multi value guarantee for `=`
+            // (or a single value). (e.g. ignore `a != foo OR a != bar`)
+            let first_term = &terms[0];
+            if terms.iter().all(|term| {
+                term.col.name() == first_term.col.name()
+                    && term.guarantee == Guarantee::In
+            }) {
+                self.aggregate_multi_conjunct(
+                    first_term.col,
+                    Guarantee::In,
+                    terms.iter().map(|term| term.lit.as_ref()),
+                )
+            } else {
+                // can't infer anything
+                self
+            }
+        }
+    }
+
     /// Aggregate a new single `AND col <op> literal` term to this builder
     /// combining with existing guarantees if possible.
     ///
@@ -274,7 +370,7 @@ impl<'a> GuaranteeBuilder<'a> {
         self.aggregate_multi_conjunct(
             col_op_lit.col,
             col_op_lit.guarantee,
-            [col_op_lit.lit.value()],
+            [col_op_lit.lit.as_ref()],
         )
     }
 
@@ -287,16 +383,16 @@ impl<'a> GuaranteeBuilder<'a> {
     /// * `AND (a IN (1,2,3))`: a is in (1, 2, or 3)
     /// * `AND (a != 1 OR a != 2 OR a != 3)`: a is not in (1, 2, or 3)
     /// * `AND (a NOT IN (1,2,3))`: a is not in (1, 2, or 3)
-    fn aggregate_multi_conjunct(
+    fn aggregate_multi_conjunct<'b>(
         mut self,
         col: &'a crate::expressions::Column,
         guarantee: Guarantee,
-        new_values: impl IntoIterator<Item = &'a ScalarValue>,
+        new_values: impl IntoIterator<Item = &'b ScalarValue>,
     ) -> Self {
         let key = (col, guarantee);
-        if let Some(index) = self.map.get(&key) {
+        if let Some(&index) = self.map.get(&key) {
             // already have a guarantee for this column
-            let entry = &mut self.guarantees[*index];
+            let entry = &mut self.guarantees[index];
 
             let Some(existing) = entry else {
                 // determined the previous guarantee for this column has been
@@ -316,6 +412,12 @@ impl<'a> GuaranteeBuilder<'a> {
                 Guarantee::NotIn => {
                     let new_values: HashSet<_> = new_values.into_iter().collect();
                     existing.literals.extend(new_values.into_iter().cloned());
+                    if exceeds_max_literals(self.max_literals, existing.literals.len()) {
+                        // a later conjunct on this column may start a new
+                        // guarantee, as each conjunct must hold on its own
+                        *entry = None;
+                        self.map.remove(&key);
+                    }
                 }
                 Guarantee::In => {
                     let intersection = new_values
@@ -329,14 +431,20 @@ impl<'a> GuaranteeBuilder<'a> {
                     if !intersection.is_empty() {
                         existing.literals = intersection.into_iter().cloned().collect();
                     } else {
-                        // at least one was not, so invalidate the guarantee
+                        // at least one was not, so invalidate the guarantee.
+                        // The column can not take any value, so the
+                        // expression can never be true
                         *entry = None;
+                        self.unsatisfiable = true;
                     }
                 }
             }
         } else {
             // This is a new guarantee
             let new_values: HashSet<_> = new_values.into_iter().collect();
+            if exceeds_max_literals(self.max_literals, new_values.len()) {
+                return self;
+            }
 
             let guarantee = LiteralGuarantee::new(col.name(), guarantee, new_values);
             // add it to the list of guarantees
@@ -344,9 +452,30 @@ impl<'a> GuaranteeBuilder<'a> {
             self.map.insert(key, self.guarantees.len() - 1);
         }
 
+        self.check_in_not_in(col);
         self
     }
 
+    /// Marks this builder as unsatisfiable if every value allowed by the
+    /// `In` guarantee for `col` is excluded by its `NotIn` guarantee, e.g.
+    /// `a IN (1, 2) AND a != 1 AND a != 2`
+    fn check_in_not_in(&mut self, col: &'a crate::expressions::Column) {
+        let (Some(&in_index), Some(&not_in_index)) = (
+            self.map.get(&(col, Guarantee::In)),
+            self.map.get(&(col, Guarantee::NotIn)),
+        ) else {
+            return;
+        };
+
+        if let (Some(in_guarantee), Some(not_in_guarantee)) =
+            (&self.guarantees[in_index], &self.guarantees[not_in_index])
+        {
+            if in_guarantee.literals.is_subset(&not_in_guarantee.literals) {
+                self.unsatisfiable = true;
+            }
+        }
+    }
+
     /// Return all guarantees that have been created so far
     fn build(self) -> Vec<LiteralGuarantee> {
         // filter out any guarantees that have been invalidated
@@ -354,11 +483,17 @@ impl<'a> GuaranteeBuilder<'a> {
     }
 }
 
+/// Returns true if a guarantee with `num_literals` literals is too large to
+/// keep with a cap of `max_literals`
+fn exceeds_max_literals(max_literals: Option<usize>, num_literals: usize) -> bool {
+    max_literals.is_some_and(|max| num_literals > max)
+}
+
 /// Represents a single `col [not]in literal` expression
 struct ColOpLit<'a> {
     col: &'a crate::expressions::Column,
     guarantee: Guarantee,
-    lit: &'a crate::expressions::Literal,
+    lit: Cow<'a, ScalarValue>,
 }
 
 impl<'a> ColOpLit<'a> {
@@ -367,54 +502,136 @@ impl<'a> ColOpLit<'a> {
     /// 2. `literal <op> col`
     /// 3. operator is `=` or `!=`
     ///
+    /// `col` may also be an invertible transform of a column, such as the
+    /// `col + 1` in `col + 1 = 5`, see [`invert_transform`]
+    ///
     /// Returns None otherwise
     fn try_new(expr: &'a Arc<dyn PhysicalExpr>) -> Option<Self> {
         let binary_expr = expr
             .as_any()
             .downcast_ref::<crate::expressions::BinaryExpr>()?;
 
-        let (left, op, right) = (
-            binary_expr.left().as_any(),
-            binary_expr.op(),
-            binary_expr.right().as_any(),
-        );
+        let (left, op, right) =
+            (binary_expr.left(), binary_expr.op(), binary_expr.right());
         let guarantee = match op {
             Operator::Eq => Guarantee::In,
             Operator::NotEq => Guarantee::NotIn,
             _ => return None,
         };
         // col <op> literal
-        if let (Some(col), Some(lit)) = (
-            left.downcast_ref::<crate::expressions::Column>(),
-            right.downcast_ref::<crate::expressions::Literal>(),
-        ) {
+        let (col_expr, lit) = if let Some(lit) =
+            right.as_any().downcast_ref::<crate::expressions::Literal>()
+        {
+            (left, lit.value())
+        }
+        // literal <op> col
+        else if let Some(lit) =
+            left.as_any().downcast_ref::<crate::expressions::Literal>()
+        {
+            (right, lit.value())
+        } else {
+            return None;
+        };
+
+        if let Some(col) = col_expr
+            .as_any()
+            .downcast_ref::<crate::expressions::Column>()
+        {
             Some(Self {
                 col,
                 guarantee,
-                lit,
+                lit: Cow::Borrowed(lit),
             })
-        }
-        // literal <op> col
-        else if let (Some(lit), Some(col)) = (
-            left.downcast_ref::<crate::expressions::Literal>(),
-            right.downcast_ref::<crate::expressions::Column>(),
-        ) {
+        } else {
+            let (col, lit) = invert_transform(col_expr, lit)?;
             Some(Self {
                 col,
                 guarantee,
-                lit,
+                lit: Cow::Owned(lit),
             })
-        } else {
-            None
         }
     }
 }
 
+/// If `expr` is `col + literal`, `literal + col`, `col - literal`,
+/// `literal - col` or `col * literal` (or `literal * col`), returns the column
+/// and the only column value for which `expr` evaluates to `value`.
+///
+/// Only integers are inverted. As their arithmetic wraps around on overflow,
+/// adding or subtracting a literal maps every column value to a distinct
+/// result. Multiplication is only inverted by odd literals, since wrapping
+/// multiplication by an even literal maps several values to the same result
+/// (for `Int8`, both `2 * 2` and `-126 * 2` are `4`). Floating point
+/// arithmetic rounds, so is never inverted.
+///
+/// Returns None otherwise
+fn invert_transform<'a>(
+    expr: &'a Arc<dyn PhysicalExpr>,
+    value: &ScalarValue,
+) -> Option<(&'a crate::expressions::Column, ScalarValue)> {
+    let binary_expr = expr
+        .as_any()
+        .downcast_ref::<crate::expressions::BinaryExpr>()?;
+
+    let (left, op, right) = (
+        binary_expr.left().as_any(),
+        binary_expr.op(),
+        binary_expr.right().as_any(),
+    );
+    // col <op> literal
+    let (col, lit, col_first) = if let (Some(col), Some(lit)) = (
+        left.downcast_ref::<crate::expressions::Column>(),
+        right.downcast_ref::<crate::expressions::Literal>(),
+    ) {
+        (col, lit.value(), true)
+    }
+    // literal <op> col
+    else if let (Some(lit), Some(col)) = (
+        left.downcast_ref::<crate::expressions::Literal>(),
+        right.downcast_ref::<crate::expressions::Column>(),
+    ) {
+        (col, lit.value(), false)
+    } else {
+        return None;
+    };
+
+    let data_type = value.data_type();
+    if !data_type.is_integer()
+        || lit.data_type() != data_type
+        || lit.is_null()
+        || value.is_null()
+    {
+        return None;
+    }
+
+    let inverted = match op {
+        Operator::Plus => value.sub(lit),
+        Operator::Minus if col_first => value.add(lit),
+        Operator::Minus => lit.sub(value),
+        Operator::Multiply => {
+            let zero = ScalarValue::new_zero(&data_type).ok()?;
+            let one = ScalarValue::new_one(&data_type).ok()?;
+            if lit.rem(one.add(&one).ok()?).ok()? == zero {
+                return None;
+            }
+            // an odd literal has a single preimage, which is only
+            // `value / lit` if the division is exact
+            if value.rem(lit).ok()? != zero {
+                return None;
+            }
+            value.div(lit)
+        }
+        _ => return None,
+    };
+    Some((col, inverted.ok()?))
+}
+
 #[cfg(test)]
 mod test {
     use std::sync::LazyLock;
 
     use super::*;
+    use crate::expressions::{InListExpr, Literal};
     use crate::planner::logical2physical;
 
     use arrow_schema::{DataType, Field, Schema, SchemaRef};
@@ -447,6 +664,44 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_transformed_column() {
+        // b + 1 = 5
+        test_analyze((col("b") + lit(1)).eq(lit(5)), vec![in_guarantee("b", [4])]);
+        // 5 != 1 + b
+        test_analyze(
+            lit(5).not_eq(lit(1) + col("b")),
+            vec![not_in_guarantee("b", [4])],
+        );
+        // b - 1 = 5
+        test_analyze((col("b") - lit(1)).eq(lit(5)), vec![in_guarantee("b", [6])]);
+        // 1 - b = 5
+        test_analyze(
+            (lit(1) - col("b")).eq(lit(5)),
+            vec![in_guarantee("b", [-4])],
+        );
+        // b * 3 = 12
+        test_analyze(
+            (col("b") * lit(3)).eq(lit(12)),
+            vec![in_guarantee("b", [4])],
+        );
+        // b + 1 = 5 OR b * -1 = 5
+        test_analyze(
+            (col("b") + lit(1))
+                .eq(lit(5))
+                .or((col("b") * lit(-1)).eq(lit(5))),
+            vec![in_guarantee("b", [4, -5])],
+        );
+        // b * 0 = 0 holds for every b
+        test_analyze((col("b") * lit(0)).eq(lit(0)), vec![]);
+        // b * 2 = 4 also holds when b wraps around
+        test_analyze((col("b") * lit(2)).eq(lit(4)), vec![]);
+        // b * 3 = 5 only holds when b wraps around
+        test_analyze((col("b") * lit(3)).eq(lit(5)), vec![]);
+        // b / 2 = 2 is not invertible
+        test_analyze((col("b") / lit(2)).eq(lit(2)), vec![]);
+    }
+
     #[test]
     fn test_conjunction_single_column() {
         // b = 1 AND b = 2. This is impossible. Ideally this expression could be simplified to false
@@ -629,6 +884,30 @@ mod test {
                 .or(col("b").eq(lit(3))),
             vec![in_guarantee("b", [1, 2, 3])],
         );
+        // b = 1 OR b = 1 OR b = 2
+        test_analyze(
+            col("b")
+                .eq(lit(1))
+                .or(col("b").eq(lit(1)))
+                .or(col("b").eq(lit(2))),
+            vec![in_guarantee("b", [1, 2])],
+        );
+        // b = 1 OR b = 1 OR b > 3 -- the duplicate does not hide the last term
+        test_analyze(
+            col("b")
+                .eq(lit(1))
+                .or(col("b").eq(lit(1)))
+                .or(col("b").gt(lit(3))),
+            vec![],
+        );
+        // b = 0 OR b = 0 OR b = 1 OR b = 1 OR ... OR b = 9 OR b = 9
+        test_analyze(
+            (0..20)
+                .map(|i| col("b").eq(lit(i / 2)))
+                .reduce(Expr::or)
+                .unwrap(),
+            vec![in_guarantee("b", 0..10)],
+        );
         // b = 1 OR b = 2 OR b > 3 -- can't guarantee that the expression is only true if a is in (1, 2)
         test_analyze(
             col("b")
@@ -871,4 +1150,151 @@ mod test {
         });
         Arc::clone(&SCHEMA)
     }
+
+    #[test]
+    fn test_to_expr_round_trip() {
+        let schema = schema();
+        let guarantees = [
+            in_guarantee("a", ["foo"]),
+            in_guarantee("a", ["foo", "bar", "baz"]),
+            not_in_guarantee("a", ["foo", "bar"]),
+            in_guarantee("b", [3, 1, 2]),
+            not_in_guarantee("b", [1]),
+        ];
+        for guarantee in guarantees {
+            let expr = guarantee.to_expr(&schema).unwrap();
+            assert_eq!(
+                LiteralGuarantee::analyze(&expr),
+                vec![guarantee.clone()],
+                "guarantee: {guarantee}, expr: {expr}"
+            );
+        }
+
+        // literals are listed in order
+        let expr = in_guarantee("b", [3, 1, 2]).to_expr(&schema).unwrap();
+        let inlist = expr.as_any().downcast_ref::<InListExpr>().unwrap();
+        let values: Vec<_> = inlist
+            .list()
+            .iter()
+            .map(|e| {
+                e.as_any()
+                    .downcast_ref::<Literal>()
+                    .unwrap()
+                    .value()
+                    .clone()
+            })
+            .collect();
+        assert_eq!(values, [1, 2, 3].map(ScalarValue::from));
+        assert!(!inlist.negated());
+
+        // unknown column
+        let err = in_guarantee("c", [1]).to_expr(&schema).unwrap_err();
+        assert!(err.to_string().contains("Unable to get field named \"c\""));
+    }
+
+    #[test]
+    fn test_satisfiability() {
+        // a = "foo" AND b = 1
+        test_analyze_with_satisfiability(
+            col("a").eq(lit("foo")).and(col("b").eq(lit(1))),
+            vec![in_guarantee("a", ["foo"]), in_guarantee("b", [1])],
+            true,
+        );
+        // a = "foo" AND a != "bar"
+        test_analyze_with_satisfiability(
+            col("a").eq(lit("foo")).and(col("a").not_eq(lit("bar"))),
+            vec![in_guarantee("a", ["foo"]), not_in_guarantee("a", ["bar"])],
+            true,
+        );
+        // a = "foo" AND a = "bar" AND b = 1: stops before b = 1
+        test_analyze_with_satisfiability(
+            col("a")
+                .eq(lit("foo"))
+                .and(col("a").eq(lit("bar")))
+                .and(col("b").eq(lit(1))),
+            vec![],
+            false,
+        );
+        // b = 1 AND b != 1
+        test_analyze_with_satisfiability(
+            col("b").eq(lit(1)).and(col("b").not_eq(lit(1))),
+            vec![in_guarantee("b", [1]), not_in_guarantee("b", [1])],
+            false,
+        );
+        // b IN (1, 2) AND b NOT IN (1, 2, 3)
+        test_analyze_with_satisfiability(
+            col("b")
+                .in_list(vec![lit(1), lit(2)], false)
+                .and(col("b").in_list(vec![lit(1), lit(2), lit(3)], true)),
+            vec![in_guarantee("b", [1, 2]), not_in_guarantee("b", [1, 2, 3])],
+            false,
+        );
+        // (b = 1 OR b = 2) AND b != 1 is still satisfiable with b = 2
+        test_analyze_with_satisfiability(
+            (col("b").eq(lit(1)).or(col("b").eq(lit(2)))).and(col("b").not_eq(lit(1))),
+            vec![in_guarantee("b", [1, 2]), not_in_guarantee("b", [1])],
+            true,
+        );
+    }
+
+    #[test]
+    fn test_max_literals() {
+        let schema = schema();
+        let analyze = |expr: Expr, max_literals| {
+            let physical_expr = logical2physical(&expr, &schema);
+            LiteralGuarantee::analyze_with_max_literals(&physical_expr, max_literals)
+        };
+        let list = |n: i32| (0..n).map(lit).collect::<Vec<_>>();
+
+        // b IN (0, ..., 99)
+        assert_eq!(
+            analyze(col("b").in_list(list(100), false), 100),
+            vec![in_guarantee("b", 0..100)]
+        );
+        // b IN (0, ..., 100)
+        assert_eq!(analyze(col("b").in_list(list(101), false), 100), vec![]);
+        // duplicated literals only count once
+        assert_eq!(
+            analyze(col("b").in_list([list(2), list(2)].concat(), false), 2),
+            vec![in_guarantee("b", 0..2)]
+        );
+        // b NOT IN (0, 1) AND b != 2 AND b != 3: the guarantee grows past the cap
+        assert_eq!(
+            analyze(
+                col("b")
+                    .in_list(list(2), true)
+                    .and(col("b").not_eq(lit(2)))
+                    .and(col("b").not_eq(lit(3))),
+                2
+            ),
+            vec![not_in_guarantee("b", [3])]
+        );
+        // b IN (0, ..., 9) AND b IN (1, 2) AND a = "foo"
+        assert_eq!(
+            analyze(
+                col("b")
+                    .in_list(list(10), false)
+                    .and(col("b").in_list(vec![lit(1), lit(2)], false))
+                    .and(col("a").eq(lit("foo"))),
+                2
+            ),
+            vec![in_guarantee("b", [1, 2]), in_guarantee("a", ["foo"])]
+        );
+    }
+
+    /// Tests that [`LiteralGuarantee::analyze_with_satisfiability`] on expr
+    /// results in the expected guarantees and satisfiability
+    fn test_analyze_with_satisfiability(
+        expr: Expr,
+        expected: Vec<LiteralGuarantee>,
+        expected_satisfiable: bool,
+    ) {
+        let schema = schema();
+        let physical_expr = logical2physical(&expr, &schema);
+
+        let (actual, satisfiable) =
+            LiteralGuarantee::analyze_with_satisfiability(&physical_expr);
+        assert_eq!(expected, actual, "expr: {expr}");
+        assert_eq!(expected_satisfiable, satisfiable, "expr: {expr}");
+    }
 }
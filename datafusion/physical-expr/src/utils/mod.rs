@@ -21,10 +21,11 @@ pub use guarantee::{Guarantee, LiteralGuarantee};
 use std::borrow::Borrow;
 use std::sync::Arc;
 
-use crate::expressions::{BinaryExpr, Column};
+use crate::equivalence::EquivalenceProperties;
+use crate::expressions::{BinaryExpr, Column, Literal};
 use crate::tree_node::ExprContext;
-use crate::PhysicalExpr;
 use crate::PhysicalSortExpr;
+use crate::{physical_exprs_contains, PhysicalExpr};
 
 use arrow::datatypes::SchemaRef;
 use datafusion_common::tree_node::{
@@ -241,6 +242,27 @@ pub fn reassign_predicate_columns(
     .data()
 }
 
+/// Replaces all references to the column `from` in `expr` with `to`, for
+/// example to substitute the expression a column is an alias of.
+///
+/// Columns are matched by both name and index. As the expression is
+/// rewritten bottom up, `to` may itself refer to `from`.
+pub fn replace_column(
+    expr: Arc<dyn PhysicalExpr>,
+    from: &Column,
+    to: Arc<dyn PhysicalExpr>,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    expr.transform(|expr| {
+        if let Some(column) = expr.as_any().downcast_ref::<Column>() {
+            if column == from {
+                return Ok(Transformed::yes(Arc::clone(&to)));
+            }
+        }
+        Ok(Transformed::no(expr))
+    })
+    .data()
+}
+
 /// Merge left and right sort expressions, checking for duplicates.
 pub fn merge_vectors(left: &LexOrdering, right: &LexOrdering) -> LexOrdering {
     left.iter()
@@ -250,6 +272,47 @@ pub fn merge_vectors(left: &LexOrdering, right: &LexOrdering) -> LexOrdering {
         .collect()
 }
 
+/// Removes the sort expressions in `exprs` that are implied by earlier sort
+/// expressions under `eq_properties`.
+///
+/// A sort expression is redundant if it is constant, if it is equivalent to
+/// an earlier sort expression (e.g. `c` after `a` when `a = c` is known), or
+/// if it is functionally dependent on earlier sort expressions (e.g. `a + b`
+/// after `a` and `b`), as rows that tie on the earlier expressions also tie
+/// on it.
+pub fn simplify_sort_exprs(
+    exprs: &LexOrdering,
+    eq_properties: &EquivalenceProperties,
+) -> LexOrdering {
+    let eq_group = eq_properties.eq_group();
+    let mut prefix: Vec<Arc<dyn PhysicalExpr>> = vec![];
+    let mut result = LexOrdering::default();
+    for sort_expr in exprs.iter() {
+        if eq_properties.is_expr_constant(&sort_expr.expr) {
+            continue;
+        }
+        let normalized = eq_group.normalize_expr(Arc::clone(&sort_expr.expr));
+        if is_determined_by(&prefix, &normalized) {
+            continue;
+        }
+        prefix.push(normalized);
+        result.push(sort_expr.clone());
+    }
+    result
+}
+
+/// Returns true if the value of `expr` is determined by the values of `exprs`
+fn is_determined_by(
+    exprs: &[Arc<dyn PhysicalExpr>],
+    expr: &Arc<dyn PhysicalExpr>,
+) -> bool {
+    if physical_exprs_contains(exprs, expr) || expr.as_any().is::<Literal>() {
+        return true;
+    }
+    let children = expr.children();
+    !children.is_empty() && children.iter().all(|c| is_determined_by(exprs, c))
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use std::any::Any;
@@ -257,6 +320,7 @@ pub(crate) mod tests {
 
     use super::*;
     use crate::expressions::{binary, cast, col, in_list, lit, Literal};
+    use crate::ConstExpr;
 
     use arrow_array::{ArrayRef, Float32Array, Float64Array};
     use arrow_schema::{DataType, Field, Schema};
@@ -530,6 +594,39 @@ pub(crate) mod tests {
         assert_eq!(actual.as_ref(), expected.as_ref());
     }
 
+    #[test]
+    fn test_replace_column() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+            Field::new("c", DataType::Int32, true),
+        ]));
+        let a = col("a", &schema)?;
+        let b = col("b", &schema)?;
+        let c = col("c", &schema)?;
+        // a + b * a
+        let expr = binary(
+            Arc::clone(&a),
+            Operator::Plus,
+            binary(Arc::clone(&b), Operator::Multiply, Arc::clone(&a), &schema)?,
+            &schema,
+        )?;
+
+        // with another column
+        let actual = replace_column(Arc::clone(&expr), &Column::new("a", 0), c)?;
+        assert_eq!(actual.to_string(), "c@2 + b@1 * c@2");
+
+        // with an expression that refers to the replaced column
+        let a_plus_1 = binary(Arc::clone(&a), Operator::Plus, lit(1), &schema)?;
+        let actual = replace_column(Arc::clone(&expr), &Column::new("a", 0), a_plus_1)?;
+        assert_eq!(actual.to_string(), "a@0 + 1 + b@1 * (a@0 + 1)");
+
+        // columns are matched by name and index
+        let actual = replace_column(Arc::clone(&expr), &Column::new("a", 1), lit(1))?;
+        assert_eq!(actual.to_string(), expr.to_string());
+        Ok(())
+    }
+
     #[test]
     fn test_collect_columns() -> Result<()> {
         let expr1 = Arc::new(Column::new("col1", 2)) as _;
@@ -549,4 +646,63 @@ pub(crate) mod tests {
         assert_eq!(collect_columns(&expr3), expected);
         Ok(())
     }
+
+    #[test]
+    fn test_simplify_sort_exprs() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+            Field::new("c", DataType::Int32, true),
+            Field::new("d", DataType::Int32, true),
+        ]));
+        let a = col("a", &schema)?;
+        let b = col("b", &schema)?;
+        let c = col("c", &schema)?;
+        let d = col("d", &schema)?;
+        let sort = |exprs: Vec<&Arc<dyn PhysicalExpr>>| {
+            exprs
+                .into_iter()
+                .map(|expr| PhysicalSortExpr::new_default(Arc::clone(expr)))
+                .collect::<LexOrdering>()
+        };
+
+        // a = c
+        let mut eq_properties = EquivalenceProperties::new(Arc::clone(&schema));
+        eq_properties.add_equal_conditions(&a, &c)?;
+
+        // the alias `c` is implied by `a`
+        let exprs = sort(vec![&a, &b, &c]);
+        assert_eq!(
+            simplify_sort_exprs(&exprs, &eq_properties),
+            sort(vec![&a, &b])
+        );
+        let exprs = sort(vec![&c, &a, &d]);
+        assert_eq!(
+            simplify_sort_exprs(&exprs, &eq_properties),
+            sort(vec![&c, &d])
+        );
+
+        // `c + b` and `a * 2` are functionally dependent on `a` and `b`
+        let c_plus_b = binary(Arc::clone(&c), Operator::Plus, Arc::clone(&b), &schema)?;
+        let a_times_2 = binary(Arc::clone(&a), Operator::Multiply, lit(2), &schema)?;
+        let exprs = sort(vec![&a, &b, &c_plus_b, &a_times_2, &d]);
+        assert_eq!(
+            simplify_sort_exprs(&exprs, &eq_properties),
+            sort(vec![&a, &b, &d])
+        );
+        // `c + b` is not dependent on `a` alone, and `b` is not derived from
+        // the expressions it is made of
+        let exprs = sort(vec![&a, &c_plus_b, &b]);
+        assert_eq!(simplify_sort_exprs(&exprs, &eq_properties), exprs);
+
+        // constant expressions are dropped
+        let eq_properties = eq_properties.with_constants([ConstExpr::from(&d)]);
+        let exprs = sort(vec![&d, &b, &a]);
+        assert_eq!(
+            simplify_sort_exprs(&exprs, &eq_properties),
+            sort(vec![&b, &a])
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,129 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! [`CoalesceOrderedMerge`] replaces a [`SortPreservingMergeExec`] whose
+//! input partitions are already ordered across partitions with a
+//! [`CoalescePartitionsExec`]
+
+use std::sync::Arc;
+
+use crate::PhysicalOptimizerRule;
+use datafusion_common::config::ConfigOptions;
+use datafusion_common::tree_node::{Transformed, TransformedResult, TreeNode};
+use datafusion_common::Result;
+use datafusion_physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use datafusion_physical_plan::limit::GlobalLimitExec;
+use datafusion_physical_plan::sorts::sort_preserving_merge::SortPreservingMergeExec;
+use datafusion_physical_plan::ExecutionPlan;
+
+/// An optimizer rule that replaces a [`SortPreservingMergeExec`] with a
+/// [`CoalescePartitionsExec`] if its input is ordered across all of its
+/// partitions, not only within each partition.
+///
+/// Whether the input is ordered across partitions is derived from the
+/// equivalence properties that hold for all input partitions, for example
+/// sort expressions that are constant with the same value in every
+/// partition:
+///
+/// ```text
+/// SortPreservingMergeExec: [a ASC]
+///   FilterExec: a = 1
+/// ```
+///
+/// becomes
+///
+/// ```text
+/// CoalescePartitionsExec
+///   FilterExec: a = 1
+/// ```
+///
+/// A fetch of the merge is applied by a [`GlobalLimitExec`] above the
+/// [`CoalescePartitionsExec`].
+#[derive(Default, Debug)]
+pub struct CoalesceOrderedMerge {}
+
+impl CoalesceOrderedMerge {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Returns `plan` with the merge replaced by a coalesce, if `plan` is
+    /// a [`SortPreservingMergeExec`] whose input is ordered across
+    /// partitions
+    fn transform_merge(
+        plan: &Arc<dyn ExecutionPlan>,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let Some(merge) = plan.as_any().downcast_ref::<SortPreservingMergeExec>() else {
+            return Ok(None);
+        };
+        // a per partition fetch can not be applied after coalescing, and the
+        // coalesce does not output the source partition of each row
+        if merge.per_partition_fetch().is_some()
+            || merge.source_partition_column().is_some()
+            || !is_globally_ordered(merge)
+        {
+            return Ok(None);
+        }
+
+        let coalesce = Arc::new(CoalescePartitionsExec::new(Arc::clone(merge.input())));
+        Ok(Some(match (merge.offset(), merge.fetch()) {
+            (0, None) => coalesce,
+            (offset, fetch) => Arc::new(GlobalLimitExec::new(coalesce, offset, fetch)),
+        }))
+    }
+}
+
+/// Returns true if the input partitions of `merge` are ordered across
+/// partitions by the ordering of `merge`, so that concatenating them in any
+/// order produces the same ordering as merging them
+pub fn is_globally_ordered(merge: &SortPreservingMergeExec) -> bool {
+    // the ordering of the merge, with its null ordering overrides applied
+    let Some(Some(requirement)) = merge.required_input_ordering().pop() else {
+        return false;
+    };
+    // coalescing keeps the equivalence properties of the input that hold
+    // across partitions
+    let coalesce = CoalescePartitionsExec::new(Arc::clone(merge.input()));
+    coalesce
+        .properties()
+        .equivalence_properties()
+        .ordering_satisfy_requirement(&requirement)
+}
+
+impl PhysicalOptimizerRule for CoalesceOrderedMerge {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        _config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        plan.transform_down(|plan| {
+            Ok(match Self::transform_merge(&plan)? {
+                Some(plan) => Transformed::yes(plan),
+                None => Transformed::no(plan),
+            })
+        })
+        .data()
+    }
+
+    fn name(&self) -> &str {
+        "CoalesceOrderedMerge"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
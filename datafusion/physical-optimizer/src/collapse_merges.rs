@@ -0,0 +1,140 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! [`CollapseMerges`] removes a [`SortPreservingMergeExec`] whose input is
+//! another [`SortPreservingMergeExec`]
+
+use std::sync::Arc;
+
+use crate::PhysicalOptimizerRule;
+use datafusion_common::config::ConfigOptions;
+use datafusion_common::tree_node::{Transformed, TransformedResult, TreeNode};
+use datafusion_common::Result;
+use datafusion_physical_plan::sorts::sort_preserving_merge::SortPreservingMergeExec;
+use datafusion_physical_plan::ExecutionPlan;
+
+/// An optimizer rule that collapses a [`SortPreservingMergeExec`] over
+/// another [`SortPreservingMergeExec`] into a single merge.
+///
+/// The inner merge already produces a single partition, sorted by an
+/// ordering that satisfies the ordering of the outer merge, so the outer
+/// merge only has to apply its limits:
+///
+/// ```text
+/// SortPreservingMergeExec: [a ASC], fetch=5
+///   SortPreservingMergeExec: [a ASC], fetch=10
+///     SortExec: expr=[a ASC], preserve_partitioning=[true]
+/// ```
+///
+/// becomes
+///
+/// ```text
+/// SortPreservingMergeExec: [a ASC], fetch=5
+///   SortExec: expr=[a ASC], preserve_partitioning=[true]
+/// ```
+///
+/// The fetches of both merges are combined by taking the smaller one, and
+/// their offsets are added up.
+#[derive(Default, Debug)]
+pub struct CollapseMerges {}
+
+impl CollapseMerges {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Returns the inner merge with the limits of the outer merge applied,
+    /// if `plan` is a [`SortPreservingMergeExec`] over another
+    /// [`SortPreservingMergeExec`] with a compatible ordering
+    fn transform_merge(
+        plan: &Arc<dyn ExecutionPlan>,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let Some(outer) = plan.as_any().downcast_ref::<SortPreservingMergeExec>() else {
+            return Ok(None);
+        };
+        // the column of the source partition of each row of the outer merge
+        // would be removed
+        if outer.source_partition_column().is_some() {
+            return Ok(None);
+        }
+        let Some(inner) = outer
+            .input()
+            .as_any()
+            .downcast_ref::<SortPreservingMergeExec>()
+        else {
+            return Ok(None);
+        };
+        // the ordering of the outer merge, with its null ordering overrides
+        // applied, must be provided by the inner merge
+        let Some(Some(requirement)) = outer.required_input_ordering().pop() else {
+            return Ok(None);
+        };
+        if !inner
+            .properties()
+            .equivalence_properties()
+            .ordering_satisfy_requirement(&requirement)
+        {
+            return Ok(None);
+        }
+
+        // the per partition fetch of the outer merge limits its only input
+        // partition, before its offset is skipped
+        let inner_fetch = [inner.fetch(), outer.per_partition_fetch()]
+            .into_iter()
+            .flatten()
+            .min();
+        let fetch = [
+            inner_fetch.map(|fetch| fetch.saturating_sub(outer.offset())),
+            outer.fetch(),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+
+        let merge = inner
+            .clone()
+            .with_fetch(fetch)
+            .with_offset(inner.offset() + outer.offset());
+        Ok(Some(Arc::new(merge)))
+    }
+}
+
+impl PhysicalOptimizerRule for CollapseMerges {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        _config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        // bottom up, so that a stack of merges collapses into one
+        plan.transform_up(|plan| {
+            Ok(match Self::transform_merge(&plan)? {
+                Some(plan) => Transformed::yes(plan),
+                None => Transformed::no(plan),
+            })
+        })
+        .data()
+    }
+
+    fn name(&self) -> &str {
+        "CollapseMerges"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
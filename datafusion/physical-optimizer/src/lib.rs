@@ -20,12 +20,15 @@
 
 pub mod aggregate_statistics;
 pub mod coalesce_batches;
+pub mod coalesce_ordered_merge;
+pub mod collapse_merges;
 pub mod combine_partial_final_agg;
 pub mod limit_pushdown;
 pub mod limited_distinct_aggregation;
 mod optimizer;
 pub mod output_requirements;
 pub mod pruning;
+pub mod sort_fetch_pushdown;
 pub mod topk_aggregation;
 pub mod update_aggr_exprs;
 
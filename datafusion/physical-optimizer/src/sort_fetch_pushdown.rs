@@ -0,0 +1,111 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! [`SortFetchPushdown`] turns the sorts below a [`SortPreservingMergeExec`]
+//! with a fetch into per-partition TopKs
+
+use std::sync::Arc;
+
+use crate::PhysicalOptimizerRule;
+use datafusion_common::config::ConfigOptions;
+use datafusion_common::tree_node::{Transformed, TransformedResult, TreeNode};
+use datafusion_common::Result;
+use datafusion_physical_plan::sorts::sort::SortExec;
+use datafusion_physical_plan::sorts::sort_preserving_merge::SortPreservingMergeExec;
+use datafusion_physical_plan::ExecutionPlan;
+
+/// An optimizer rule that pushes the fetch `k` of a
+/// [`SortPreservingMergeExec`] into the [`SortExec`] that sorts its input
+/// partitions.
+///
+/// Each partition only needs to contribute its own top `k` rows to the
+/// merged top `k`, so the partition-wise full sorts can be replaced by
+/// bounded TopK sorts of size `k`:
+///
+/// ```text
+/// SortPreservingMergeExec: [a ASC], fetch=10
+///   SortExec: expr=[a ASC], preserve_partitioning=[true]
+/// ```
+///
+/// becomes
+///
+/// ```text
+/// SortPreservingMergeExec: [a ASC], fetch=10
+///   SortExec: TopK(fetch=10), expr=[a ASC], preserve_partitioning=[true]
+/// ```
+#[derive(Default, Debug)]
+pub struct SortFetchPushdown {}
+
+impl SortFetchPushdown {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Returns `plan` with the fetch pushed into its input sort, if `plan`
+    /// is a [`SortPreservingMergeExec`] with a fetch over a [`SortExec`]
+    /// that could return more rows
+    fn transform_merge(
+        plan: &Arc<dyn ExecutionPlan>,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let Some(merge) = plan.as_any().downcast_ref::<SortPreservingMergeExec>() else {
+            return Ok(None);
+        };
+        // the rows skipped by the merge must be produced by the sort as well
+        let Some(fetch) = merge.fetch().map(|fetch| fetch + merge.offset()) else {
+            return Ok(None);
+        };
+        let Some(sort) = merge.input().as_any().downcast_ref::<SortExec>() else {
+            return Ok(None);
+        };
+        if sort.fetch().is_some_and(|sort_fetch| sort_fetch <= fetch) {
+            return Ok(None);
+        }
+        // the top k of the merge must be the top k of each sorted
+        // partition, which requires the partitions to be sorted on (at
+        // least) the merge ordering
+        if !sort.expr().starts_with(merge.expr()) {
+            return Ok(None);
+        }
+
+        let new_sort = Arc::new(sort.with_fetch(Some(fetch)));
+        Arc::clone(plan).with_new_children(vec![new_sort]).map(Some)
+    }
+}
+
+impl PhysicalOptimizerRule for SortFetchPushdown {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        _config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        plan.transform_down(|plan| {
+            Ok(match Self::transform_merge(&plan)? {
+                Some(plan) => Transformed::yes(plan),
+                None => Transformed::no(plan),
+            })
+        })
+        .data()
+    }
+
+    fn name(&self) -> &str {
+        "SortFetchPushdown"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,86 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::sync::Arc;
+
+use arrow::record_batch::RecordBatch;
+use arrow_array::{ArrayRef, Int64Array};
+use datafusion_execution::TaskContext;
+use datafusion_functions_aggregate::count::count_udaf;
+use datafusion_physical_expr::aggregate::AggregateExprBuilder;
+use datafusion_physical_expr::expressions::col;
+use datafusion_physical_plan::aggregates::{
+    AggregateExec, AggregateMode, PhysicalGroupBy,
+};
+use datafusion_physical_plan::memory::MemoryExec;
+use datafusion_physical_plan::{collect, ExecutionPlan};
+
+use criterion::async_executor::FuturesExecutor;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// Counts the rows of each of `num_groups` distinct `Int64` keys, over
+/// 1M rows in batches of 8192 rows
+fn generate_int64_group_by(num_groups: i64) -> AggregateExec {
+    let num_rows = 1024 * 1024;
+    let values: Vec<i64> = (0..num_rows)
+        .map(|i| (i * 7919) % num_groups * 1024)
+        .collect();
+    let a: ArrayRef = Arc::new(Int64Array::from(values));
+    let rb = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
+    let batches = (0..num_rows as usize / 8192)
+        .map(|i| rb.slice(i * 8192, 8192))
+        .collect::<Vec<_>>();
+
+    let schema = rb.schema();
+    let group_by =
+        PhysicalGroupBy::new_single(vec![(col("a", &schema).unwrap(), "a".to_string())]);
+    let aggr_expr = vec![Arc::new(
+        AggregateExprBuilder::new(count_udaf(), vec![col("a", &schema).unwrap()])
+            .schema(Arc::clone(&schema))
+            .alias("COUNT(a)")
+            .build()
+            .unwrap(),
+    )];
+    let input = MemoryExec::try_new(&[batches], Arc::clone(&schema), None).unwrap();
+    AggregateExec::try_new(
+        AggregateMode::Single,
+        group_by,
+        aggr_expr,
+        vec![None],
+        Arc::new(input),
+        schema,
+    )
+    .unwrap()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let task_ctx = Arc::new(TaskContext::default());
+    for (num_groups, description) in [
+        (1_000, "group_by_int64_1k_groups"),
+        (100_000, "group_by_int64_100k_groups"),
+    ] {
+        let exec =
+            Arc::new(generate_int64_group_by(num_groups)) as Arc<dyn ExecutionPlan>;
+        c.bench_function(description, |b| {
+            b.to_async(FuturesExecutor)
+                .iter(|| black_box(collect(Arc::clone(&exec), Arc::clone(&task_ctx))))
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -17,6 +17,7 @@
 
 use std::sync::Arc;
 
+use arrow::compute::SortOptions;
 use arrow::record_batch::RecordBatch;
 use arrow_array::{ArrayRef, Int32Array, Int64Array, StringArray};
 use datafusion_execution::TaskContext;
@@ -142,5 +143,51 @@ fn criterion_benchmark(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, criterion_benchmark);
+/// Merges partitions sorted by a single `Int64` column, in either sort
+/// direction
+fn generate_spm_for_direction(descending: bool) -> SortPreservingMergeExec {
+    let partitions = (0..8i64)
+        .map(|partition| {
+            let mut values: Vec<i64> = (0..64 * 1024)
+                .map(|i| (i * 7919 + partition) % 100_003)
+                .collect();
+            values.sort_unstable();
+            if descending {
+                values.reverse();
+            }
+            let a: ArrayRef = Arc::new(Int64Array::from(values));
+            let rb = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
+            (0..64).map(|i| rb.slice(i * 1024, 1024)).collect()
+        })
+        .collect::<Vec<Vec<_>>>();
+
+    let schema = partitions[0][0].schema();
+    let sort = LexOrdering::new(vec![PhysicalSortExpr {
+        expr: col("a", &schema).unwrap(),
+        options: SortOptions {
+            descending,
+            nulls_first: true,
+        },
+    }]);
+
+    let exec = MemoryExec::try_new(&partitions, schema, None).unwrap();
+    SortPreservingMergeExec::new(sort, Arc::new(exec))
+}
+
+fn direction_benchmark(c: &mut Criterion) {
+    let task_ctx = Arc::new(TaskContext::default());
+    for (descending, description) in [
+        (false, "single_column_ascending"),
+        (true, "single_column_descending"),
+    ] {
+        let spm =
+            Arc::new(generate_spm_for_direction(descending)) as Arc<dyn ExecutionPlan>;
+        c.bench_function(description, |b| {
+            b.to_async(FuturesExecutor)
+                .iter(|| black_box(collect(Arc::clone(&spm), Arc::clone(&task_ctx))))
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark, direction_benchmark);
 criterion_main!(benches);
@@ -0,0 +1,85 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::sync::Arc;
+
+use arrow::compute::SortOptions;
+use arrow::record_batch::RecordBatch;
+use arrow_array::{ArrayRef, Float64Array, Int64Array};
+use datafusion_execution::TaskContext;
+use datafusion_physical_expr::expressions::{col, lit};
+use datafusion_physical_expr::PhysicalSortExpr;
+use datafusion_physical_expr_common::sort_expr::LexOrdering;
+use datafusion_physical_plan::memory::MemoryExec;
+use datafusion_physical_plan::sorts::sort::SortExec;
+use datafusion_physical_plan::{collect, ExecutionPlan};
+
+use criterion::async_executor::FuturesExecutor;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// Sorts 64 batches of 8192 rows with a limit of `k`, on column `a` and,
+/// if `row_format` is set, on a constant, which does not change the
+/// order but uses the row format for all rows
+fn generate_topk(a: ArrayRef, k: usize, row_format: bool) -> SortExec {
+    let rb = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
+    let batches = (0..64).map(|i| rb.slice(i * 8192, 8192)).collect();
+    let schema = rb.schema();
+
+    let options = SortOptions {
+        descending: true,
+        nulls_first: false,
+    };
+    let mut sort = vec![PhysicalSortExpr {
+        expr: col("a", &schema).unwrap(),
+        options,
+    }];
+    if row_format {
+        sort.push(PhysicalSortExpr {
+            expr: lit(0),
+            options,
+        });
+    }
+
+    let exec = MemoryExec::try_new(&[batches], schema, None).unwrap();
+    SortExec::new(LexOrdering::new(sort), Arc::new(exec)).with_fetch(Some(k))
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let task_ctx = Arc::new(TaskContext::default());
+    let values = || (0..64 * 8192i64).map(|i| (i * 7919) % 1_000_003);
+    let ints: ArrayRef = Arc::new(Int64Array::from_iter_values(values()));
+    let floats: ArrayRef =
+        Arc::new(Float64Array::from_iter_values(values().map(|v| v as f64)));
+
+    for (a, type_label) in [(ints, "i64"), (floats, "f64")] {
+        for k in [10, 1000] {
+            for (row_format, path_label) in [(false, "primitive"), (true, "row_format")] {
+                let topk = Arc::new(generate_topk(Arc::clone(&a), k, row_format))
+                    as Arc<dyn ExecutionPlan>;
+                let description = format!("topk_{type_label}_k_{k}_{path_label}");
+                c.bench_function(&description, |b| {
+                    b.to_async(FuturesExecutor).iter(|| {
+                        black_box(collect(Arc::clone(&topk), Arc::clone(&task_ctx)))
+                    })
+                });
+            }
+        }
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -17,11 +17,13 @@
 
 //! [`GroupValues`] trait for storing and interning group keys
 
+use ahash::RandomState;
 use arrow::record_batch::RecordBatch;
 use arrow_array::types::{
-    Date32Type, Date64Type, Decimal128Type, Time32MillisecondType, Time32SecondType,
-    Time64MicrosecondType, Time64NanosecondType, TimestampMicrosecondType,
-    TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType,
+    Date32Type, Date64Type, Decimal128Type, Int16Type, Int32Type, Int64Type, Int8Type,
+    Time32MillisecondType, Time32SecondType, Time64MicrosecondType, Time64NanosecondType,
+    TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+    TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
 };
 use arrow_array::{downcast_primitive, ArrayRef};
 use arrow_schema::TimeUnit;
@@ -42,8 +44,9 @@ pub(crate) use single_group_by::primitive::HashValue;
 
 use crate::aggregates::{
     group_values::single_group_by::{
-        bytes::GroupValuesByes, bytes_view::GroupValuesBytesView,
-        primitive::GroupValuesPrimitive,
+        bytes::GroupValuesByes,
+        bytes_view::GroupValuesBytesView,
+        primitive::{DirectHashState, GroupValuesPrimitive},
     },
     order::GroupOrdering,
 };
@@ -98,6 +101,21 @@ pub(crate) trait GroupValues: Send {
     /// assigned.
     fn intern(&mut self, cols: &[ArrayRef], groups: &mut Vec<usize>) -> Result<()>;
 
+    /// Like [`Self::intern`], but with the hash of each row of `cols`
+    /// already computed in `hashes`, which must be equal for rows with
+    /// equal group values.
+    ///
+    /// The default implementation ignores `hashes` and calls
+    /// [`Self::intern`].
+    fn intern_with_hashes(
+        &mut self,
+        cols: &[ArrayRef],
+        _hashes: &[u64],
+        groups: &mut Vec<usize>,
+    ) -> Result<()> {
+        self.intern(cols, groups)
+    }
+
     /// Returns the number of bytes of memory used by this [`GroupValues`]
     fn size(&self) -> usize;
 
@@ -112,6 +130,13 @@ pub(crate) trait GroupValues: Send {
 
     /// Clear the contents and shrink the capacity to the size of the batch (free up memory usage)
     fn clear_shrink(&mut self, batch: &RecordBatch);
+
+    /// Returns the number of entries and the capacity of the hash table
+    /// used to find the groups, if any. The capacity is the number of
+    /// entries the table can hold before it grows and rehashes its entries.
+    fn hash_table_usage(&self) -> Option<(usize, usize)> {
+        None
+    }
 }
 
 /// Return a specialized implementation of [`GroupValues`] for the given schema.
@@ -127,21 +152,87 @@ pub(crate) trait GroupValues: Send {
 ///
 ///   - Otherwise, the general implementation [`GroupValuesRows`] will be chosen.
 ///
+/// `constant_columns` are the indices of group columns known to be constant,
+/// which [`GroupValuesColumn`] excludes from hashing.
+///
+/// If `normalize_floats` is true, `Float32` / `Float64` group values are
+/// normalized so that all NaNs form one group and `-0.0` groups together with
+/// `+0.0`. This is supported by [`GroupValuesColumn`], which is then also
+/// used for a single float column, and by [`GroupValuesRows`].
+///
+/// If `views_as_offsets` is true, `Utf8View` / `BinaryView` group values are
+/// stored by [`GroupValuesColumn`] as `LargeUtf8` / `LargeBinary`, including
+/// for a single view column.
+///
+/// If `large_as_views` is true, `LargeUtf8` / `LargeBinary` group values are
+/// stored by [`GroupValuesColumn`] as `Utf8View` / `BinaryView`, including
+/// for a single large column.
+///
+/// If `max_completed_view_blocks` is set, the blocks storing view group
+/// values grow after that many blocks, see
+/// [`GroupValuesColumn::with_max_completed_view_blocks`]. [`GroupValuesColumn`]
+/// is then also used for a single view column.
+///
 /// [`GroupColumn`]:  crate::aggregates::group_values::multi_group_by::GroupColumn
 ///
 pub(crate) fn new_group_values(
     schema: SchemaRef,
     group_ordering: &GroupOrdering,
+    constant_columns: Vec<usize>,
+    normalize_floats: bool,
+    views_as_offsets: bool,
+    large_as_views: bool,
+    max_completed_view_blocks: Option<usize>,
 ) -> Result<Box<dyn GroupValues>> {
-    if schema.fields.len() == 1 {
+    if schema.fields.len() == 1
+        && !(normalize_floats
+            && matches!(
+                schema.fields[0].data_type(),
+                DataType::Float32 | DataType::Float64
+            ))
+        && !((views_as_offsets || max_completed_view_blocks.is_some())
+            && matches!(
+                schema.fields[0].data_type(),
+                DataType::Utf8View | DataType::BinaryView
+            ))
+        && !(large_as_views
+            && matches!(
+                schema.fields[0].data_type(),
+                DataType::LargeUtf8 | DataType::LargeBinary
+            ))
+    {
         let d = schema.fields[0].data_type();
 
         macro_rules! downcast_helper {
             ($t:ty, $d:ident) => {
-                return Ok(Box::new(GroupValuesPrimitive::<$t>::new($d.clone())))
+                return Ok(Box::new(GroupValuesPrimitive::<$t, RandomState>::new(
+                    $d.clone(),
+                )))
+            };
+        }
+
+        // Integer keys are hashed directly from their value, skipping the
+        // general purpose hasher
+        macro_rules! direct_hash_helper {
+            ($t:ty, $d:ident) => {
+                return Ok(Box::new(GroupValuesPrimitive::<$t, DirectHashState>::new(
+                    $d.clone(),
+                )))
             };
         }
 
+        match d {
+            DataType::Int8 => direct_hash_helper!(Int8Type, d),
+            DataType::Int16 => direct_hash_helper!(Int16Type, d),
+            DataType::Int32 => direct_hash_helper!(Int32Type, d),
+            DataType::Int64 => direct_hash_helper!(Int64Type, d),
+            DataType::UInt8 => direct_hash_helper!(UInt8Type, d),
+            DataType::UInt16 => direct_hash_helper!(UInt16Type, d),
+            DataType::UInt32 => direct_hash_helper!(UInt32Type, d),
+            DataType::UInt64 => direct_hash_helper!(UInt64Type, d),
+            _ => {}
+        }
+
         downcast_primitive! {
             d => (downcast_helper, d),
             _ => {}
@@ -149,26 +240,30 @@ pub(crate) fn new_group_values(
 
         match d {
             DataType::Date32 => {
-                downcast_helper!(Date32Type, d);
+                direct_hash_helper!(Date32Type, d);
             }
             DataType::Date64 => {
-                downcast_helper!(Date64Type, d);
+                direct_hash_helper!(Date64Type, d);
             }
             DataType::Time32(t) => match t {
-                TimeUnit::Second => downcast_helper!(Time32SecondType, d),
-                TimeUnit::Millisecond => downcast_helper!(Time32MillisecondType, d),
+                TimeUnit::Second => direct_hash_helper!(Time32SecondType, d),
+                TimeUnit::Millisecond => direct_hash_helper!(Time32MillisecondType, d),
                 _ => {}
             },
             DataType::Time64(t) => match t {
-                TimeUnit::Microsecond => downcast_helper!(Time64MicrosecondType, d),
-                TimeUnit::Nanosecond => downcast_helper!(Time64NanosecondType, d),
+                TimeUnit::Microsecond => direct_hash_helper!(Time64MicrosecondType, d),
+                TimeUnit::Nanosecond => direct_hash_helper!(Time64NanosecondType, d),
                 _ => {}
             },
             DataType::Timestamp(t, _tz) => match t {
-                TimeUnit::Second => downcast_helper!(TimestampSecondType, d),
-                TimeUnit::Millisecond => downcast_helper!(TimestampMillisecondType, d),
-                TimeUnit::Microsecond => downcast_helper!(TimestampMicrosecondType, d),
-                TimeUnit::Nanosecond => downcast_helper!(TimestampNanosecondType, d),
+                TimeUnit::Second => direct_hash_helper!(TimestampSecondType, d),
+                TimeUnit::Millisecond => {
+                    direct_hash_helper!(TimestampMillisecondType, d)
+                }
+                TimeUnit::Microsecond => {
+                    direct_hash_helper!(TimestampMicrosecondType, d)
+                }
+                TimeUnit::Nanosecond => direct_hash_helper!(TimestampNanosecondType, d),
             },
             DataType::Decimal128(_, _) => {
                 downcast_helper!(Decimal128Type, d);
@@ -197,11 +292,27 @@ pub(crate) fn new_group_values(
 
     if multi_group_by::supported_schema(schema.as_ref()) {
         if matches!(group_ordering, GroupOrdering::None) {
-            Ok(Box::new(GroupValuesColumn::<false>::try_new(schema)?))
+            Ok(Box::new(
+                GroupValuesColumn::<false>::try_new(schema)?
+                    .with_constant_columns(constant_columns)
+                    .with_float_normalization(normalize_floats)
+                    .with_views_as_offsets(views_as_offsets)
+                    .with_large_as_views(large_as_views)
+                    .with_max_completed_view_blocks(max_completed_view_blocks),
+            ))
         } else {
-            Ok(Box::new(GroupValuesColumn::<true>::try_new(schema)?))
+            Ok(Box::new(
+                GroupValuesColumn::<true>::try_new(schema)?
+                    .with_constant_columns(constant_columns)
+                    .with_float_normalization(normalize_floats)
+                    .with_views_as_offsets(views_as_offsets)
+                    .with_large_as_views(large_as_views)
+                    .with_max_completed_view_blocks(max_completed_view_blocks),
+            ))
         }
     } else {
-        Ok(Box::new(GroupValuesRows::try_new(schema)?))
+        Ok(Box::new(
+            GroupValuesRows::try_new(schema)?.with_float_normalization(normalize_floats),
+        ))
     }
 }
@@ -153,11 +153,7 @@ where
             }
 
             Some(false) => {
-                self.nulls.append_n(rows.len(), true);
-
-                let new_len = self.offsets.len() + rows.len();
-                let offset = self.buffer.len();
-                self.offsets.resize(new_len, O::usize_as(offset));
+                self.append_nulls(rows.len());
             }
         }
     }
@@ -176,7 +172,8 @@ where
         if let Some(result) = nulls_equal_to(exist_null, input_null) {
             return result;
         }
-        // Otherwise, we need to check their values
+        // Otherwise, we need to check their values. Comparing the slices
+        // checks their lengths first, and stops at the first differing byte
         self.value(lhs_row) == (array.value(rhs_row).as_ref() as &[u8])
     }
 
@@ -203,20 +200,11 @@ where
     O: OffsetSizeTrait,
 {
     fn equal_to(&self, lhs_row: usize, column: &ArrayRef, rhs_row: usize) -> bool {
-        // Sanity array type
         match self.output_type {
             OutputType::Binary => {
-                debug_assert!(matches!(
-                    column.data_type(),
-                    DataType::Binary | DataType::LargeBinary
-                ));
                 self.equal_to_inner::<GenericBinaryType<O>>(lhs_row, column, rhs_row)
             }
             OutputType::Utf8 => {
-                debug_assert!(matches!(
-                    column.data_type(),
-                    DataType::Utf8 | DataType::LargeUtf8
-                ));
                 self.equal_to_inner::<GenericStringType<O>>(lhs_row, column, rhs_row)
             }
             _ => unreachable!("View types should use `ArrowBytesViewMap`"),
@@ -224,20 +212,11 @@ where
     }
 
     fn append_val(&mut self, column: &ArrayRef, row: usize) {
-        // Sanity array type
         match self.output_type {
             OutputType::Binary => {
-                debug_assert!(matches!(
-                    column.data_type(),
-                    DataType::Binary | DataType::LargeBinary
-                ));
                 self.append_val_inner::<GenericBinaryType<O>>(column, row)
             }
             OutputType::Utf8 => {
-                debug_assert!(matches!(
-                    column.data_type(),
-                    DataType::Utf8 | DataType::LargeUtf8
-                ));
                 self.append_val_inner::<GenericStringType<O>>(column, row)
             }
             _ => unreachable!("View types should use `ArrowBytesViewMap`"),
@@ -251,13 +230,8 @@ where
         rhs_rows: &[usize],
         equal_to_results: &mut [bool],
     ) {
-        // Sanity array type
         match self.output_type {
             OutputType::Binary => {
-                debug_assert!(matches!(
-                    array.data_type(),
-                    DataType::Binary | DataType::LargeBinary
-                ));
                 self.vectorized_equal_to_inner::<GenericBinaryType<O>>(
                     lhs_rows,
                     array,
@@ -266,10 +240,6 @@ where
                 );
             }
             OutputType::Utf8 => {
-                debug_assert!(matches!(
-                    array.data_type(),
-                    DataType::Utf8 | DataType::LargeUtf8
-                ));
                 self.vectorized_equal_to_inner::<GenericStringType<O>>(
                     lhs_rows,
                     array,
@@ -284,23 +254,32 @@ where
     fn vectorized_append(&mut self, column: &ArrayRef, rows: &[usize]) {
         match self.output_type {
             OutputType::Binary => {
-                debug_assert!(matches!(
-                    column.data_type(),
-                    DataType::Binary | DataType::LargeBinary
-                ));
                 self.vectorized_append_inner::<GenericBinaryType<O>>(column, rows)
             }
             OutputType::Utf8 => {
-                debug_assert!(matches!(
-                    column.data_type(),
-                    DataType::Utf8 | DataType::LargeUtf8
-                ));
                 self.vectorized_append_inner::<GenericStringType<O>>(column, rows)
             }
             _ => unreachable!("View types should use `ArrowBytesViewMap`"),
         };
     }
 
+    fn accepts(&self, data_type: &DataType) -> bool {
+        match self.output_type {
+            OutputType::Binary => data_type == &GenericBinaryType::<O>::DATA_TYPE,
+            OutputType::Utf8 => data_type == &GenericStringType::<O>::DATA_TYPE,
+            _ => false,
+        }
+    }
+
+    fn append_nulls(&mut self, n: usize) {
+        self.nulls.append_n(n, true);
+
+        // nulls need a zero length in the offset buffer
+        let new_len = self.offsets.len() + n;
+        let offset = self.buffer.len();
+        self.offsets.resize(new_len, O::usize_as(offset));
+    }
+
     fn len(&self) -> usize {
         self.offsets.len() - 1
     }
@@ -455,6 +434,29 @@ mod tests {
         assert_eq!(&output, &array);
     }
 
+    #[test]
+    fn test_byte_append_nulls() {
+        // 63 nulls plus the initial offset fill a power of two capacity, so
+        // the amortized growth of per call appends ends at the same size
+        let null_array = Arc::new(StringArray::from(vec![None::<&str>; 63])) as ArrayRef;
+
+        let mut per_call_builder = ByteGroupValueBuilder::<i32>::new(OutputType::Utf8);
+        for row in 0..null_array.len() {
+            per_call_builder.append_val(&null_array, row);
+        }
+
+        let mut bulk_builder = ByteGroupValueBuilder::<i32>::new(OutputType::Utf8);
+        bulk_builder.append_nulls(null_array.len());
+
+        assert_eq!(bulk_builder.len(), per_call_builder.len());
+        assert_eq!(bulk_builder.size(), per_call_builder.size());
+
+        let per_call_output = Box::new(per_call_builder).build();
+        let bulk_output = Box::new(bulk_builder).build();
+        assert_eq!(bulk_output.null_count(), 63);
+        assert_eq!(&bulk_output, &per_call_output);
+    }
+
     #[test]
     fn test_byte_equal_to() {
         let append = |builder: &mut ByteGroupValueBuilder<i32>,
@@ -503,6 +505,51 @@ mod tests {
         test_byte_equal_to_internal(append, equal_to);
     }
 
+    #[test]
+    fn test_byte_equal_to_long_values() {
+        // long values sharing a common prefix, which differ in their length,
+        // in their first bytes or only in their last byte
+        let common = "a".repeat(100);
+        let values: Vec<String> = vec![
+            common.clone(),
+            format!("{common}b"),
+            format!("{common}c"),
+            format!("{common}bb"),
+            format!("x{}", &common[1..]),
+            "ab".to_string(),
+            "abc".to_string(),
+            "b".to_string(),
+            String::new(),
+        ];
+
+        let mut builder = ByteGroupValueBuilder::<i32>::new(OutputType::Utf8);
+        let builder_array = Arc::new(StringArray::from(values.clone())) as ArrayRef;
+        builder.vectorized_append(&builder_array, &(0..values.len()).collect::<Vec<_>>());
+
+        let input_array = Arc::new(StringArray::from(values.clone())) as ArrayRef;
+        for lhs_row in 0..values.len() {
+            let rhs_rows: Vec<usize> = (0..values.len()).collect();
+            let lhs_rows = vec![lhs_row; values.len()];
+            let mut equal_to_results = vec![true; values.len()];
+            builder.vectorized_equal_to(
+                &lhs_rows,
+                &input_array,
+                &rhs_rows,
+                &mut equal_to_results,
+            );
+
+            for rhs_row in rhs_rows {
+                let expected = values[lhs_row] == values[rhs_row];
+                assert_eq!(equal_to_results[rhs_row], expected, "{lhs_row} {rhs_row}");
+                assert_eq!(
+                    builder.equal_to(lhs_row, &input_array, rhs_row),
+                    expected,
+                    "{lhs_row} {rhs_row}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_byte_vectorized_operation_special_case() {
         // Test the special `all nulls` or `not nulls` input array case
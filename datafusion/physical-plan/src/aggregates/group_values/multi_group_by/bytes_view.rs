@@ -22,6 +22,7 @@ use arrow::buffer::ScalarBuffer;
 use arrow::datatypes::ByteViewType;
 use arrow_array::{Array, ArrayRef, GenericByteViewArray};
 use arrow_buffer::Buffer;
+use arrow_schema::DataType;
 use itertools::izip;
 use std::marker::PhantomData;
 use std::mem::{replace, size_of};
@@ -59,12 +60,26 @@ pub struct ByteViewGroupValueBuilder<B: ByteViewType> {
     /// The max size of `in_progress`
     ///
     /// `in_progress` will be flushed into `completed`, and create new `in_progress`
-    /// when found its remaining capacity(`max_block_size` - `len(in_progress)`),
+    /// when found its remaining capacity(`block_size` - `len(in_progress)`),
     /// is no enough to store the appended value.
     ///
+    /// Starts at `max_block_size`, and grows after `max_completed_blocks`
+    /// blocks were completed.
+    block_size: usize,
+
+    /// The initial block size
+    ///
     /// Currently it is fixed at 2MB.
     max_block_size: usize,
 
+    /// The number of completed blocks after which each new block is twice
+    /// as large as the previous one, if set
+    ///
+    /// This bounds the number of blocks for inputs with many large values,
+    /// at the cost of larger allocations. Blocks grow up to
+    /// `max_completed_blocks` times `max_block_size`.
+    max_completed_blocks: Option<usize>,
+
     /// Nulls
     nulls: MaybeNullBufferBuilder,
 
@@ -78,7 +93,9 @@ impl<B: ByteViewType> ByteViewGroupValueBuilder<B> {
             views: Vec::new(),
             in_progress: Vec::new(),
             completed: Vec::new(),
+            block_size: BYTE_VIEW_MAX_BLOCK_SIZE,
             max_block_size: BYTE_VIEW_MAX_BLOCK_SIZE,
+            max_completed_blocks: None,
             nulls: MaybeNullBufferBuilder::new(),
             _phantom: PhantomData {},
         }
@@ -86,10 +103,21 @@ impl<B: ByteViewType> ByteViewGroupValueBuilder<B> {
 
     /// Set the max block size
     fn with_max_block_size(mut self, max_block_size: usize) -> Self {
+        self.block_size = max_block_size;
         self.max_block_size = max_block_size;
         self
     }
 
+    /// Double the block size for every block completed after the first
+    /// `max_completed_blocks` blocks, if set
+    pub fn with_max_completed_blocks(
+        mut self,
+        max_completed_blocks: Option<usize>,
+    ) -> Self {
+        self.max_completed_blocks = max_completed_blocks;
+        self
+    }
+
     fn equal_to_inner(&self, lhs_row: usize, array: &ArrayRef, rhs_row: usize) -> bool {
         let array = array.as_byte_view::<B>();
         self.do_equal_to_inner(lhs_row, array, rhs_row)
@@ -169,9 +197,7 @@ impl<B: ByteViewType> ByteViewGroupValueBuilder<B> {
             }
 
             Some(false) => {
-                self.nulls.append_n(rows.len(), true);
-                let new_len = self.views.len() + rows.len();
-                self.views.resize(new_len, 0);
+                self.append_nulls(rows.len());
             }
         }
     }
@@ -206,13 +232,8 @@ impl<B: ByteViewType> ByteViewGroupValueBuilder<B> {
         let require_cap = self.in_progress.len() + value_len;
 
         // If current block isn't big enough, flush it and create a new in progress block
-        if require_cap > self.max_block_size {
-            let flushed_block = replace(
-                &mut self.in_progress,
-                Vec::with_capacity(self.max_block_size),
-            );
-            let buffer = Buffer::from_vec(flushed_block);
-            self.completed.push(buffer);
+        if require_cap > self.block_size {
+            self.flush_in_progress();
         }
     }
 
@@ -332,7 +353,9 @@ impl<B: ByteViewType> ByteViewGroupValueBuilder<B> {
 
         // The `n == len` case, we need to take all
         if self.len() == n {
-            let new_builder = Self::new().with_max_block_size(self.max_block_size);
+            let mut new_builder = Self::new().with_max_block_size(self.max_block_size);
+            new_builder.block_size = self.block_size;
+            new_builder.max_completed_blocks = self.max_completed_blocks;
             let cur_builder = replace(self, new_builder);
             return cur_builder.build_inner();
         }
@@ -452,12 +475,8 @@ impl<B: ByteViewType> ByteViewGroupValueBuilder<B> {
     ) -> Vec<Buffer> {
         let mut take_buffers = Vec::with_capacity(last_remaining_buffer_index + 1);
 
-        // Take `0 ~ last_remaining_buffer_index - 1` buffers
-        if !self.completed.is_empty() || last_remaining_buffer_index == 0 {
-            take_buffers.extend(self.completed.drain(0..last_remaining_buffer_index));
-        }
-
-        // Process the `last_remaining_buffer_index` buffers
+        // Process the `last_remaining_buffer_index` buffers, before the
+        // buffers in front of it are removed from `completed`
         let last_buffer = if last_remaining_buffer_index < self.completed.len() {
             // If it is in `completed`, simply clone
             self.completed[last_remaining_buffer_index].clone()
@@ -466,6 +485,11 @@ impl<B: ByteViewType> ByteViewGroupValueBuilder<B> {
             let taken_last_buffer = self.in_progress[0..last_take_len].to_vec();
             Buffer::from_vec(taken_last_buffer)
         };
+
+        // Take `0 ~ last_remaining_buffer_index - 1` buffers
+        if !self.completed.is_empty() || last_remaining_buffer_index == 0 {
+            take_buffers.extend(self.completed.drain(0..last_remaining_buffer_index));
+        }
         take_buffers.push(last_buffer);
 
         take_buffers
@@ -481,9 +505,23 @@ impl<B: ByteViewType> ByteViewGroupValueBuilder<B> {
     }
 
     fn flush_in_progress(&mut self) {
+        let mut new_block_capacity = self.block_size;
+        if let Some(max_completed_blocks) = self.max_completed_blocks {
+            if self.completed.len() >= max_completed_blocks {
+                // the views store offsets into a block as u32
+                let limit = self
+                    .max_block_size
+                    .saturating_mul(max_completed_blocks)
+                    .min(u32::MAX as usize);
+                self.block_size = (self.block_size * 2).min(limit).max(self.block_size);
+                // grown blocks are only allocated as far as they are used
+                new_block_capacity = 0;
+            }
+        }
+
         let flushed_block = replace(
             &mut self.in_progress,
-            Vec::with_capacity(self.max_block_size),
+            Vec::with_capacity(new_block_capacity),
         );
         let buffer = Buffer::from_vec(flushed_block);
         self.completed.push(buffer);
@@ -513,6 +551,16 @@ impl<B: ByteViewType> GroupColumn for ByteViewGroupValueBuilder<B> {
         self.vectorized_append_inner(array, rows);
     }
 
+    fn accepts(&self, data_type: &DataType) -> bool {
+        data_type == &B::DATA_TYPE
+    }
+
+    fn append_nulls(&mut self, n: usize) {
+        self.nulls.append_n(n, true);
+        let new_len = self.views.len() + n;
+        self.views.resize(new_len, 0);
+    }
+
     fn len(&self) -> usize {
         self.views.len()
     }
@@ -792,6 +840,45 @@ mod tests {
         assert!(equal_to_results[11]);
     }
 
+    #[test]
+    fn test_byte_view_max_completed_blocks() {
+        let input_array: ArrayRef = Arc::new(StringViewArray::from_iter_values(
+            (0..1000).map(|i| format!("a long value that is not inlined {i:04}")),
+        ));
+        let rows: Vec<usize> = (0..input_array.len()).collect();
+
+        // each block holds a single value
+        let mut builder =
+            ByteViewGroupValueBuilder::<StringViewType>::new().with_max_block_size(64);
+        builder.vectorized_append(&input_array, &rows);
+        assert_eq!(builder.completed.len(), 999);
+
+        // after 4 blocks the block size doubles, so the number of blocks
+        // only grows logarithmically with the size of the values
+        let mut builder = ByteViewGroupValueBuilder::<StringViewType>::new()
+            .with_max_block_size(64)
+            .with_max_completed_blocks(Some(4));
+        builder.vectorized_append(&input_array, &rows);
+        assert!(
+            builder.completed.len() < 999 / 4,
+            "unexpected number of blocks: {}",
+            builder.completed.len()
+        );
+        // the block size stops growing at 4 times the initial size
+        assert_eq!(builder.block_size, 4 * 64);
+        assert!(builder.completed.iter().all(|block| block.len() <= 4 * 64));
+
+        let mut equal_to_results = vec![true; rows.len()];
+        builder.vectorized_equal_to(&rows, &input_array, &rows, &mut equal_to_results);
+        assert!(equal_to_results.iter().all(|&equal| equal));
+
+        // taking some values keeps the remaining values intact
+        let taken_array = builder.take_n(300);
+        assert_eq!(&taken_array, &input_array.slice(0, 300));
+        let output = Box::new(builder).build();
+        assert_eq!(&output, &input_array.slice(300, 700));
+    }
+
     #[test]
     fn test_byte_view_take_n() {
         // ####### Define cases and init #######
@@ -908,4 +995,24 @@ mod tests {
         let taken_array = builder.take_n(final_ones_to_append);
         assert_eq!(&taken_array, &input_array);
     }
+
+    #[test]
+    fn test_byte_view_take_n_partial_last_completed_buffer() {
+        // each block holds two values
+        let mut builder =
+            ByteViewGroupValueBuilder::<StringViewType>::new().with_max_block_size(60);
+        let input_array: ArrayRef = Arc::new(StringViewArray::from_iter_values(
+            (0..6).map(|i| format!("this string is long {i:05}")),
+        ));
+        for row in 0..input_array.len() {
+            builder.append_val(&input_array, row);
+        }
+        assert_eq!(builder.completed.len(), 2);
+
+        // the last taken value is the first one of the second completed block
+        let taken_array = builder.take_n(3);
+        assert_eq!(&taken_array, &input_array.slice(0, 3));
+        let output = Box::new(builder).build();
+        assert_eq!(&output, &input_array.slice(3, 3));
+    }
 }
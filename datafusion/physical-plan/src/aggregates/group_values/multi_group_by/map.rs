@@ -0,0 +1,429 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::aggregates::group_values::multi_group_by::{
+    instantiate_group_column, nulls_equal_to, GroupColumn,
+};
+use crate::aggregates::group_values::null_builder::MaybeNullBufferBuilder;
+use arrow::array::AsArray;
+use arrow::buffer::{NullBuffer, OffsetBuffer, ScalarBuffer};
+use arrow::compute::{lexsort_to_indices, take, SortColumn};
+use arrow::datatypes::{DataType, FieldRef};
+use arrow_array::{Array, ArrayRef, MapArray, StructArray, UInt32Array};
+use datafusion_common::utils::proxy::VecAllocExt;
+use datafusion_common::{internal_err, Result};
+use itertools::izip;
+use std::iter;
+use std::sync::Arc;
+
+/// An implementation of [`GroupColumn`] for `Map` values
+///
+/// Two maps are in the same group if they have the same entries, regardless
+/// of the order of the entries. To compare maps entry by entry, the input
+/// arrays must have their entries sorted by key with [`sort_map_entries`],
+/// which also makes the hash of equal maps the same. Consequently the
+/// entries of the output maps are sorted by key as well.
+///
+/// The keys and values of the entries are stored in nested [`GroupColumn`]s.
+pub struct MapGroupValueBuilder {
+    /// The field of the map entries, a struct of the key and value
+    entries_field: FieldRef,
+    /// Whether the output map is declared to have sorted keys
+    keys_sorted: bool,
+    /// Offsets into `keys` and `values` for each map: the entries of the
+    /// `i`th map are stored at `offsets[i]..offsets[i+1]`. Null maps are
+    /// stored without any entries
+    offsets: Vec<i32>,
+    /// Nulls
+    nulls: MaybeNullBufferBuilder,
+    /// The keys of the entries of all maps
+    keys: Box<dyn GroupColumn>,
+    /// The values of the entries of all maps
+    values: Box<dyn GroupColumn>,
+}
+
+impl MapGroupValueBuilder {
+    /// Create a new builder for a `Map` with `entries_field`
+    pub fn try_new(entries_field: FieldRef, keys_sorted: bool) -> Result<Self> {
+        let DataType::Struct(entry_fields) = entries_field.data_type() else {
+            return internal_err!(
+                "Map entries must be a struct, got {}",
+                entries_field.data_type()
+            );
+        };
+        if entry_fields.len() != 2 {
+            return internal_err!(
+                "Map entries must have a key and a value, got {entry_fields:?}"
+            );
+        }
+
+        let mut builders = Vec::with_capacity(2);
+        for field in entry_fields.iter() {
+            instantiate_group_column(
+                &mut builders,
+                field.data_type(),
+                field.is_nullable(),
+                false,
+                false,
+                false,
+                None,
+            )?;
+        }
+        let values = builders.pop().unwrap();
+        let keys = builders.pop().unwrap();
+
+        Ok(Self {
+            entries_field,
+            keys_sorted,
+            offsets: vec![0],
+            nulls: MaybeNullBufferBuilder::new(),
+            keys,
+            values,
+        })
+    }
+}
+
+impl GroupColumn for MapGroupValueBuilder {
+    fn equal_to(&self, lhs_row: usize, array: &ArrayRef, rhs_row: usize) -> bool {
+        let array = array.as_map();
+        let exist_null = self.nulls.is_null(lhs_row);
+        let input_null = array.is_null(rhs_row);
+        if let Some(result) = nulls_equal_to(exist_null, input_null) {
+            return result;
+        }
+
+        let lhs_start = self.offsets[lhs_row] as usize;
+        let lhs_end = self.offsets[lhs_row + 1] as usize;
+        let rhs_start = array.value_offsets()[rhs_row] as usize;
+        let rhs_end = array.value_offsets()[rhs_row + 1] as usize;
+        if lhs_end - lhs_start != rhs_end - rhs_start {
+            return false;
+        }
+
+        let (keys, values) = (array.keys(), array.values());
+        (lhs_start..lhs_end)
+            .zip(rhs_start..rhs_end)
+            .all(|(lhs_entry, rhs_entry)| {
+                self.keys.equal_to(lhs_entry, keys, rhs_entry)
+                    && self.values.equal_to(lhs_entry, values, rhs_entry)
+            })
+    }
+
+    fn append_val(&mut self, array: &ArrayRef, row: usize) {
+        self.vectorized_append(array, &[row])
+    }
+
+    fn vectorized_equal_to(
+        &self,
+        lhs_rows: &[usize],
+        array: &ArrayRef,
+        rhs_rows: &[usize],
+        equal_to_results: &mut [bool],
+    ) {
+        let iter = izip!(
+            lhs_rows.iter(),
+            rhs_rows.iter(),
+            equal_to_results.iter_mut(),
+        );
+
+        for (&lhs_row, &rhs_row, equal_to_result) in iter {
+            // Has found not equal to, don't need to check
+            if !*equal_to_result {
+                continue;
+            }
+
+            *equal_to_result = self.equal_to(lhs_row, array, rhs_row);
+        }
+    }
+
+    fn vectorized_append(&mut self, array: &ArrayRef, rows: &[usize]) {
+        let map = array.as_map();
+        let offsets = map.value_offsets();
+
+        let mut entry_rows = vec![];
+        for &row in rows {
+            let mut last_offset = *self.offsets.last().unwrap();
+            if map.is_null(row) {
+                self.nulls.append(true);
+            } else {
+                self.nulls.append(false);
+                let (start, end) = (offsets[row], offsets[row + 1]);
+                entry_rows.extend(start as usize..end as usize);
+                last_offset += end - start;
+            }
+            self.offsets.push(last_offset);
+        }
+
+        self.keys.vectorized_append(map.keys(), &entry_rows);
+        self.values.vectorized_append(map.values(), &entry_rows);
+    }
+
+    fn accepts(&self, data_type: &DataType) -> bool {
+        let DataType::Map(entries_field, _) = data_type else {
+            return false;
+        };
+        match entries_field.data_type() {
+            DataType::Struct(fields) if fields.len() == 2 => {
+                self.keys.accepts(fields[0].data_type())
+                    && self.values.accepts(fields[1].data_type())
+            }
+            _ => false,
+        }
+    }
+
+    fn append_nulls(&mut self, n: usize) {
+        self.nulls.append_n(n, true);
+
+        // nulls have no entries
+        let offset = *self.offsets.last().unwrap();
+        self.offsets.resize(self.offsets.len() + n, offset);
+    }
+
+    fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    fn size(&self) -> usize {
+        self.keys.size()
+            + self.values.size()
+            + self.offsets.allocated_size()
+            + self.nulls.allocated_size()
+    }
+
+    fn build(self: Box<Self>) -> ArrayRef {
+        let Self {
+            entries_field,
+            keys_sorted,
+            offsets,
+            nulls,
+            keys,
+            values,
+        } = *self;
+
+        new_map_array(
+            entries_field,
+            keys_sorted,
+            offsets,
+            keys.build(),
+            values.build(),
+            nulls.build(),
+        )
+    }
+
+    fn take_n(&mut self, n: usize) -> ArrayRef {
+        debug_assert!(self.len() >= n);
+        let nulls = self.nulls.take_n(n);
+
+        // Given offsets like [0, 2, 4, 5] and n = 1, the first n maps have
+        // offsets [0, 2] and the remaining ones [0, 2, 3]
+        let num_entries = self.offsets[n];
+        let mut first_n_offsets = self.offsets.drain(0..n).collect::<Vec<_>>();
+        first_n_offsets.push(num_entries);
+        self.offsets
+            .iter_mut()
+            .for_each(|offset| *offset -= num_entries);
+
+        let keys = self.keys.take_n(num_entries as usize);
+        let values = self.values.take_n(num_entries as usize);
+        new_map_array(
+            Arc::clone(&self.entries_field),
+            self.keys_sorted,
+            first_n_offsets,
+            keys,
+            values,
+            nulls,
+        )
+    }
+}
+
+/// Creates a [`MapArray`] from the stored entries of [`MapGroupValueBuilder`]
+fn new_map_array(
+    entries_field: FieldRef,
+    keys_sorted: bool,
+    offsets: Vec<i32>,
+    keys: ArrayRef,
+    values: ArrayRef,
+    nulls: Option<NullBuffer>,
+) -> ArrayRef {
+    let DataType::Struct(entry_fields) = entries_field.data_type() else {
+        unreachable!("checked in MapGroupValueBuilder::try_new")
+    };
+    let entries = StructArray::new(entry_fields.clone(), vec![keys, values], None);
+    // SAFETY: the offsets are monotonically increasing, as every map only
+    // appends entries
+    let offsets = unsafe { OffsetBuffer::new_unchecked(ScalarBuffer::from(offsets)) };
+    Arc::new(MapArray::new(
+        entries_field,
+        offsets,
+        entries,
+        nulls,
+        keys_sorted,
+    ))
+}
+
+/// Returns a copy of `array` with the entries of each map sorted by key
+///
+/// This gives equal maps the same representation, independent of the order
+/// of their entries, as required by [`MapGroupValueBuilder`].
+pub fn sort_map_entries(array: &MapArray) -> Result<ArrayRef> {
+    let offsets = array.value_offsets();
+    // nothing to sort if no map has more than one entry
+    if offsets.windows(2).all(|w| w[1] - w[0] <= 1) {
+        return Ok(Arc::new(array.clone()));
+    }
+
+    // sort the entries by the map they belong to first, so entries are only
+    // reordered within each map
+    let first = offsets[0];
+    let num_entries = (offsets[array.len()] - first) as usize;
+    let parents: UInt32Array = offsets
+        .windows(2)
+        .enumerate()
+        .flat_map(|(i, w)| iter::repeat(i as u32).take((w[1] - w[0]) as usize))
+        .collect();
+    let entries = array.entries().slice(first as usize, num_entries);
+    let indices = lexsort_to_indices(
+        &[
+            SortColumn {
+                values: Arc::new(parents),
+                options: None,
+            },
+            SortColumn {
+                values: Arc::clone(entries.column(0)),
+                options: None,
+            },
+        ],
+        None,
+    )?;
+    let entries = take(&entries, &indices, None)?.as_struct().clone();
+
+    let offsets = offsets
+        .iter()
+        .map(|offset| offset - first)
+        .collect::<Vec<_>>();
+    let DataType::Map(entries_field, keys_sorted) = array.data_type() else {
+        unreachable!("MapArray has a map type")
+    };
+    Ok(Arc::new(MapArray::try_new(
+        Arc::clone(entries_field),
+        OffsetBuffer::new(ScalarBuffer::from(offsets)),
+        entries,
+        array.nulls().cloned(),
+        *keys_sorted,
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow::array::{Int32Builder, MapBuilder, StringBuilder};
+
+    type Entries<'a> = &'a [(&'a str, Option<i32>)];
+
+    /// Creates a map array from `maps`, where `None` is a null map
+    fn map_array(maps: &[Option<Entries>]) -> ArrayRef {
+        let mut builder =
+            MapBuilder::new(None, StringBuilder::new(), Int32Builder::new());
+        for map in maps {
+            match map {
+                Some(entries) => {
+                    for (key, value) in entries.iter() {
+                        builder.keys().append_value(key);
+                        builder.values().append_option(*value);
+                    }
+                    builder.append(true).unwrap();
+                }
+                None => builder.append(false).unwrap(),
+            }
+        }
+        Arc::new(builder.finish())
+    }
+
+    fn new_builder(array: &ArrayRef) -> MapGroupValueBuilder {
+        let DataType::Map(field, sorted) = array.data_type() else {
+            unreachable!()
+        };
+        MapGroupValueBuilder::try_new(Arc::clone(field), *sorted).unwrap()
+    }
+
+    #[test]
+    fn test_map_equal_to() {
+        let input = map_array(&[
+            Some(&[("b", Some(2)), ("a", Some(1))]),
+            Some(&[("a", Some(1)), ("b", Some(2))]),
+            Some(&[("a", Some(1)), ("b", None)]),
+            Some(&[]),
+            None,
+        ]);
+        let input = sort_map_entries(input.as_map()).unwrap();
+
+        let mut builder = new_builder(&input);
+        builder.vectorized_append(&input, &[0, 2, 3, 4]);
+        assert_eq!(builder.len(), 4);
+
+        // maps with the same entries in a different order
+        assert!(builder.equal_to(0, &input, 0));
+        assert!(builder.equal_to(0, &input, 1));
+        // null values, empty and null maps
+        assert!(builder.equal_to(1, &input, 2));
+        assert!(!builder.equal_to(0, &input, 2));
+        assert!(builder.equal_to(2, &input, 3));
+        assert!(!builder.equal_to(2, &input, 4));
+        assert!(builder.equal_to(3, &input, 4));
+        assert!(!builder.equal_to(3, &input, 3));
+
+        let mut equal_to_results = vec![true, true, false, true];
+        builder.vectorized_equal_to(
+            &[0, 1, 2, 3],
+            &input,
+            &[1, 1, 3, 3],
+            &mut equal_to_results,
+        );
+        assert_eq!(equal_to_results, vec![true, false, false, false]);
+    }
+
+    #[test]
+    fn test_map_take_n_and_build() {
+        let input = map_array(&[
+            Some(&[("b", Some(2)), ("a", Some(1))]),
+            None,
+            Some(&[]),
+            Some(&[("c", None)]),
+        ]);
+        let input = sort_map_entries(input.as_map()).unwrap();
+
+        let mut builder = new_builder(&input);
+        builder.vectorized_append(&input, &[0, 1]);
+        builder.append_val(&input, 2);
+        builder.append_nulls(1);
+        builder.append_val(&input, 3);
+        assert_eq!(builder.len(), 5);
+
+        let first = builder.take_n(2);
+        let expected = map_array(&[Some(&[("a", Some(1)), ("b", Some(2))]), None]);
+        assert_eq!(&first, &expected);
+
+        assert_eq!(builder.len(), 3);
+        assert!(builder.equal_to(0, &input, 2));
+        assert!(builder.equal_to(2, &input, 3));
+
+        let rest = Box::new(builder).build();
+        let expected = map_array(&[Some(&[]), None, Some(&[("c", None)])]);
+        assert_eq!(&rest, &expected);
+    }
+}
@@ -19,13 +19,18 @@
 
 mod bytes;
 mod bytes_view;
+mod map;
 mod primitive;
 
+use std::borrow::Cow;
 use std::mem::{self, size_of};
+use std::sync::Arc;
 
 use crate::aggregates::group_values::multi_group_by::{
-    bytes::ByteGroupValueBuilder, bytes_view::ByteViewGroupValueBuilder,
-    primitive::PrimitiveGroupValueBuilder,
+    bytes::ByteGroupValueBuilder,
+    bytes_view::ByteViewGroupValueBuilder,
+    map::{sort_map_entries, MapGroupValueBuilder},
+    primitive::{normalize_f32, normalize_f64, PrimitiveGroupValueBuilder},
 };
 use crate::aggregates::group_values::GroupValues;
 use ahash::RandomState;
@@ -38,16 +43,19 @@ use arrow::datatypes::{
     TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
 };
 use arrow::record_batch::RecordBatch;
+use arrow_array::cast::AsArray;
 use arrow_array::{Array, ArrayRef};
 use arrow_schema::{DataType, Schema, SchemaRef, TimeUnit};
 use datafusion_common::hash_utils::create_hashes;
-use datafusion_common::{not_impl_err, DataFusionError, Result};
+use datafusion_common::{internal_err, not_impl_err, DataFusionError, Result};
 use datafusion_execution::memory_pool::proxy::{HashTableAllocExt, VecAllocExt};
 use datafusion_expr::EmitTo;
 use datafusion_physical_expr::binary_map::OutputType;
 
 use hashbrown::hash_table::HashTable;
 
+pub(crate) use primitive::normalize_float_array;
+
 const NON_INLINED_FLAG: u64 = 0x8000000000000000;
 const VALUE_MASK: u64 = 0x7FFFFFFFFFFFFFFF;
 
@@ -89,6 +97,24 @@ pub trait GroupColumn: Send + Sync {
     /// The vectorized version `append_val`
     fn vectorized_append(&mut self, array: &ArrayRef, rows: &[usize]);
 
+    /// Returns true if values of `data_type` can be compared to and appended
+    /// to this builder
+    ///
+    /// [`GroupValuesColumn`] checks its input columns with this before
+    /// interning them, so the other methods may assume that `array` has a
+    /// type accepted by the builder.
+    ///
+    /// [`GroupValuesColumn`]: crate::aggregates::group_values::GroupValuesColumn
+    fn accepts(&self, data_type: &DataType) -> bool;
+
+    /// Appends `n` null values to this builder
+    ///
+    /// This is equivalent to calling `append_val` with a null row `n` times,
+    /// but extends the null mask and value storage in bulk.
+    ///
+    /// Panics if the builder is specialized for non nullable input.
+    fn append_nulls(&mut self, n: usize);
+
     /// Returns the number of rows stored in this builder
     fn len(&self) -> usize;
 
@@ -218,6 +244,37 @@ pub struct GroupValuesColumn<const STREAMING: bool> {
     /// reused buffer to store hashes
     hashes_buffer: Vec<u64>,
 
+    /// Indices of the group columns that are known to be constant (for
+    /// example from the input's equivalence properties).
+    ///
+    /// Constant columns can not distinguish groups, so they are excluded
+    /// when hashing the group values. They are still stored in
+    /// [`Self::group_values`] and compared, so grouping remains correct
+    /// even if a column turns out not to be constant.
+    constant_columns: Vec<usize>,
+
+    /// If true, float group values are normalized so that all NaNs are
+    /// in one group and `-0.0` is in the same group as `+0.0`, matching
+    /// SQL grouping semantics. Otherwise float values are grouped by their
+    /// exact bit pattern.
+    normalize_floats: bool,
+
+    /// If true, `Utf8View` / `BinaryView` group values are cast to
+    /// `LargeUtf8` / `LargeBinary` on input and stored with
+    /// [`ByteGroupValueBuilder`] instead of [`ByteViewGroupValueBuilder`].
+    /// They are cast back to the view types on output.
+    views_as_offsets: bool,
+
+    /// If true, `LargeUtf8` / `LargeBinary` group values are cast to
+    /// `Utf8View` / `BinaryView` on input and stored with
+    /// [`ByteViewGroupValueBuilder`] instead of [`ByteGroupValueBuilder`].
+    /// They are cast back to the large types on output.
+    large_as_views: bool,
+
+    /// If set, the number of completed blocks after which each new block of
+    /// a [`ByteViewGroupValueBuilder`] is twice as large as the previous one
+    max_completed_view_blocks: Option<usize>,
+
     /// Random state for creating hashes
     random_state: RandomState,
 }
@@ -271,10 +328,157 @@ impl<const STREAMING: bool> GroupValuesColumn<STREAMING> {
             map_size: 0,
             group_values: vec![],
             hashes_buffer: Default::default(),
+            constant_columns: vec![],
+            normalize_floats: false,
+            views_as_offsets: false,
+            large_as_views: false,
+            max_completed_view_blocks: None,
             random_state: Default::default(),
         })
     }
 
+    /// Exclude the group columns at `constant_columns` from the hash
+    /// computation. See [`Self::constant_columns`] for details.
+    pub fn with_constant_columns(mut self, constant_columns: Vec<usize>) -> Self {
+        self.constant_columns = constant_columns;
+        self
+    }
+
+    /// Set whether float group values are normalized. See
+    /// [`Self::normalize_floats`] for details.
+    pub fn with_float_normalization(mut self, normalize_floats: bool) -> Self {
+        self.normalize_floats = normalize_floats;
+        self
+    }
+
+    /// Set whether view group values are stored as offset based values. See
+    /// [`Self::views_as_offsets`] for details.
+    pub fn with_views_as_offsets(mut self, views_as_offsets: bool) -> Self {
+        self.views_as_offsets = views_as_offsets;
+        self
+    }
+
+    /// Set whether large group values are stored as view values. See
+    /// [`Self::large_as_views`] for details.
+    pub fn with_large_as_views(mut self, large_as_views: bool) -> Self {
+        self.large_as_views = large_as_views;
+        self
+    }
+
+    /// Set the number of completed blocks of view group values after which
+    /// the block size grows. See [`Self::max_completed_view_blocks`] for
+    /// details.
+    pub fn with_max_completed_view_blocks(
+        mut self,
+        max_completed_view_blocks: Option<usize>,
+    ) -> Self {
+        self.max_completed_view_blocks = max_completed_view_blocks;
+        self
+    }
+
+    /// Casts the view and large columns in `cols` to the types they are
+    /// stored as if [`Self::views_as_offsets`] or [`Self::large_as_views`]
+    /// is set
+    fn cast_to_stored_types<'a>(
+        &self,
+        cols: &'a [ArrayRef],
+    ) -> Result<Cow<'a, [ArrayRef]>> {
+        if !self.views_as_offsets && !self.large_as_views {
+            return Ok(Cow::Borrowed(cols));
+        }
+        let cols = cols
+            .iter()
+            .map(|col| match col.data_type() {
+                DataType::Utf8View if self.views_as_offsets => {
+                    Ok(cast(col, &DataType::LargeUtf8)?)
+                }
+                DataType::BinaryView if self.views_as_offsets => {
+                    Ok(cast(col, &DataType::LargeBinary)?)
+                }
+                DataType::LargeUtf8 if self.large_as_views => {
+                    Ok(cast(col, &DataType::Utf8View)?)
+                }
+                DataType::LargeBinary if self.large_as_views => {
+                    Ok(cast(col, &DataType::BinaryView)?)
+                }
+                _ => Ok(Arc::clone(col)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Cow::Owned(cols))
+    }
+
+    /// Returns an error if `cols` does not match the group columns, so that
+    /// the [`GroupColumn`]s never see an input of a type they do not accept
+    fn check_input_types(&self, cols: &[ArrayRef]) -> Result<()> {
+        if cols.len() != self.group_values.len() {
+            return internal_err!(
+                "GroupValuesColumn expected {} group columns, got {}",
+                self.group_values.len(),
+                cols.len()
+            );
+        }
+        for (i, (group_value, col)) in self.group_values.iter().zip(cols).enumerate() {
+            if !group_value.accepts(col.data_type()) {
+                return internal_err!(
+                    "GroupValuesColumn can not intern values of type {} into group column {i} of type {}",
+                    col.data_type(),
+                    self.schema.field(i).data_type()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Sorts the entries of the `Map` columns in `cols` by key, so that equal
+    /// maps are hashed and compared the same regardless of the order of
+    /// their entries. See [`MapGroupValueBuilder`] for details.
+    fn sort_map_entries(cols: &[ArrayRef]) -> Result<Cow<'_, [ArrayRef]>> {
+        if !cols
+            .iter()
+            .any(|col| matches!(col.data_type(), DataType::Map(_, _)))
+        {
+            return Ok(Cow::Borrowed(cols));
+        }
+        let cols = cols
+            .iter()
+            .map(|col| match col.data_type() {
+                DataType::Map(_, _) => sort_map_entries(col.as_map()),
+                _ => Ok(Arc::clone(col)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Cow::Owned(cols))
+    }
+
+    /// Calculates the hash of each row in `cols` into `batch_hashes`,
+    /// ignoring the columns in [`Self::constant_columns`] and hashing
+    /// normalized float values if [`Self::normalize_floats`] is set
+    fn create_group_hashes(
+        constant_columns: &[usize],
+        normalize_floats: bool,
+        cols: &[ArrayRef],
+        random_state: &RandomState,
+        batch_hashes: &mut Vec<u64>,
+    ) -> Result<()> {
+        if constant_columns.is_empty() && !normalize_floats {
+            create_hashes(cols, random_state, batch_hashes)?;
+        } else {
+            let hash_cols: Vec<_> = cols
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| !constant_columns.contains(idx))
+                .map(|(_, col)| {
+                    if normalize_floats {
+                        normalize_float_array(col)
+                    } else {
+                        Arc::clone(col)
+                    }
+                })
+                .collect();
+            create_hashes(&hash_cols, random_state, batch_hashes)?;
+        }
+        Ok(())
+    }
+
     // ========================================================================
     // Scalarized intern
     // ========================================================================
@@ -333,7 +537,13 @@ impl<const STREAMING: bool> GroupValuesColumn<STREAMING> {
         let batch_hashes = &mut self.hashes_buffer;
         batch_hashes.clear();
         batch_hashes.resize(n_rows, 0);
-        create_hashes(cols, &self.random_state, batch_hashes)?;
+        Self::create_group_hashes(
+            &self.constant_columns,
+            self.normalize_floats,
+            cols,
+            &self.random_state,
+            batch_hashes,
+        )?;
 
         for (row, &target_hash) in batch_hashes.iter().enumerate() {
             let entry = self
@@ -435,7 +645,13 @@ impl<const STREAMING: bool> GroupValuesColumn<STREAMING> {
         let mut batch_hashes = mem::take(&mut self.hashes_buffer);
         batch_hashes.clear();
         batch_hashes.resize(n_rows, 0);
-        create_hashes(cols, &self.random_state, &mut batch_hashes)?;
+        Self::create_group_hashes(
+            &self.constant_columns,
+            self.normalize_floats,
+            cols,
+            &self.random_state,
+            &mut batch_hashes,
+        )?;
 
         // General steps for one round `vectorized equal_to & append`:
         //   1. Collect vectorized context by checking hash values of `cols` in `map`,
@@ -878,6 +1094,8 @@ impl<const STREAMING: bool> GroupValuesColumn<STREAMING> {
 /// `$v`: the vector to push the new builder into
 /// `$nullable`: whether the input can contains nulls
 /// `$t`: the primitive type of the builder
+/// `$normalize`: optional function to normalize the input values with, see
+/// [`PrimitiveGroupValueBuilder::with_normalization`]
 ///
 macro_rules! instantiate_primitive {
     ($v:expr, $nullable:expr, $t:ty, $data_type:ident) => {
@@ -889,6 +1107,170 @@ macro_rules! instantiate_primitive {
             $v.push(Box::new(b) as _)
         }
     };
+    ($v:expr, $nullable:expr, $t:ty, $data_type:ident, $normalize:expr) => {
+        if $nullable {
+            let b = PrimitiveGroupValueBuilder::<$t, true>::new($data_type.to_owned())
+                .with_normalization($normalize);
+            $v.push(Box::new(b) as _)
+        } else {
+            let b = PrimitiveGroupValueBuilder::<$t, false>::new($data_type.to_owned())
+                .with_normalization($normalize);
+            $v.push(Box::new(b) as _)
+        }
+    };
+}
+
+/// Appends the [`GroupColumn`] that stores values of `data_type` to `v`
+///
+/// See [`GroupValuesColumn::normalize_floats`],
+/// [`GroupValuesColumn::views_as_offsets`],
+/// [`GroupValuesColumn::large_as_views`] and
+/// [`GroupValuesColumn::max_completed_view_blocks`] for the meaning of the
+/// options
+fn instantiate_group_column(
+    v: &mut Vec<Box<dyn GroupColumn>>,
+    data_type: &DataType,
+    nullable: bool,
+    normalize_floats: bool,
+    views_as_offsets: bool,
+    large_as_views: bool,
+    max_completed_view_blocks: Option<usize>,
+) -> Result<()> {
+    match data_type {
+        &DataType::Int8 => {
+            instantiate_primitive!(v, nullable, Int8Type, data_type)
+        }
+        &DataType::Int16 => {
+            instantiate_primitive!(v, nullable, Int16Type, data_type)
+        }
+        &DataType::Int32 => {
+            instantiate_primitive!(v, nullable, Int32Type, data_type)
+        }
+        &DataType::Int64 => {
+            instantiate_primitive!(v, nullable, Int64Type, data_type)
+        }
+        &DataType::UInt8 => {
+            instantiate_primitive!(v, nullable, UInt8Type, data_type)
+        }
+        &DataType::UInt16 => {
+            instantiate_primitive!(v, nullable, UInt16Type, data_type)
+        }
+        &DataType::UInt32 => {
+            instantiate_primitive!(v, nullable, UInt32Type, data_type)
+        }
+        &DataType::UInt64 => {
+            instantiate_primitive!(v, nullable, UInt64Type, data_type)
+        }
+        &DataType::Float32 if normalize_floats => {
+            instantiate_primitive!(v, nullable, Float32Type, data_type, normalize_f32)
+        }
+        &DataType::Float32 => {
+            instantiate_primitive!(v, nullable, Float32Type, data_type)
+        }
+        &DataType::Float64 if normalize_floats => {
+            instantiate_primitive!(v, nullable, Float64Type, data_type, normalize_f64)
+        }
+        &DataType::Float64 => {
+            instantiate_primitive!(v, nullable, Float64Type, data_type)
+        }
+        &DataType::Date32 => {
+            instantiate_primitive!(v, nullable, Date32Type, data_type)
+        }
+        &DataType::Date64 => {
+            instantiate_primitive!(v, nullable, Date64Type, data_type)
+        }
+        &DataType::Time32(t) => match t {
+            TimeUnit::Second => {
+                instantiate_primitive!(v, nullable, Time32SecondType, data_type)
+            }
+            TimeUnit::Millisecond => {
+                instantiate_primitive!(v, nullable, Time32MillisecondType, data_type)
+            }
+            _ => {}
+        },
+        &DataType::Time64(t) => match t {
+            TimeUnit::Microsecond => {
+                instantiate_primitive!(v, nullable, Time64MicrosecondType, data_type)
+            }
+            TimeUnit::Nanosecond => {
+                instantiate_primitive!(v, nullable, Time64NanosecondType, data_type)
+            }
+            _ => {}
+        },
+        &DataType::Timestamp(t, _) => match t {
+            TimeUnit::Second => {
+                instantiate_primitive!(v, nullable, TimestampSecondType, data_type)
+            }
+            TimeUnit::Millisecond => {
+                instantiate_primitive!(v, nullable, TimestampMillisecondType, data_type)
+            }
+            TimeUnit::Microsecond => {
+                instantiate_primitive!(v, nullable, TimestampMicrosecondType, data_type)
+            }
+            TimeUnit::Nanosecond => {
+                instantiate_primitive!(v, nullable, TimestampNanosecondType, data_type)
+            }
+        },
+        &DataType::Decimal128(_, _) => {
+            instantiate_primitive! {
+                v,
+                nullable,
+                Decimal128Type,
+                data_type
+            }
+        }
+        &DataType::Utf8 => {
+            let b = ByteGroupValueBuilder::<i32>::new(OutputType::Utf8);
+            v.push(Box::new(b) as _)
+        }
+        &DataType::LargeUtf8 if large_as_views => {
+            let b = ByteViewGroupValueBuilder::<StringViewType>::new()
+                .with_max_completed_blocks(max_completed_view_blocks);
+            v.push(Box::new(b) as _)
+        }
+        &DataType::LargeUtf8 => {
+            let b = ByteGroupValueBuilder::<i64>::new(OutputType::Utf8);
+            v.push(Box::new(b) as _)
+        }
+        &DataType::Binary => {
+            let b = ByteGroupValueBuilder::<i32>::new(OutputType::Binary);
+            v.push(Box::new(b) as _)
+        }
+        &DataType::LargeBinary if large_as_views => {
+            let b = ByteViewGroupValueBuilder::<BinaryViewType>::new()
+                .with_max_completed_blocks(max_completed_view_blocks);
+            v.push(Box::new(b) as _)
+        }
+        &DataType::LargeBinary => {
+            let b = ByteGroupValueBuilder::<i64>::new(OutputType::Binary);
+            v.push(Box::new(b) as _)
+        }
+        &DataType::Utf8View if views_as_offsets => {
+            let b = ByteGroupValueBuilder::<i64>::new(OutputType::Utf8);
+            v.push(Box::new(b) as _)
+        }
+        &DataType::Utf8View => {
+            let b = ByteViewGroupValueBuilder::<StringViewType>::new()
+                .with_max_completed_blocks(max_completed_view_blocks);
+            v.push(Box::new(b) as _)
+        }
+        &DataType::BinaryView if views_as_offsets => {
+            let b = ByteGroupValueBuilder::<i64>::new(OutputType::Binary);
+            v.push(Box::new(b) as _)
+        }
+        &DataType::BinaryView => {
+            let b = ByteViewGroupValueBuilder::<BinaryViewType>::new()
+                .with_max_completed_blocks(max_completed_view_blocks);
+            v.push(Box::new(b) as _)
+        }
+        DataType::Map(entries_field, keys_sorted) => {
+            let b =
+                MapGroupValueBuilder::try_new(Arc::clone(entries_field), *keys_sorted)?;
+            v.push(Box::new(b) as _)
+        }
+        dt => return not_impl_err!("{dt} not supported in GroupValuesColumn"),
+    }
+    Ok(())
 }
 
 impl<const STREAMING: bool> GroupValues for GroupValuesColumn<STREAMING> {
@@ -897,161 +1279,26 @@ impl<const STREAMING: bool> GroupValues for GroupValuesColumn<STREAMING> {
             let mut v = Vec::with_capacity(cols.len());
 
             for f in self.schema.fields().iter() {
-                let nullable = f.is_nullable();
-                let data_type = f.data_type();
-                match data_type {
-                    &DataType::Int8 => {
-                        instantiate_primitive!(v, nullable, Int8Type, data_type)
-                    }
-                    &DataType::Int16 => {
-                        instantiate_primitive!(v, nullable, Int16Type, data_type)
-                    }
-                    &DataType::Int32 => {
-                        instantiate_primitive!(v, nullable, Int32Type, data_type)
-                    }
-                    &DataType::Int64 => {
-                        instantiate_primitive!(v, nullable, Int64Type, data_type)
-                    }
-                    &DataType::UInt8 => {
-                        instantiate_primitive!(v, nullable, UInt8Type, data_type)
-                    }
-                    &DataType::UInt16 => {
-                        instantiate_primitive!(v, nullable, UInt16Type, data_type)
-                    }
-                    &DataType::UInt32 => {
-                        instantiate_primitive!(v, nullable, UInt32Type, data_type)
-                    }
-                    &DataType::UInt64 => {
-                        instantiate_primitive!(v, nullable, UInt64Type, data_type)
-                    }
-                    &DataType::Float32 => {
-                        instantiate_primitive!(v, nullable, Float32Type, data_type)
-                    }
-                    &DataType::Float64 => {
-                        instantiate_primitive!(v, nullable, Float64Type, data_type)
-                    }
-                    &DataType::Date32 => {
-                        instantiate_primitive!(v, nullable, Date32Type, data_type)
-                    }
-                    &DataType::Date64 => {
-                        instantiate_primitive!(v, nullable, Date64Type, data_type)
-                    }
-                    &DataType::Time32(t) => match t {
-                        TimeUnit::Second => {
-                            instantiate_primitive!(
-                                v,
-                                nullable,
-                                Time32SecondType,
-                                data_type
-                            )
-                        }
-                        TimeUnit::Millisecond => {
-                            instantiate_primitive!(
-                                v,
-                                nullable,
-                                Time32MillisecondType,
-                                data_type
-                            )
-                        }
-                        _ => {}
-                    },
-                    &DataType::Time64(t) => match t {
-                        TimeUnit::Microsecond => {
-                            instantiate_primitive!(
-                                v,
-                                nullable,
-                                Time64MicrosecondType,
-                                data_type
-                            )
-                        }
-                        TimeUnit::Nanosecond => {
-                            instantiate_primitive!(
-                                v,
-                                nullable,
-                                Time64NanosecondType,
-                                data_type
-                            )
-                        }
-                        _ => {}
-                    },
-                    &DataType::Timestamp(t, _) => match t {
-                        TimeUnit::Second => {
-                            instantiate_primitive!(
-                                v,
-                                nullable,
-                                TimestampSecondType,
-                                data_type
-                            )
-                        }
-                        TimeUnit::Millisecond => {
-                            instantiate_primitive!(
-                                v,
-                                nullable,
-                                TimestampMillisecondType,
-                                data_type
-                            )
-                        }
-                        TimeUnit::Microsecond => {
-                            instantiate_primitive!(
-                                v,
-                                nullable,
-                                TimestampMicrosecondType,
-                                data_type
-                            )
-                        }
-                        TimeUnit::Nanosecond => {
-                            instantiate_primitive!(
-                                v,
-                                nullable,
-                                TimestampNanosecondType,
-                                data_type
-                            )
-                        }
-                    },
-                    &DataType::Decimal128(_, _) => {
-                        instantiate_primitive! {
-                            v,
-                            nullable,
-                            Decimal128Type,
-                            data_type
-                        }
-                    }
-                    &DataType::Utf8 => {
-                        let b = ByteGroupValueBuilder::<i32>::new(OutputType::Utf8);
-                        v.push(Box::new(b) as _)
-                    }
-                    &DataType::LargeUtf8 => {
-                        let b = ByteGroupValueBuilder::<i64>::new(OutputType::Utf8);
-                        v.push(Box::new(b) as _)
-                    }
-                    &DataType::Binary => {
-                        let b = ByteGroupValueBuilder::<i32>::new(OutputType::Binary);
-                        v.push(Box::new(b) as _)
-                    }
-                    &DataType::LargeBinary => {
-                        let b = ByteGroupValueBuilder::<i64>::new(OutputType::Binary);
-                        v.push(Box::new(b) as _)
-                    }
-                    &DataType::Utf8View => {
-                        let b = ByteViewGroupValueBuilder::<StringViewType>::new();
-                        v.push(Box::new(b) as _)
-                    }
-                    &DataType::BinaryView => {
-                        let b = ByteViewGroupValueBuilder::<BinaryViewType>::new();
-                        v.push(Box::new(b) as _)
-                    }
-                    dt => {
-                        return not_impl_err!("{dt} not supported in GroupValuesColumn")
-                    }
-                }
+                instantiate_group_column(
+                    &mut v,
+                    f.data_type(),
+                    f.is_nullable(),
+                    self.normalize_floats,
+                    self.views_as_offsets,
+                    self.large_as_views,
+                    self.max_completed_view_blocks,
+                )?;
             }
             self.group_values = v;
         }
 
+        let cols = self.cast_to_stored_types(cols)?;
+        let cols = Self::sort_map_entries(&cols)?;
+        self.check_input_types(&cols)?;
         if !STREAMING {
-            self.vectorized_intern(cols, groups)
+            self.vectorized_intern(&cols, groups)
         } else {
-            self.scalarized_intern(cols, groups)
+            self.scalarized_intern(&cols, groups)
         }
     }
 
@@ -1168,12 +1415,22 @@ impl<const STREAMING: bool> GroupValues for GroupValuesColumn<STREAMING> {
                     )));
                 }
                 *array = cast(array.as_ref(), expected)?;
+            } else if (self.views_as_offsets
+                && matches!(expected, DataType::Utf8View | DataType::BinaryView))
+                || (self.large_as_views
+                    && matches!(expected, DataType::LargeUtf8 | DataType::LargeBinary))
+            {
+                *array = cast(array.as_ref(), expected)?;
             }
         }
 
         Ok(output)
     }
 
+    fn hash_table_usage(&self) -> Option<(usize, usize)> {
+        Some((self.map.len(), self.map.capacity()))
+    }
+
     fn clear_shrink(&mut self, batch: &RecordBatch) {
         let count = batch.num_rows();
         self.group_values.clear();
@@ -1206,6 +1463,17 @@ pub fn supported_schema(schema: &Schema) -> bool {
 /// In order to be supported, there must be a specialized implementation of
 /// [`GroupColumn`] for the data type, instantiated in [`GroupValuesColumn::intern`]
 fn supported_type(data_type: &DataType) -> bool {
+    if let DataType::Map(entries_field, _) = data_type {
+        // nested maps would need their entries sorted as well
+        return match entries_field.data_type() {
+            DataType::Struct(fields) => fields.iter().all(|f| {
+                !matches!(f.data_type(), DataType::Map(_, _))
+                    && supported_type(f.data_type())
+            }),
+            _ => false,
+        };
+    }
+
     matches!(
         *data_type,
         DataType::Int8
@@ -1236,12 +1504,27 @@ fn supported_type(data_type: &DataType) -> bool {
 mod tests {
     use std::{collections::HashMap, sync::Arc};
 
+    use arrow::compute::take;
+    use arrow::datatypes::{Float64Type, Int64Type};
     use arrow::{compute::concat_batches, util::pretty::pretty_format_batches};
-    use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray, StringViewArray};
+    use arrow_array::builder::{Int64Builder, MapBuilder, StringBuilder};
+    use arrow_array::cast::AsArray;
+    use arrow_array::{
+        Array, ArrayRef, BinaryArray, BinaryViewArray, Float64Array, Int64Array,
+        LargeBinaryArray, LargeStringArray, RecordBatch, StringArray, StringViewArray,
+        UInt32Array,
+    };
     use arrow_schema::{DataType, Field, Schema, SchemaRef};
+    use datafusion_common::assert_contains;
+    use datafusion_common::hash_utils::create_hashes;
     use datafusion_common::utils::proxy::HashTableAllocExt;
     use datafusion_expr::EmitTo;
+    use datafusion_physical_expr::binary_map::OutputType;
+    use itertools::Itertools;
 
+    use crate::aggregates::group_values::multi_group_by::{
+        bytes::ByteGroupValueBuilder, primitive::PrimitiveGroupValueBuilder, GroupColumn,
+    };
     use crate::aggregates::group_values::{
         multi_group_by::GroupValuesColumn, GroupValues,
     };
@@ -1301,6 +1584,346 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_intern_with_constant_columns() {
+        check_intern_with_constant_columns::<false>();
+        check_intern_with_constant_columns::<true>();
+    }
+
+    fn check_intern_with_constant_columns<const STREAMING: bool>() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let cols: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![1, 2, 1, 3, 2])),
+            Arc::new(StringArray::from(vec!["x"; 5])),
+        ];
+
+        let mut expected_values =
+            GroupValuesColumn::<STREAMING>::try_new(Arc::clone(&schema)).unwrap();
+        let mut expected_groups = vec![];
+        expected_values.intern(&cols, &mut expected_groups).unwrap();
+
+        let mut actual_values =
+            GroupValuesColumn::<STREAMING>::try_new(Arc::clone(&schema))
+                .unwrap()
+                .with_constant_columns(vec![1]);
+        let mut actual_groups = vec![];
+        actual_values.intern(&cols, &mut actual_groups).unwrap();
+
+        // the constant column does not contribute to the hash
+        let mut hashes = vec![0; 5];
+        create_hashes(&cols[..1], &actual_values.random_state, &mut hashes).unwrap();
+        assert_eq!(actual_values.hashes_buffer, hashes);
+
+        assert_eq!(actual_groups, expected_groups);
+        assert_eq!(actual_values.len(), 3);
+
+        let expected_batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            expected_values.emit(EmitTo::All).unwrap(),
+        )
+        .unwrap();
+        let actual_batch =
+            RecordBatch::try_new(schema, actual_values.emit(EmitTo::All).unwrap())
+                .unwrap();
+        check_result(&actual_batch, &expected_batch);
+    }
+
+    #[test]
+    fn test_intern_with_float_normalization() {
+        check_intern_with_float_normalization::<false>();
+        check_intern_with_float_normalization::<true>();
+    }
+
+    fn check_intern_with_float_normalization<const STREAMING: bool>() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Float64, true),
+            Field::new("b", DataType::Int64, true),
+        ]));
+        let cols: Vec<ArrayRef> = vec![
+            Arc::new(Float64Array::from(vec![
+                Some(f64::NAN),
+                Some(-0.0),
+                Some(f64::from_bits(0x7ff8_0000_0000_0001)),
+                Some(0.0),
+                Some(-f64::NAN),
+                Some(1.0),
+                None,
+            ])),
+            Arc::new(Int64Array::from(vec![1; 7])),
+        ];
+
+        // by default NaNs and signed zeros are distinct groups
+        let mut group_values =
+            GroupValuesColumn::<STREAMING>::try_new(Arc::clone(&schema)).unwrap();
+        let mut groups = vec![];
+        group_values.intern(&cols, &mut groups).unwrap();
+        assert_eq!(group_values.len(), 7);
+
+        let mut group_values =
+            GroupValuesColumn::<STREAMING>::try_new(Arc::clone(&schema))
+                .unwrap()
+                .with_float_normalization(true);
+        let mut groups = vec![];
+        group_values.intern(&cols, &mut groups).unwrap();
+        assert_eq!(groups, vec![0, 1, 0, 1, 0, 2, 3]);
+
+        let output = group_values.emit(EmitTo::All).unwrap();
+        let output = output[0].as_primitive::<Float64Type>();
+        assert_eq!(output.len(), 4);
+        assert_eq!(output.value(0).to_bits(), f64::NAN.to_bits());
+        assert_eq!(output.value(1).to_bits(), 0.0_f64.to_bits());
+        assert_eq!(output.value(2), 1.0);
+        assert!(output.is_null(3));
+    }
+
+    #[test]
+    fn test_intern_views_as_offsets() {
+        check_intern_views_as_offsets::<false>();
+        check_intern_views_as_offsets::<true>();
+    }
+
+    fn check_intern_views_as_offsets<const STREAMING: bool>() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Utf8View, true),
+            Field::new("b", DataType::BinaryView, true),
+            Field::new("c", DataType::Int64, true),
+        ]));
+        let batch = |a: Vec<Option<&str>>, b: Vec<Option<&[u8]>>, c: Vec<i64>| {
+            vec![
+                Arc::new(StringViewArray::from(a)) as ArrayRef,
+                Arc::new(BinaryViewArray::from(b)) as ArrayRef,
+                Arc::new(Int64Array::from(c)) as ArrayRef,
+            ]
+        };
+        let long = "a string longer than twelve bytes";
+        let batches = [
+            batch(
+                vec![Some("x"), Some(long), None, Some("x"), Some(long)],
+                vec![Some(b"1"), Some(b"22"), Some(b"1"), Some(b"1"), None],
+                vec![1, 2, 3, 1, 2],
+            ),
+            batch(
+                vec![Some(long), None, Some("y"), Some("x")],
+                vec![Some(b"22"), Some(b"1"), None, Some(b"1")],
+                vec![2, 3, 4, 5],
+            ),
+        ];
+
+        let intern_all = |views_as_offsets: bool| {
+            let mut group_values =
+                GroupValuesColumn::<STREAMING>::try_new(Arc::clone(&schema))
+                    .unwrap()
+                    .with_views_as_offsets(views_as_offsets);
+            let mut all_groups = vec![];
+            for cols in &batches {
+                let mut groups = vec![];
+                group_values.intern(cols, &mut groups).unwrap();
+                all_groups.extend(groups);
+            }
+            (all_groups, group_values.emit(EmitTo::All).unwrap())
+        };
+
+        let (view_groups, view_output) = intern_all(false);
+        let (offset_groups, offset_output) = intern_all(true);
+        assert_eq!(view_groups, vec![0, 1, 2, 0, 3, 1, 2, 4, 5]);
+        assert_eq!(offset_groups, view_groups);
+        assert_eq!(offset_output[0].data_type(), &DataType::Utf8View);
+        assert_eq!(offset_output[1].data_type(), &DataType::BinaryView);
+        assert_eq!(offset_output, view_output);
+    }
+
+    #[test]
+    fn test_intern_large_as_views() {
+        check_intern_large_as_views::<false>();
+        check_intern_large_as_views::<true>();
+    }
+
+    fn check_intern_large_as_views<const STREAMING: bool>() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::LargeUtf8, true),
+            Field::new("b", DataType::LargeBinary, true),
+            Field::new("c", DataType::Int64, true),
+        ]));
+        let batch = |a: Vec<Option<&str>>, b: Vec<Option<&[u8]>>, c: Vec<i64>| {
+            vec![
+                Arc::new(LargeStringArray::from(a)) as ArrayRef,
+                Arc::new(LargeBinaryArray::from(b)) as ArrayRef,
+                Arc::new(Int64Array::from(c)) as ArrayRef,
+            ]
+        };
+        let long = "a string longer than twelve bytes";
+        let batches = [
+            batch(
+                vec![Some("x"), Some(long), None, Some("x"), Some(long)],
+                vec![Some(b"1"), Some(b"22"), Some(b"1"), Some(b"1"), None],
+                vec![1, 2, 3, 1, 2],
+            ),
+            batch(
+                vec![Some(long), None, Some("y"), Some("x")],
+                vec![Some(b"22"), Some(b"1"), None, Some(b"1")],
+                vec![2, 3, 4, 5],
+            ),
+        ];
+
+        let intern_all = |large_as_views: bool| {
+            let mut group_values =
+                GroupValuesColumn::<STREAMING>::try_new(Arc::clone(&schema))
+                    .unwrap()
+                    .with_large_as_views(large_as_views);
+            let mut all_groups = vec![];
+            for cols in &batches {
+                let mut groups = vec![];
+                group_values.intern(cols, &mut groups).unwrap();
+                all_groups.extend(groups);
+            }
+            (all_groups, group_values.emit(EmitTo::All).unwrap())
+        };
+
+        let (offset_groups, offset_output) = intern_all(false);
+        let (view_groups, view_output) = intern_all(true);
+        assert_eq!(offset_groups, vec![0, 1, 2, 0, 3, 1, 2, 4, 5]);
+        assert_eq!(view_groups, offset_groups);
+        assert_eq!(view_output[0].data_type(), &DataType::LargeUtf8);
+        assert_eq!(view_output[1].data_type(), &DataType::LargeBinary);
+        assert_eq!(view_output, offset_output);
+    }
+
+    #[test]
+    fn test_intern_maps() {
+        check_intern_maps::<false>();
+        check_intern_maps::<true>();
+    }
+
+    fn check_intern_maps<const STREAMING: bool>() {
+        let map = |maps: &[Option<&[(&str, i64)]>]| {
+            let mut builder =
+                MapBuilder::new(None, StringBuilder::new(), Int64Builder::new());
+            for map in maps {
+                if let Some(entries) = map {
+                    for (key, value) in entries.iter() {
+                        builder.keys().append_value(key);
+                        builder.values().append_value(*value);
+                    }
+                }
+                builder.append(map.is_some()).unwrap();
+            }
+            Arc::new(builder.finish()) as ArrayRef
+        };
+        let batches = [
+            vec![
+                map(&[
+                    Some(&[("a", 1), ("b", 2)]),
+                    Some(&[("b", 2), ("a", 1)]),
+                    Some(&[]),
+                    None,
+                    Some(&[("a", 1)]),
+                ]),
+                Arc::new(Int64Array::from(vec![1, 1, 1, 1, 1])) as ArrayRef,
+            ],
+            vec![
+                map(&[
+                    None,
+                    Some(&[("b", 2), ("a", 1)]),
+                    Some(&[("a", 2), ("b", 1)]),
+                ]),
+                Arc::new(Int64Array::from(vec![1, 1, 1])) as ArrayRef,
+            ],
+        ];
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("m", batches[0][0].data_type().clone(), true),
+            Field::new("i", DataType::Int64, true),
+        ]));
+
+        let mut group_values =
+            GroupValuesColumn::<STREAMING>::try_new(Arc::clone(&schema)).unwrap();
+        let mut all_groups = vec![];
+        for cols in &batches {
+            let mut groups = vec![];
+            group_values.intern(cols, &mut groups).unwrap();
+            all_groups.extend(groups);
+        }
+        // maps with the same entries in a different order are in one group
+        assert_eq!(all_groups.iter().unique().count(), 5);
+        assert_eq!(all_groups[0], all_groups[1]);
+        assert_eq!(all_groups[0], all_groups[6]);
+        assert_eq!(all_groups[3], all_groups[5]);
+
+        // the entries of the output maps are sorted by key
+        let output = group_values.emit(EmitTo::All).unwrap();
+        let indices = UInt32Array::from_iter_values(all_groups.iter().map(|&g| g as u32));
+        let actual = take(&output[0], &indices, None).unwrap();
+        let expected = map(&[
+            Some(&[("a", 1), ("b", 2)]),
+            Some(&[("a", 1), ("b", 2)]),
+            Some(&[]),
+            None,
+            Some(&[("a", 1)]),
+            None,
+            Some(&[("a", 1), ("b", 2)]),
+            Some(&[("a", 2), ("b", 1)]),
+        ]);
+        assert_eq!(&actual, &expected);
+    }
+
+    #[test]
+    fn test_group_column_accepts() {
+        let utf8 = ByteGroupValueBuilder::<i32>::new(OutputType::Utf8);
+        assert!(utf8.accepts(&DataType::Utf8));
+        assert!(!utf8.accepts(&DataType::LargeUtf8));
+        assert!(!utf8.accepts(&DataType::Binary));
+
+        let large_binary = ByteGroupValueBuilder::<i64>::new(OutputType::Binary);
+        assert!(large_binary.accepts(&DataType::LargeBinary));
+        assert!(!large_binary.accepts(&DataType::LargeUtf8));
+
+        let int64 = PrimitiveGroupValueBuilder::<Int64Type, true>::new(DataType::Int64);
+        assert!(int64.accepts(&DataType::Int64));
+        assert!(!int64.accepts(&DataType::UInt64));
+        assert!(!int64.accepts(&DataType::Float64));
+    }
+
+    #[test]
+    fn test_intern_rejects_mismatched_types() {
+        check_intern_rejects_mismatched_types::<false>();
+        check_intern_rejects_mismatched_types::<true>();
+    }
+
+    fn check_intern_rejects_mismatched_types<const STREAMING: bool>() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let mut group_values = GroupValuesColumn::<STREAMING>::try_new(schema).unwrap();
+
+        let int_array: ArrayRef = Arc::new(Int64Array::from(vec![1, 2]));
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec!["a", "b"]));
+        let binary_array: ArrayRef =
+            Arc::new(BinaryArray::from_vec(vec![b"a".as_slice(), b"b"]));
+
+        let mut groups = vec![];
+        let err = group_values
+            .intern(&[Arc::clone(&int_array), binary_array], &mut groups)
+            .unwrap_err();
+        assert_contains!(
+            err.to_string(),
+            "can not intern values of type Binary into group column 1 of type Utf8"
+        );
+        let err = group_values
+            .intern(&[Arc::clone(&int_array)], &mut groups)
+            .unwrap_err();
+        assert_contains!(err.to_string(), "expected 2 group columns, got 1");
+
+        // the group values are still usable after a rejected input
+        group_values
+            .intern(&[int_array, string_array], &mut groups)
+            .unwrap();
+        assert_eq!(groups, vec![0, 1]);
+        assert_eq!(group_values.len(), 2);
+    }
+
     #[test]
     fn test_hashtable_modifying_in_emit_first_n() {
         // Situations should be covered:
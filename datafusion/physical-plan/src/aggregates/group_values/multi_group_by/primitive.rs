@@ -19,13 +19,60 @@ use crate::aggregates::group_values::multi_group_by::{nulls_equal_to, GroupColum
 use crate::aggregates::group_values::null_builder::MaybeNullBufferBuilder;
 use arrow::buffer::ScalarBuffer;
 use arrow_array::cast::AsArray;
-use arrow_array::{Array, ArrayRef, ArrowPrimitiveType, PrimitiveArray};
+use arrow_array::types::{Float32Type, Float64Type};
+use arrow_array::{
+    Array, ArrayRef, ArrowNativeTypeOp, ArrowPrimitiveType, PrimitiveArray,
+};
 use arrow_schema::DataType;
 use datafusion_execution::memory_pool::proxy::VecAllocExt;
 use itertools::izip;
 use std::iter;
 use std::sync::Arc;
 
+/// Returns `v` with every NaN replaced by [`f32::NAN`] and `-0.0` replaced
+/// by `+0.0`, so that values SQL considers equal share one bit pattern
+#[inline]
+pub fn normalize_f32(v: f32) -> f32 {
+    if v.is_nan() {
+        f32::NAN
+    } else if v == 0.0 {
+        0.0
+    } else {
+        v
+    }
+}
+
+/// Returns `v` with every NaN replaced by [`f64::NAN`] and `-0.0` replaced
+/// by `+0.0`, so that values SQL considers equal share one bit pattern
+#[inline]
+pub fn normalize_f64(v: f64) -> f64 {
+    if v.is_nan() {
+        f64::NAN
+    } else if v == 0.0 {
+        0.0
+    } else {
+        v
+    }
+}
+
+/// Applies [`normalize_f32`] or [`normalize_f64`] to the values of a float
+/// array. Arrays of any other type are returned unchanged.
+pub fn normalize_float_array(array: &ArrayRef) -> ArrayRef {
+    match array.data_type() {
+        DataType::Float32 => Arc::new(
+            array
+                .as_primitive::<Float32Type>()
+                .unary::<_, Float32Type>(normalize_f32),
+        ),
+        DataType::Float64 => Arc::new(
+            array
+                .as_primitive::<Float64Type>()
+                .unary::<_, Float64Type>(normalize_f64),
+        ),
+        _ => Arc::clone(array),
+    }
+}
+
 /// An implementation of [`GroupColumn`] for primitive values
 ///
 /// Optimized to skip null buffer construction if the input is known to be non nullable
@@ -39,6 +86,9 @@ pub struct PrimitiveGroupValueBuilder<T: ArrowPrimitiveType, const NULLABLE: boo
     data_type: DataType,
     group_values: Vec<T::Native>,
     nulls: MaybeNullBufferBuilder,
+    /// If set, applied to every input value before it is compared or
+    /// stored, see [`Self::with_normalization`]
+    normalize: Option<fn(T::Native) -> T::Native>,
 }
 
 impl<T, const NULLABLE: bool> PrimitiveGroupValueBuilder<T, NULLABLE>
@@ -51,6 +101,43 @@ where
             data_type,
             group_values: vec![],
             nulls: MaybeNullBufferBuilder::new(),
+            normalize: None,
+        }
+    }
+
+    /// Canonicalize every input value with `normalize` before comparing or
+    /// storing it, and compare the canonical values bitwise.
+    ///
+    /// Used with [`normalize_f32`] / [`normalize_f64`] so that all NaNs form
+    /// a single group and `-0.0` groups together with `+0.0`.
+    pub fn with_normalization(mut self, normalize: fn(T::Native) -> T::Native) -> Self {
+        self.normalize = Some(normalize);
+        self
+    }
+
+    /// Returns the (normalized) value of `array` at `row`
+    #[inline]
+    fn value(&self, array: &PrimitiveArray<T>, row: usize) -> T::Native {
+        match self.normalize {
+            Some(normalize) => normalize(array.value(row)),
+            None => array.value(row),
+        }
+    }
+
+    /// Compares the stored group value at `lhs_row` to the input value
+    /// at `rhs_row`
+    #[inline]
+    fn value_equal_to(
+        &self,
+        lhs_row: usize,
+        array: &PrimitiveArray<T>,
+        rhs_row: usize,
+    ) -> bool {
+        let rhs = self.value(array, rhs_row);
+        if self.normalize.is_some() {
+            self.group_values[lhs_row].is_eq(rhs)
+        } else {
+            self.group_values[lhs_row] == rhs
         }
     }
 }
@@ -69,7 +156,7 @@ impl<T: ArrowPrimitiveType, const NULLABLE: bool> GroupColumn
             // Otherwise, we need to check their values
         }
 
-        self.group_values[lhs_row] == array.as_primitive::<T>().value(rhs_row)
+        self.value_equal_to(lhs_row, array.as_primitive::<T>(), rhs_row)
     }
 
     fn append_val(&mut self, array: &ArrayRef, row: usize) {
@@ -80,10 +167,12 @@ impl<T: ArrowPrimitiveType, const NULLABLE: bool> GroupColumn
                 self.group_values.push(T::default_value());
             } else {
                 self.nulls.append(false);
-                self.group_values.push(array.as_primitive::<T>().value(row));
+                let value = self.value(array.as_primitive::<T>(), row);
+                self.group_values.push(value);
             }
         } else {
-            self.group_values.push(array.as_primitive::<T>().value(row));
+            let value = self.value(array.as_primitive::<T>(), row);
+            self.group_values.push(value);
         }
     }
 
@@ -119,7 +208,7 @@ impl<T: ArrowPrimitiveType, const NULLABLE: bool> GroupColumn
                 // Otherwise, we need to check their values
             }
 
-            *equal_to_result = self.group_values[lhs_row] == array.value(rhs_row);
+            *equal_to_result = self.value_equal_to(lhs_row, array, rhs_row);
         }
     }
 
@@ -144,7 +233,8 @@ impl<T: ArrowPrimitiveType, const NULLABLE: bool> GroupColumn
                         self.group_values.push(T::default_value());
                     } else {
                         self.nulls.append(false);
-                        self.group_values.push(arr.value(row));
+                        let value = self.value(arr, row);
+                        self.group_values.push(value);
                     }
                 }
             }
@@ -152,24 +242,35 @@ impl<T: ArrowPrimitiveType, const NULLABLE: bool> GroupColumn
             (true, Some(true)) => {
                 self.nulls.append_n(rows.len(), false);
                 for &row in rows {
-                    self.group_values.push(arr.value(row));
+                    let value = self.value(arr, row);
+                    self.group_values.push(value);
                 }
             }
 
             (true, Some(false)) => {
-                self.nulls.append_n(rows.len(), true);
-                self.group_values
-                    .extend(iter::repeat(T::default_value()).take(rows.len()));
+                self.append_nulls(rows.len());
             }
 
             (false, _) => {
                 for &row in rows {
-                    self.group_values.push(arr.value(row));
+                    let value = self.value(arr, row);
+                    self.group_values.push(value);
                 }
             }
         }
     }
 
+    fn accepts(&self, data_type: &DataType) -> bool {
+        PrimitiveArray::<T>::is_compatible(data_type)
+    }
+
+    fn append_nulls(&mut self, n: usize) {
+        assert!(NULLABLE, "unexpected nulls in non nullable input");
+        self.nulls.append_n(n, true);
+        self.group_values
+            .extend(iter::repeat(T::default_value()).take(n));
+    }
+
     fn len(&self) -> usize {
         self.group_values.len()
     }
@@ -183,6 +284,7 @@ impl<T: ArrowPrimitiveType, const NULLABLE: bool> GroupColumn
             data_type,
             group_values,
             nulls,
+            normalize: _,
         } = *self;
 
         let nulls = nulls.build();
@@ -211,9 +313,12 @@ impl<T: ArrowPrimitiveType, const NULLABLE: bool> GroupColumn
 mod tests {
     use std::sync::Arc;
 
-    use crate::aggregates::group_values::multi_group_by::primitive::PrimitiveGroupValueBuilder;
-    use arrow::datatypes::Int64Type;
-    use arrow_array::{ArrayRef, Int64Array};
+    use crate::aggregates::group_values::multi_group_by::primitive::{
+        normalize_f64, PrimitiveGroupValueBuilder,
+    };
+    use arrow::datatypes::{Float64Type, Int64Type};
+    use arrow_array::cast::AsArray;
+    use arrow_array::{ArrayRef, Float64Array, Int64Array};
     use arrow_buffer::{BooleanBufferBuilder, NullBuffer};
     use arrow_schema::DataType;
 
@@ -476,4 +581,93 @@ mod tests {
         assert!(equal_to_results[3]);
         assert!(equal_to_results[4]);
     }
+
+    #[test]
+    fn test_primitive_append_nulls() {
+        let null_array = Arc::new(Int64Array::from(vec![None; 64])) as ArrayRef;
+
+        let mut per_call_builder =
+            PrimitiveGroupValueBuilder::<Int64Type, true>::new(DataType::Int64);
+        for row in 0..null_array.len() {
+            per_call_builder.append_val(&null_array, row);
+        }
+
+        let mut bulk_builder =
+            PrimitiveGroupValueBuilder::<Int64Type, true>::new(DataType::Int64);
+        bulk_builder.append_nulls(null_array.len());
+
+        assert_eq!(bulk_builder.len(), per_call_builder.len());
+        assert_eq!(bulk_builder.size(), per_call_builder.size());
+
+        let per_call_output = Box::new(per_call_builder).build();
+        let bulk_output = Box::new(bulk_builder).build();
+        assert_eq!(bulk_output.null_count(), 64);
+        assert_eq!(&bulk_output, &per_call_output);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected nulls in non nullable input")]
+    fn test_not_nullable_primitive_append_nulls() {
+        let mut builder =
+            PrimitiveGroupValueBuilder::<Int64Type, false>::new(DataType::Int64);
+        builder.append_nulls(1);
+    }
+
+    #[test]
+    fn test_primitive_float_normalization() {
+        let input_array = Arc::new(Float64Array::from(vec![
+            Some(f64::NAN),
+            Some(-0.0),
+            Some(f64::from_bits(0x7ff8_0000_0000_0001)),
+            Some(0.0),
+            Some(-f64::NAN),
+            None,
+        ])) as ArrayRef;
+
+        // scalarized
+        let mut builder =
+            PrimitiveGroupValueBuilder::<Float64Type, true>::new(DataType::Float64)
+                .with_normalization(normalize_f64);
+        builder.append_val(&input_array, 0);
+        builder.append_val(&input_array, 1);
+        assert!(builder.equal_to(0, &input_array, 2));
+        assert!(builder.equal_to(0, &input_array, 4));
+        assert!(builder.equal_to(1, &input_array, 3));
+        assert!(!builder.equal_to(0, &input_array, 1));
+        assert!(!builder.equal_to(1, &input_array, 5));
+
+        // vectorized
+        let mut vectorized_builder =
+            PrimitiveGroupValueBuilder::<Float64Type, true>::new(DataType::Float64)
+                .with_normalization(normalize_f64);
+        vectorized_builder.vectorized_append(&input_array, &[0, 1]);
+        let mut equal_to_results = vec![true; 5];
+        vectorized_builder.vectorized_equal_to(
+            &[0, 0, 1, 0, 1],
+            &input_array,
+            &[2, 4, 3, 1, 5],
+            &mut equal_to_results,
+        );
+        assert_eq!(equal_to_results, vec![true, true, true, false, false]);
+
+        // the stored values are canonical
+        for builder in [builder, vectorized_builder] {
+            let output = Box::new(builder).build();
+            let output = output.as_primitive::<Float64Type>();
+            assert_eq!(output.value(0).to_bits(), f64::NAN.to_bits());
+            assert_eq!(output.value(1).to_bits(), 0.0_f64.to_bits());
+        }
+
+        // without normalization NaNs are never equal and -0.0 is stored as is
+        let mut builder =
+            PrimitiveGroupValueBuilder::<Float64Type, true>::new(DataType::Float64);
+        builder.append_val(&input_array, 0);
+        builder.append_val(&input_array, 1);
+        assert!(!builder.equal_to(0, &input_array, 0));
+        let output = Box::new(builder).build();
+        assert!(output
+            .as_primitive::<Float64Type>()
+            .value(1)
+            .is_sign_negative());
+    }
 }
@@ -15,6 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use crate::aggregates::group_values::multi_group_by::normalize_float_array;
 use crate::aggregates::group_values::GroupValues;
 use ahash::RandomState;
 use arrow::compute::cast;
@@ -23,11 +24,12 @@ use arrow::row::{RowConverter, Rows, SortField};
 use arrow_array::{Array, ArrayRef, ListArray, StructArray};
 use arrow_schema::{DataType, SchemaRef};
 use datafusion_common::hash_utils::create_hashes;
-use datafusion_common::Result;
+use datafusion_common::{internal_err, Result};
 use datafusion_execution::memory_pool::proxy::{HashTableAllocExt, VecAllocExt};
 use datafusion_expr::EmitTo;
 use hashbrown::hash_table::HashTable;
 use log::debug;
+use std::borrow::Cow;
 use std::mem::size_of;
 use std::sync::Arc;
 
@@ -77,6 +79,11 @@ pub struct GroupValuesRows {
 
     /// Random state for creating hashes
     random_state: RandomState,
+
+    /// If true, top level `Float32` / `Float64` group values are normalized
+    /// so that all NaNs are one group and `-0.0` is grouped with `+0.0`,
+    /// see [`Self::with_float_normalization`]
+    normalize_floats: bool,
 }
 
 impl GroupValuesRows {
@@ -108,17 +115,43 @@ impl GroupValuesRows {
             hashes_buffer: Default::default(),
             rows_buffer,
             random_state: Default::default(),
+            normalize_floats: false,
         })
     }
-}
 
-impl GroupValues for GroupValuesRows {
-    fn intern(&mut self, cols: &[ArrayRef], groups: &mut Vec<usize>) -> Result<()> {
+    /// Set whether float group values are normalized, so that all NaNs
+    /// form one group and `-0.0` is grouped with `+0.0`.
+    ///
+    /// Only top level float columns are normalized, floats nested in lists
+    /// or structs are grouped by their exact value.
+    pub fn with_float_normalization(mut self, normalize_floats: bool) -> Self {
+        self.normalize_floats = normalize_floats;
+        self
+    }
+
+    /// Interns the rows of `cols`, using `hashes` as the hash of each row
+    /// if provided
+    fn intern_impl(
+        &mut self,
+        cols: &[ArrayRef],
+        hashes: Option<&[u64]>,
+        groups: &mut Vec<usize>,
+    ) -> Result<()> {
+        let cols = if self.normalize_floats {
+            Cow::Owned(cols.iter().map(normalize_float_array).collect())
+        } else {
+            Cow::Borrowed(cols)
+        };
+        let cols = cols.as_ref();
+
         // Convert the group keys into the row format
         let group_rows = &mut self.rows_buffer;
         group_rows.clear();
         self.row_converter.append(group_rows, cols)?;
         let n_rows = group_rows.num_rows();
+        if let Some(hashes) = hashes.filter(|hashes| hashes.len() != n_rows) {
+            return internal_err!("Expected {n_rows} group hashes, got {}", hashes.len());
+        }
 
         let mut group_values = match self.group_values.take() {
             Some(group_values) => group_values,
@@ -131,8 +164,13 @@ impl GroupValues for GroupValuesRows {
         // 1.1 Calculate the group keys for the group values
         let batch_hashes = &mut self.hashes_buffer;
         batch_hashes.clear();
-        batch_hashes.resize(n_rows, 0);
-        create_hashes(cols, &self.random_state, batch_hashes)?;
+        match hashes {
+            Some(hashes) => batch_hashes.extend_from_slice(hashes),
+            None => {
+                batch_hashes.resize(n_rows, 0);
+                create_hashes(cols, &self.random_state, batch_hashes)?;
+            }
+        }
 
         for (row, &target_hash) in batch_hashes.iter().enumerate() {
             let entry = self.map.find_mut(target_hash, |(exist_hash, group_idx)| {
@@ -172,6 +210,21 @@ impl GroupValues for GroupValuesRows {
 
         Ok(())
     }
+}
+
+impl GroupValues for GroupValuesRows {
+    fn intern(&mut self, cols: &[ArrayRef], groups: &mut Vec<usize>) -> Result<()> {
+        self.intern_impl(cols, None, groups)
+    }
+
+    fn intern_with_hashes(
+        &mut self,
+        cols: &[ArrayRef],
+        hashes: &[u64],
+        groups: &mut Vec<usize>,
+    ) -> Result<()> {
+        self.intern_impl(cols, Some(hashes), groups)
+    }
 
     fn size(&self) -> usize {
         let group_values_size = self.group_values.as_ref().map(|v| v.size()).unwrap_or(0);
@@ -244,6 +297,10 @@ impl GroupValues for GroupValuesRows {
         Ok(output)
     }
 
+    fn hash_table_usage(&self) -> Option<(usize, usize)> {
+        Some((self.map.len(), self.map.capacity()))
+    }
+
     fn clear_shrink(&mut self, batch: &RecordBatch) {
         let count = batch.num_rows();
         self.group_values = self.group_values.take().map(|mut rows| {
@@ -299,3 +356,78 @@ fn dictionary_encode_if_necessary(
         (_, _) => Ok(Arc::<dyn Array>::clone(&array)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, Int32Type};
+    use arrow_array::Float64Array;
+    use arrow_schema::{Field, Schema};
+
+    use crate::aggregates::group_values::new_group_values;
+    use crate::aggregates::order::GroupOrdering;
+
+    use super::*;
+
+    #[test]
+    fn test_intern_with_float_normalization() -> Result<()> {
+        // a list column is not supported by `GroupValuesColumn`, so the
+        // float column is grouped by `GroupValuesRows`
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Float64, true),
+            Field::new_list("b", Field::new_list_field(DataType::Int32, true), true),
+        ]));
+        let a: ArrayRef = Arc::new(Float64Array::from(vec![
+            Some(f64::NAN),
+            Some(-0.0),
+            Some(f64::from_bits(0x7ff8_0000_0000_0001)),
+            Some(0.0),
+            Some(-f64::NAN),
+            Some(1.0),
+            None,
+        ]));
+        let b: ArrayRef =
+            Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+                Some(
+                    vec![Some(1)]
+                );
+                7
+            ]));
+        let cols = [a, b];
+
+        // by default NaNs and signed zeros are distinct groups
+        let mut group_values = new_group_values(
+            Arc::clone(&schema),
+            &GroupOrdering::None,
+            vec![],
+            false,
+            false,
+            false,
+            None,
+        )?;
+        let mut groups = vec![];
+        group_values.intern(&cols, &mut groups)?;
+        assert_eq!(group_values.len(), 7);
+
+        let mut group_values = new_group_values(
+            Arc::clone(&schema),
+            &GroupOrdering::None,
+            vec![],
+            true,
+            false,
+            false,
+            None,
+        )?;
+        group_values.intern(&cols, &mut groups)?;
+        assert_eq!(groups, vec![0, 1, 0, 1, 0, 2, 3]);
+
+        let output = group_values.emit(EmitTo::All)?;
+        let output = output[0].as_primitive::<Float64Type>();
+        assert_eq!(output.len(), 4);
+        assert_eq!(output.value(0).to_bits(), f64::NAN.to_bits());
+        assert_eq!(output.value(1).to_bits(), 0.0_f64.to_bits());
+        assert_eq!(output.value(2), 1.0);
+        assert!(output.is_null(3));
+        Ok(())
+    }
+}
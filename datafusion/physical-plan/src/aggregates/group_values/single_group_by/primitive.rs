@@ -75,11 +75,68 @@ macro_rules! hash_float {
 
 hash_float!(f16, f32, f64);
 
+/// Computes the hash of a group value stored in [`GroupValuesPrimitive`]
+pub(crate) trait GroupHashState<N>: Default + Send {
+    fn hash_value(&self, value: &N) -> u64;
+}
+
+impl<N: HashValue> GroupHashState<N> for RandomState {
+    fn hash_value(&self, value: &N) -> u64 {
+        value.hash(self)
+    }
+}
+
+/// Hashes integer group values directly from their value, with a single
+/// folded multiplication instead of running the general purpose hasher.
+///
+/// The multiplication spreads the value over both the low bits (used to
+/// pick the bucket) and the high bits (used as the tag) of the hash, so
+/// sequential or strided keys are still evenly distributed. The value is
+/// mixed with a seed and multiplied by an odd factor, both drawn from a new
+/// [`RandomState`] for each instance, so key sets that collide can not be
+/// built in advance.
+#[derive(Debug)]
+pub(crate) struct DirectHashState {
+    seed: u64,
+    multiple: u64,
+}
+
+impl Default for DirectHashState {
+    fn default() -> Self {
+        let state = RandomState::new();
+        Self {
+            seed: state.hash_one(0_u64),
+            multiple: state.hash_one(1_u64) | 1,
+        }
+    }
+}
+
+macro_rules! direct_hash_integer {
+    ($($t:ty),+) => {
+        $(impl GroupHashState<$t> for DirectHashState {
+            #[cfg(not(feature = "force_hash_collisions"))]
+            fn hash_value(&self, value: &$t) -> u64 {
+                let full =
+                    ((*value as u64 ^ self.seed) as u128) * (self.multiple as u128);
+                (full as u64) ^ ((full >> 64) as u64)
+            }
+
+            #[cfg(feature = "force_hash_collisions")]
+            fn hash_value(&self, _value: &$t) -> u64 {
+                0
+            }
+        })+
+    };
+}
+direct_hash_integer!(i8, i16, i32, i64);
+direct_hash_integer!(u8, u16, u32, u64);
+
 /// A [`GroupValues`] storing a single column of primitive values
 ///
 /// This specialization is significantly faster than using the more general
-/// purpose `Row`s format
-pub struct GroupValuesPrimitive<T: ArrowPrimitiveType> {
+/// purpose `Row`s format. The values are hashed with `S`, either a
+/// [`RandomState`] or, for integers, the cheaper [`DirectHashState`].
+pub struct GroupValuesPrimitive<T: ArrowPrimitiveType, S = RandomState> {
     /// The data type of the output array
     data_type: DataType,
     /// Stores the group index based on the hash of its value
@@ -91,11 +148,11 @@ pub struct GroupValuesPrimitive<T: ArrowPrimitiveType> {
     null_group: Option<usize>,
     /// The values for each group index
     values: Vec<T::Native>,
-    /// The random state used to generate hashes
-    random_state: RandomState,
+    /// The state used to generate hashes
+    hash_state: S,
 }
 
-impl<T: ArrowPrimitiveType> GroupValuesPrimitive<T> {
+impl<T: ArrowPrimitiveType, S: Default> GroupValuesPrimitive<T, S> {
     pub fn new(data_type: DataType) -> Self {
         assert!(PrimitiveArray::<T>::is_compatible(&data_type));
         Self {
@@ -103,14 +160,13 @@ impl<T: ArrowPrimitiveType> GroupValuesPrimitive<T> {
             map: HashTable::with_capacity(128),
             values: Vec::with_capacity(128),
             null_group: None,
-            random_state: Default::default(),
+            hash_state: Default::default(),
         }
     }
 }
 
-impl<T: ArrowPrimitiveType> GroupValues for GroupValuesPrimitive<T>
-where
-    T::Native: HashValue,
+impl<T: ArrowPrimitiveType, S: GroupHashState<T::Native>> GroupValues
+    for GroupValuesPrimitive<T, S>
 {
     fn intern(&mut self, cols: &[ArrayRef], groups: &mut Vec<usize>) -> Result<()> {
         assert_eq!(cols.len(), 1);
@@ -124,12 +180,12 @@ where
                     group_id
                 }),
                 Some(key) => {
-                    let state = &self.random_state;
-                    let hash = key.hash(state);
+                    let state = &self.hash_state;
+                    let hash = state.hash_value(&key);
                     let insert = self.map.entry(
                         hash,
                         |g| unsafe { self.values.get_unchecked(*g).is_eq(key) },
-                        |g| unsafe { self.values.get_unchecked(*g).hash(state) },
+                        |g| unsafe { state.hash_value(self.values.get_unchecked(*g)) },
                     );
 
                     match insert {
@@ -209,6 +265,10 @@ where
         Ok(vec![Arc::new(array.with_data_type(self.data_type.clone()))])
     }
 
+    fn hash_table_usage(&self) -> Option<(usize, usize)> {
+        Some((self.map.len(), self.map.capacity()))
+    }
+
     fn clear_shrink(&mut self, batch: &RecordBatch) {
         let count = batch.num_rows();
         self.values.clear();
@@ -217,3 +277,64 @@ where
         self.map.shrink_to(count, |_| 0); // hasher does not matter since the map is cleared
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow::datatypes::Int64Type;
+    use arrow_array::Int64Array;
+
+    #[test]
+    fn test_direct_hash_matches_random_state() {
+        let batches: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![
+                Some(3),
+                Some(-1),
+                None,
+                Some(3),
+                Some(i64::MAX),
+                Some(1 << 40),
+            ])),
+            Arc::new(Int64Array::from_iter_values((0..1024).map(|v| v * 1024))),
+            Arc::new(Int64Array::from(vec![Some(i64::MIN), None, Some(-1)])),
+        ];
+
+        let mut random =
+            GroupValuesPrimitive::<Int64Type, RandomState>::new(DataType::Int64);
+        let mut direct =
+            GroupValuesPrimitive::<Int64Type, DirectHashState>::new(DataType::Int64);
+        for values in &batches {
+            let mut random_groups = vec![];
+            random
+                .intern(&[Arc::clone(values)], &mut random_groups)
+                .unwrap();
+            let mut direct_groups = vec![];
+            direct
+                .intern(&[Arc::clone(values)], &mut direct_groups)
+                .unwrap();
+            assert_eq!(random_groups, direct_groups);
+        }
+        assert_eq!(direct.len(), 1030);
+
+        let first = direct.emit(EmitTo::First(4)).unwrap();
+        assert_eq!(first, random.emit(EmitTo::First(4)).unwrap());
+        assert_eq!(
+            first[0].as_ref(),
+            &Int64Array::from(vec![Some(3), Some(-1), None, Some(i64::MAX)])
+        );
+        assert_eq!(
+            direct.emit(EmitTo::All).unwrap(),
+            random.emit(EmitTo::All).unwrap()
+        );
+    }
+
+    #[cfg(not(feature = "force_hash_collisions"))]
+    #[test]
+    fn test_direct_hash_is_seeded() {
+        let first = DirectHashState::default();
+        let second = DirectHashState::default();
+        assert_eq!(first.hash_value(&42_i64), first.hash_value(&42_i64));
+        assert_ne!(first.hash_value(&42_i64), second.hash_value(&42_i64));
+    }
+}
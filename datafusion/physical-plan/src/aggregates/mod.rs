@@ -28,18 +28,19 @@ use crate::aggregates::{
 use crate::execution_plan::{CardinalityEffect, EmissionType};
 use crate::metrics::{ExecutionPlanMetricsSet, MetricsSet};
 use crate::projection::get_field_metadata;
+use crate::repartition::RepartitionExec;
 use crate::windows::get_ordered_partition_by_indices;
 use crate::{
-    DisplayFormatType, Distribution, ExecutionPlan, InputOrderMode,
+    DisplayFormatType, Distribution, ExecutionPlan, InputOrderMode, Partitioning,
     SendableRecordBatchStream, Statistics,
 };
 
 use arrow::array::ArrayRef;
-use arrow::datatypes::{Field, Schema, SchemaRef};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use arrow::record_batch::RecordBatch;
 use arrow_array::{UInt16Array, UInt32Array, UInt64Array, UInt8Array};
 use datafusion_common::stats::Precision;
-use datafusion_common::{internal_err, not_impl_err, Result};
+use datafusion_common::{internal_err, not_impl_err, plan_err, HashMap, Result};
 use datafusion_execution::TaskContext;
 use datafusion_expr::{Accumulator, Aggregate};
 use datafusion_physical_expr::aggregate::AggregateFunctionExpr;
@@ -51,6 +52,7 @@ use datafusion_physical_expr::{
 };
 
 use itertools::Itertools;
+use parking_lot::Mutex;
 
 pub(crate) mod group_values;
 mod no_grouping;
@@ -167,6 +169,27 @@ impl PhysicalGroupBy {
         }
     }
 
+    /// Create a GROUP BY of the columns at `indices` of `schema`, named like
+    /// the columns, with a single group, such as `GROUP BY a, b`.
+    ///
+    /// Returns an error if an index is out of bounds for `schema`.
+    pub fn new_from_indices(indices: &[usize], schema: &SchemaRef) -> Result<Self> {
+        let expr = indices
+            .iter()
+            .map(|&index| match schema.fields().get(index) {
+                Some(field) => Ok((
+                    Arc::new(Column::new(field.name(), index)) as _,
+                    field.name().clone(),
+                )),
+                None => plan_err!(
+                    "Group by column index {index} is out of bounds for a schema with {} fields",
+                    schema.fields().len()
+                ),
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self::new_single(expr))
+    }
+
     /// Calculate GROUP BY expressions nullable
     pub fn exprs_nullable(&self) -> Vec<bool> {
         let mut exprs_nullable = vec![false; self.expr.len()];
@@ -371,6 +394,19 @@ pub struct AggregateExec {
     required_input_ordering: Option<LexRequirement>,
     /// Describes how the input is ordered relative to the group by columns
     input_order_mode: InputOrderMode,
+    /// Order of the output columns, see [`Self::with_output_column_order`]
+    output_column_order: Option<Vec<usize>>,
+    /// See [`Self::with_reverse_emit_order`]
+    reverse_emit_order: bool,
+    /// See [`Self::with_snapshot_interval`]
+    snapshot_interval: Option<usize>,
+    /// See [`Self::with_group_hashes_column`]
+    group_hashes_column: Option<String>,
+    /// See [`Self::with_output_hash_partitions`]
+    output_hash_partitions: Option<usize>,
+    /// Distributes the output into the hash partitions, created on the first
+    /// call to `execute` if [`Self::output_hash_partitions`] is set
+    hash_partitioned_output: Arc<Mutex<Option<Arc<RepartitionExec>>>>,
     cache: PlanProperties,
 }
 
@@ -388,6 +424,12 @@ impl AggregateExec {
             required_input_ordering: self.required_input_ordering.clone(),
             metrics: ExecutionPlanMetricsSet::new(),
             input_order_mode: self.input_order_mode.clone(),
+            output_column_order: self.output_column_order.clone(),
+            reverse_emit_order: self.reverse_emit_order,
+            snapshot_interval: self.snapshot_interval,
+            group_hashes_column: self.group_hashes_column.clone(),
+            output_hash_partitions: self.output_hash_partitions,
+            hash_partitioned_output: Default::default(),
             cache: self.cache.clone(),
             mode: self.mode,
             group_by: self.group_by.clone(),
@@ -523,6 +565,12 @@ impl AggregateExec {
             required_input_ordering,
             limit: None,
             input_order_mode,
+            output_column_order: None,
+            reverse_emit_order: false,
+            snapshot_interval: None,
+            group_hashes_column: None,
+            output_hash_partitions: None,
+            hash_partitioned_output: Default::default(),
             cache,
         })
     }
@@ -572,6 +620,302 @@ impl AggregateExec {
         self.limit
     }
 
+    /// Output the columns in `order` instead of the group keys followed by
+    /// the aggregate values, so that the output matches a target schema
+    /// without a separate projection.
+    ///
+    /// `order` must be a permutation of the output column indices: output
+    /// column `i` is the column at index `order[i]` in the current output.
+    ///
+    /// Returns an error for an aggregation without GROUP BY, or in
+    /// `Partial` mode, as the next aggregation reads the partial state by
+    /// position.
+    pub fn with_output_column_order(mut self, order: Vec<usize>) -> Result<Self> {
+        let schema = self.schema();
+        let num_columns = schema.fields().len();
+        let mut seen = vec![false; num_columns];
+        let is_permutation = order.len() == num_columns
+            && order.iter().all(|&idx| {
+                idx < num_columns && !std::mem::replace(&mut seen[idx], true)
+            });
+        if !is_permutation {
+            return plan_err!(
+                "Invalid output column order {order:?} for {num_columns} columns"
+            );
+        }
+        if self.group_by.expr.is_empty() {
+            return not_impl_err!(
+                "Reordering the output columns of an aggregation without GROUP BY"
+            );
+        }
+        if self.mode == AggregateMode::Partial {
+            return plan_err!(
+                "Cannot reorder the output columns of a partial aggregation"
+            );
+        }
+
+        // move the columns of the current properties to their new position
+        let exprs = order
+            .iter()
+            .map(|&idx| {
+                let name = schema.field(idx).name();
+                (Arc::new(Column::new(name, idx)) as _, name.clone())
+            })
+            .collect::<Vec<_>>();
+        let projection_mapping = ProjectionMapping::try_new(&exprs, &schema)?;
+        let eq_properties = self
+            .cache
+            .eq_properties
+            .project(&projection_mapping, Arc::new(schema.project(&order)?));
+        let partitioning = self
+            .cache
+            .partitioning
+            .project(&projection_mapping, &self.cache.eq_properties);
+        self.cache = PlanProperties::new(
+            eq_properties,
+            partitioning,
+            self.cache.emission_type,
+            self.cache.boundedness,
+        );
+
+        self.output_column_order = Some(match self.output_column_order.take() {
+            Some(current) => order.iter().map(|&idx| current[idx]).collect(),
+            None => order,
+        });
+        Ok(self)
+    }
+
+    /// Order of the output columns, see [`Self::with_output_column_order`]
+    pub fn output_column_order(&self) -> Option<&[usize]> {
+        self.output_column_order.as_deref()
+    }
+
+    /// Emit the groups in the reverse of the order in which they were
+    /// first seen, instead of in insertion order.
+    ///
+    /// This is a heuristic for aggregates feeding a `ORDER BY .. DESC
+    /// LIMIT k`: for some monotonic inputs (e.g. increasing keys or
+    /// timestamps) the newest groups are the most likely to sort first, so
+    /// the downstream sort or top k sees its final rows earlier. No order
+    /// is guaranteed. Each emitted chunk of groups is reversed separately,
+    /// so if groups are emitted early (e.g. under memory pressure) the
+    /// output is only reversed within each chunk.
+    ///
+    /// Returns an error for an aggregation without GROUP BY, or if the
+    /// input is ordered by the group keys, as the output would then no
+    /// longer follow the declared output ordering.
+    pub fn with_reverse_emit_order(mut self, reverse_emit_order: bool) -> Result<Self> {
+        if reverse_emit_order {
+            if self.group_by.expr.is_empty() {
+                return not_impl_err!(
+                    "Reversing the emit order of an aggregation without GROUP BY"
+                );
+            }
+            if self.input_order_mode != InputOrderMode::Linear {
+                return plan_err!(
+                    "Cannot reverse the emit order of an aggregation over input ordered by the group keys"
+                );
+            }
+        }
+        self.reverse_emit_order = reverse_emit_order;
+        Ok(self)
+    }
+
+    /// See [`Self::with_reverse_emit_order`]
+    pub fn reverse_emit_order(&self) -> bool {
+        self.reverse_emit_order
+    }
+
+    /// Emit a snapshot of the current value of all groups after every
+    /// `num_batches` input batches, while continuing to accumulate the
+    /// input.
+    ///
+    /// This allows consumers of a continuous input to observe the results
+    /// periodically. Each snapshot contains all groups seen so far, and
+    /// the final output follows the last snapshot as usual. The snapshots
+    /// do not disturb the accumulated state, but are not supported once
+    /// the aggregation spilled to disk.
+    ///
+    /// Returns an error for an aggregation without GROUP BY, or in
+    /// `Partial` mode, as the downstream aggregation would merge the same
+    /// state more than once. The snapshots would be split across the
+    /// partitions, so they can not be combined with
+    /// [`Self::with_output_hash_partitions`].
+    pub fn with_snapshot_interval(mut self, num_batches: Option<usize>) -> Result<Self> {
+        if num_batches.is_some() {
+            if num_batches == Some(0) {
+                return plan_err!("Snapshot interval must be at least one batch");
+            }
+            if self.group_by.expr.is_empty() {
+                return not_impl_err!("Snapshots of an aggregation without GROUP BY");
+            }
+            if self.mode == AggregateMode::Partial {
+                return plan_err!("Cannot emit snapshots of a partial aggregation");
+            }
+            if self.output_hash_partitions.is_some() {
+                return not_impl_err!("Snapshots of a hash partitioned output");
+            }
+        }
+        self.snapshot_interval = num_batches;
+        Ok(self)
+    }
+
+    /// See [`Self::with_snapshot_interval`]
+    pub fn snapshot_interval(&self) -> Option<usize> {
+        self.snapshot_interval
+    }
+
+    /// Use the `UInt64` input column `name` as the hash of the group
+    /// values of each row, instead of hashing the group values, for
+    /// example when the input was repartitioned by the hash of the group
+    /// keys and kept it.
+    ///
+    /// Rows with equal group values must have equal hashes, which is
+    /// checked for each batch in debug builds. The hashes of the rows
+    /// with different group values only need to differ for performance.
+    /// They are ignored by group values implementations that always
+    /// compute their own hashes, and when merging spilled state.
+    ///
+    /// Returns an error for an aggregation without GROUP BY or with
+    /// grouping sets, or if the input has no `UInt64` column `name`.
+    pub fn with_group_hashes_column(mut self, name: Option<String>) -> Result<Self> {
+        if let Some(name) = &name {
+            if self.group_by.expr.is_empty() {
+                return not_impl_err!(
+                    "Precomputed group hashes for an aggregation without GROUP BY"
+                );
+            }
+            if !self.group_by.is_single() {
+                return plan_err!(
+                    "Cannot use precomputed group hashes with grouping sets"
+                );
+            }
+            let input_schema = self.input.schema();
+            let Ok(field) = input_schema.field_with_name(name) else {
+                return plan_err!("Group hashes column '{name}' not found in input");
+            };
+            if field.data_type() != &DataType::UInt64 {
+                return plan_err!(
+                    "Group hashes column '{name}' has type {}, expected UInt64",
+                    field.data_type()
+                );
+            }
+        }
+        self.group_hashes_column = name;
+        Ok(self)
+    }
+
+    /// See [`Self::with_group_hashes_column`]
+    pub fn group_hashes_column(&self) -> Option<&str> {
+        self.group_hashes_column.as_deref()
+    }
+
+    /// Output `num_partitions` partitions, hash partitioned by the group
+    /// values, instead of one output partition per input partition.
+    ///
+    /// The output is partitioned like that of a [`RepartitionExec`] with
+    /// [`Partitioning::Hash`] on the group columns, and the plan properties
+    /// report that partitioning. This allows an aggregation whose output is
+    /// redistributed by its group keys, for example to be joined on them,
+    /// to do so without a separate repartition. If the input has more than
+    /// one partition, the output is no longer ordered.
+    ///
+    /// Returns an error for an aggregation without GROUP BY, together with
+    /// [`Self::with_snapshot_interval`], or if a different number of output
+    /// hash partitions was already set.
+    pub fn with_output_hash_partitions(
+        mut self,
+        num_partitions: Option<usize>,
+    ) -> Result<Self> {
+        if num_partitions == self.output_hash_partitions {
+            return Ok(self);
+        }
+        let Some(num_partitions) = num_partitions else {
+            return plan_err!("Cannot remove the output hash partitions once set");
+        };
+        if self.output_hash_partitions.is_some() {
+            return plan_err!("Output hash partitions are already set");
+        }
+        if num_partitions == 0 {
+            return plan_err!("Output must have at least one hash partition");
+        }
+        if self.group_by.expr.is_empty() {
+            return not_impl_err!(
+                "Hash partitioning the output of an aggregation without GROUP BY"
+            );
+        }
+        if self.snapshot_interval.is_some() {
+            return not_impl_err!("Snapshots of a hash partitioned output");
+        }
+
+        // the group columns, which may have been moved by the output column order
+        let schema = self.schema();
+        let exprs = (0..self.group_by.num_output_exprs())
+            .map(|idx| {
+                let idx = match &self.output_column_order {
+                    Some(order) => order.iter().position(|&i| i == idx).unwrap(),
+                    None => idx,
+                };
+                Arc::new(Column::new(schema.field(idx).name(), idx)) as _
+            })
+            .collect();
+        // the output partitions receive the groups of all input partitions
+        let mut eq_properties = self.cache.eq_properties.clone();
+        if self.cache.partitioning.partition_count() > 1 {
+            eq_properties.clear_orderings();
+            eq_properties.clear_per_partition_constants();
+        }
+        self.cache = PlanProperties::new(
+            eq_properties,
+            Partitioning::Hash(exprs, num_partitions),
+            self.cache.emission_type,
+            self.cache.boundedness,
+        );
+        self.output_hash_partitions = Some(num_partitions);
+        Ok(self)
+    }
+
+    /// See [`Self::with_output_hash_partitions`]
+    pub fn output_hash_partitions(&self) -> Option<usize> {
+        self.output_hash_partitions
+    }
+
+    /// Returns true if an option only supported by
+    /// [`GroupedHashAggregateStream`] is set
+    fn requires_grouped_hash_stream(&self) -> bool {
+        self.output_column_order.is_some()
+            || self.reverse_emit_order
+            || self.snapshot_interval.is_some()
+            || self.group_hashes_column.is_some()
+    }
+
+    /// Returns the [`RepartitionExec`] distributing the output of this
+    /// aggregation, without output hash partitions, into the output hash
+    /// partitions, see [`Self::with_output_hash_partitions`]
+    ///
+    /// All output partitions are read from the same [`RepartitionExec`],
+    /// which is created on the first call.
+    fn hash_partitioned_output(&self) -> Result<Arc<RepartitionExec>> {
+        let mut output = self.hash_partitioned_output.lock();
+        if let Some(repartition) = output.as_ref() {
+            return Ok(Arc::clone(repartition));
+        }
+
+        let mut aggregate = self.clone();
+        aggregate.output_hash_partitions = None;
+        aggregate.hash_partitioned_output = Default::default();
+        let input_partitions = self.input.output_partitioning().partition_count();
+        aggregate.cache = aggregate
+            .cache
+            .with_partitioning(Partitioning::UnknownPartitioning(input_partitions));
+        let repartition = Arc::new(RepartitionExec::try_new(
+            Arc::new(aggregate),
+            self.cache.partitioning.clone(),
+        )?);
+        *output = Some(Arc::clone(&repartition));
+        Ok(repartition)
+    }
+
     fn execute_typed(
         &self,
         partition: usize,
@@ -586,7 +930,9 @@ impl AggregateExec {
 
         // grouping by an expression that has a sort/limit upstream
         if let Some(limit) = self.limit {
-            if !self.is_unordered_unfiltered_group_by_distinct() {
+            if !self.is_unordered_unfiltered_group_by_distinct()
+                && !self.requires_grouped_hash_stream()
+            {
                 return Ok(StreamType::GroupedPriorityQueue(
                     GroupedTopKAggregateStream::new(self, context, partition, limit)?,
                 ));
@@ -750,6 +1096,21 @@ impl DisplayAs for AggregateExec {
                 if let Some(limit) = self.limit {
                     write!(f, ", lim=[{limit}]")?;
                 }
+                if let Some(order) = &self.output_column_order {
+                    write!(f, ", output_order={order:?}")?;
+                }
+                if self.reverse_emit_order {
+                    write!(f, ", reverse_emit_order=true")?;
+                }
+                if let Some(interval) = self.snapshot_interval {
+                    write!(f, ", snapshot_interval={interval}")?;
+                }
+                if let Some(name) = &self.group_hashes_column {
+                    write!(f, ", group_hashes={name}")?;
+                }
+                if let Some(num_partitions) = self.output_hash_partitions {
+                    write!(f, ", output_hash_partitions={num_partitions}")?;
+                }
 
                 if self.input_order_mode != InputOrderMode::Linear {
                     write!(f, ", ordering_mode={:?}", self.input_order_mode)?;
@@ -802,7 +1163,10 @@ impl ExecutionPlan for AggregateExec {
     /// `Ordered` and `PartiallyOrdered` cases, the `group_by` columns do have
     /// an ordering, which is preserved in the output.
     fn maintains_input_order(&self) -> Vec<bool> {
-        vec![self.input_order_mode != InputOrderMode::Linear]
+        // the hash partitions mix the groups of all input partitions
+        let mixes_partitions = self.output_hash_partitions.is_some()
+            && self.input.output_partitioning().partition_count() > 1;
+        vec![self.input_order_mode != InputOrderMode::Linear && !mixes_partitions]
     }
 
     fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
@@ -823,6 +1187,14 @@ impl ExecutionPlan for AggregateExec {
             Arc::clone(&self.schema),
         )?;
         me.limit = self.limit;
+        if let Some(order) = &self.output_column_order {
+            me = me.with_output_column_order(order.clone())?;
+        }
+        me = me
+            .with_reverse_emit_order(self.reverse_emit_order)?
+            .with_snapshot_interval(self.snapshot_interval)?
+            .with_group_hashes_column(self.group_hashes_column.clone())?
+            .with_output_hash_partitions(self.output_hash_partitions)?;
 
         Ok(Arc::new(me))
     }
@@ -832,6 +1204,9 @@ impl ExecutionPlan for AggregateExec {
         partition: usize,
         context: Arc<TaskContext>,
     ) -> Result<SendableRecordBatchStream> {
+        if self.output_hash_partitions.is_some() {
+            return self.hash_partitioned_output()?.execute(partition, context);
+        }
         self.execute_typed(partition, context)
             .map(|stream| stream.into())
     }
@@ -888,7 +1263,12 @@ impl ExecutionPlan for AggregateExec {
     }
 
     fn cardinality_effect(&self) -> CardinalityEffect {
-        CardinalityEffect::LowerEqual
+        // each snapshot outputs the groups again
+        if self.snapshot_interval.is_some() {
+            CardinalityEffect::Unknown
+        } else {
+            CardinalityEffect::LowerEqual
+        }
     }
 }
 
@@ -903,9 +1283,25 @@ fn create_schema(
 
     match mode {
         AggregateMode::Partial => {
-            // in partial mode, the fields of the accumulator's state
+            // in partial mode, the fields of the accumulator's state, which
+            // the next aggregation must be able to tell apart by name. The
+            // state of identical aggregates is identical, so they may share
+            // their state field names
+            let mut state_names: HashMap<String, &Arc<AggregateFunctionExpr>> =
+                HashMap::new();
             for expr in aggr_expr {
-                fields.extend(expr.state_fields()?.iter().cloned())
+                for field in expr.state_fields()? {
+                    let other = *state_names.entry(field.name().clone()).or_insert(expr);
+                    if other != expr {
+                        return internal_err!(
+                            "Aggregates {} and {} have a state field named {}, aggregates must have unique names",
+                            other.name(),
+                            expr.name(),
+                            field.name()
+                        );
+                    }
+                    fields.push(field)
+                }
             }
         }
         AggregateMode::Final
@@ -1319,13 +1715,14 @@ mod tests {
     use crate::memory::MemoryExec;
     use crate::test::assert_is_pending;
     use crate::test::exec::{assert_strong_count_converges_to_zero, BlockingExec};
-    use crate::RecordBatchStream;
+    use crate::{displayable, RecordBatchStream};
 
-    use arrow::array::{Float64Array, UInt32Array};
+    use arrow::array::{AsArray, Float64Array, UInt32Array};
     use arrow::compute::{concat_batches, SortOptions};
-    use arrow::datatypes::{DataType, Int32Type};
+    use arrow::datatypes::{Float64Type, Int32Type, Int64Type};
     use arrow_array::{
-        DictionaryArray, Float32Array, Int32Array, StructArray, UInt64Array,
+        BooleanArray, DictionaryArray, Float32Array, Int32Array, Int64Array, StringArray,
+        StringViewArray, StructArray, UInt64Array,
     };
     use datafusion_common::{
         assert_batches_eq, assert_batches_sorted_eq, internal_err, DataFusionError,
@@ -1334,6 +1731,10 @@ mod tests {
     use datafusion_execution::config::SessionConfig;
     use datafusion_execution::memory_pool::FairSpillPool;
     use datafusion_execution::runtime_env::RuntimeEnvBuilder;
+    use datafusion_expr::function::AccumulatorArgs;
+    use datafusion_expr::{
+        create_udaf, AggregateUDF, AggregateUDFImpl, Signature, Volatility,
+    };
     use datafusion_functions_aggregate::array_agg::array_agg_udaf;
     use datafusion_functions_aggregate::average::avg_udaf;
     use datafusion_functions_aggregate::count::count_udaf;
@@ -2572,8 +2973,7 @@ mod tests {
             Field::new("val", DataType::Int32, true),
         ]));
 
-        let group_by =
-            PhysicalGroupBy::new_single(vec![(col("key", &schema)?, "key".to_string())]);
+        let group_by = PhysicalGroupBy::new_from_indices(&[0], &schema)?;
 
         let aggr_expr =
             vec![
@@ -2654,8 +3054,7 @@ mod tests {
             Field::new("val", DataType::Int32, true),
         ]));
 
-        let group_by =
-            PhysicalGroupBy::new_single(vec![(col("key", &schema)?, "key".to_string())]);
+        let group_by = PhysicalGroupBy::new_from_indices(&[0], &schema)?;
 
         let aggr_expr =
             vec![
@@ -2738,6 +3137,1377 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_group_by_new_from_indices() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Int64, false),
+        ]));
+
+        let group_by = PhysicalGroupBy::new_from_indices(&[2, 0], &schema)?;
+        assert_eq!(
+            group_by,
+            PhysicalGroupBy::new_single(vec![
+                (col("c", &schema)?, "c".to_string()),
+                (col("a", &schema)?, "a".to_string()),
+            ])
+        );
+        assert_eq!(
+            group_by.output_fields(&schema)?,
+            vec![
+                Field::new("c", DataType::Int64, false),
+                Field::new("a", DataType::Int32, false),
+            ]
+        );
+
+        let err = PhysicalGroupBy::new_from_indices(&[0, 3], &schema)
+            .unwrap_err()
+            .strip_backtrace();
+        assert_eq!(
+            err,
+            "Error during planning: Group by column index 3 is out of bounds for a schema with 3 fields"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grouped_hash_output_column_order() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, false),
+            Field::new("c", DataType::Int64, false),
+        ]));
+
+        let group_by = PhysicalGroupBy::new_from_indices(&[0, 1], &schema)?;
+        let aggr_expr = vec![
+            AggregateExprBuilder::new(sum_udaf(), vec![col("c", &schema)?])
+                .schema(Arc::clone(&schema))
+                .alias("SUM(c)")
+                .build()
+                .map(Arc::new)?,
+            AggregateExprBuilder::new(count_udaf(), vec![col("c", &schema)?])
+                .schema(Arc::clone(&schema))
+                .alias("COUNT(c)")
+                .build()
+                .map(Arc::new)?,
+        ];
+
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 1, 2])),
+                Arc::new(StringArray::from(vec!["x", "y", "x", "z"])),
+                Arc::new(Int64Array::from(vec![10, 20, 30, 40])),
+            ],
+        )?;
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![batch]],
+            Arc::clone(&schema),
+            None,
+        )?);
+        let aggregate_exec = AggregateExec::try_new(
+            AggregateMode::Single,
+            group_by,
+            aggr_expr,
+            vec![None, None],
+            input,
+            schema,
+        )?;
+
+        let aggregate_exec = aggregate_exec.with_output_column_order(vec![2, 0, 3, 1])?;
+        let field_names = aggregate_exec
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(field_names, vec!["SUM(c)", "a", "COUNT(c)", "b"]);
+        assert_eq!(
+            displayable(&aggregate_exec).one_line().to_string(),
+            "AggregateExec: mode=Single, gby=[a@0 as a, b@1 as b], aggr=[SUM(c), COUNT(c)], output_order=[2, 0, 3, 1]\n"
+        );
+
+        let expected = [
+            "+--------+---+----------+---+",
+            "| SUM(c) | a | COUNT(c) | b |",
+            "+--------+---+----------+---+",
+            "| 40     | 1 | 2        | x |",
+            "| 20     | 2 | 1        | y |",
+            "| 40     | 2 | 1        | z |",
+            "+--------+---+----------+---+",
+        ];
+        let task_ctx = Arc::new(TaskContext::default());
+        let output = collect(aggregate_exec.execute(0, Arc::clone(&task_ctx))?).await?;
+        assert_batches_sorted_eq!(expected, &output);
+
+        // the order is kept when the plan is rebuilt, and a soft limit
+        // does not switch to an aggregation that ignores it
+        let input = Arc::clone(aggregate_exec.input());
+        let aggregate_exec = Arc::new(aggregate_exec.with_limit(Some(10)))
+            .with_new_children(vec![input])?;
+        assert_eq!(aggregate_exec.schema().field(0).name(), "SUM(c)");
+        let output = collect(aggregate_exec.execute(0, task_ctx)?).await?;
+        assert_batches_sorted_eq!(expected, &output);
+
+        // orders are relative to the current output
+        let aggregate_exec = aggregate_exec
+            .as_any()
+            .downcast_ref::<AggregateExec>()
+            .unwrap()
+            .clone()
+            .with_output_column_order(vec![1, 3, 0, 2])?;
+        assert_eq!(
+            aggregate_exec.output_column_order(),
+            Some([0, 1, 2, 3].as_slice())
+        );
+
+        // not a permutation of the output columns
+        let err = aggregate_exec
+            .with_output_column_order(vec![0, 0, 1, 2])
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("Invalid output column order"),
+            "unexpected error: {err}"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grouped_hash_reverse_emit_order() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("c", DataType::Int64, false),
+        ]));
+
+        let group_by =
+            PhysicalGroupBy::new_single(vec![(col("a", &schema)?, "a".to_string())]);
+        let aggr_expr =
+            vec![
+                AggregateExprBuilder::new(sum_udaf(), vec![col("c", &schema)?])
+                    .schema(Arc::clone(&schema))
+                    .alias("SUM(c)")
+                    .build()
+                    .map(Arc::new)?,
+            ];
+
+        let input_data = vec![
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(Int32Array::from(vec![3, 1, 3])),
+                    Arc::new(Int64Array::from(vec![10, 20, 30])),
+                ],
+            )?,
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(Int32Array::from(vec![2, 1, 4])),
+                    Arc::new(Int64Array::from(vec![40, 50, 60])),
+                ],
+            )?,
+        ];
+        let input = Arc::new(MemoryExec::try_new(
+            &[input_data],
+            Arc::clone(&schema),
+            None,
+        )?);
+        let aggregate_exec = AggregateExec::try_new(
+            AggregateMode::Single,
+            group_by,
+            aggr_expr,
+            vec![None],
+            input,
+            schema,
+        )?;
+
+        // groups are first seen in the order 3, 1, 2, 4
+        let task_ctx = Arc::new(TaskContext::default());
+        let output = collect(aggregate_exec.execute(0, Arc::clone(&task_ctx))?).await?;
+        let expected = [
+            "+---+--------+",
+            "| a | SUM(c) |",
+            "+---+--------+",
+            "| 3 | 40     |",
+            "| 1 | 70     |",
+            "| 2 | 40     |",
+            "| 4 | 60     |",
+            "+---+--------+",
+        ];
+        assert_batches_eq!(expected, &output);
+
+        let aggregate_exec = aggregate_exec.with_reverse_emit_order(true)?;
+        assert_eq!(
+            displayable(&aggregate_exec).one_line().to_string(),
+            "AggregateExec: mode=Single, gby=[a@0 as a], aggr=[SUM(c)], reverse_emit_order=true\n"
+        );
+        let output = collect(aggregate_exec.execute(0, Arc::clone(&task_ctx))?).await?;
+        let expected = [
+            "+---+--------+",
+            "| a | SUM(c) |",
+            "+---+--------+",
+            "| 4 | 60     |",
+            "| 2 | 40     |",
+            "| 1 | 70     |",
+            "| 3 | 40     |",
+            "+---+--------+",
+        ];
+        assert_batches_eq!(expected, &output);
+
+        // the option is kept when the plan is rebuilt
+        let input = Arc::clone(aggregate_exec.input());
+        let aggregate_exec = Arc::new(aggregate_exec).with_new_children(vec![input])?;
+        let output = collect(aggregate_exec.execute(0, task_ctx)?).await?;
+        assert_batches_eq!(expected, &output);
+
+        // input ordered by the group keys
+        let schema = aggregate_exec.children()[0].schema();
+        let sorted_input = Arc::new(
+            MemoryExec::try_new(&[vec![]], Arc::clone(&schema), None)?
+                .try_with_sort_information(vec![LexOrdering::new(vec![
+                    PhysicalSortExpr::new_default(col("a", &schema)?),
+                ])])?,
+        );
+        let err = Arc::clone(&aggregate_exec)
+            .with_new_children(vec![sorted_input])
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("Cannot reverse the emit order of an aggregation over input ordered by the group keys"),
+            "unexpected error: {err}"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grouped_hash_snapshot_interval() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("c", DataType::Float64, false),
+        ]));
+
+        let group_by =
+            PhysicalGroupBy::new_single(vec![(col("a", &schema)?, "a".to_string())]);
+        let aggr_expr = vec![
+            AggregateExprBuilder::new(sum_udaf(), vec![col("c", &schema)?])
+                .schema(Arc::clone(&schema))
+                .alias("SUM(c)")
+                .build()
+                .map(Arc::new)?,
+            AggregateExprBuilder::new(avg_udaf(), vec![col("c", &schema)?])
+                .schema(Arc::clone(&schema))
+                .alias("AVG(c)")
+                .build()
+                .map(Arc::new)?,
+        ];
+
+        let input_data = vec![
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(Int32Array::from(vec![1, 2, 1])),
+                    Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0])),
+                ],
+            )?,
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(Int32Array::from(vec![2, 3])),
+                    Arc::new(Float64Array::from(vec![10.0, 20.0])),
+                ],
+            )?,
+        ];
+        let input = Arc::new(MemoryExec::try_new(
+            &[input_data],
+            Arc::clone(&schema),
+            None,
+        )?);
+        let aggregate_exec = AggregateExec::try_new(
+            AggregateMode::Single,
+            group_by,
+            aggr_expr,
+            vec![None, None],
+            input,
+            schema,
+        )?;
+
+        let aggregate_exec = aggregate_exec.with_snapshot_interval(Some(1))?;
+        assert_eq!(
+            displayable(&aggregate_exec).one_line().to_string(),
+            "AggregateExec: mode=Single, gby=[a@0 as a], aggr=[SUM(c), AVG(c)], snapshot_interval=1\n"
+        );
+        assert!(matches!(
+            aggregate_exec.cardinality_effect(),
+            CardinalityEffect::Unknown
+        ));
+        let task_ctx = Arc::new(TaskContext::default());
+        let output = collect(aggregate_exec.execute(0, task_ctx)?).await?;
+        assert_eq!(output.len(), 3);
+
+        // snapshot after the first batch
+        let expected = [
+            "+---+--------+--------+",
+            "| a | SUM(c) | AVG(c) |",
+            "+---+--------+--------+",
+            "| 1 | 4.0    | 2.0    |",
+            "| 2 | 2.0    | 2.0    |",
+            "+---+--------+--------+",
+        ];
+        assert_batches_eq!(expected, &output[..1]);
+
+        // the snapshot after the second batch includes the updated values,
+        // and the final output matches it
+        let expected = [
+            "+---+--------+--------+",
+            "| a | SUM(c) | AVG(c) |",
+            "+---+--------+--------+",
+            "| 1 | 4.0    | 2.0    |",
+            "| 2 | 12.0   | 6.0    |",
+            "| 3 | 20.0   | 20.0   |",
+            "+---+--------+--------+",
+        ];
+        assert_batches_eq!(expected, &output[1..2]);
+        assert_batches_eq!(expected, &output[2..]);
+
+        let err = aggregate_exec
+            .clone()
+            .with_snapshot_interval(Some(0))
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("Snapshot interval must be at least one batch"),
+            "unexpected error: {err}"
+        );
+        let partial_exec = AggregateExec::try_new(
+            AggregateMode::Partial,
+            aggregate_exec.group_expr().clone(),
+            aggregate_exec.aggr_expr().to_vec(),
+            vec![None, None],
+            Arc::clone(aggregate_exec.input()),
+            aggregate_exec.input_schema(),
+        )?;
+        let err = partial_exec
+            .with_snapshot_interval(Some(1))
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("Cannot emit snapshots of a partial aggregation"),
+            "unexpected error: {err}"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grouped_hash_max_chunk_rows() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("c", DataType::Float64, false),
+        ]));
+        // a single large batch of distinct groups
+        let num_rows = 20_000;
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..num_rows)),
+                Arc::new(Float64Array::from_iter_values(
+                    (0..num_rows).map(|i| i as f64),
+                )),
+            ],
+        )?;
+
+        let aggregate_exec = |input_data: Vec<RecordBatch>| -> Result<AggregateExec> {
+            let group_by =
+                PhysicalGroupBy::new_single(vec![(col("a", &schema)?, "a".to_string())]);
+            let aggr_expr =
+                vec![
+                    AggregateExprBuilder::new(sum_udaf(), vec![col("c", &schema)?])
+                        .schema(Arc::clone(&schema))
+                        .alias("SUM(c)")
+                        .build()
+                        .map(Arc::new)?,
+                ];
+            let input = Arc::new(MemoryExec::try_new(
+                &[input_data],
+                Arc::clone(&schema),
+                None,
+            )?);
+            AggregateExec::try_new(
+                AggregateMode::Single,
+                group_by,
+                aggr_expr,
+                vec![None],
+                input,
+                Arc::clone(&schema),
+            )
+        };
+        // a batch size above the number of groups, so exceeding the limit
+        // is not deferred to a spill
+        let task_ctx = |max_aggregate_chunk_rows: Option<usize>| {
+            let mut session_config = SessionConfig::new().with_batch_size(100_000);
+            session_config
+                .options_mut()
+                .execution
+                .max_aggregate_chunk_rows = max_aggregate_chunk_rows;
+            let runtime = RuntimeEnvBuilder::new()
+                .with_memory_pool(Arc::new(FairSpillPool::new(64 * 1024)))
+                .build_arc()
+                .unwrap();
+            let task_ctx = TaskContext::default()
+                .with_session_config(session_config)
+                .with_runtime(runtime);
+            Arc::new(task_ctx)
+        };
+
+        // the whole batch is aggregated before the reservation fails
+        let exec = aggregate_exec(vec![batch.clone()])?;
+        let err = collect(exec.execute(0, task_ctx(None))?)
+            .await
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("Resources exhausted"),
+            "unexpected error: {err}"
+        );
+        let group_count = exec.metrics().unwrap().sum_by_name("group_count").unwrap();
+        assert_eq!(group_count.as_usize(), num_rows as usize);
+
+        // the aggregation fails at the first chunk exceeding the limit
+        let exec = aggregate_exec(vec![batch])?;
+        let err = collect(exec.execute(0, task_ctx(Some(1000)))?)
+            .await
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("Resources exhausted"),
+            "unexpected error: {err}"
+        );
+        let group_count = exec
+            .metrics()
+            .unwrap()
+            .sum_by_name("group_count")
+            .unwrap()
+            .as_usize();
+        assert!(
+            group_count > 0 && group_count < num_rows as usize,
+            "unexpected group count {group_count}"
+        );
+        assert_eq!(group_count % 1000, 0);
+
+        let exec = aggregate_exec(vec![])?;
+        let err = exec
+            .execute(0, task_ctx(Some(0)))
+            .err()
+            .unwrap()
+            .strip_backtrace();
+        assert!(
+            err.contains("max_aggregate_chunk_rows must be at least 1"),
+            "unexpected error: {err}"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grouped_hash_max_chunk_rows_spill() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("c", DataType::Float64, false),
+        ]));
+        // a single large batch of distinct groups
+        let num_rows = 20_000;
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..num_rows)),
+                Arc::new(Float64Array::from_iter_values(
+                    (0..num_rows).map(|i| i as f64),
+                )),
+            ],
+        )?;
+
+        let group_by =
+            PhysicalGroupBy::new_single(vec![(col("a", &schema)?, "a".to_string())]);
+        let aggr_expr = vec![AggregateExprBuilder::new(
+            sum_udaf(),
+            vec![col("c", &schema)?],
+        )
+        .schema(Arc::clone(&schema))
+        .alias("SUM(c)")
+        .build()
+        .map(Arc::new)?];
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![batch]],
+            Arc::clone(&schema),
+            None,
+        )?);
+        let exec = AggregateExec::try_new(
+            AggregateMode::Single,
+            group_by,
+            aggr_expr,
+            vec![None],
+            input,
+            Arc::clone(&schema),
+        )?;
+
+        // a batch size below the number of groups, so the groups of the
+        // previous chunks are spilled when the limit is hit
+        let mut session_config = SessionConfig::new().with_batch_size(1000);
+        session_config.options_mut().execution.max_aggregate_chunk_rows = Some(1000);
+        let runtime = RuntimeEnvBuilder::new()
+            .with_memory_pool(Arc::new(FairSpillPool::new(256 * 1024)))
+            .build_arc()?;
+        let task_ctx = TaskContext::default()
+            .with_session_config(session_config)
+            .with_runtime(runtime);
+
+        let result = collect(exec.execute(0, Arc::new(task_ctx))?).await?;
+        let result = concat_batches(&exec.schema(), &result)?;
+        assert_eq!(result.num_rows(), num_rows as usize);
+        let keys = result.column(0).as_primitive::<Int32Type>();
+        let sums = result.column(1).as_primitive::<Float64Type>();
+        for (key, sum) in keys.values().iter().zip(sums.values()) {
+            assert_eq!(*key as f64, *sum);
+        }
+
+        let spill_count = exec.metrics().unwrap().spill_count().unwrap();
+        assert!(spill_count > 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grouped_hash_group_hashes_column() -> Result<()> {
+        // a boolean group column is hashed by `GroupValuesRows`
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Boolean, true),
+            Field::new("h", DataType::UInt64, false),
+            Field::new("c", DataType::Int64, false),
+        ]));
+        let a = [1, 2, 1, 3, 2, 1, 3, 1];
+        let b = [
+            Some(true),
+            Some(false),
+            Some(true),
+            None,
+            Some(false),
+            Some(false),
+            None,
+            Some(true),
+        ];
+        let batch = |hash: fn(i32, Option<bool>) -> u64| -> Result<RecordBatch> {
+            Ok(RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(Int32Array::from(a.to_vec())),
+                    Arc::new(BooleanArray::from(b.to_vec())),
+                    Arc::new(UInt64Array::from_iter_values(
+                        a.iter().zip(b).map(|(&a, b)| hash(a, b)),
+                    )),
+                    Arc::new(Int64Array::from_iter_values(0..a.len() as i64)),
+                ],
+            )?)
+        };
+
+        let aggregate_exec = |input_data: Vec<RecordBatch>| -> Result<AggregateExec> {
+            let group_by = PhysicalGroupBy::new_single(vec![
+                (col("a", &schema)?, "a".to_string()),
+                (col("b", &schema)?, "b".to_string()),
+            ]);
+            let aggr_expr =
+                vec![
+                    AggregateExprBuilder::new(sum_udaf(), vec![col("c", &schema)?])
+                        .schema(Arc::clone(&schema))
+                        .alias("SUM(c)")
+                        .build()
+                        .map(Arc::new)?,
+                ];
+            let input = Arc::new(MemoryExec::try_new(
+                &[input_data],
+                Arc::clone(&schema),
+                None,
+            )?);
+            AggregateExec::try_new(
+                AggregateMode::Single,
+                group_by,
+                aggr_expr,
+                vec![None],
+                input,
+                Arc::clone(&schema),
+            )
+        };
+        let task_ctx = Arc::new(TaskContext::default());
+
+        let expected = [
+            "+---+-------+--------+",
+            "| a | b     | SUM(c) |",
+            "+---+-------+--------+",
+            "| 1 | false | 5      |",
+            "| 1 | true  | 9      |",
+            "| 2 | false | 5      |",
+            "| 3 |       | 9      |",
+            "+---+-------+--------+",
+        ];
+        let exec = aggregate_exec(vec![batch(|_, _| 0)?])?;
+        let output = collect(exec.execute(0, Arc::clone(&task_ctx))?).await?;
+        assert_batches_sorted_eq!(expected, &output);
+
+        // precomputed hashes, distinct or colliding, group the same rows
+        let hashes: [fn(i32, Option<bool>) -> u64; 2] = [
+            |a, b| a as u64 * 3 + b.map_or(2, u64::from),
+            |a, _| a as u64 % 2,
+        ];
+        for hash in hashes {
+            let exec = aggregate_exec(vec![batch(hash)?, batch(hash)?.slice(0, 4)])?
+                .with_group_hashes_column(Some("h".to_string()))?;
+            assert_eq!(exec.group_hashes_column(), Some("h"));
+            assert_eq!(
+                displayable(&exec).one_line().to_string(),
+                "AggregateExec: mode=Single, gby=[a@0 as a, b@1 as b], aggr=[SUM(c)], group_hashes=h\n"
+            );
+            // carried over to new children
+            let exec =
+                Arc::new(exec).with_new_children(vec![Arc::new(MemoryExec::try_new(
+                    &[vec![batch(hash)?, batch(hash)?.slice(0, 4)]],
+                    Arc::clone(&schema),
+                    None,
+                )?)])?;
+            let output = collect(exec.execute(0, Arc::clone(&task_ctx))?).await?;
+            assert_batches_sorted_eq!(
+                [
+                    "+---+-------+--------+",
+                    "| a | b     | SUM(c) |",
+                    "+---+-------+--------+",
+                    "| 1 | false | 5      |",
+                    "| 1 | true  | 11     |",
+                    "| 2 | false | 6      |",
+                    "| 3 |       | 12     |",
+                    "+---+-------+--------+",
+                ],
+                &output
+            );
+        }
+
+        let err = aggregate_exec(vec![])?
+            .with_group_hashes_column(Some("c".to_string()))
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("Group hashes column 'c' has type Int64, expected UInt64"),
+            "unexpected error: {err}"
+        );
+        let err = aggregate_exec(vec![])?
+            .with_group_hashes_column(Some("x".to_string()))
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("Group hashes column 'x' not found in input"),
+            "unexpected error: {err}"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grouped_hash_output_hash_partitions() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::UInt32, false),
+            Field::new("c", DataType::Int64, false),
+        ]));
+        // two input partitions with different groups
+        let batch = |offset: u32| {
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(UInt32Array::from_iter_values(
+                        (0..1_500).map(|i| offset + i % 500),
+                    )),
+                    Arc::new(Int64Array::from_iter_values(0..1_500)),
+                ],
+            )
+        };
+        let input_partitions = vec![vec![batch(0)?], vec![batch(500)?]];
+
+        let aggregate_exec = || -> Result<AggregateExec> {
+            let group_by =
+                PhysicalGroupBy::new_single(vec![(col("a", &schema)?, "a".to_string())]);
+            let aggr_expr =
+                vec![
+                    AggregateExprBuilder::new(sum_udaf(), vec![col("c", &schema)?])
+                        .schema(Arc::clone(&schema))
+                        .alias("SUM(c)")
+                        .build()
+                        .map(Arc::new)?,
+                ];
+            let input = Arc::new(MemoryExec::try_new(
+                &input_partitions,
+                Arc::clone(&schema),
+                None,
+            )?);
+            AggregateExec::try_new(
+                AggregateMode::SinglePartitioned,
+                group_by,
+                aggr_expr,
+                vec![None],
+                input,
+                Arc::clone(&schema),
+            )
+        };
+        let task_ctx = Arc::new(TaskContext::default());
+
+        let exec = aggregate_exec()?;
+        assert_eq!(exec.properties().output_partitioning().partition_count(), 2);
+        let mut expected = vec![];
+        for partition in 0..2 {
+            expected.extend(collect(exec.execute(partition, Arc::clone(&task_ctx))?).await?);
+        }
+
+        let num_partitions = 4;
+        let exec = exec.with_output_hash_partitions(Some(num_partitions))?;
+        assert_eq!(exec.output_hash_partitions(), Some(num_partitions));
+        assert_eq!(
+            displayable(&exec).one_line().to_string(),
+            "AggregateExec: mode=SinglePartitioned, gby=[a@0 as a], aggr=[SUM(c)], output_hash_partitions=4\n"
+        );
+        let Partitioning::Hash(exprs, 4) = exec.properties().output_partitioning() else {
+            panic!("unexpected partitioning {:?}", exec.properties().output_partitioning());
+        };
+        assert_eq!(exprs, &vec![col("a", &exec.schema())?]);
+        // carried over to new children
+        let input = Arc::clone(exec.input());
+        let exec = Arc::new(exec).with_new_children(vec![input])?;
+        assert_eq!(exec.output_partitioning().partition_count(), num_partitions);
+        // read all partitions concurrently, as they are filled together
+        let output = futures::future::try_join_all((0..num_partitions).map(|partition| {
+            collect(exec.execute(partition, Arc::clone(&task_ctx)).unwrap())
+        }))
+        .await?;
+
+        // each output partition holds the groups whose hash modulo the
+        // number of partitions is its index, like a `RepartitionExec`
+        let random_state = ahash::RandomState::with_seeds(0, 0, 0, 0);
+        for (partition, batches) in output.iter().enumerate() {
+            for batch in batches {
+                let mut hashes = vec![0; batch.num_rows()];
+                crate::hash_utils::create_hashes(
+                    &[Arc::clone(batch.column(0))],
+                    &random_state,
+                    &mut hashes,
+                )?;
+                assert!(hashes
+                    .iter()
+                    .all(|hash| hash % num_partitions as u64 == partition as u64));
+            }
+        }
+
+        // together the partitions hold all groups
+        let expected = concat_batches(&exec.schema(), &expected)?;
+        let output = concat_batches(&exec.schema(), output.iter().flatten())?;
+        let sorted = |batch: &RecordBatch| -> Result<RecordBatch> {
+            let indices = arrow::compute::sort_to_indices(batch.column(0), None, None)?;
+            Ok(arrow::compute::take_record_batch(batch, &indices)?)
+        };
+        assert_eq!(output.num_rows(), 1_000);
+        assert_eq!(sorted(&output)?, sorted(&expected)?);
+
+        // the partitioning follows the group column to its output position
+        let exec = aggregate_exec()?
+            .with_output_column_order(vec![1, 0])?
+            .with_output_hash_partitions(Some(num_partitions))?;
+        let Partitioning::Hash(exprs, 4) = exec.properties().output_partitioning() else {
+            panic!("unexpected partitioning {:?}", exec.properties().output_partitioning());
+        };
+        assert_eq!(exprs, &vec![col("a", &exec.schema())?]);
+        assert_eq!(exec.schema().index_of("a")?, 1);
+
+        let err = aggregate_exec()?
+            .with_output_hash_partitions(Some(0))
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("Output must have at least one hash partition"),
+            "unexpected error: {err}"
+        );
+        let err = aggregate_exec()?
+            .with_output_hash_partitions(Some(num_partitions))?
+            .with_output_hash_partitions(None)
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("Cannot remove the output hash partitions once set"),
+            "unexpected error: {err}"
+        );
+        let err = aggregate_exec()?
+            .with_snapshot_interval(Some(1))?
+            .with_output_hash_partitions(Some(num_partitions))
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("Snapshots of a hash partitioned output"),
+            "unexpected error: {err}"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grouping_sets_no_double_counting() -> Result<()> {
+        // `b` is always null, so without the grouping id the groups of
+        // `(a, NULL)` and `(a, b)` would have the same keys
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::UInt32, false),
+            Field::new("b", DataType::Float64, true),
+            Field::new("c", DataType::Float64, false),
+        ]));
+
+        let grouping_set = PhysicalGroupBy::new(
+            vec![
+                (col("a", &schema)?, "a".to_string()),
+                (col("b", &schema)?, "b".to_string()),
+            ],
+            vec![
+                (lit(ScalarValue::UInt32(None)), "a".to_string()),
+                (lit(ScalarValue::Float64(None)), "b".to_string()),
+            ],
+            vec![
+                vec![false, true],  // (a, NULL)
+                vec![false, false], // (a, b)
+                vec![true, true],   // (NULL, NULL)
+            ],
+        );
+        let aggr_expr = vec![
+            AggregateExprBuilder::new(sum_udaf(), vec![col("c", &schema)?])
+                .schema(Arc::clone(&schema))
+                .alias("SUM(c)")
+                .build()
+                .map(Arc::new)?,
+            AggregateExprBuilder::new(count_udaf(), vec![col("c", &schema)?])
+                .schema(Arc::clone(&schema))
+                .alias("COUNT(c)")
+                .build()
+                .map(Arc::new)?,
+        ];
+
+        let batch = |a: Vec<u32>, c: Vec<f64>| {
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(UInt32Array::from(a)),
+                    Arc::new(Float64Array::from(vec![None; c.len()])),
+                    Arc::new(Float64Array::from(c)),
+                ],
+            )
+        };
+        let input_data = vec![
+            batch(vec![1, 2, 1], vec![1.0, 2.0, 3.0])?,
+            batch(vec![2, 3], vec![10.0, 20.0])?,
+        ];
+        let input = Arc::new(MemoryExec::try_new(
+            &[input_data],
+            Arc::clone(&schema),
+            None,
+        )?);
+        let aggregate_exec = Arc::new(AggregateExec::try_new(
+            AggregateMode::Single,
+            grouping_set,
+            aggr_expr,
+            vec![None, None],
+            input,
+            schema,
+        )?);
+
+        let task_ctx = Arc::new(TaskContext::default());
+        let result = collect(aggregate_exec.execute(0, task_ctx)?).await?;
+
+        // every input row is counted exactly once by each grouping set
+        let expected = [
+            "+---+---+---------------+--------+----------+",
+            "| a | b | __grouping_id | SUM(c) | COUNT(c) |",
+            "+---+---+---------------+--------+----------+",
+            "|   |   | 3             | 36.0   | 5        |",
+            "| 1 |   | 0             | 4.0    | 2        |",
+            "| 1 |   | 1             | 4.0    | 2        |",
+            "| 2 |   | 0             | 12.0   | 2        |",
+            "| 2 |   | 1             | 12.0   | 2        |",
+            "| 3 |   | 0             | 20.0   | 1        |",
+            "| 3 |   | 1             | 20.0   | 1        |",
+            "+---+---+---------------+--------+----------+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_group_count_metric() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Int32, true),
+            Field::new("val", DataType::Int32, true),
+        ]));
+
+        let group_by = PhysicalGroupBy::new_from_indices(&[0], &schema)?;
+        let aggr_expr =
+            vec![
+                AggregateExprBuilder::new(count_udaf(), vec![col("val", &schema)?])
+                    .schema(Arc::clone(&schema))
+                    .alias(String::from("COUNT(val)"))
+                    .build()
+                    .map(Arc::new)?,
+            ];
+
+        let input_data = vec![
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(Int32Array::from(vec![1, 2, 3, 2])),
+                    Arc::new(Int32Array::from(vec![0, 0, 0, 0])),
+                ],
+            )?,
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(Int32Array::from(vec![Some(4), Some(1), None])),
+                    Arc::new(Int32Array::from(vec![0, 0, 0])),
+                ],
+            )?,
+        ];
+        let input = Arc::new(MemoryExec::try_new(
+            &[input_data],
+            Arc::clone(&schema),
+            None,
+        )?);
+        let aggregate_exec = Arc::new(AggregateExec::try_new(
+            AggregateMode::Single,
+            group_by,
+            aggr_expr,
+            vec![None],
+            input,
+            schema,
+        )?);
+
+        let output =
+            collect(aggregate_exec.execute(0, Arc::new(TaskContext::default()))?).await?;
+        assert_eq!(output.iter().map(|b| b.num_rows()).sum::<usize>(), 5);
+
+        let group_count = aggregate_exec
+            .metrics()
+            .unwrap()
+            .sum_by_name("group_count")
+            .unwrap();
+        assert_eq!(group_count.as_usize(), 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hash_table_metrics() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Int32, true),
+            Field::new("val", DataType::Int32, true),
+        ]));
+
+        // returns the rehash count and load of aggregating `num_groups` groups
+        let aggregate = |num_groups: i32| {
+            let schema = Arc::clone(&schema);
+            async move {
+                let group_by = PhysicalGroupBy::new_single(vec![(
+                    col("key", &schema)?,
+                    "key".to_string(),
+                )]);
+                let aggr_expr = vec![AggregateExprBuilder::new(
+                    count_udaf(),
+                    vec![col("val", &schema)?],
+                )
+                .schema(Arc::clone(&schema))
+                .alias(String::from("COUNT(val)"))
+                .build()
+                .map(Arc::new)?];
+                let batch = RecordBatch::try_new(
+                    Arc::clone(&schema),
+                    vec![
+                        Arc::new(Int32Array::from_iter_values(0..num_groups)),
+                        Arc::new(Int32Array::from_iter_values(0..num_groups)),
+                    ],
+                )?;
+                let input = Arc::new(MemoryExec::try_new(
+                    &[vec![batch]],
+                    Arc::clone(&schema),
+                    None,
+                )?);
+                let aggregate_exec = Arc::new(AggregateExec::try_new(
+                    AggregateMode::Single,
+                    group_by,
+                    aggr_expr,
+                    vec![None],
+                    input,
+                    schema,
+                )?);
+                collect(aggregate_exec.execute(0, Arc::new(TaskContext::default()))?)
+                    .await?;
+
+                let metrics = aggregate_exec.metrics().unwrap();
+                let metric = |name| metrics.sum_by_name(name).unwrap().as_usize();
+                Ok::<_, DataFusionError>((
+                    metric("hash_table_rehashes"),
+                    metric("hash_table_load_percent"),
+                ))
+            }
+        };
+
+        // the groups fit into the initial capacity of the hash table
+        let (rehashes, load_percent) = aggregate(100).await?;
+        assert_eq!(rehashes, 0);
+        assert!(load_percent > 0 && load_percent <= 100, "{load_percent}");
+
+        let (rehashes, load_percent) = aggregate(10_000).await?;
+        assert!(rehashes > 0);
+        assert!(load_percent > 0 && load_percent <= 100, "{load_percent}");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_max_distinct_groups() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Int32, true),
+            Field::new("val", DataType::Int32, true),
+        ]));
+
+        // 3 new groups per batch, 9 groups in total
+        let input_data = (0..3)
+            .map(|i| {
+                RecordBatch::try_new(
+                    Arc::clone(&schema),
+                    vec![
+                        Arc::new(Int32Array::from(vec![
+                            i * 3,
+                            i * 3 + 1,
+                            i * 3 + 2,
+                            i * 3,
+                        ])),
+                        Arc::new(Int32Array::from(vec![0, 0, 0, 0])),
+                    ],
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let input = Arc::new(MemoryExec::try_new(
+            &[input_data],
+            Arc::clone(&schema),
+            None,
+        )?);
+
+        let run = |max_distinct_groups: Option<usize>| {
+            let group_by = PhysicalGroupBy::new_single(vec![(
+                col("key", &schema)?,
+                "key".to_string(),
+            )]);
+            let aggr_expr =
+                vec![
+                    AggregateExprBuilder::new(count_udaf(), vec![col("val", &schema)?])
+                        .schema(Arc::clone(&schema))
+                        .alias(String::from("COUNT(val)"))
+                        .build()
+                        .map(Arc::new)?,
+                ];
+            let aggregate_exec = Arc::new(AggregateExec::try_new(
+                AggregateMode::Single,
+                group_by,
+                aggr_expr,
+                vec![None],
+                Arc::clone(&input) as Arc<dyn ExecutionPlan>,
+                Arc::clone(&schema),
+            )?);
+
+            let mut session_config = SessionConfig::default();
+            session_config.options_mut().execution.max_distinct_groups =
+                max_distinct_groups;
+            let ctx = TaskContext::default().with_session_config(session_config);
+            aggregate_exec.execute(0, Arc::new(ctx))
+        };
+
+        // the limit is inclusive
+        for max_distinct_groups in [None, Some(9)] {
+            let output = collect(run(max_distinct_groups)?).await?;
+            assert_eq!(output.iter().map(|b| b.num_rows()).sum::<usize>(), 9);
+        }
+
+        // fails on the second batch, which brings the number of groups to 6
+        let err = collect(run(Some(5))?).await.unwrap_err();
+        assert_eq!(
+            err.strip_backtrace(),
+            "Resources exhausted: Aggregation created 6 distinct groups, exceeding \
+             the limit of 5 set by datafusion.execution.max_distinct_groups"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_max_group_key_view_blocks() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "key",
+            DataType::Utf8View,
+            true,
+        )]));
+
+        // 2000 distinct 2KB keys, seen twice each, take several 2MB blocks
+        let keys = (0..2000).map(|i| format!("{i:0>2048}")).collect::<Vec<_>>();
+        let input_data = (0..2)
+            .map(|_| {
+                RecordBatch::try_new(
+                    Arc::clone(&schema),
+                    vec![Arc::new(StringViewArray::from_iter_values(&keys))],
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let input = Arc::new(MemoryExec::try_new(
+            &[input_data],
+            Arc::clone(&schema),
+            None,
+        )?);
+
+        let (schema, input) = (&schema, &input);
+        let run = |max_group_key_view_blocks: Option<usize>| async move {
+            let group_by = PhysicalGroupBy::new_single(vec![(
+                col("key", schema)?,
+                "key".to_string(),
+            )]);
+            let aggr_expr =
+                vec![
+                    AggregateExprBuilder::new(count_udaf(), vec![col("key", schema)?])
+                        .schema(Arc::clone(schema))
+                        .alias(String::from("COUNT(key)"))
+                        .build()
+                        .map(Arc::new)?,
+                ];
+            let aggregate_exec = Arc::new(AggregateExec::try_new(
+                AggregateMode::Single,
+                group_by,
+                aggr_expr,
+                vec![None],
+                Arc::clone(input) as Arc<dyn ExecutionPlan>,
+                Arc::clone(schema),
+            )?);
+
+            let mut session_config = SessionConfig::default();
+            session_config
+                .options_mut()
+                .execution
+                .max_group_key_view_blocks = max_group_key_view_blocks;
+            let ctx = TaskContext::default().with_session_config(session_config);
+            let output = collect(aggregate_exec.execute(0, Arc::new(ctx))?).await?;
+
+            let mut groups = vec![];
+            for batch in &output {
+                let keys = batch.column(0).as_string_view();
+                let counts = batch.column(1).as_primitive::<Int64Type>();
+                for i in 0..batch.num_rows() {
+                    groups.push((keys.value(i).to_string(), counts.value(i)));
+                }
+            }
+            groups.sort();
+            Ok::<_, DataFusionError>(groups)
+        };
+
+        let expected = keys.iter().map(|k| (k.clone(), 2)).collect::<Vec<_>>();
+        // with one completed block, the blocks double from the second one on
+        for max_group_key_view_blocks in [None, Some(0), Some(1)] {
+            let mut expected = expected.clone();
+            expected.sort();
+            assert_eq!(run(max_group_key_view_blocks).await?, expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_partial_state_field_names() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Int64, true),
+        ]));
+        // names its state fields `0` and `1`, whatever the aggregate's name
+        let udaf = Arc::new(create_udaf(
+            "fixed_state_names",
+            vec![DataType::Int64],
+            Arc::new(DataType::Int64),
+            Volatility::Immutable,
+            Arc::new(|_| not_impl_err!("fixed_state_names accumulator")),
+            Arc::new(vec![DataType::Int64, DataType::Int64]),
+        ));
+        let aggregate = |alias: &str, arg: &str| {
+            AggregateExprBuilder::new(Arc::clone(&udaf), vec![col(arg, &schema)?])
+                .schema(Arc::clone(&schema))
+                .alias(alias)
+                .build()
+                .map(Arc::new)
+        };
+        let partial_aggregate = |aggr_expr: Vec<Arc<AggregateFunctionExpr>>| {
+            let input = MemoryExec::try_new(&[vec![]], Arc::clone(&schema), None)?;
+            let filter_expr = vec![None; aggr_expr.len()];
+            AggregateExec::try_new(
+                AggregateMode::Partial,
+                PhysicalGroupBy::default(),
+                aggr_expr,
+                filter_expr,
+                Arc::new(input),
+                Arc::clone(&schema),
+            )
+        };
+
+        let state_names = |exec: AggregateExec| -> Vec<String> {
+            exec.schema()
+                .fields()
+                .iter()
+                .map(|f| f.name().clone())
+                .collect()
+        };
+
+        let exec = partial_aggregate(vec![aggregate("x", "a")?, aggregate("y", "a")?])?;
+        assert_eq!(state_names(exec), ["x[0]", "x[1]", "y[0]", "y[1]"]);
+
+        // identical aggregates have identical state
+        let exec = partial_aggregate(vec![aggregate("x", "a")?, aggregate("x", "a")?])?;
+        assert_eq!(state_names(exec), ["x[0]", "x[1]", "x[0]", "x[1]"]);
+
+        let err = partial_aggregate(vec![aggregate("x", "a")?, aggregate("x", "b")?])
+            .unwrap_err()
+            .strip_backtrace();
+        assert_eq!(
+            err,
+            "Internal error: Aggregates x and x have a state field named x[0], aggregates must have unique names.\nThis was likely caused by a bug in DataFusion's code and we would welcome that you file an bug report in our issue tracker"
+        );
+        Ok(())
+    }
+
+    /// An aggregate declared to return `Int64` whose accumulator produces
+    /// `Utf8` values and state, like a buggy user defined aggregate
+    #[derive(Debug)]
+    struct MismatchedTypeUdaf {
+        signature: Signature,
+    }
+
+    impl AggregateUDFImpl for MismatchedTypeUdaf {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn name(&self) -> &str {
+            "mismatched"
+        }
+
+        fn signature(&self) -> &Signature {
+            &self.signature
+        }
+
+        fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+            Ok(DataType::Int64)
+        }
+
+        fn accumulator(
+            &self,
+            _acc_args: AccumulatorArgs,
+        ) -> Result<Box<dyn Accumulator>> {
+            Ok(Box::new(MismatchedTypeAccumulator))
+        }
+    }
+
+    #[derive(Debug)]
+    struct MismatchedTypeAccumulator;
+
+    impl Accumulator for MismatchedTypeAccumulator {
+        fn update_batch(&mut self, _values: &[ArrayRef]) -> Result<()> {
+            Ok(())
+        }
+
+        fn evaluate(&mut self) -> Result<ScalarValue> {
+            Ok(ScalarValue::from("not an integer"))
+        }
+
+        fn size(&self) -> usize {
+            size_of_val(self)
+        }
+
+        fn state(&mut self) -> Result<Vec<ScalarValue>> {
+            Ok(vec![ScalarValue::from("not an integer")])
+        }
+
+        fn merge_batch(&mut self, _states: &[ArrayRef]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_accumulator_type() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Int32, true),
+            Field::new("val", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 1])),
+                Arc::new(Int32Array::from(vec![4, 5, 6])),
+            ],
+        )?;
+        let udaf = Arc::new(AggregateUDF::from(MismatchedTypeUdaf {
+            signature: Signature::any(1, Volatility::Immutable),
+        }));
+
+        for (mode, expected) in [
+            (
+                AggregateMode::Single,
+                "Aggregate mismatched(val) produced value column 'mismatched(val)' \
+                 of type Utf8, expected Int64",
+            ),
+            (
+                AggregateMode::Partial,
+                "Aggregate mismatched(val) produced state column \
+                 'mismatched(val)[value]' of type Utf8, expected Int64",
+            ),
+        ] {
+            let group_by = PhysicalGroupBy::new_single(vec![(
+                col("key", &schema)?,
+                "key".to_string(),
+            )]);
+            let aggr_expr = vec![AggregateExprBuilder::new(
+                Arc::clone(&udaf),
+                vec![col("val", &schema)?],
+            )
+            .schema(Arc::clone(&schema))
+            .alias("mismatched(val)")
+            .build()
+            .map(Arc::new)?];
+            let input = Arc::new(MemoryExec::try_new(
+                &[vec![batch.clone()]],
+                Arc::clone(&schema),
+                None,
+            )?);
+            let aggregate_exec = Arc::new(AggregateExec::try_new(
+                mode,
+                group_by,
+                aggr_expr,
+                vec![None],
+                input,
+                Arc::clone(&schema),
+            )?);
+
+            let err =
+                collect(aggregate_exec.execute(0, Arc::new(TaskContext::default()))?)
+                    .await
+                    .unwrap_err();
+            assert_eq!(
+                err.strip_backtrace(),
+                format!("Execution error: {expected}")
+            );
+        }
+        Ok(())
+    }
+
     #[test]
     fn group_exprs_nullable() -> Result<()> {
         let input_schema = Arc::new(Schema::new(vec![
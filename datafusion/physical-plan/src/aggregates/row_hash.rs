@@ -36,9 +36,14 @@ use crate::{aggregates, metrics, ExecutionPlan, PhysicalExpr};
 use crate::{RecordBatchStream, SendableRecordBatchStream};
 
 use arrow::array::*;
-use arrow::datatypes::SchemaRef;
+use arrow::compute::take_arrays;
+use arrow::datatypes::{Field, SchemaRef, UInt64Type};
 use arrow_schema::SortOptions;
-use datafusion_common::{internal_err, DataFusionError, Result};
+use datafusion_common::config::SpillCompression;
+use datafusion_common::{
+    config_err, exec_err, internal_err, not_impl_err, resources_err, DataFusionError,
+    Result,
+};
 use datafusion_execution::disk_manager::RefCountedTempFile;
 use datafusion_execution::memory_pool::proxy::VecAllocExt;
 use datafusion_execution::memory_pool::{MemoryConsumer, MemoryReservation};
@@ -91,6 +96,9 @@ struct SpillState {
     /// GROUP BY expressions for merging spilled data
     merging_group_by: PhysicalGroupBy,
 
+    /// Compression codec used for spill files
+    spill_compression: SpillCompression,
+
     // ========================================================================
     // STATES:
     // Fields changes during execution. Can be buffer, or state flags that
@@ -381,6 +389,36 @@ pub(crate) struct GroupedHashAggregateStream {
     /// output mode and emits all groups.
     group_values_soft_limit: Option<usize>,
 
+    /// Optional hard limit on the number of `group_values`. Creating a
+    /// group past this limit returns an error, see
+    /// `datafusion.execution.max_distinct_groups`
+    max_distinct_groups: Option<usize>,
+
+    /// Optional order of the output columns. The output column `i` is
+    /// column `output_column_order[i]` of the group keys followed by the
+    /// aggregate values. `schema` is already reordered accordingly, see
+    /// [`AggregateExec::with_output_column_order`]
+    output_column_order: Option<Vec<usize>>,
+
+    /// If true, groups are emitted in the reverse of the order in which
+    /// they were first seen, see [`AggregateExec::with_reverse_emit_order`]
+    reverse_emit_order: bool,
+
+    /// If set, a snapshot of the current value of all groups is emitted
+    /// after every `snapshot_interval` input batches, see
+    /// [`AggregateExec::with_snapshot_interval`]
+    snapshot_interval: Option<usize>,
+
+    /// If set, input batches with more rows are aggregated in chunks of at
+    /// most this many rows, growing the memory reservation after each chunk,
+    /// see `datafusion.execution.max_aggregate_chunk_rows`
+    max_chunk_rows: Option<usize>,
+
+    /// If set, the name of an input column with the precomputed hash of
+    /// the group values of each row, see
+    /// [`AggregateExec::with_group_hashes_column`]
+    group_hashes_column: Option<String>,
+
     // ========================================================================
     // STATE FLAGS:
     // These fields will be updated during the execution. And control the flow of
@@ -392,6 +430,10 @@ pub(crate) struct GroupedHashAggregateStream {
     /// Have we seen the end of the input
     input_done: bool,
 
+    /// Number of input batches aggregated since the last snapshot, see
+    /// [`Self::snapshot_interval`]
+    batches_since_snapshot: usize,
+
     // ========================================================================
     // STATE BUFFERS:
     // These fields will accumulate intermediate results during the execution.
@@ -410,6 +452,10 @@ pub(crate) struct GroupedHashAggregateStream {
     /// specialized for that particular aggregate and its input types
     accumulators: Vec<Box<dyn GroupsAccumulator>>,
 
+    /// The aggregate expression of each accumulator, used to report
+    /// accumulators that produce values of an unexpected type
+    aggregate_exprs: Vec<Arc<AggregateFunctionExpr>>,
+
     // ========================================================================
     // TASK-SPECIFIC STATES:
     // Inner states groups together properties, states for a specific task.
@@ -436,6 +482,18 @@ pub(crate) struct GroupedHashAggregateStream {
     /// Execution metrics
     baseline_metrics: BaselineMetrics,
 
+    /// Number of distinct groups accumulated so far, updated after each
+    /// input batch
+    group_count: metrics::Gauge,
+
+    /// Percentage of the capacity of the group values hash table in use,
+    /// updated after each input batch
+    hash_table_load_percent: metrics::Gauge,
+
+    /// Number of times the group values hash table grew, rehashing all of
+    /// its entries
+    hash_table_rehashes: metrics::Count,
+
     /// The [`RuntimeEnv`] associated with the [`TaskContext`] argument
     runtime: Arc<RuntimeEnv>,
 }
@@ -514,7 +572,29 @@ impl GroupedHashAggregateStream {
             ordering.as_ref(),
         )?;
 
-        let group_values = new_group_values(group_schema, &group_ordering)?;
+        // Group columns that are constant in the input do not distinguish
+        // groups, so there is no need to hash them
+        let input_eq_properties = agg.input().properties().equivalence_properties();
+        let constant_columns = agg_group_by
+            .expr()
+            .iter()
+            .enumerate()
+            .filter(|(_, (expr, _))| input_eq_properties.is_expr_constant(expr))
+            .map(|(idx, _)| idx)
+            .collect();
+        let execution_options = &context.session_config().options().execution;
+        if execution_options.max_aggregate_chunk_rows == Some(0) {
+            return config_err!("max_aggregate_chunk_rows must be at least 1");
+        }
+        let group_values = new_group_values(
+            group_schema,
+            &group_ordering,
+            constant_columns,
+            execution_options.normalize_float_group_keys,
+            execution_options.cast_view_group_keys_to_offsets,
+            execution_options.cast_large_group_keys_to_views,
+            execution_options.max_group_key_view_blocks,
+        )?;
         timer.done();
 
         let exec_state = ExecutionState::ReadingInput;
@@ -522,10 +602,15 @@ impl GroupedHashAggregateStream {
         let spill_state = SpillState {
             spills: vec![],
             spill_expr,
-            spill_schema: Arc::clone(&agg_schema),
+            spill_schema: agg_schema,
             is_stream_merging: false,
             merging_aggregate_arguments,
             merging_group_by: PhysicalGroupBy::new_single(agg_group_by.expr.clone()),
+            spill_compression: context
+                .session_config()
+                .options()
+                .execution
+                .spill_compression,
             peak_mem_used: MetricBuilder::new(&agg.metrics)
                 .gauge("peak_mem_used", partition),
             spill_count: MetricBuilder::new(&agg.metrics).spill_count(partition),
@@ -564,10 +649,11 @@ impl GroupedHashAggregateStream {
         };
 
         Ok(GroupedHashAggregateStream {
-            schema: agg_schema,
+            schema: agg.schema(),
             input,
             mode: agg.mode,
             accumulators,
+            aggregate_exprs,
             aggregate_arguments,
             filter_expressions,
             group_by: agg_group_by,
@@ -576,15 +662,104 @@ impl GroupedHashAggregateStream {
             current_group_indices: Default::default(),
             exec_state,
             baseline_metrics,
+            group_count: MetricBuilder::new(&agg.metrics).gauge("group_count", partition),
+            hash_table_load_percent: MetricBuilder::new(&agg.metrics)
+                .gauge("hash_table_load_percent", partition),
+            hash_table_rehashes: MetricBuilder::new(&agg.metrics)
+                .counter("hash_table_rehashes", partition),
             batch_size,
             group_ordering,
             input_done: false,
+            batches_since_snapshot: 0,
             runtime: context.runtime_env(),
             spill_state,
             group_values_soft_limit: agg.limit,
+            max_distinct_groups: context
+                .session_config()
+                .options()
+                .execution
+                .max_distinct_groups,
+            output_column_order: agg.output_column_order.clone(),
+            reverse_emit_order: agg.reverse_emit_order,
+            snapshot_interval: agg.snapshot_interval,
+            max_chunk_rows: execution_options.max_aggregate_chunk_rows,
+            group_hashes_column: agg.group_hashes_column.clone(),
             skip_aggregation_probe,
         })
     }
+
+    /// Returns the current value of all groups aggregated so far, or
+    /// `None` if there are no groups yet, without ending the input or
+    /// discarding the accumulated state.
+    ///
+    /// As [`GroupsAccumulator::evaluate`] consumes the groups it evaluates,
+    /// the intermediate state of all groups is emitted and merged back into
+    /// the group values and accumulators, which keeps the order of the
+    /// groups. The snapshot evaluates the same state merged into new
+    /// accumulators.
+    fn snapshot(&mut self) -> Result<Option<RecordBatch>> {
+        if self.group_values.is_empty() {
+            return Ok(None);
+        }
+        if !self.spill_state.spills.is_empty() {
+            return not_impl_err!("Snapshot of an aggregation that spilled to disk");
+        }
+
+        let group_keys = self.group_values.emit(EmitTo::All)?;
+        let states = self
+            .accumulators
+            .iter_mut()
+            .map(|acc| acc.state(EmitTo::All))
+            .collect::<Result<Vec<_>>>()?;
+
+        // the keys are distinct, so interning them again restores the
+        // groups with the same group indices
+        self.group_values
+            .intern(&group_keys, &mut self.current_group_indices)?;
+        let num_groups = self.group_values.len();
+        let group_indices = &self.current_group_indices;
+
+        let mut output = group_keys;
+        let iter = self
+            .accumulators
+            .iter_mut()
+            .zip(states.iter())
+            .zip(self.aggregate_exprs.iter());
+        for ((acc, state), aggr_expr) in iter {
+            acc.merge_batch(state, group_indices, None, num_groups)?;
+
+            let mut snapshot_acc = create_group_accumulator(aggr_expr)?;
+            snapshot_acc.merge_batch(state, group_indices, None, num_groups)?;
+            let value = snapshot_acc.evaluate(EmitTo::All)?;
+            let fields = [aggr_expr.field()];
+            let values = std::slice::from_ref(&value);
+            check_aggregate_output(aggr_expr, "value", values, &fields)?;
+            output.push(value)
+        }
+
+        let output = self.reverse_emitted_groups(output)?;
+        let output = self.reorder_output_columns(output);
+        let _ = self.update_memory_reservation();
+        Ok(Some(RecordBatch::try_new(self.schema(), output)?))
+    }
+
+    /// Returns true if a snapshot is due after aggregating an input batch,
+    /// see [`AggregateExec::with_snapshot_interval`]
+    fn snapshot_due(&mut self) -> bool {
+        let Some(interval) = self.snapshot_interval else {
+            return false;
+        };
+        // spilled data merged back at the end of the input is not new input
+        if self.spill_state.is_stream_merging {
+            return false;
+        }
+        self.batches_since_snapshot += 1;
+        if self.batches_since_snapshot < interval {
+            return false;
+        }
+        self.batches_since_snapshot = 0;
+        true
+    }
 }
 
 /// Create an accumulator for `agg_expr` -- a [`GroupsAccumulator`] if
@@ -708,6 +883,16 @@ impl Stream for GroupedHashAggregateStream {
                                 break 'reading_input;
                             }
 
+                            if self.snapshot_due() {
+                                timer.done();
+                                if let Some(batch) = extract_ok!(self.snapshot()) {
+                                    self.exec_state =
+                                        ExecutionState::ProducingOutput(batch);
+                                };
+                                // make sure the exec_state just set is not overwritten below
+                                break 'reading_input;
+                            }
+
                             timer.done();
                         }
 
@@ -749,6 +934,7 @@ impl Stream for GroupedHashAggregateStream {
                 }
 
                 ExecutionState::ProducingOutput(batch) => {
+                    let batch = batch.clone();
                     // slice off a part of the batch, if needed
                     let output_batch;
                     let size = self.batch_size;
@@ -766,7 +952,7 @@ impl Stream for GroupedHashAggregateStream {
                             } else {
                                 ExecutionState::ReadingInput
                             },
-                            batch.clone(),
+                            batch,
                         )
                     } else {
                         // output first batch_size rows
@@ -805,6 +991,47 @@ impl RecordBatchStream for GroupedHashAggregateStream {
 impl GroupedHashAggregateStream {
     /// Perform group-by aggregation for the given [`RecordBatch`].
     fn group_aggregate_batch(&mut self, batch: RecordBatch) -> Result<()> {
+        let num_rows = batch.num_rows();
+        let Some(max_chunk_rows) = self.max_chunk_rows.filter(|&n| num_rows > n) else {
+            self.group_aggregate_chunk(batch)?;
+            return self.update_memory_reservation_after_input();
+        };
+
+        let mut offset = 0;
+        while offset < num_rows {
+            let len = max_chunk_rows.min(num_rows - offset);
+            let chunk = batch.slice(offset, len);
+            // The first chunk was already checked against the whole batch. An
+            // early emit in Partial mode still happens after the last chunk,
+            // as the stream can only produce output between input batches.
+            if offset > 0 && self.mode != AggregateMode::Partial {
+                self.spill_previous_if_necessary(&chunk)?;
+            }
+            self.group_aggregate_chunk(chunk)?;
+            self.update_memory_reservation_after_input()?;
+            offset += len;
+        }
+        Ok(())
+    }
+
+    /// Update the memory reservation after aggregating (a chunk of) an
+    /// input batch
+    fn update_memory_reservation_after_input(&mut self) -> Result<()> {
+        match self.update_memory_reservation() {
+            // Here we can ignore `insufficient_capacity_err` because we will spill later,
+            // but at least one batch should fit in the memory
+            Err(DataFusionError::ResourcesExhausted(_))
+                if self.group_values.len() >= self.batch_size =>
+            {
+                Ok(())
+            }
+            other => other,
+        }
+    }
+
+    /// Aggregate the rows of `batch`, without updating the memory
+    /// reservation
+    fn group_aggregate_chunk(&mut self, batch: RecordBatch) -> Result<()> {
         // Evaluate the grouping expressions
         let group_by_values = if self.spill_state.is_stream_merging {
             evaluate_group_by(&self.spill_state.merging_group_by, &batch)?
@@ -827,16 +1054,41 @@ impl GroupedHashAggregateStream {
             evaluate_optional(&self.filter_expressions, &batch)?
         };
 
+        let group_hashes = match &self.group_hashes_column {
+            Some(name) if !self.spill_state.is_stream_merging => {
+                Some(group_hashes(&batch, name)?)
+            }
+            _ => None,
+        };
+
+        // Each grouping set feeds every input row to the accumulators once.
+        // The grouping id column keeps the groups of different sets distinct,
+        // so `input_values` can be shared without double counting
         for group_values in &group_by_values {
             // calculate the group indices for each input row
             let starting_num_groups = self.group_values.len();
-            self.group_values
-                .intern(group_values, &mut self.current_group_indices)?;
+            let starting_usage = self.group_values.hash_table_usage();
+            match &group_hashes {
+                Some(hashes) => {
+                    #[cfg(debug_assertions)]
+                    check_group_hashes(group_values, hashes.values());
+                    self.group_values.intern_with_hashes(
+                        group_values,
+                        hashes.values(),
+                        &mut self.current_group_indices,
+                    )?
+                }
+                None => self
+                    .group_values
+                    .intern(group_values, &mut self.current_group_indices)?,
+            }
+            self.record_hash_table_growth(starting_usage);
             let group_indices = &self.current_group_indices;
 
             // Update ordering information if necessary
             let total_num_groups = self.group_values.len();
             if total_num_groups > starting_num_groups {
+                self.check_max_distinct_groups(total_num_groups)?;
                 self.group_ordering.new_groups(
                     group_values,
                     group_indices,
@@ -882,15 +1134,33 @@ impl GroupedHashAggregateStream {
             }
         }
 
-        match self.update_memory_reservation() {
-            // Here we can ignore `insufficient_capacity_err` because we will spill later,
-            // but at least one batch should fit in the memory
-            Err(DataFusionError::ResourcesExhausted(_))
-                if self.group_values.len() >= self.batch_size =>
-            {
-                Ok(())
-            }
-            other => other,
+        self.group_count.set(self.current_group_count());
+        if let Some((len, capacity)) = self.group_values.hash_table_usage() {
+            let load_percent = (len * 100).checked_div(capacity).unwrap_or(0);
+            self.hash_table_load_percent.set(load_percent);
+        }
+        Ok(())
+    }
+
+    /// Counts the rehashes of the group values hash table since its usage
+    /// was `starting_usage`.
+    ///
+    /// The table doubles its number of buckets each time it grows, so one
+    /// batch may cause several rehashes. Allocating an empty table does not
+    /// rehash any entries, and is not counted.
+    fn record_hash_table_growth(&self, starting_usage: Option<(usize, usize)>) {
+        let (Some((_, starting_capacity)), Some((_, capacity))) =
+            (starting_usage, self.group_values.hash_table_usage())
+        else {
+            return;
+        };
+        if starting_capacity > 0 && capacity > starting_capacity {
+            // the capacity is 7/8 of the number of buckets, a power of two,
+            // or one less than the number of buckets for small tables
+            let buckets = |capacity: usize| (capacity + 1).next_power_of_two();
+            let rehashes = buckets(capacity).trailing_zeros()
+                - buckets(starting_capacity).trailing_zeros();
+            self.hash_table_rehashes.add(rehashes as usize);
         }
     }
 
@@ -929,21 +1199,33 @@ impl GroupedHashAggregateStream {
         }
 
         // Next output each aggregate value
-        for acc in self.accumulators.iter_mut() {
-            match self.mode {
-                AggregateMode::Partial => output.extend(acc.state(emit_to)?),
-                _ if spilling => {
-                    // If spilling, output partial state because the spilled data will be
-                    // merged and re-evaluated later.
-                    output.extend(acc.state(emit_to)?)
-                }
-                AggregateMode::Final
-                | AggregateMode::FinalPartitioned
-                | AggregateMode::Single
-                | AggregateMode::SinglePartitioned => output.push(acc.evaluate(emit_to)?),
+        let iter = self
+            .accumulators
+            .iter_mut()
+            .zip(self.aggregate_exprs.iter());
+        for (acc, aggr_expr) in iter {
+            // If spilling, output partial state because the spilled data will be
+            // merged and re-evaluated later.
+            if spilling || self.mode == AggregateMode::Partial {
+                let state = acc.state(emit_to)?;
+                let fields = aggr_expr.state_fields()?;
+                check_aggregate_output(aggr_expr, "state", &state, &fields)?;
+                output.extend(state)
+            } else {
+                let value = acc.evaluate(emit_to)?;
+                let fields = [aggr_expr.field()];
+                let values = std::slice::from_ref(&value);
+                check_aggregate_output(aggr_expr, "value", values, &fields)?;
+                output.push(value)
             }
         }
 
+        // spilled data keeps the default layout, as it is merged back later
+        if !spilling {
+            output = self.reverse_emitted_groups(output)?;
+            output = self.reorder_output_columns(output);
+        }
+
         // emit reduces the memory usage. Ignore Err from update_memory_reservation. Even if it is
         // over the target memory size after emission, we can emit again rather than returning Err.
         let _ = self.update_memory_reservation();
@@ -952,6 +1234,24 @@ impl GroupedHashAggregateStream {
         Ok(Some(batch))
     }
 
+    /// Returns an error if `num_groups` exceeds the configured maximum
+    /// number of distinct groups
+    fn check_max_distinct_groups(&self, num_groups: usize) -> Result<()> {
+        match self.max_distinct_groups {
+            Some(max_groups) if num_groups > max_groups => resources_err!(
+                "Aggregation created {num_groups} distinct groups, exceeding the \
+                 limit of {max_groups} set by datafusion.execution.max_distinct_groups"
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the number of distinct groups accumulated so far and not
+    /// yet emitted
+    pub(crate) fn current_group_count(&self) -> usize {
+        self.group_values.len()
+    }
+
     /// Optimistically, [`Self::group_aggregate_batch`] allows to exceed the memory target slightly
     /// (~ 1 [`RecordBatch`]) for simplicity. In such cases, spill the data to disk and clear the
     /// memory. Currently only [`GroupOrdering::None`] is supported for spilling.
@@ -986,6 +1286,7 @@ impl GroupedHashAggregateStream {
             spillfile.path().into(),
             sorted.schema(),
             self.batch_size,
+            self.spill_state.spill_compression,
         )?;
         self.spill_state.spills.push(spillfile);
 
@@ -1146,16 +1447,118 @@ impl GroupedHashAggregateStream {
         let iter = self
             .accumulators
             .iter()
+            .zip(self.aggregate_exprs.iter())
             .zip(input_values.iter())
             .zip(filter_values.iter());
 
-        for ((acc, values), opt_filter) in iter {
+        for (((acc, aggr_expr), values), opt_filter) in iter {
             let opt_filter = opt_filter.as_ref().map(|filter| filter.as_boolean());
-            output.extend(acc.convert_to_state(values, opt_filter)?);
+            let state = acc.convert_to_state(values, opt_filter)?;
+            let fields = aggr_expr.state_fields()?;
+            check_aggregate_output(aggr_expr, "state", &state, &fields)?;
+            output.extend(state);
         }
 
+        let output = self.reorder_output_columns(output);
         let states_batch = RecordBatch::try_new(self.schema(), output)?;
 
         Ok(states_batch)
     }
+
+    /// Reverses the order of the emitted groups in `output` if
+    /// `reverse_emit_order` is set
+    fn reverse_emitted_groups(&self, output: Vec<ArrayRef>) -> Result<Vec<ArrayRef>> {
+        let num_rows = match output.first() {
+            Some(array) if self.reverse_emit_order => array.len(),
+            _ => return Ok(output),
+        };
+        let indices = UInt32Array::from_iter_values((0..num_rows as u32).rev());
+        Ok(take_arrays(&output, &indices, None)?)
+    }
+
+    /// Reorders `output`, laid out as the group keys followed by the
+    /// aggregate values, according to `output_column_order`
+    fn reorder_output_columns(&self, output: Vec<ArrayRef>) -> Vec<ArrayRef> {
+        match &self.output_column_order {
+            Some(order) => order.iter().map(|&idx| Arc::clone(&output[idx])).collect(),
+            None => output,
+        }
+    }
+}
+
+/// Returns an error naming `aggr_expr` if the `kind` ("value" or "state")
+/// arrays its accumulator produced do not match the declared `fields`.
+///
+/// Accumulators of user defined aggregates can return arrays of a different
+/// type than the aggregate declares, which would otherwise only be reported
+/// as a schema mismatch of the whole output batch.
+fn check_aggregate_output(
+    aggr_expr: &AggregateFunctionExpr,
+    kind: &str,
+    arrays: &[ArrayRef],
+    fields: &[Field],
+) -> Result<()> {
+    if arrays.len() != fields.len() {
+        return exec_err!(
+            "Aggregate {} produced {} {kind} columns, expected {}",
+            aggr_expr.name(),
+            arrays.len(),
+            fields.len()
+        );
+    }
+    for (array, field) in arrays.iter().zip(fields) {
+        if !array.data_type().equals_datatype(field.data_type()) {
+            return exec_err!(
+                "Aggregate {} produced {kind} column '{}' of type {}, expected {}",
+                aggr_expr.name(),
+                field.name(),
+                array.data_type(),
+                field.data_type()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Returns the precomputed group hashes of `batch` in its column `name`
+fn group_hashes(batch: &RecordBatch, name: &str) -> Result<UInt64Array> {
+    let Some(column) = batch.column_by_name(name) else {
+        return internal_err!("Group hashes column '{name}' not found in input");
+    };
+    let Some(hashes) = column.as_primitive_opt::<UInt64Type>() else {
+        return internal_err!(
+            "Group hashes column '{name}' has type {}, expected UInt64",
+            column.data_type()
+        );
+    };
+    if hashes.null_count() > 0 {
+        return internal_err!("Group hashes column '{name}' contains nulls");
+    }
+    Ok(hashes.clone())
+}
+
+/// Panics if two rows of `group_values` with equal values have different
+/// `hashes`
+#[cfg(debug_assertions)]
+fn check_group_hashes(group_values: &[ArrayRef], hashes: &[u64]) {
+    use arrow::row::{RowConverter, SortField};
+    use std::collections::HashMap;
+
+    let fields = group_values
+        .iter()
+        .map(|array| SortField::new(array.data_type().clone()))
+        .collect();
+    let Ok(rows) = RowConverter::new(fields)
+        .and_then(|converter| converter.convert_columns(group_values))
+    else {
+        return;
+    };
+    let mut seen = HashMap::with_capacity(rows.num_rows());
+    for (row, &hash) in rows.iter().zip(hashes) {
+        let expected = *seen.entry(row).or_insert(hash);
+        assert_eq!(
+            expected, hash,
+            "Rows with equal group values have different precomputed hashes"
+        );
+    }
 }
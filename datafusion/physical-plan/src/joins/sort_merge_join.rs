@@ -42,6 +42,7 @@ use arrow::datatypes::{DataType, SchemaRef, TimeUnit};
 use arrow::error::ArrowError;
 use arrow::ipc::reader::FileReader;
 use arrow_array::types::UInt64Type;
+use datafusion_common::config::SpillCompression;
 use datafusion_common::{
     exec_err, internal_err, not_impl_err, plan_err, DataFusionError, HashSet, JoinSide,
     JoinType, Result,
@@ -485,6 +486,11 @@ impl ExecutionPlan for SortMergeJoinExec {
             SortMergeJoinMetrics::new(partition, &self.metrics),
             reservation,
             context.runtime_env(),
+            context
+                .session_config()
+                .options()
+                .execution
+                .spill_compression,
         )?))
     }
 
@@ -807,6 +813,8 @@ struct SortMergeJoinStream {
     pub reservation: MemoryReservation,
     /// Runtime env
     pub runtime_env: Arc<RuntimeEnv>,
+    /// Compression codec used for spill files
+    pub spill_compression: SpillCompression,
     /// A unique number for each batch
     pub streamed_batch_counter: AtomicUsize,
 }
@@ -1187,6 +1195,7 @@ impl SortMergeJoinStream {
         join_metrics: SortMergeJoinMetrics,
         reservation: MemoryReservation,
         runtime_env: Arc<RuntimeEnv>,
+        spill_compression: SpillCompression,
     ) -> Result<Self> {
         let streamed_schema = streamed.schema();
         let buffered_schema = buffered.schema();
@@ -1221,6 +1230,7 @@ impl SortMergeJoinStream {
             join_metrics,
             reservation,
             runtime_env,
+            spill_compression,
             streamed_batch_counter: AtomicUsize::new(0),
         })
     }
@@ -1301,6 +1311,7 @@ impl SortMergeJoinStream {
                         vec![batch],
                         spill_file.path().into(),
                         Arc::clone(&self.buffered_schema),
+                        self.spill_compression,
                     )?;
                     buffered_batch.spill_file = Some(spill_file);
                     buffered_batch.batch = None;
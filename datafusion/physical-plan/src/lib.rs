@@ -43,7 +43,10 @@ pub use crate::execution_plan::{
 pub use crate::metrics::Metric;
 pub use crate::ordering::InputOrderMode;
 pub use crate::stream::EmptyRecordBatchStream;
-pub use crate::topk::TopK;
+pub use crate::topk::{
+    sort_key_bounds, BoundedMinKeeper, TopK, TopKComparator, TopKHeapGrowth,
+    TopKNanPolicy, TopKRankPolicy, TopKSaturatedCallback, TopKStorage,
+};
 pub use crate::visitor::{accept, visit_execution_plan, ExecutionPlanVisitor};
 
 mod ordering;
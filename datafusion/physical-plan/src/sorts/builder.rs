@@ -16,8 +16,9 @@
 // under the License.
 
 use crate::spill::get_record_batch_memory_size;
+use arrow::array::UInt32Array;
 use arrow::compute::interleave;
-use arrow::datatypes::SchemaRef;
+use arrow::datatypes::{FieldRef, Schema, SchemaRef};
 use arrow::record_batch::RecordBatch;
 use datafusion_common::Result;
 use datafusion_execution::memory_pool::MemoryReservation;
@@ -49,6 +50,10 @@ pub struct BatchBuilder {
     /// The accumulated stream indexes from which to pull rows
     /// Consists of a tuple of `(batch_idx, row_idx)`
     indices: Vec<(usize, usize)>,
+
+    /// Whether the last column of `schema` is the index of the stream of
+    /// each row, see [`Self::with_source_partition_column`]
+    source_partition_column: bool,
 }
 
 impl BatchBuilder {
@@ -65,11 +70,30 @@ impl BatchBuilder {
             cursors: vec![BatchCursor::default(); stream_count],
             indices: Vec::with_capacity(batch_size),
             reservation,
+            source_partition_column: false,
         }
     }
 
+    /// Append a `UInt32` column `field` to the built batches, holding the
+    /// index of the stream each row was pulled from
+    pub fn with_source_partition_column(mut self, field: FieldRef) -> Self {
+        let mut fields = self.schema.fields().to_vec();
+        fields.push(field);
+        self.schema = Arc::new(Schema::new_with_metadata(
+            fields,
+            self.schema.metadata().clone(),
+        ));
+        self.source_partition_column = true;
+        self
+    }
+
     /// Append a new batch in `stream_idx`
     pub fn push_batch(&mut self, stream_idx: usize, batch: RecordBatch) -> Result<()> {
+        // Without in-progress rows, batches whose rows have all been
+        // skipped are no longer referenced
+        if self.is_empty() {
+            self.drop_finished_batches();
+        }
         self.reservation
             .try_grow(get_record_batch_memory_size(&batch))?;
         let batch_idx = self.batches.len();
@@ -89,6 +113,11 @@ impl BatchBuilder {
         self.indices.push((cursor.batch_idx, row_idx));
     }
 
+    /// Skips the next row from `stream_idx` without adding it to the output
+    pub fn skip_row(&mut self, stream_idx: usize) {
+        self.cursors[stream_idx].row_idx += 1;
+    }
+
     /// Returns the number of in-progress rows in this [`BatchBuilder`]
     pub fn len(&self) -> usize {
         self.indices.len()
@@ -114,7 +143,9 @@ impl BatchBuilder {
             return Ok(None);
         }
 
-        let columns = (0..self.schema.fields.len())
+        let num_columns =
+            self.schema.fields.len() - usize::from(self.source_partition_column);
+        let mut columns = (0..num_columns)
             .map(|column_idx| {
                 let arrays: Vec<_> = self
                     .batches
@@ -124,12 +155,29 @@ impl BatchBuilder {
                 Ok(interleave(&arrays, &self.indices)?)
             })
             .collect::<Result<Vec<_>>>()?;
+        if self.source_partition_column {
+            let partitions: UInt32Array = self
+                .indices
+                .iter()
+                .map(|(batch_idx, _)| Some(self.batches[*batch_idx].0 as u32))
+                .collect();
+            columns.push(Arc::new(partitions));
+        }
 
         self.indices.clear();
+        self.drop_finished_batches();
 
+        Ok(Some(RecordBatch::try_new(
+            Arc::clone(&self.schema),
+            columns,
+        )?))
+    }
+
+    /// Drops all batches that are no longer referenced by in-progress rows
+    fn drop_finished_batches(&mut self) {
         // New cursors are only created once the previous cursor for the stream
         // is finished. This means all remaining rows from all but the last batch
-        // for each stream have been yielded to the newly created record batch
+        // for each stream have been yielded to a record batch or skipped
         //
         // We can therefore drop all but the last batch for each stream
         let mut batch_idx = 0;
@@ -147,10 +195,5 @@ impl BatchBuilder {
             }
             retain
         });
-
-        Ok(Some(RecordBatch::try_new(
-            Arc::clone(&self.schema),
-            columns,
-        )?))
     }
 }
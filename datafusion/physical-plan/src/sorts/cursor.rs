@@ -284,9 +284,14 @@ impl<T: ByteArrayType> CursorArray for GenericByteArray<T> {
 
 /// A collection of sorted, nullable [`CursorValues`]
 ///
+/// The sort direction is the `DESCENDING` parameter rather than a field of
+/// `options`, so that comparing two non null values does not check it for
+/// every comparison. This is only needed for the single column cursors, as
+/// [`RowValues`] already encode the sort options of every column.
+///
 /// Note: comparing cursors with different `SortOptions` will yield an arbitrary ordering
 #[derive(Debug)]
-pub struct ArrayValues<T: CursorValues> {
+pub struct ArrayValues<T: CursorValues, const DESCENDING: bool> {
     values: T,
     // If nulls first, the first non-null index
     // Otherwise, the first null index
@@ -294,13 +299,18 @@ pub struct ArrayValues<T: CursorValues> {
     options: SortOptions,
 }
 
-impl<T: CursorValues> ArrayValues<T> {
+impl<T: CursorValues, const DESCENDING: bool> ArrayValues<T, DESCENDING> {
     /// Create a new [`ArrayValues`] from the provided `values` sorted according
     /// to `options`.
     ///
-    /// Panics if the array is empty
+    /// Panics if the array is empty, or if `options.descending` differs
+    /// from `DESCENDING`
     pub fn new<A: CursorArray<Values = T>>(options: SortOptions, array: &A) -> Self {
         assert!(array.len() > 0, "Empty array passed to FieldCursor");
+        assert_eq!(
+            options.descending, DESCENDING,
+            "ArrayValues created with a different sort direction"
+        );
         let null_threshold = match options.nulls_first {
             true => array.null_count(),
             false => array.len() - array.null_count(),
@@ -318,7 +328,9 @@ impl<T: CursorValues> ArrayValues<T> {
     }
 }
 
-impl<T: CursorValues> CursorValues for ArrayValues<T> {
+impl<T: CursorValues, const DESCENDING: bool> CursorValues
+    for ArrayValues<T, DESCENDING>
+{
     fn len(&self) -> usize {
         self.values.len()
     }
@@ -351,10 +363,10 @@ impl<T: CursorValues> CursorValues for ArrayValues<T> {
                 true => Ordering::Greater,
                 false => Ordering::Less,
             },
-            (false, false) => match l.options.descending {
-                true => T::compare(&r.values, r_idx, &l.values, l_idx),
-                false => T::compare(&l.values, l_idx, &r.values, r_idx),
-            },
+            (false, false) if DESCENDING => {
+                T::compare(&r.values, r_idx, &l.values, l_idx)
+            }
+            (false, false) => T::compare(&l.values, l_idx, &r.values, r_idx),
         }
     }
 }
@@ -363,11 +375,12 @@ impl<T: CursorValues> CursorValues for ArrayValues<T> {
 mod tests {
     use super::*;
 
-    fn new_primitive(
+    fn new_primitive<const DESCENDING: bool>(
         options: SortOptions,
         values: ScalarBuffer<i32>,
         null_count: usize,
-    ) -> Cursor<ArrayValues<PrimitiveValues<i32>>> {
+    ) -> Cursor<ArrayValues<PrimitiveValues<i32>, DESCENDING>> {
+        assert_eq!(options.descending, DESCENDING);
         let null_threshold = match options.nulls_first {
             true => null_count,
             false => values.len() - null_count,
@@ -390,9 +403,9 @@ mod tests {
         };
 
         let buffer = ScalarBuffer::from(vec![i32::MAX, 1, 2, 3]);
-        let mut a = new_primitive(options, buffer, 1);
+        let mut a = new_primitive::<false>(options, buffer, 1);
         let buffer = ScalarBuffer::from(vec![1, 2, -2, -1, 1, 9]);
-        let mut b = new_primitive(options, buffer, 2);
+        let mut b = new_primitive::<false>(options, buffer, 2);
 
         // NULL == NULL
         assert_eq!(a.cmp(&b), Ordering::Equal);
@@ -434,9 +447,9 @@ mod tests {
         };
 
         let buffer = ScalarBuffer::from(vec![0, 1, i32::MIN, i32::MAX]);
-        let mut a = new_primitive(options, buffer, 2);
+        let mut a = new_primitive::<false>(options, buffer, 2);
         let buffer = ScalarBuffer::from(vec![-1, i32::MAX, i32::MIN]);
-        let mut b = new_primitive(options, buffer, 2);
+        let mut b = new_primitive::<false>(options, buffer, 2);
 
         // 0 > -1
         assert_eq!(a.cmp(&b), Ordering::Greater);
@@ -460,9 +473,9 @@ mod tests {
         };
 
         let buffer = ScalarBuffer::from(vec![6, 1, i32::MIN, i32::MAX]);
-        let mut a = new_primitive(options, buffer, 3);
+        let mut a = new_primitive::<true>(options, buffer, 3);
         let buffer = ScalarBuffer::from(vec![67, -3, i32::MAX, i32::MIN]);
-        let mut b = new_primitive(options, buffer, 2);
+        let mut b = new_primitive::<true>(options, buffer, 2);
 
         // 6 > 67
         assert_eq!(a.cmp(&b), Ordering::Greater);
@@ -490,9 +503,9 @@ mod tests {
         };
 
         let buffer = ScalarBuffer::from(vec![i32::MIN, i32::MAX, 6, 3]);
-        let mut a = new_primitive(options, buffer, 2);
+        let mut a = new_primitive::<true>(options, buffer, 2);
         let buffer = ScalarBuffer::from(vec![i32::MAX, 4546, -3]);
-        let mut b = new_primitive(options, buffer, 1);
+        let mut b = new_primitive::<true>(options, buffer, 1);
 
         // NULL == NULL
         assert_eq!(a.cmp(&b), Ordering::Equal);
@@ -23,13 +23,13 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{ready, Context, Poll};
 
-use crate::metrics::BaselineMetrics;
+use crate::metrics::{BaselineMetrics, Count};
 use crate::sorts::builder::BatchBuilder;
 use crate::sorts::cursor::{Cursor, CursorValues};
 use crate::sorts::stream::PartitionedStream;
 use crate::RecordBatchStream;
 
-use arrow::datatypes::SchemaRef;
+use arrow::datatypes::{FieldRef, SchemaRef};
 use arrow::record_batch::RecordBatch;
 use datafusion_common::Result;
 use datafusion_execution::memory_pool::MemoryReservation;
@@ -143,6 +143,18 @@ pub(crate) struct SortPreservingMergeStream<C: CursorValues> {
     /// number of rows produced
     produced: usize,
 
+    /// Number of rows to skip before producing any rows
+    offset: usize,
+
+    /// Number of rows skipped so far, at most `offset`
+    skipped: usize,
+
+    /// Number of output rows contributed by each stream, if not empty
+    input_rows: Vec<Count>,
+
+    /// Output rows of each stream not yet added to `input_rows`
+    pending_input_rows: Vec<usize>,
+
     /// This queue contains partition indices in order. When a partition is polled and returns `Poll::Ready`,
     /// it is removed from the vector. If a partition returns `Poll::Pending`, it is moved to the end of the
     /// vector to ensure the next iteration starts with a different partition, preventing the same partition
@@ -151,19 +163,28 @@ pub(crate) struct SortPreservingMergeStream<C: CursorValues> {
 }
 
 impl<C: CursorValues> SortPreservingMergeStream<C> {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         streams: CursorStream<C>,
         schema: SchemaRef,
         metrics: BaselineMetrics,
         batch_size: usize,
         fetch: Option<usize>,
+        offset: usize,
         reservation: MemoryReservation,
         enable_round_robin_tie_breaker: bool,
+        source_partition_column: Option<FieldRef>,
+        input_rows: Vec<Count>,
     ) -> Self {
         let stream_count = streams.partitions();
+        let mut in_progress =
+            BatchBuilder::new(schema, stream_count, batch_size, reservation);
+        if let Some(field) = source_partition_column {
+            in_progress = in_progress.with_source_partition_column(field);
+        }
 
         Self {
-            in_progress: BatchBuilder::new(schema, stream_count, batch_size, reservation),
+            in_progress,
             streams,
             metrics,
             aborted: false,
@@ -178,6 +199,10 @@ impl<C: CursorValues> SortPreservingMergeStream<C> {
             batch_size,
             fetch,
             produced: 0,
+            offset,
+            skipped: 0,
+            pending_input_rows: vec![0; input_rows.len()],
+            input_rows,
             uninitiated_partitions: (0..stream_count).collect(),
             enable_round_robin_tie_breaker,
         }
@@ -267,7 +292,15 @@ impl<C: CursorValues> SortPreservingMergeStream<C> {
             let stream_idx = self.loser_tree[0];
             if self.advance_cursors(stream_idx) {
                 self.loser_tree_adjusted = false;
+                if self.skipped < self.offset {
+                    self.in_progress.skip_row(stream_idx);
+                    self.skipped += 1;
+                    continue;
+                }
                 self.in_progress.push_row(stream_idx);
+                if let Some(rows) = self.pending_input_rows.get_mut(stream_idx) {
+                    *rows += 1;
+                }
 
                 // stop sorting if fetch has been reached
                 if self.fetch_reached() {
@@ -278,6 +311,10 @@ impl<C: CursorValues> SortPreservingMergeStream<C> {
             }
 
             self.produced += self.in_progress.len();
+            for (count, rows) in self.input_rows.iter().zip(&mut self.pending_input_rows)
+            {
+                count.add(std::mem::take(rows));
+            }
 
             return Poll::Ready(self.in_progress.build_record_batch().transpose());
         }
@@ -49,6 +49,7 @@ use arrow::record_batch::RecordBatch;
 use arrow::row::{RowConverter, SortField};
 use arrow_array::{Array, RecordBatchOptions, UInt32Array};
 use arrow_schema::DataType;
+use datafusion_common::config::SpillCompression;
 use datafusion_common::{internal_err, Result};
 use datafusion_execution::disk_manager::RefCountedTempFile;
 use datafusion_execution::memory_pool::{MemoryConsumer, MemoryReservation};
@@ -251,6 +252,8 @@ struct ExternalSorter {
     /// How much memory to reserve for performing in-memory sort/merges
     /// prior to spilling.
     sort_spill_reservation_bytes: usize,
+    /// Compression codec used for spill files
+    spill_compression: SpillCompression,
 }
 
 impl ExternalSorter {
@@ -265,6 +268,7 @@ impl ExternalSorter {
         fetch: Option<usize>,
         sort_spill_reservation_bytes: usize,
         sort_in_place_threshold_bytes: usize,
+        spill_compression: SpillCompression,
         metrics: &ExecutionPlanMetricsSet,
         runtime: Arc<RuntimeEnv>,
     ) -> Self {
@@ -291,6 +295,7 @@ impl ExternalSorter {
             batch_size,
             sort_spill_reservation_bytes,
             sort_in_place_threshold_bytes,
+            spill_compression,
         }
     }
 
@@ -417,6 +422,7 @@ impl ExternalSorter {
             batches,
             spill_file.path().into(),
             Arc::clone(&self.schema),
+            self.spill_compression,
         )?;
         let used = self.reservation.free();
         self.metrics.spill_count.add(1);
@@ -961,13 +967,22 @@ impl ExecutionPlan for SortExec {
                     context.session_config().batch_size(),
                     context.runtime_env(),
                     &self.metrics_set,
-                )?;
+                )?
+                .with_eager_allocation_limit(
+                    execution_options.topk_eager_allocation_limit,
+                );
+                if let Some(input_ordering) = self.input.output_ordering() {
+                    topk = topk.with_input_ordering(input_ordering.clone())?;
+                }
                 Ok(Box::pin(RecordBatchStreamAdapter::new(
                     self.schema(),
                     futures::stream::once(async move {
                         while let Some(batch) = input.next().await {
                             let batch = batch?;
-                            topk.insert_batch(batch)?;
+                            if topk.insert_batch(batch)? {
+                                // the remaining input cannot change the top k
+                                break;
+                            }
                         }
                         topk.emit()
                     })
@@ -983,6 +998,7 @@ impl ExecutionPlan for SortExec {
                     self.fetch,
                     execution_options.sort_spill_reservation_bytes,
                     execution_options.sort_in_place_threshold_bytes,
+                    execution_options.spill_compression,
                     &self.metrics_set,
                     context.runtime_env(),
                 );
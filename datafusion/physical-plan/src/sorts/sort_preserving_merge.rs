@@ -21,19 +21,28 @@ use std::any::Any;
 use std::sync::Arc;
 
 use crate::common::spawn_buffered;
+use crate::execution_plan::{Boundedness, EmissionType};
 use crate::limit::LimitStream;
-use crate::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use crate::metrics::{
+    BaselineMetrics, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet,
+};
 use crate::sorts::streaming_merge::StreamingMergeBuilder;
+use crate::stream::RecordBatchStreamAdapter;
 use crate::{
     DisplayAs, DisplayFormatType, Distribution, ExecutionPlan, ExecutionPlanProperties,
     Partitioning, PlanProperties, SendableRecordBatchStream, Statistics,
 };
 
-use datafusion_common::{internal_err, Result};
+use arrow::array::UInt32Array;
+use arrow::datatypes::{DataType, Field, FieldRef, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion_common::{config_err, internal_err, plan_err, Result};
 use datafusion_execution::memory_pool::MemoryConsumer;
 use datafusion_execution::TaskContext;
 
+use datafusion_physical_expr::EquivalenceProperties;
 use datafusion_physical_expr_common::sort_expr::{LexOrdering, LexRequirement};
+use futures::StreamExt;
 use log::{debug, trace};
 
 /// Sort preserving merge execution plan
@@ -90,31 +99,97 @@ pub struct SortPreservingMergeExec {
     metrics: ExecutionPlanMetricsSet,
     /// Optional number of rows to fetch. Stops producing rows after this fetch
     fetch: Option<usize>,
+    /// Number of rows to skip before producing any rows, applied before
+    /// `fetch`
+    offset: usize,
+    /// Optional number of rows to fetch from each input partition before
+    /// merging, independent of `fetch`
+    per_partition_fetch: Option<usize>,
     /// Cache holding plan properties like equivalences, output partitioning etc.
     cache: PlanProperties,
     /// Use round-robin selection of tied winners of loser tree
     ///
     /// See [`Self::with_round_robin_repartition`] for more information.
     enable_round_robin_repartition: bool,
+    /// Break ties between equal rows by input partition index, then by row
+    /// index within the partition
+    ///
+    /// See [`Self::with_tiebreak_by_partition`] for more information.
+    tiebreak_by_partition: bool,
+    /// Overrides `nulls_first` of the sort options of each expression in
+    /// `expr` when merging, if not empty
+    ///
+    /// See [`Self::with_null_ordering_overrides`] for more information.
+    null_ordering_overrides: Vec<Option<bool>>,
+    /// Appended column holding the input partition of each output row, if
+    /// any
+    ///
+    /// See [`Self::with_source_partition_column`] for more information.
+    source_partition_column: Option<FieldRef>,
 }
 
 impl SortPreservingMergeExec {
     /// Create a new sort execution plan
     pub fn new(expr: LexOrdering, input: Arc<dyn ExecutionPlan>) -> Self {
-        let cache = Self::compute_properties(&input, expr.clone());
+        let cache = Self::compute_properties(&input, expr.clone(), None, None, None);
         Self {
             input,
             expr,
             metrics: ExecutionPlanMetricsSet::new(),
             fetch: None,
+            offset: 0,
+            per_partition_fetch: None,
             cache,
             enable_round_robin_repartition: true,
+            tiebreak_by_partition: false,
+            null_ordering_overrides: vec![],
+            source_partition_column: None,
         }
     }
 
     /// Sets the number of rows to fetch
     pub fn with_fetch(mut self, fetch: Option<usize>) -> Self {
         self.fetch = fetch;
+        self.cache = Self::compute_properties(
+            &self.input,
+            self.merge_ordering(),
+            fetch,
+            self.per_partition_fetch,
+            self.source_partition_column.as_ref(),
+        );
+        self
+    }
+
+    /// Sets the number of rows to skip at the start of the merged output
+    ///
+    /// The offset is applied before [`Self::with_fetch`], so the merge
+    /// produces rows `offset..offset + fetch`, the same as a
+    /// [`GlobalLimitExec`] with `skip = offset` above the merge.
+    ///
+    /// [`GlobalLimitExec`]: crate::limit::GlobalLimitExec
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the number of rows to fetch from each input partition
+    ///
+    /// Unlike [`Self::with_fetch`], which limits the merged output, this
+    /// limits every input partition independently before the merge, for
+    /// example to merge the top k rows of each partition. Both limits can
+    /// be combined.
+    pub fn with_per_partition_fetch(
+        mut self,
+        per_partition_fetch: Option<usize>,
+    ) -> Self {
+        self.per_partition_fetch = per_partition_fetch;
+        self.cache = Self::compute_properties(
+            &self.input,
+            self.merge_ordering(),
+            self.fetch,
+            per_partition_fetch,
+            self.source_partition_column.as_ref(),
+        );
         self
     }
 
@@ -135,6 +210,74 @@ impl SortPreservingMergeExec {
         self
     }
 
+    /// Breaks ties between rows with equal sort keys deterministically
+    ///
+    /// If true, equal rows are output by ascending index of their input
+    /// partition, and rows of the same partition in their input order, so
+    /// the output only depends on the contents of the input partitions and
+    /// not on the order in which they were polled. This overrides
+    /// [`Self::with_round_robin_repartition`], trading the more even
+    /// consumption of the inputs for a reproducible order. Defaults to
+    /// false.
+    ///
+    /// Either way the merge maintains the order of each input partition,
+    /// see [`ExecutionPlan::maintains_input_order`]; this option only
+    /// additionally fixes the order of equal rows across partitions.
+    pub fn with_tiebreak_by_partition(mut self, tiebreak_by_partition: bool) -> Self {
+        self.tiebreak_by_partition = tiebreak_by_partition;
+        self
+    }
+
+    /// Overrides whether nulls sort first for each sort expression
+    ///
+    /// `overrides[i]`, if set, replaces `nulls_first` of the sort options of
+    /// `expr[i]`, so inputs that were sorted with a different null ordering
+    /// can be merged without rebuilding the sort expressions. The merged
+    /// output, and the ordering required of the inputs, use the overridden
+    /// null ordering. An empty `overrides` removes all overrides.
+    ///
+    /// Returns an error if `overrides` is not empty and does not have one
+    /// entry per sort expression.
+    pub fn with_null_ordering_overrides(
+        mut self,
+        overrides: Vec<Option<bool>>,
+    ) -> Result<Self> {
+        if !overrides.is_empty() && overrides.len() != self.expr.len() {
+            return plan_err!(
+                "SortPreservingMergeExec expected {} null ordering overrides, got {}",
+                self.expr.len(),
+                overrides.len()
+            );
+        }
+        self.null_ordering_overrides = overrides;
+        self.cache = Self::compute_properties(
+            &self.input,
+            self.merge_ordering(),
+            self.fetch,
+            self.per_partition_fetch,
+            self.source_partition_column.as_ref(),
+        );
+        Ok(self)
+    }
+
+    /// Appends a non null `UInt32` column `name` to the output, holding the
+    /// index of the input partition each row was read from
+    ///
+    /// This is intended for debugging the merge and tracing the lineage of
+    /// the merged rows.
+    pub fn with_source_partition_column(mut self, name: impl Into<String>) -> Self {
+        self.source_partition_column =
+            Some(Arc::new(Field::new(name, DataType::UInt32, false)));
+        self.cache = Self::compute_properties(
+            &self.input,
+            self.merge_ordering(),
+            self.fetch,
+            self.per_partition_fetch,
+            self.source_partition_column.as_ref(),
+        );
+        self
+    }
+
     /// Input schema
     pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
         &self.input
@@ -150,20 +293,98 @@ impl SortPreservingMergeExec {
         self.fetch
     }
 
+    /// Number of rows skipped at the start of the merged output
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Fetch of each input partition
+    pub fn per_partition_fetch(&self) -> Option<usize> {
+        self.per_partition_fetch
+    }
+
+    /// Overrides of the null ordering of each sort expression, see
+    /// [`Self::with_null_ordering_overrides`]
+    pub fn null_ordering_overrides(&self) -> &[Option<bool>] {
+        &self.null_ordering_overrides
+    }
+
+    /// Whether ties are broken by input partition, see
+    /// [`Self::with_tiebreak_by_partition`]
+    pub fn tiebreak_by_partition(&self) -> bool {
+        self.tiebreak_by_partition
+    }
+
+    /// The column holding the input partition of each output row, see
+    /// [`Self::with_source_partition_column`]
+    pub fn source_partition_column(&self) -> Option<&FieldRef> {
+        self.source_partition_column.as_ref()
+    }
+
+    /// Returns the sort expressions with the null ordering overrides
+    /// applied, which is the ordering of the inputs and the merged output
+    fn merge_ordering(&self) -> LexOrdering {
+        let mut ordering = self.expr.clone();
+        for (sort_expr, nulls_first) in
+            ordering.inner.iter_mut().zip(&self.null_ordering_overrides)
+        {
+            if let Some(nulls_first) = nulls_first {
+                sort_expr.options.nulls_first = *nulls_first;
+            }
+        }
+        ordering
+    }
+
     /// Creates the cache object that stores the plan properties
     /// such as schema, equivalence properties, ordering, partitioning, etc.
     fn compute_properties(
         input: &Arc<dyn ExecutionPlan>,
         ordering: LexOrdering,
+        fetch: Option<usize>,
+        per_partition_fetch: Option<usize>,
+        source_partition_column: Option<&FieldRef>,
     ) -> PlanProperties {
-        let mut eq_properties = input.equivalence_properties().clone();
-        eq_properties.clear_per_partition_constants();
-        eq_properties.add_new_orderings(vec![ordering]);
+        let eq_properties = match source_partition_column {
+            Some(field) => {
+                // the merged rows are still ordered by `ordering`, whose
+                // columns keep their indices in the extended schema
+                let input_schema = input.schema();
+                let mut fields = input_schema.fields().to_vec();
+                fields.push(Arc::clone(field));
+                let schema = Arc::new(Schema::new_with_metadata(
+                    fields,
+                    input_schema.metadata().clone(),
+                ));
+                EquivalenceProperties::new_with_orderings(schema, &[ordering])
+            }
+            None => {
+                let mut eq_properties = input.equivalence_properties().clone();
+                eq_properties.clear_per_partition_constants();
+                eq_properties.add_new_orderings(vec![ordering]);
+                eq_properties
+            }
+        };
+
+        // If the input can emit incrementally, the merge stops reading its
+        // inputs once `fetch` rows have been produced (or `per_partition_fetch`
+        // rows of every input), so the output is bounded even if the input
+        // is not.
+        let is_pipeline_friendly = matches!(
+            input.pipeline_behavior(),
+            EmissionType::Incremental | EmissionType::Both
+        );
+        let has_fetch = fetch.is_some() || per_partition_fetch.is_some();
+        let boundedness = if has_fetch && is_pipeline_friendly {
+            Boundedness::Bounded
+        } else {
+            input.boundedness()
+        };
+
         PlanProperties::new(
             eq_properties,                        // Equivalence Properties
             Partitioning::UnknownPartitioning(1), // Output Partitioning
             input.pipeline_behavior(),            // Pipeline Behavior
-            input.boundedness(),                  // Boundedness
+            boundedness,                          // Boundedness
         )
     }
 }
@@ -176,10 +397,22 @@ impl DisplayAs for SortPreservingMergeExec {
     ) -> std::fmt::Result {
         match t {
             DisplayFormatType::Default | DisplayFormatType::Verbose => {
-                write!(f, "SortPreservingMergeExec: [{}]", self.expr)?;
+                write!(f, "SortPreservingMergeExec: [{}]", self.merge_ordering())?;
+                if self.offset > 0 {
+                    write!(f, ", offset={}", self.offset)?;
+                };
                 if let Some(fetch) = self.fetch {
                     write!(f, ", fetch={fetch}")?;
                 };
+                if let Some(fetch) = self.per_partition_fetch {
+                    write!(f, ", per_partition_fetch={fetch}")?;
+                };
+                if let Some(field) = &self.source_partition_column {
+                    write!(f, ", source_partition_column={}", field.name())?;
+                };
+                if self.tiebreak_by_partition {
+                    write!(f, ", tiebreak_by_partition=true")?;
+                };
 
                 Ok(())
             }
@@ -212,8 +445,19 @@ impl ExecutionPlan for SortPreservingMergeExec {
             expr: self.expr.clone(),
             metrics: self.metrics.clone(),
             fetch: limit,
-            cache: self.cache.clone(),
+            offset: self.offset,
+            per_partition_fetch: self.per_partition_fetch,
+            cache: Self::compute_properties(
+                &self.input,
+                self.merge_ordering(),
+                limit,
+                self.per_partition_fetch,
+                self.source_partition_column.as_ref(),
+            ),
             enable_round_robin_repartition: true,
+            tiebreak_by_partition: self.tiebreak_by_partition,
+            null_ordering_overrides: self.null_ordering_overrides.clone(),
+            source_partition_column: self.source_partition_column.clone(),
         }))
     }
 
@@ -226,9 +470,12 @@ impl ExecutionPlan for SortPreservingMergeExec {
     }
 
     fn required_input_ordering(&self) -> Vec<Option<LexRequirement>> {
-        vec![Some(LexRequirement::from(self.expr.clone()))]
+        vec![Some(LexRequirement::from(self.merge_ordering()))]
     }
 
+    /// The rows of each input partition are output in their input order.
+    /// Equal rows of different partitions are only ordered by partition
+    /// with [`SortPreservingMergeExec::with_tiebreak_by_partition`]
     fn maintains_input_order(&self) -> Vec<bool> {
         vec![true]
     }
@@ -241,10 +488,17 @@ impl ExecutionPlan for SortPreservingMergeExec {
         self: Arc<Self>,
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        Ok(Arc::new(
+        let merge =
             SortPreservingMergeExec::new(self.expr.clone(), Arc::clone(&children[0]))
-                .with_fetch(self.fetch),
-        ))
+                .with_fetch(self.fetch)
+                .with_offset(self.offset)
+                .with_per_partition_fetch(self.per_partition_fetch)
+                .with_tiebreak_by_partition(self.tiebreak_by_partition)
+                .with_null_ordering_overrides(self.null_ordering_overrides.clone())?;
+        Ok(Arc::new(match &self.source_partition_column {
+            Some(field) => merge.with_source_partition_column(field.name()),
+            None => merge,
+        }))
     }
 
     fn execute(
@@ -267,7 +521,7 @@ impl ExecutionPlan for SortPreservingMergeExec {
             "Number of input partitions of  SortPreservingMergeExec::execute: {}",
             input_partitions
         );
-        let schema = self.schema();
+        let schema = self.input.schema();
 
         let reservation =
             MemoryConsumer::new(format!("SortPreservingMergeExec[{partition}]"))
@@ -277,44 +531,122 @@ impl ExecutionPlan for SortPreservingMergeExec {
             0 => internal_err!(
                 "SortPreservingMergeExec requires at least one input partition"
             ),
-            1 => match self.fetch {
-                Some(fetch) => {
-                    let stream = self.input.execute(0, context)?;
-                    debug!("Done getting stream for SortPreservingMergeExec::execute with 1 input with {fetch}");
-                    Ok(Box::pin(LimitStream::new(
-                        stream,
-                        0,
-                        Some(fetch),
-                        BaselineMetrics::new(&self.metrics, partition),
-                    )))
+            1 => {
+                let stream: SendableRecordBatchStream = match [
+                    self.fetch,
+                    // the rows of the input partition are limited before the
+                    // offset is skipped
+                    self.per_partition_fetch
+                        .map(|fetch| fetch.saturating_sub(self.offset)),
+                ]
+                .into_iter()
+                .flatten()
+                .min()
+                {
+                    fetch if fetch.is_some() || self.offset > 0 => {
+                        let stream = self.input.execute(0, context)?;
+                        debug!("Done getting stream for SortPreservingMergeExec::execute with 1 input with offset {} and fetch {fetch:?}", self.offset);
+                        Ok(Box::pin(LimitStream::new(
+                            stream,
+                            self.offset,
+                            fetch,
+                            BaselineMetrics::new(&self.metrics, partition),
+                        )) as _)
+                    }
+                    _ => {
+                        let stream = self.input.execute(0, context);
+                        debug!("Done getting stream for SortPreservingMergeExec::execute with 1 input without fetch");
+                        stream
+                    }
+                }?;
+                Ok(match &self.source_partition_column {
+                    // all rows are read from the only input partition
+                    Some(_) => {
+                        let schema = self.schema();
+                        Box::pin(RecordBatchStreamAdapter::new(
+                            Arc::clone(&schema),
+                            stream.map(move |batch| {
+                                let batch = batch?;
+                                let mut columns = batch.columns().to_vec();
+                                columns.push(Arc::new(UInt32Array::from(vec![
+                                    0;
+                                    batch
+                                        .num_rows(
+                                        )
+                                ])));
+                                Ok(RecordBatch::try_new(Arc::clone(&schema), columns)?)
+                            }),
+                        ))
+                    }
+                    None => stream,
+                })
+            }
+            _ => {
+                let options = &context.session_config().options().execution;
+                if options.sort_preserving_merge_buffer_size == 0 {
+                    return config_err!(
+                        "sort_preserving_merge_buffer_size must be at least 1"
+                    );
                 }
-                None => {
-                    let stream = self.input.execute(0, context);
-                    debug!("Done getting stream for SortPreservingMergeExec::execute with 1 input without fetch");
-                    stream
+                if options.sort_merge_prefetch_batches == 0 {
+                    return config_err!("sort_merge_prefetch_batches must be at least 1");
                 }
-            },
-            _ => {
+                let buffer_size = options
+                    .sort_preserving_merge_buffer_size
+                    .max(options.sort_merge_prefetch_batches);
                 let receivers = (0..input_partitions)
                     .map(|partition| {
-                        let stream =
+                        let mut stream =
                             self.input.execute(partition, Arc::clone(&context))?;
-                        Ok(spawn_buffered(stream, 1))
+                        if let Some(fetch) = self.per_partition_fetch {
+                            // the rows are counted by the merge, so the
+                            // metrics of the limits are not reported
+                            let metrics = ExecutionPlanMetricsSet::new();
+                            stream = Box::pin(LimitStream::new(
+                                stream,
+                                0,
+                                Some(fetch),
+                                BaselineMetrics::new(&metrics, partition),
+                            ));
+                        }
+                        Ok(spawn_buffered(stream, buffer_size))
                     })
                     .collect::<Result<_>>()?;
 
                 debug!("Done setting up sender-receiver for SortPreservingMergeExec::execute");
 
+                // the number of rows each input partition contributes, for
+                // example to find out whether the output is skewed
+                let input_rows = (0..input_partitions)
+                    .map(|input_partition| {
+                        MetricBuilder::new(&self.metrics)
+                            .with_new_label("inputPartition", input_partition.to_string())
+                            .counter("input_rows", partition)
+                    })
+                    .collect();
+
+                let merge_ordering = self.merge_ordering();
                 let result = StreamingMergeBuilder::new()
                     .with_streams(receivers)
                     .with_schema(schema)
-                    .with_expressions(self.expr.as_ref())
+                    .with_expressions(&merge_ordering)
                     .with_metrics(BaselineMetrics::new(&self.metrics, partition))
                     .with_batch_size(context.session_config().batch_size())
                     .with_fetch(self.fetch)
+                    .with_offset(self.offset)
+                    .with_input_rows(input_rows)
                     .with_reservation(reservation)
-                    .with_round_robin_tie_breaker(self.enable_round_robin_repartition)
-                    .build()?;
+                    // the loser tree breaks ties by stream index without
+                    // the round robin tie breaker
+                    .with_round_robin_tie_breaker(
+                        self.enable_round_robin_repartition
+                            && !self.tiebreak_by_partition,
+                    );
+                let result = match &self.source_partition_column {
+                    Some(field) => result.with_source_partition_column(Arc::clone(field)),
+                    None => result,
+                }
+                .build()?;
 
                 debug!("Got stream result from SortPreservingMergeStream::new_from_receivers");
 
@@ -331,8 +663,10 @@ impl ExecutionPlan for SortPreservingMergeExec {
         self.input.statistics()
     }
 
+    /// A limit pushed to the inputs would not include the rows skipped by
+    /// the offset, so only the fetch of the merge itself can be set then
     fn supports_limit_pushdown(&self) -> bool {
-        true
+        self.offset == 0
     }
 }
 
@@ -340,6 +674,7 @@ impl ExecutionPlan for SortPreservingMergeExec {
 mod tests {
     use std::fmt::Formatter;
     use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
     use std::sync::Mutex;
     use std::task::{Context, Poll};
     use std::time::Duration;
@@ -347,20 +682,24 @@ mod tests {
     use super::*;
     use crate::coalesce_batches::CoalesceBatchesExec;
     use crate::coalesce_partitions::CoalescePartitionsExec;
-    use crate::execution_plan::{Boundedness, EmissionType};
     use crate::expressions::col;
     use crate::memory::MemoryExec;
     use crate::metrics::{MetricValue, Timestamp};
     use crate::repartition::RepartitionExec;
     use crate::sorts::sort::SortExec;
-    use crate::stream::RecordBatchReceiverStream;
+    use crate::stream::{RecordBatchReceiverStream, RecordBatchStreamAdapter};
     use crate::test::exec::{assert_strong_count_converges_to_zero, BlockingExec};
     use crate::test::{self, assert_is_pending, make_partition};
-    use crate::{collect, common};
-
-    use arrow::array::{ArrayRef, Int32Array, StringArray, TimestampNanosecondArray};
-    use arrow::compute::SortOptions;
-    use arrow::datatypes::{DataType, Field, Schema};
+    use crate::{collect, common, displayable};
+
+    use arrow::array::{
+        ArrayRef, AsArray, Int32Array, StringArray, TimestampNanosecondArray,
+    };
+    use arrow::compute::{
+        concat_batches, lexsort_to_indices, take, take_record_batch, SortColumn,
+        SortOptions,
+    };
+    use arrow::datatypes::{DataType, Field, Int32Type, Schema, UInt32Type};
     use arrow::record_batch::RecordBatch;
     use arrow_array::Int64Array;
     use arrow_schema::SchemaRef;
@@ -960,6 +1299,157 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_merge_all_descending() -> Result<()> {
+        let task_ctx = Arc::new(TaskContext::default());
+        let options = SortOptions {
+            descending: true,
+            nulls_first: true,
+        };
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let partitions = (0..8)
+            .map(|partition| {
+                let a: Int64Array = (0..10_000)
+                    .map(|i| {
+                        let value = (i * 7919 + partition * 104729) % 10007;
+                        (value % 97 != 0).then_some(value)
+                    })
+                    .collect();
+                let b: StringArray =
+                    a.iter().map(|a| a.map(|a| format!("{}", a % 13))).collect();
+                RecordBatch::try_new(Arc::clone(&schema), vec![Arc::new(a), Arc::new(b)])
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let input = concat_batches(&schema, &partitions)?;
+
+        // a single column uses a cursor over the column, two columns compare
+        // their row format
+        for columns in [vec!["a"], vec!["b"], vec!["a", "b"]] {
+            let sort_columns = |batch: &RecordBatch| {
+                columns
+                    .iter()
+                    .map(|name| SortColumn {
+                        values: Arc::clone(batch.column_by_name(name).unwrap()),
+                        options: Some(options),
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+            // each partition is sorted, and split into batches of 1000 rows
+            let sorted_partitions = partitions
+                .iter()
+                .map(|batch| {
+                    let indices = lexsort_to_indices(&sort_columns(batch), None)?;
+                    let batch = take_record_batch(batch, &indices)?;
+                    Ok((0..10).map(|i| batch.slice(i * 1000, 1000)).collect())
+                })
+                .collect::<Result<Vec<Vec<_>>>>()?;
+
+            let sort: LexOrdering = columns
+                .iter()
+                .map(|name| PhysicalSortExpr {
+                    expr: col(name, &schema).unwrap(),
+                    options,
+                })
+                .collect();
+            let exec =
+                MemoryExec::try_new(&sorted_partitions, Arc::clone(&schema), None)?;
+            let merge = Arc::new(SortPreservingMergeExec::new(sort, Arc::new(exec)));
+            let merged = collect(merge, Arc::clone(&task_ctx)).await?;
+            let merged = concat_batches(&schema, &merged)?;
+            assert_eq!(merged.num_rows(), input.num_rows());
+
+            // rows with equal sort keys may be merged in any order, so only
+            // the sort keys are compared
+            let sort_columns = sort_columns(&input);
+            let indices = lexsort_to_indices(&sort_columns, None)?;
+            for (name, column) in columns.iter().zip(sort_columns) {
+                let expected = take(&column.values, &indices, None)?;
+                assert_eq!(
+                    merged.column_by_name(name).unwrap(),
+                    &expected,
+                    "{columns:?}"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_null_ordering_overrides() -> Result<()> {
+        let task_ctx = Arc::new(TaskContext::default());
+        // both inputs are sorted with nulls last for `a`
+        let batch = |a: Vec<Option<i32>>, b: Vec<Option<&str>>| {
+            let a: ArrayRef = Arc::new(Int32Array::from(a));
+            let b: ArrayRef = Arc::new(StringArray::from(b));
+            RecordBatch::try_from_iter(vec![("a", a), ("b", b)]).unwrap()
+        };
+        let b1 = batch(
+            vec![Some(1), Some(1), Some(3), None],
+            vec![None, Some("x"), Some("y"), Some("z")],
+        );
+        let b2 = batch(
+            vec![Some(1), Some(2), None, None],
+            vec![Some("w"), None, None, Some("a")],
+        );
+        let schema = b1.schema();
+
+        // the sort expressions use nulls first for both columns
+        let sort = LexOrdering::new(vec![
+            PhysicalSortExpr {
+                expr: col("a", &schema)?,
+                options: SortOptions::default(),
+            },
+            PhysicalSortExpr {
+                expr: col("b", &schema)?,
+                options: SortOptions::default(),
+            },
+        ]);
+        let exec = MemoryExec::try_new(&[vec![b1], vec![b2]], schema, None)?;
+        let merge = SortPreservingMergeExec::new(sort.clone(), Arc::new(exec))
+            .with_null_ordering_overrides(vec![Some(false), None])?;
+        assert_eq!(merge.expr(), &sort);
+        assert_eq!(
+            merge.properties().output_ordering().unwrap().to_string(),
+            "a@0 ASC NULLS LAST, b@1 ASC"
+        );
+
+        let collected = collect(Arc::new(merge), task_ctx).await?;
+        assert_batches_eq!(
+            &[
+                "+---+---+",
+                "| a | b |",
+                "+---+---+",
+                "| 1 |   |",
+                "| 1 | w |",
+                "| 1 | x |",
+                "| 2 |   |",
+                "| 3 | y |",
+                "|   |   |",
+                "|   | a |",
+                "|   | z |",
+                "+---+---+",
+            ],
+            collected.as_slice()
+        );
+
+        // one override per sort expression is required
+        let exec = MemoryExec::try_new(&[], Arc::new(Schema::empty()), None)?;
+        let err = SortPreservingMergeExec::new(sort, Arc::new(exec))
+            .with_null_ordering_overrides(vec![Some(false)])
+            .unwrap_err()
+            .strip_backtrace();
+        assert_eq!(
+            err,
+            "Error during planning: SortPreservingMergeExec expected 2 null ordering overrides, got 1"
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_sort_merge_single_partition_with_fetch() {
         let task_ctx = Arc::new(TaskContext::default());
@@ -996,6 +1486,321 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_sort_merge_per_partition_fetch() {
+        let task_ctx = Arc::new(TaskContext::default());
+        let batch = |a: Vec<i32>| {
+            let a: ArrayRef = Arc::new(Int32Array::from(a));
+            RecordBatch::try_from_iter(vec![("a", a)]).unwrap()
+        };
+        let b1 = batch(vec![1, 3, 5, 7]);
+        let b2 = batch(vec![2, 4]);
+        let b3 = batch(vec![6, 8, 9]);
+        let schema = b1.schema();
+
+        let sort = LexOrdering::new(vec![PhysicalSortExpr {
+            expr: col("a", &schema).unwrap(),
+            options: SortOptions::default(),
+        }]);
+        let exec = MemoryExec::try_new(&[vec![b1], vec![b2, b3]], schema, None).unwrap();
+        let merge = Arc::new(
+            SortPreservingMergeExec::new(sort, Arc::new(exec))
+                .with_per_partition_fetch(Some(3)),
+        );
+        assert_eq!(merge.fetch(), None);
+
+        // each partition contributes at most 3 rows
+        let collected = collect(Arc::clone(&merge) as _, Arc::clone(&task_ctx))
+            .await
+            .unwrap();
+        assert_batches_eq!(
+            &[
+                "+---+", "| a |", "+---+", "| 1 |", "| 2 |", "| 3 |", "| 4 |", "| 5 |",
+                "| 6 |", "+---+",
+            ],
+            collected.as_slice()
+        );
+
+        // combined with a global fetch
+        let merge = Arc::new(Arc::unwrap_or_clone(merge).with_fetch(Some(4)));
+        let collected = collect(merge, task_ctx).await.unwrap();
+        assert_batches_eq!(
+            &["+---+", "| a |", "+---+", "| 1 |", "| 2 |", "| 3 |", "| 4 |", "+---+"],
+            collected.as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sort_merge_offset() -> Result<()> {
+        // small batches, so that the merge skips rows across batches
+        let task_ctx = Arc::new(
+            TaskContext::default()
+                .with_session_config(SessionConfig::new().with_batch_size(2)),
+        );
+        let batch = |a: Vec<i32>| {
+            let a: ArrayRef = Arc::new(Int32Array::from(a));
+            RecordBatch::try_from_iter(vec![("a", a)]).unwrap()
+        };
+        let schema = batch(vec![]).schema();
+        let sort = LexOrdering::new(vec![PhysicalSortExpr {
+            expr: col("a", &schema)?,
+            options: SortOptions::default(),
+        }]);
+
+        let multi_partition = vec![
+            vec![batch(vec![1, 3]), batch(vec![5, 7])],
+            vec![batch(vec![2, 4])],
+            vec![batch(vec![6]), batch(vec![8, 9])],
+        ];
+        let single_partition =
+            vec![vec![batch(vec![1, 2, 3, 4]), batch(vec![5, 6, 7, 8, 9])]];
+
+        for partitions in [multi_partition, single_partition] {
+            // (offset, fetch, expected)
+            let cases = [
+                (3, None, vec![4, 5, 6, 7, 8, 9]),
+                (3, Some(4), vec![4, 5, 6, 7]),
+                (7, Some(4), vec![8, 9]),
+                (9, None, vec![]),
+                (20, Some(2), vec![]),
+            ];
+            for (offset, fetch, expected) in cases {
+                let exec = MemoryExec::try_new(&partitions, Arc::clone(&schema), None)?;
+                let merge = SortPreservingMergeExec::new(sort.clone(), Arc::new(exec))
+                    .with_offset(offset)
+                    .with_fetch(fetch);
+                assert_eq!(merge.offset(), offset);
+
+                let collected = collect(Arc::new(merge), Arc::clone(&task_ctx)).await?;
+                let values: Vec<i32> = collected
+                    .iter()
+                    .flat_map(|batch| {
+                        batch
+                            .column(0)
+                            .as_primitive::<Int32Type>()
+                            .values()
+                            .to_vec()
+                    })
+                    .collect();
+                assert_eq!(
+                    values,
+                    expected,
+                    "offset={offset}, fetch={fetch:?}, partitions={}",
+                    partitions.len()
+                );
+            }
+        }
+
+        // the offset is applied after the rows of each partition are limited
+        let exec = MemoryExec::try_new(
+            &[vec![batch(vec![1, 3, 5, 7])], vec![batch(vec![2, 4, 6])]],
+            Arc::clone(&schema),
+            None,
+        )?;
+        let merge = SortPreservingMergeExec::new(sort.clone(), Arc::new(exec))
+            .with_per_partition_fetch(Some(2))
+            .with_offset(1);
+        let collected = collect(Arc::new(merge), Arc::clone(&task_ctx)).await?;
+        assert_batches_eq!(
+            &["+---+", "| a |", "+---+", "| 2 |", "| 3 |", "| 4 |", "+---+"],
+            collected.as_slice()
+        );
+
+        let exec = MemoryExec::try_new(&[vec![batch(vec![1, 3, 5, 7])]], schema, None)?;
+        let merge = SortPreservingMergeExec::new(sort, Arc::new(exec))
+            .with_per_partition_fetch(Some(3))
+            .with_offset(2);
+        let collected = collect(Arc::new(merge), Arc::clone(&task_ctx)).await?;
+        assert_batches_eq!(
+            &["+---+", "| a |", "+---+", "| 5 |", "+---+"],
+            collected.as_slice()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_merge_offset_display() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let sort = LexOrdering::new(vec![PhysicalSortExpr {
+            expr: col("a", &schema)?,
+            options: SortOptions::default(),
+        }]);
+        let exec = MemoryExec::try_new(&[], schema, None)?;
+        let merge = SortPreservingMergeExec::new(sort, Arc::new(exec))
+            .with_offset(3)
+            .with_fetch(Some(5));
+        assert_eq!(
+            displayable(&merge).one_line().to_string(),
+            "SortPreservingMergeExec: [a@0 ASC], offset=3, fetch=5\n"
+        );
+
+        // the offset is kept when the fetch is changed
+        let merge = ExecutionPlan::with_fetch(&merge, Some(2)).unwrap();
+        assert_eq!(
+            displayable(merge.as_ref()).one_line().to_string(),
+            "SortPreservingMergeExec: [a@0 ASC], offset=3, fetch=2\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_merge_source_partition_column() -> Result<()> {
+        let task_ctx = Arc::new(TaskContext::default());
+        let batch = |a: Vec<i32>| {
+            let a: ArrayRef = Arc::new(Int32Array::from(a));
+            RecordBatch::try_from_iter(vec![("a", a)]).unwrap()
+        };
+        let schema = batch(vec![]).schema();
+        let sort = LexOrdering::new(vec![PhysicalSortExpr {
+            expr: col("a", &schema)?,
+            options: SortOptions::default(),
+        }]);
+
+        // the last digit of each value is the index of its partition
+        let exec = MemoryExec::try_new(
+            &[
+                vec![batch(vec![10, 40]), batch(vec![50])],
+                vec![batch(vec![21, 31, 61])],
+                vec![batch(vec![12, 42, 72])],
+            ],
+            Arc::clone(&schema),
+            None,
+        )?;
+        let merge = SortPreservingMergeExec::new(sort.clone(), Arc::new(exec))
+            .with_source_partition_column("partition");
+        assert_eq!(
+            merge.schema().field(1),
+            &Field::new("partition", DataType::UInt32, false)
+        );
+        assert_eq!(
+            displayable(&merge).one_line().to_string(),
+            "SortPreservingMergeExec: [a@0 ASC], source_partition_column=partition\n"
+        );
+        let merge: Arc<dyn ExecutionPlan> = Arc::new(merge);
+        let merge = Arc::clone(&merge)
+            .with_new_children(vec![Arc::clone(merge.children()[0])])?;
+        assert_eq!(merge.schema().fields().len(), 2);
+
+        let collected = collect(merge, Arc::clone(&task_ctx)).await?;
+        let merged = concat_batches(&collected[0].schema(), &collected)?;
+        let values = merged.column(0).as_primitive::<Int32Type>();
+        let partitions = merged.column(1).as_primitive::<UInt32Type>();
+        assert_eq!(merged.num_rows(), 9);
+        for (value, partition) in values.values().iter().zip(partitions.values()) {
+            assert_eq!(*value as u32 % 10, *partition, "value {value}");
+        }
+
+        // a single input partition
+        let exec = MemoryExec::try_new(
+            &[vec![batch(vec![1, 2, 3])]],
+            Arc::clone(&schema),
+            None,
+        )?;
+        let merge = SortPreservingMergeExec::new(sort, Arc::new(exec))
+            .with_source_partition_column("partition")
+            .with_fetch(Some(2));
+        let collected = collect(Arc::new(merge), task_ctx).await?;
+        assert_batches_eq!(
+            &[
+                "+---+-----------+",
+                "| a | partition |",
+                "+---+-----------+",
+                "| 1 | 0         |",
+                "| 2 | 0         |",
+                "+---+-----------+",
+            ],
+            collected.as_slice()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_merge_tiebreak_by_partition() -> Result<()> {
+        let task_ctx = Arc::new(
+            TaskContext::default()
+                .with_session_config(SessionConfig::new().with_batch_size(3)),
+        );
+        // equal keys spread over the partitions, labelled by partition and
+        // row within the partition
+        let keys = [
+            vec![1, 1, 1, 1, 2, 2],
+            vec![0, 1, 1, 2, 2, 2],
+            vec![1, 1, 1, 1, 1, 2],
+        ];
+        let partitions = keys
+            .iter()
+            .enumerate()
+            .map(|(partition, keys)| {
+                let labels: StringArray = (0..keys.len())
+                    .map(|row| Some(format!("p{partition}r{row}")))
+                    .collect();
+                let keys: ArrayRef = Arc::new(Int32Array::from(keys.clone()));
+                let batch = RecordBatch::try_from_iter(vec![
+                    ("k", keys),
+                    ("l", Arc::new(labels) as _),
+                ])
+                .unwrap();
+                // split each partition into multiple batches
+                vec![batch.slice(0, 2), batch.slice(2, 4)]
+            })
+            .collect::<Vec<_>>();
+        let schema = partitions[0][0].schema();
+        let sort = LexOrdering::new(vec![PhysicalSortExpr {
+            expr: col("k", &schema)?,
+            options: SortOptions::default(),
+        }]);
+
+        // ordered by key, then partition, then row
+        let mut expected = keys
+            .iter()
+            .enumerate()
+            .flat_map(|(partition, keys)| {
+                keys.iter()
+                    .enumerate()
+                    .map(move |(row, key)| (*key, partition, row))
+            })
+            .collect::<Vec<_>>();
+        expected.sort();
+        let expected = expected
+            .into_iter()
+            .map(|(_, partition, row)| format!("p{partition}r{row}"))
+            .collect::<Vec<_>>();
+
+        for round_robin in [true, false] {
+            let exec = MemoryExec::try_new(&partitions, Arc::clone(&schema), None)?;
+            let merge = SortPreservingMergeExec::new(sort.clone(), Arc::new(exec))
+                .with_round_robin_repartition(round_robin)
+                .with_tiebreak_by_partition(true);
+            assert!(merge.tiebreak_by_partition());
+            let merge: Arc<dyn ExecutionPlan> = Arc::new(merge);
+            assert_eq!(merge.maintains_input_order(), vec![true]);
+            assert_eq!(
+                displayable(merge.as_ref()).one_line().to_string(),
+                "SortPreservingMergeExec: [k@0 ASC], tiebreak_by_partition=true\n"
+            );
+            let merge = Arc::clone(&merge)
+                .with_new_children(vec![Arc::clone(merge.children()[0])])?;
+
+            let collected = collect(merge, Arc::clone(&task_ctx)).await?;
+            let labels = collected
+                .iter()
+                .flat_map(|batch| {
+                    batch
+                        .column(1)
+                        .as_string::<i32>()
+                        .iter()
+                        .map(|label| label.unwrap().to_string())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(labels, expected, "round_robin={round_robin}");
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_sort_merge_single_partition_without_fetch() {
         let task_ctx = Arc::new(TaskContext::default());
@@ -1104,6 +1909,205 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_merge_buffer_size() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        // the values of each partition are greater than those of the previous
+        // partitions, so the merge reads the partitions one after another
+        let partitions = (0..3)
+            .map(|partition| {
+                (0..8)
+                    .map(|i| {
+                        RecordBatch::try_new(
+                            Arc::clone(&schema),
+                            vec![Arc::new(Int32Array::from(vec![partition * 100 + i]))],
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let sort =
+            LexOrdering::new(vec![PhysicalSortExpr::new_default(col("a", &schema)?)]);
+
+        // returns the merge stream after its first row, and the number of
+        // batches read from the inputs so far
+        let merge = |buffer_size: usize| {
+            let memory: Arc<dyn ExecutionPlan> = Arc::new(
+                MemoryExec::try_new(&partitions, Arc::clone(&schema), None).unwrap(),
+            );
+            let input = Arc::new(LatencyExec {
+                input: memory,
+                latency: Duration::from_millis(1),
+                produced: Arc::default(),
+            });
+            let mut config = SessionConfig::new().with_batch_size(1);
+            config
+                .options_mut()
+                .execution
+                .sort_preserving_merge_buffer_size = buffer_size;
+            let task_ctx = Arc::new(TaskContext::default().with_session_config(config));
+            let merge =
+                SortPreservingMergeExec::new(sort.clone(), Arc::clone(&input) as _);
+            async move {
+                let mut stream = merge.execute(0, task_ctx)?;
+                stream.next().await.unwrap()?;
+                Ok::<_, DataFusionError>((stream, Arc::clone(&input.produced)))
+            }
+        };
+
+        // with a lookahead of one batch, the later partitions wait for the
+        // merge to read them, while a larger lookahead reads them in parallel
+        let (stream, produced) = merge(1).await?;
+        let unbuffered = wait_for_produced(&produced, 3).await;
+        assert!(unbuffered <= 3 * 3, "unbuffered: {unbuffered}");
+        let batches = common::collect(stream).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 23);
+
+        let (stream, produced) = merge(8).await?;
+        let buffered = wait_for_produced(&produced, 3 * 8).await;
+        assert_eq!(buffered, 3 * 8);
+        let batches = common::collect(stream).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 23);
+
+        let err = merge(0).await.err().unwrap().strip_backtrace();
+        assert_contains!(err, "sort_preserving_merge_buffer_size must be at least 1");
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_merge_prefetch_batches() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let partitions = (0..2)
+            .map(|partition| {
+                (0..16)
+                    .map(|i| {
+                        RecordBatch::try_new(
+                            Arc::clone(&schema),
+                            vec![Arc::new(Int32Array::from(vec![i * 2 + partition]))],
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let sort =
+            LexOrdering::new(vec![PhysicalSortExpr::new_default(col("a", &schema)?)]);
+
+        // returns the number of batches read from the inputs while the
+        // merge waits after producing its first row
+        let prefetched = |buffer_size: usize, prefetch_batches: usize| {
+            let memory: Arc<dyn ExecutionPlan> = Arc::new(
+                MemoryExec::try_new(&partitions, Arc::clone(&schema), None).unwrap(),
+            );
+            let input = Arc::new(LatencyExec {
+                input: memory,
+                latency: Duration::from_millis(1),
+                produced: Arc::default(),
+            });
+            let mut config = SessionConfig::new().with_batch_size(1);
+            let options = &mut config.options_mut().execution;
+            options.sort_preserving_merge_buffer_size = buffer_size;
+            options.sort_merge_prefetch_batches = prefetch_batches;
+            let task_ctx = Arc::new(TaskContext::default().with_session_config(config));
+            let merge =
+                SortPreservingMergeExec::new(sort.clone(), Arc::clone(&input) as _);
+            async move {
+                let mut stream = merge.execute(0, task_ctx)?;
+                stream.next().await.unwrap()?;
+                let buffered = buffer_size.max(prefetch_batches);
+                let prefetched = wait_for_produced(&input.produced, 2 * (buffered + 2));
+                Ok::<_, DataFusionError>(prefetched.await)
+            }
+        };
+
+        // each input holds one batch in its cursor, the larger of the two
+        // options batches in the buffer and one batch waiting to be buffered
+        let unbuffered = prefetched(1, 1).await?;
+        assert_eq!(unbuffered, 2 * 3);
+        let prefetched_4 = prefetched(1, 4).await?;
+        assert_eq!(prefetched_4, 2 * 6);
+        let buffered_4 = prefetched(4, 2).await?;
+        assert_eq!(buffered_4, 2 * 6);
+
+        let err = prefetched(1, 0).await.err().unwrap().strip_backtrace();
+        assert_contains!(err, "sort_merge_prefetch_batches must be at least 1");
+        Ok(())
+    }
+
+    /// Waits until the inputs produced at least `count` batches, then gives
+    /// them the chance to produce more before returning the number of
+    /// batches produced
+    async fn wait_for_produced(produced: &AtomicUsize, count: usize) -> usize {
+        let wait = async {
+            while produced.load(AtomicOrdering::Relaxed) < count {
+                tokio::task::yield_now().await;
+            }
+        };
+        timeout(Duration::from_secs(30), wait)
+            .await
+            .unwrap_or_else(|_| panic!("inputs produced fewer than {count} batches"));
+        for _ in 0..100 {
+            tokio::task::yield_now().await;
+        }
+        produced.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Delays each batch of `input` by `latency`, for example to simulate a
+    /// remote input
+    #[derive(Debug)]
+    struct LatencyExec {
+        input: Arc<dyn ExecutionPlan>,
+        latency: Duration,
+        /// Number of batches produced over all partitions
+        produced: Arc<AtomicUsize>,
+    }
+
+    impl DisplayAs for LatencyExec {
+        fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+            write!(f, "LatencyExec: latency={:?}", self.latency)
+        }
+    }
+
+    impl ExecutionPlan for LatencyExec {
+        fn name(&self) -> &'static str {
+            Self::static_name()
+        }
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+        fn properties(&self) -> &PlanProperties {
+            self.input.properties()
+        }
+        fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+            vec![&self.input]
+        }
+        fn with_new_children(
+            self: Arc<Self>,
+            _: Vec<Arc<dyn ExecutionPlan>>,
+        ) -> Result<Arc<dyn ExecutionPlan>> {
+            Ok(self)
+        }
+        fn execute(
+            &self,
+            partition: usize,
+            context: Arc<TaskContext>,
+        ) -> Result<SendableRecordBatchStream> {
+            let latency = self.latency;
+            let produced = Arc::clone(&self.produced);
+            let stream = self.input.execute(partition, context)?.then(move |batch| {
+                let produced = Arc::clone(&produced);
+                async move {
+                    tokio::time::sleep(latency).await;
+                    produced.fetch_add(1, AtomicOrdering::Relaxed);
+                    batch
+                }
+            });
+            Ok(Box::pin(RecordBatchStreamAdapter::new(
+                self.schema(),
+                stream,
+            )))
+        }
+    }
+
     #[tokio::test]
     async fn test_merge_metrics() {
         let task_ctx = Arc::new(TaskContext::default());
@@ -1162,6 +2166,53 @@ mod tests {
         assert!(saw_end);
     }
 
+    #[tokio::test]
+    async fn test_merge_input_rows_metrics() -> Result<()> {
+        let task_ctx = Arc::new(
+            TaskContext::default()
+                .with_session_config(SessionConfig::new().with_batch_size(2)),
+        );
+        let batch = |a: Vec<i32>| {
+            let a: ArrayRef = Arc::new(Int32Array::from(a));
+            RecordBatch::try_from_iter(vec![("a", a)]).unwrap()
+        };
+        let schema = batch(vec![]).schema();
+        let exec = MemoryExec::try_new(
+            &[
+                vec![batch(vec![1, 4, 7])],
+                vec![batch(vec![2, 3]), batch(vec![5, 6, 8])],
+                vec![batch(vec![0, 9])],
+            ],
+            Arc::clone(&schema),
+            None,
+        )?;
+        let sort = LexOrdering::new(vec![PhysicalSortExpr {
+            expr: col("a", &schema)?,
+            options: SortOptions::default(),
+        }]);
+        let merge = Arc::new(SortPreservingMergeExec::new(sort, Arc::new(exec)));
+
+        let collected = collect(Arc::clone(&merge) as _, task_ctx).await?;
+        assert_eq!(collected.iter().map(|b| b.num_rows()).sum::<usize>(), 10);
+
+        let metrics = merge.metrics().unwrap();
+        let mut input_rows = metrics
+            .iter()
+            .filter(|m| m.value().name() == "input_rows")
+            .map(|m| m.to_string())
+            .collect::<Vec<_>>();
+        input_rows.sort();
+        assert_eq!(
+            input_rows,
+            [
+                "input_rows{partition=0, inputPartition=0}=3",
+                "input_rows{partition=0, inputPartition=1}=5",
+                "input_rows{partition=0, inputPartition=2}=2",
+            ]
+        );
+        Ok(())
+    }
+
     fn nanos_from_timestamp(ts: &Timestamp) -> i64 {
         ts.value().unwrap().timestamp_nanos_opt().unwrap()
     }
@@ -1426,4 +2477,31 @@ mod tests {
             )),
         }
     }
+
+    #[test]
+    fn test_spm_boundedness_with_fetch() {
+        let schema = Schema::new(vec![Field::new("c1", DataType::UInt64, false)]);
+        let source: Arc<dyn ExecutionPlan> = Arc::new(CongestedExec {
+            schema: schema.clone(),
+            cache: CongestedExec::compute_properties(Arc::new(schema)),
+            congestion_cleared: Arc::new(Mutex::new(false)),
+        });
+        let sort = LexOrdering::new(vec![PhysicalSortExpr::new_default(Arc::new(
+            Column::new("c1", 0),
+        ))]);
+
+        // without fetch the merge is as unbounded as its input
+        let spm = SortPreservingMergeExec::new(sort.clone(), Arc::clone(&source));
+        assert!(spm.properties().boundedness.is_unbounded());
+
+        // with fetch the merge stops after producing `fetch` rows
+        let spm = SortPreservingMergeExec::new(sort, source).with_fetch(Some(10));
+        assert_eq!(spm.properties().boundedness, Boundedness::Bounded);
+
+        // removing the fetch again makes it unbounded
+        let spm = ExecutionPlan::with_fetch(&spm, None).unwrap();
+        assert!(spm.properties().boundedness.is_unbounded());
+        let spm = ExecutionPlan::with_fetch(spm.as_ref(), Some(5)).unwrap();
+        assert_eq!(spm.properties().boundedness, Boundedness::Bounded);
+    }
 }
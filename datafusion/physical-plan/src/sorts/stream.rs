@@ -154,7 +154,7 @@ impl PartitionedStream for RowCursorStream {
 }
 
 /// Specialized stream for sorts on single primitive columns
-pub struct FieldCursorStream<T: CursorArray> {
+pub struct FieldCursorStream<T: CursorArray, const DESCENDING: bool> {
     /// The physical expressions to sort by
     sort: PhysicalSortExpr,
     /// Input streams
@@ -162,7 +162,9 @@ pub struct FieldCursorStream<T: CursorArray> {
     phantom: PhantomData<fn(T) -> T>,
 }
 
-impl<T: CursorArray> std::fmt::Debug for FieldCursorStream<T> {
+impl<T: CursorArray, const DESCENDING: bool> std::fmt::Debug
+    for FieldCursorStream<T, DESCENDING>
+{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("PrimitiveCursorStream")
             .field("num_streams", &self.streams)
@@ -170,7 +172,7 @@ impl<T: CursorArray> std::fmt::Debug for FieldCursorStream<T> {
     }
 }
 
-impl<T: CursorArray> FieldCursorStream<T> {
+impl<T: CursorArray, const DESCENDING: bool> FieldCursorStream<T, DESCENDING> {
     pub fn new(sort: PhysicalSortExpr, streams: Vec<SendableRecordBatchStream>) -> Self {
         let streams = streams.into_iter().map(|s| s.fuse()).collect();
         Self {
@@ -180,7 +182,10 @@ impl<T: CursorArray> FieldCursorStream<T> {
         }
     }
 
-    fn convert_batch(&mut self, batch: &RecordBatch) -> Result<ArrayValues<T::Values>> {
+    fn convert_batch(
+        &mut self,
+        batch: &RecordBatch,
+    ) -> Result<ArrayValues<T::Values, DESCENDING>> {
         let value = self.sort.expr.evaluate(batch)?;
         let array = value.into_array(batch.num_rows())?;
         let array = array.as_any().downcast_ref::<T>().expect("field values");
@@ -188,8 +193,10 @@ impl<T: CursorArray> FieldCursorStream<T> {
     }
 }
 
-impl<T: CursorArray> PartitionedStream for FieldCursorStream<T> {
-    type Output = Result<(ArrayValues<T::Values>, RecordBatch)>;
+impl<T: CursorArray, const DESCENDING: bool> PartitionedStream
+    for FieldCursorStream<T, DESCENDING>
+{
+    type Output = Result<(ArrayValues<T::Values, DESCENDING>, RecordBatch)>;
 
     fn partitions(&self) -> usize {
         self.streams.0.len()
@@ -18,13 +18,13 @@
 //! Merge that deals with an arbitrary size of streaming inputs.
 //! This is an order-preserving merge.
 
-use crate::metrics::BaselineMetrics;
+use crate::metrics::{BaselineMetrics, Count};
 use crate::sorts::{
     merge::SortPreservingMergeStream,
     stream::{FieldCursorStream, RowCursorStream},
 };
 use crate::SendableRecordBatchStream;
-use arrow::datatypes::{DataType, SchemaRef};
+use arrow::datatypes::{DataType, FieldRef, SchemaRef};
 use arrow_array::*;
 use datafusion_common::{internal_err, Result};
 use datafusion_execution::memory_pool::MemoryReservation;
@@ -37,16 +37,28 @@ macro_rules! primitive_merge_helper {
 }
 
 macro_rules! merge_helper {
-    ($t:ty, $sort:ident, $streams:ident, $schema:ident, $tracking_metrics:ident, $batch_size:ident, $fetch:ident, $reservation:ident, $enable_round_robin_tie_breaker:ident) => {{
-        let streams = FieldCursorStream::<$t>::new($sort, $streams);
+    ($t:ty, $sort:ident, $($v:ident),+) => {{
+        // The sort direction is a parameter of the cursor, so that it is not
+        // checked for every comparison
+        if $sort.options.descending {
+            merge_helper!(@direction true, $t, $sort, $($v),+)
+        } else {
+            merge_helper!(@direction false, $t, $sort, $($v),+)
+        }
+    }};
+    (@direction $descending:literal, $t:ty, $sort:ident, $streams:ident, $schema:ident, $tracking_metrics:ident, $batch_size:ident, $fetch:ident, $offset:ident, $reservation:ident, $enable_round_robin_tie_breaker:ident, $source_partition_column:ident, $input_rows:ident) => {{
+        let streams = FieldCursorStream::<$t, $descending>::new($sort, $streams);
         return Ok(Box::pin(SortPreservingMergeStream::new(
             Box::new(streams),
             $schema,
             $tracking_metrics,
             $batch_size,
             $fetch,
+            $offset,
             $reservation,
             $enable_round_robin_tie_breaker,
+            $source_partition_column,
+            $input_rows,
         )));
     }};
 }
@@ -58,8 +70,11 @@ pub struct StreamingMergeBuilder<'a> {
     metrics: Option<BaselineMetrics>,
     batch_size: Option<usize>,
     fetch: Option<usize>,
+    offset: usize,
     reservation: Option<MemoryReservation>,
     enable_round_robin_tie_breaker: bool,
+    source_partition_column: Option<FieldRef>,
+    input_rows: Vec<Count>,
 }
 
 impl Default for StreamingMergeBuilder<'_> {
@@ -71,8 +86,11 @@ impl Default for StreamingMergeBuilder<'_> {
             metrics: None,
             batch_size: None,
             fetch: None,
+            offset: 0,
             reservation: None,
             enable_round_robin_tie_breaker: false,
+            source_partition_column: None,
+            input_rows: vec![],
         }
     }
 }
@@ -115,6 +133,13 @@ impl<'a> StreamingMergeBuilder<'a> {
         self
     }
 
+    /// Sets the number of rows to skip at the start of the merged output,
+    /// before any `fetch` is applied
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
     pub fn with_reservation(mut self, reservation: MemoryReservation) -> Self {
         self.reservation = Some(reservation);
         self
@@ -132,6 +157,20 @@ impl<'a> StreamingMergeBuilder<'a> {
         self
     }
 
+    /// Append a `UInt32` column `field` to the merged output, holding the
+    /// index of the input stream each row was pulled from
+    pub fn with_source_partition_column(mut self, field: FieldRef) -> Self {
+        self.source_partition_column = Some(field);
+        self
+    }
+
+    /// Record the number of rows each input stream contributed to the merged
+    /// output in `input_rows`, which has one counter per input stream
+    pub fn with_input_rows(mut self, input_rows: Vec<Count>) -> Self {
+        self.input_rows = input_rows;
+        self
+    }
+
     pub fn build(self) -> Result<SendableRecordBatchStream> {
         let Self {
             streams,
@@ -140,8 +179,11 @@ impl<'a> StreamingMergeBuilder<'a> {
             batch_size,
             reservation,
             fetch,
+            offset,
             expressions,
             enable_round_robin_tie_breaker,
+            source_partition_column,
+            input_rows,
         } = self;
 
         // Early return if streams or expressions are empty
@@ -160,6 +202,13 @@ impl<'a> StreamingMergeBuilder<'a> {
         {
             return internal_err!("{}", error_message);
         }
+        if !input_rows.is_empty() && input_rows.len() != streams.len() {
+            return internal_err!(
+                "Expected {} input row counters for streaming merge, got {}",
+                streams.len(),
+                input_rows.len()
+            );
+        }
 
         // Unwrapping mandatory fields
         let schema = schema.expect("Schema cannot be empty for streaming merge");
@@ -174,11 +223,11 @@ impl<'a> StreamingMergeBuilder<'a> {
             let sort = expressions[0].clone();
             let data_type = sort.expr.data_type(schema.as_ref())?;
             downcast_primitive! {
-                data_type => (primitive_merge_helper, sort, streams, schema, metrics, batch_size, fetch, reservation, enable_round_robin_tie_breaker),
-                DataType::Utf8 => merge_helper!(StringArray, sort, streams, schema, metrics, batch_size, fetch, reservation, enable_round_robin_tie_breaker)
-                DataType::LargeUtf8 => merge_helper!(LargeStringArray, sort, streams, schema, metrics, batch_size, fetch, reservation, enable_round_robin_tie_breaker)
-                DataType::Binary => merge_helper!(BinaryArray, sort, streams, schema, metrics, batch_size, fetch, reservation, enable_round_robin_tie_breaker)
-                DataType::LargeBinary => merge_helper!(LargeBinaryArray, sort, streams, schema, metrics, batch_size, fetch, reservation, enable_round_robin_tie_breaker)
+                data_type => (primitive_merge_helper, sort, streams, schema, metrics, batch_size, fetch, offset, reservation, enable_round_robin_tie_breaker, source_partition_column, input_rows),
+                DataType::Utf8 => merge_helper!(StringArray, sort, streams, schema, metrics, batch_size, fetch, offset, reservation, enable_round_robin_tie_breaker, source_partition_column, input_rows)
+                DataType::LargeUtf8 => merge_helper!(LargeStringArray, sort, streams, schema, metrics, batch_size, fetch, offset, reservation, enable_round_robin_tie_breaker, source_partition_column, input_rows)
+                DataType::Binary => merge_helper!(BinaryArray, sort, streams, schema, metrics, batch_size, fetch, offset, reservation, enable_round_robin_tie_breaker, source_partition_column, input_rows)
+                DataType::LargeBinary => merge_helper!(LargeBinaryArray, sort, streams, schema, metrics, batch_size, fetch, offset, reservation, enable_round_robin_tie_breaker, source_partition_column, input_rows)
                 _ => {}
             }
         }
@@ -195,8 +244,11 @@ impl<'a> StreamingMergeBuilder<'a> {
             metrics,
             batch_size,
             fetch,
+            offset,
             reservation,
             enable_round_robin_tie_breaker,
+            source_partition_column,
+            input_rows,
         )))
     }
 }
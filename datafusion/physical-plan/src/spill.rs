@@ -23,12 +23,15 @@ use std::path::{Path, PathBuf};
 use std::ptr::NonNull;
 
 use arrow::array::ArrayData;
-use arrow::datatypes::SchemaRef;
+use arrow::datatypes::{Schema, SchemaRef};
 use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::IpcWriteOptions;
+use arrow::ipc::CompressionType;
 use arrow::record_batch::RecordBatch;
 use log::debug;
 use tokio::sync::mpsc::Sender;
 
+use datafusion_common::config::SpillCompression;
 use datafusion_common::{exec_datafusion_err, HashSet, Result};
 use datafusion_execution::disk_manager::RefCountedTempFile;
 use datafusion_execution::memory_pool::human_readable_size;
@@ -55,15 +58,17 @@ pub(crate) fn read_spill_as_stream(
     Ok(builder.build())
 }
 
-/// Spills in-memory `batches` to disk.
+/// Spills in-memory `batches` to disk, compressing the IPC buffers with
+/// `compression`.
 ///
 /// Returns total number of the rows spilled to disk.
 pub(crate) fn spill_record_batches(
     batches: Vec<RecordBatch>,
     path: PathBuf,
     schema: SchemaRef,
+    compression: SpillCompression,
 ) -> Result<usize> {
-    let mut writer = IPCWriter::new(path.as_ref(), schema.as_ref())?;
+    let mut writer = new_spill_writer(path.as_ref(), schema.as_ref(), compression)?;
     for batch in batches {
         writer.write(&batch)?;
     }
@@ -77,6 +82,24 @@ pub(crate) fn spill_record_batches(
     Ok(writer.num_rows)
 }
 
+/// Creates an [`IPCWriter`] for a spill file using the given `compression`.
+///
+/// Spill files are read back with a [`FileReader`], which decompresses the
+/// buffers based on the codec recorded in the file.
+fn new_spill_writer(
+    path: &Path,
+    schema: &Schema,
+    compression: SpillCompression,
+) -> Result<IPCWriter> {
+    let compression = match compression {
+        SpillCompression::Zstd => Some(CompressionType::ZSTD),
+        SpillCompression::Lz4Frame => Some(CompressionType::LZ4_FRAME),
+        SpillCompression::Uncompressed => None,
+    };
+    let options = IpcWriteOptions::default().try_with_compression(compression)?;
+    IPCWriter::new_with_options(path, schema, options)
+}
+
 fn read_spill(sender: Sender<Result<RecordBatch>>, path: &Path) -> Result<()> {
     let file = BufReader::new(File::open(path)?);
     let reader = FileReader::try_new(file, None)?;
@@ -89,16 +112,17 @@ fn read_spill(sender: Sender<Result<RecordBatch>>, path: &Path) -> Result<()> {
 }
 
 /// Spill the `RecordBatch` to disk as smaller batches
-/// split by `batch_size_rows`
+/// split by `batch_size_rows`, compressed with `compression`
 pub fn spill_record_batch_by_size(
     batch: &RecordBatch,
     path: PathBuf,
     schema: SchemaRef,
     batch_size_rows: usize,
+    compression: SpillCompression,
 ) -> Result<()> {
     let mut offset = 0;
     let total_rows = batch.num_rows();
-    let mut writer = IPCWriter::new(&path, schema.as_ref())?;
+    let mut writer = new_spill_writer(&path, schema.as_ref(), compression)?;
 
     while offset < total_rows {
         let length = std::cmp::min(total_rows - offset, batch_size_rows);
@@ -218,6 +242,7 @@ mod tests {
             vec![batch1, batch2],
             spill_file.path().into(),
             Arc::clone(&schema),
+            SpillCompression::Uncompressed,
         );
         assert_eq!(cnt.unwrap(), num_rows);
 
@@ -247,6 +272,7 @@ mod tests {
             spill_file.path().into(),
             Arc::clone(&schema),
             1,
+            SpillCompression::Uncompressed,
         )?;
 
         let file = BufReader::new(File::open(spill_file.path())?);
@@ -258,6 +284,61 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_batch_spill_compression_round_trip() -> Result<()> {
+        let batch1 = build_table_i32(
+            ("a2", &vec![0, 1, 2, 3]),
+            ("b2", &vec![3, 4, 5, 6]),
+            ("c2", &vec![4, 5, 6, 7]),
+        );
+
+        let batch2 = build_table_i32(
+            ("a2", &vec![10, 11, 12, 13]),
+            ("b2", &vec![13, 14, 15, 16]),
+            ("c2", &vec![14, 15, 16, 17]),
+        );
+
+        let disk_manager = DiskManager::try_new(DiskManagerConfig::NewOs)?;
+        let schema = batch1.schema();
+
+        for compression in [
+            SpillCompression::Zstd,
+            SpillCompression::Lz4Frame,
+            SpillCompression::Uncompressed,
+        ] {
+            let spill_file = disk_manager.create_tmp_file("Test Spill")?;
+            let cnt = spill_record_batches(
+                vec![batch1.clone(), batch2.clone()],
+                spill_file.path().into(),
+                Arc::clone(&schema),
+                compression,
+            )?;
+            assert_eq!(cnt, 8);
+
+            let file = BufReader::new(File::open(spill_file.path())?);
+            let reader = FileReader::try_new(file, None)?;
+            assert_eq!(reader.schema(), schema);
+            let batches = reader.collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(batches, vec![batch1.clone(), batch2.clone()]);
+
+            let spill_file = disk_manager.create_tmp_file("Test Spill")?;
+            spill_record_batch_by_size(
+                &batch1,
+                spill_file.path().into(),
+                Arc::clone(&schema),
+                3,
+                compression,
+            )?;
+
+            let file = BufReader::new(File::open(spill_file.path())?);
+            let reader = FileReader::try_new(file, None)?;
+            let batches = reader.collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(batches, vec![batch1.slice(0, 3), batch1.slice(3, 1)]);
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_get_record_batch_memory_size() {
         // Create a simple record batch with two columns
@@ -0,0 +1,354 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! [`BoundedMinKeeper`]: keeps the smallest k items pushed into it
+
+use std::cmp::Ordering;
+
+/// Keeps the *smallest* `k` items pushed into it, evicting the largest
+/// retained item when a smaller one arrives once `k` items are retained.
+///
+/// This is the bounded buffer behind [`TopK`](super::TopK), which keeps the
+/// rows with the smallest sort keys. It is also useful for other operators
+/// that need the k best items of their input, such as the k nearest
+/// neighbors of a point.
+///
+/// The largest retained item is the boundary a new item must be less than
+/// to be retained, see [`Self::max`] and [`Self::accepts`]. Items equal to
+/// the boundary are not retained, so among equal items the first ones
+/// pushed are kept.
+///
+/// Items are ordered by [`Ord`], or by a comparison function passed to the
+/// `*_by` methods, like [`slice::sort_by`]. The same order must be used for
+/// all calls on a keeper.
+///
+/// ```
+/// # use datafusion_physical_plan::BoundedMinKeeper;
+/// let mut keeper = BoundedMinKeeper::new(2);
+/// assert_eq!(keeper.push(5), None);
+/// assert_eq!(keeper.push(3), None);
+/// // 4 evicts 5, the largest retained item
+/// assert_eq!(keeper.push(4), Some(5));
+/// // 7 is not among the 2 smallest items
+/// assert_eq!(keeper.push(7), Some(7));
+/// assert_eq!(keeper.into_sorted_vec(), vec![3, 4]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BoundedMinKeeper<T> {
+    /// The maximum number of items to retain
+    k: usize,
+    /// The retained items, as a binary max heap with the largest first
+    heap: Vec<T>,
+}
+
+impl<T> BoundedMinKeeper<T> {
+    /// Creates a new, empty, [`BoundedMinKeeper`] retaining at most `k`
+    /// items
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: Vec::new(),
+        }
+    }
+
+    /// Like [`Self::from_vec`], ordering the items by `compare`
+    pub fn from_vec_by<F>(k: usize, items: Vec<T>, compare: F) -> Self
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        assert!(items.len() <= k, "{} items exceed k = {k}", items.len());
+        let mut keeper = Self { k, heap: items };
+        for pos in (0..keeper.heap.len() / 2).rev() {
+            keeper.sift_down(pos, &compare);
+        }
+        keeper
+    }
+
+    /// Returns the maximum number of items retained
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the number of items retained
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Returns true if no items are retained
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns true if `k` items are retained, so that a new item must be
+    /// less than [`Self::max`] to be retained
+    pub fn is_full(&self) -> bool {
+        self.heap.len() >= self.k
+    }
+
+    /// Returns the largest retained item if `k` items are retained,
+    /// otherwise `None`, as any new item would be retained
+    pub fn max(&self) -> Option<&T> {
+        if self.is_full() {
+            self.heap.first()
+        } else {
+            None
+        }
+    }
+
+    /// Like [`Self::accepts`], ordering the items by `compare`
+    pub fn accepts_by<F>(&self, item: &T, compare: F) -> bool
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        match self.max() {
+            Some(max) => compare(item, max).is_lt(),
+            None => self.k > 0,
+        }
+    }
+
+    /// Like [`Self::push`], ordering the items by `compare`
+    pub fn push_by<F>(&mut self, item: T, compare: F) -> Option<T>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        if !self.accepts_by(&item, &compare) {
+            return Some(item);
+        }
+        if self.is_full() {
+            // replace the largest item, which is first
+            let evicted = std::mem::replace(&mut self.heap[0], item);
+            self.sift_down(0, &compare);
+            return Some(evicted);
+        }
+        self.heap.push(item);
+        self.sift_up(self.heap.len() - 1, &compare);
+        None
+    }
+
+    /// Like [`Self::pop_max`], ordering the items by `compare`
+    pub fn pop_max_by<F>(&mut self, compare: F) -> Option<T>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        if self.heap.is_empty() {
+            return None;
+        }
+        let max = self.heap.swap_remove(0);
+        if !self.heap.is_empty() {
+            self.sift_down(0, &compare);
+        }
+        Some(max)
+    }
+
+    /// Returns the retained items, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.heap.iter()
+    }
+
+    /// Like [`Self::take_sorted`], ordering the items by `compare`
+    pub fn take_sorted_by<F>(&mut self, compare: F) -> Vec<T>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        let mut items = std::mem::take(&mut self.heap);
+        items.sort_unstable_by(compare);
+        items
+    }
+
+    /// Like [`Self::into_sorted_vec`], ordering the items by `compare`
+    pub fn into_sorted_vec_by<F>(mut self, compare: F) -> Vec<T>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        self.take_sorted_by(compare)
+    }
+
+    /// Returns the number of items that can be retained without
+    /// reallocating
+    pub fn capacity(&self) -> usize {
+        self.heap.capacity()
+    }
+
+    /// Reserves the space to retain all `k` items without reallocating
+    pub fn reserve_k(&mut self) {
+        self.heap.reserve(self.k.saturating_sub(self.heap.len()));
+    }
+
+    /// Releases the space not used by the retained items
+    pub fn shrink_to_fit(&mut self) {
+        self.heap.shrink_to_fit();
+    }
+
+    /// Moves the item at `pos` up until it is not larger than its parent
+    fn sift_up<F>(&mut self, mut pos: usize, compare: &F)
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if compare(&self.heap[pos], &self.heap[parent]).is_le() {
+                break;
+            }
+            self.heap.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    /// Moves the item at `pos` down until it is not smaller than its
+    /// children
+    fn sift_down<F>(&mut self, mut pos: usize, compare: &F)
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        let len = self.heap.len();
+        loop {
+            let mut largest = pos;
+            for child in [2 * pos + 1, 2 * pos + 2] {
+                if child < len && compare(&self.heap[child], &self.heap[largest]).is_gt()
+                {
+                    largest = child;
+                }
+            }
+            if largest == pos {
+                break;
+            }
+            self.heap.swap(pos, largest);
+            pos = largest;
+        }
+    }
+}
+
+impl<T: Ord> BoundedMinKeeper<T> {
+    /// Creates a new [`BoundedMinKeeper`] retaining at most `k` items,
+    /// initially retaining `items`.
+    ///
+    /// Panics if there are more than `k` items.
+    pub fn from_vec(k: usize, items: Vec<T>) -> Self {
+        Self::from_vec_by(k, items, T::cmp)
+    }
+
+    /// Returns true if `item` would be retained by [`Self::push`]
+    pub fn accepts(&self, item: &T) -> bool {
+        self.accepts_by(item, T::cmp)
+    }
+
+    /// Pushes `item`, retaining it if it is among the `k` smallest items.
+    ///
+    /// Returns the largest retained item if it was evicted to make room
+    /// for `item`, or `item` itself if it was not retained.
+    pub fn push(&mut self, item: T) -> Option<T> {
+        self.push_by(item, T::cmp)
+    }
+
+    /// Removes and returns the largest retained item, if any
+    pub fn pop_max(&mut self) -> Option<T> {
+        self.pop_max_by(T::cmp)
+    }
+
+    /// Removes the retained items, returning them from smallest to largest
+    pub fn take_sorted(&mut self) -> Vec<T> {
+        self.take_sorted_by(T::cmp)
+    }
+
+    /// Returns the retained items, from smallest to largest
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.into_sorted_vec_by(T::cmp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounded_min_keeper() {
+        let mut keeper = BoundedMinKeeper::new(3);
+        assert!(keeper.is_empty());
+        assert_eq!(keeper.max(), None);
+
+        let mut evicted = vec![];
+        for item in [8, 3, 9, 5, 1, 7, 5, 2, 0] {
+            evicted.extend(keeper.push(item));
+            assert!(keeper.len() <= 3);
+        }
+        assert!(keeper.is_full());
+        assert_eq!(keeper.max(), Some(&2));
+        // 7 and the second 5 are rejected, the others are evicted later
+        assert_eq!(evicted, vec![9, 8, 7, 5, 5, 3]);
+        assert_eq!(keeper.into_sorted_vec(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_bounded_min_keeper_boundary() {
+        let mut keeper = BoundedMinKeeper::from_vec(2, vec![(4, "a"), (1, "b")]);
+        assert_eq!(keeper.max(), Some(&(4, "a")));
+
+        // an item equal to the boundary is not retained
+        assert!(!keeper.accepts(&(4, "a")));
+        assert_eq!(keeper.push((4, "a")), Some((4, "a")));
+        assert!(keeper.accepts(&(3, "z")));
+        assert_eq!(keeper.push((3, "z")), Some((4, "a")));
+        assert_eq!(keeper.pop_max(), Some((3, "z")));
+        assert!(!keeper.is_full());
+        assert_eq!(keeper.max(), None);
+        assert_eq!(keeper.take_sorted(), vec![(1, "b")]);
+        assert!(keeper.is_empty());
+
+        // nothing is retained for k = 0
+        let mut keeper = BoundedMinKeeper::new(0);
+        assert!(!keeper.accepts(&1));
+        assert_eq!(keeper.push(1), Some(1));
+        assert!(keeper.is_empty());
+    }
+
+    #[test]
+    fn test_bounded_min_keeper_matches_sort() {
+        // pseudo random items with duplicates
+        let items: Vec<u32> = (0..1_000).map(|i| (i * 7_919) % 251).collect();
+        for k in [1, 10, 100, 251, 2_000] {
+            let mut keeper = BoundedMinKeeper::new(k);
+            keeper.reserve_k();
+            assert!(keeper.capacity() >= k);
+            let mut num_evicted = 0;
+            for &item in &items {
+                num_evicted += keeper.push(item).iter().count();
+            }
+
+            let mut expected = items.clone();
+            expected.sort_unstable();
+            expected.truncate(k);
+            assert_eq!(num_evicted, items.len() - expected.len());
+            assert_eq!(keeper.into_sorted_vec(), expected);
+        }
+    }
+
+    #[test]
+    fn test_bounded_min_keeper_by() {
+        // keep the 3 largest items by reversing the order
+        let reversed = |a: &u32, b: &u32| b.cmp(a);
+        let mut keeper = BoundedMinKeeper::from_vec_by(3, vec![4, 9], reversed);
+        assert!(keeper.accepts_by(&1, reversed));
+        for item in [1, 7, 2, 8, 9] {
+            keeper.push_by(item, reversed);
+        }
+        assert_eq!(keeper.max(), Some(&8));
+        assert!(!keeper.accepts_by(&8, reversed));
+        assert_eq!(keeper.push_by(3, reversed), Some(3));
+        assert_eq!(keeper.pop_max_by(reversed), Some(8));
+        assert_eq!(keeper.into_sorted_vec_by(reversed), vec![9, 9]);
+    }
+}
@@ -18,25 +18,124 @@
 //! TopK: Combination of Sort / LIMIT
 
 use arrow::{
-    compute::interleave,
+    compute::{
+        concat_batches, filter, filter_record_batch, interleave, is_null, max,
+        max_binary, max_binary_view, max_boolean, max_string, max_string_view, min,
+        min_binary, min_binary_view, min_boolean, min_string, min_string_view, nullif,
+        take_arrays, take_record_batch,
+    },
     row::{RowConverter, Rows, SortField},
 };
+use std::fmt::{self, Debug, Formatter};
 use std::mem::size_of;
-use std::{cmp::Ordering, collections::BinaryHeap, sync::Arc};
+use std::{cmp::Ordering, sync::Arc};
 
-use super::metrics::{BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder};
+mod bounded;
+pub use bounded::BoundedMinKeeper;
+
+use super::metrics::{
+    BaselineMetrics, Count, ExecutionPlanMetricsSet, Gauge, MetricBuilder,
+};
+use crate::projection::get_field_metadata;
 use crate::spill::get_record_batch_memory_size;
 use crate::{stream::RecordBatchStreamAdapter, SendableRecordBatchStream};
-use arrow_array::{Array, ArrayRef, RecordBatch};
-use arrow_schema::SchemaRef;
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float16Type, Float32Type, Float64Type};
+use arrow_array::{
+    downcast_primitive_array, Array, ArrayRef, ArrowNativeTypeOp, ArrowPrimitiveType,
+    BinaryArray, BooleanArray, PrimitiveArray, RecordBatch, RecordBatchOptions,
+    StructArray, UInt32Array, UInt64Array,
+};
+use arrow_buffer::BooleanBuffer;
+use arrow_schema::{DataType, Field, FieldRef, Schema, SchemaRef, SortOptions};
 use datafusion_common::HashMap;
-use datafusion_common::Result;
+use datafusion_common::{internal_err, not_impl_err, Result, ScalarValue};
 use datafusion_execution::{
     memory_pool::{MemoryConsumer, MemoryReservation},
     runtime_env::RuntimeEnv,
 };
-use datafusion_physical_expr::PhysicalSortExpr;
+use datafusion_physical_expr::{PhysicalExpr, PhysicalSortExpr};
 use datafusion_physical_expr_common::sort_expr::LexOrdering;
+use half::f16;
+
+/// Number of consecutive narrow batches after which the scratch space
+/// used to convert sort keys is reallocated, see [`SCRATCH_ROWS_SHRINK_RATIO`]
+const SCRATCH_ROWS_SHRINK_BATCHES: usize = 8;
+
+/// A batch is narrow if converting its sort keys uses less than
+/// `1 / SCRATCH_ROWS_SHRINK_RATIO` of the peak size of the scratch space
+const SCRATCH_ROWS_SHRINK_RATIO: usize = 4;
+
+/// Compares two sort keys encoded in the [arrow::row] format, used to
+/// override the default byte-wise comparison in [`TopK`]
+pub type TopKComparator = Arc<dyn Fn(&[u8], &[u8]) -> Ordering + Send + Sync>;
+
+/// Called once when a [`TopK`] first holds `k` rows, see
+/// [`TopK::with_on_saturated`]
+pub type TopKSaturatedCallback = Box<dyn FnOnce() + Send>;
+
+/// How [`TopK`] orders rows whose sort key is NaN
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TopKNanPolicy {
+    /// Order NaN like the arrow row format does: in ascending order, NaN
+    /// with a positive sign after all other values and NaN with a negative
+    /// sign before them
+    #[default]
+    RowFormat,
+    /// Ignore rows with NaN in any sort key, so they never enter the top k
+    Exclude,
+    /// Order NaN before all other non null values, in either sort direction
+    First,
+    /// Order NaN after all other non null values, in either sort direction
+    Last,
+}
+
+/// How [`TopK`] numbers the rows of its output in the rank column, see
+/// [`TopK::with_rank_column`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TopKRankPolicy {
+    /// Number the rows `1, 2, 3, ...` in output order, like `ROW_NUMBER()`,
+    /// even if their sort keys are equal
+    #[default]
+    RowNumber,
+    /// Rows with equal sort keys share the position of the first of them,
+    /// and the following rank skips the tied positions, like `RANK()`:
+    /// `1, 1, 3`
+    Rank,
+    /// Rows with equal sort keys share a rank, and the following rank has
+    /// no gap, like `DENSE_RANK()`: `1, 1, 2`
+    DenseRank,
+}
+
+/// How [`TopK`] retains the values of the rows currently in the top k
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TopKStorage {
+    /// Retain each input batch that has at least one row in the top k, and
+    /// `interleave` the rows from these batches on emit. Batches with
+    /// unused rows are compacted from time to time.
+    #[default]
+    Batches,
+    /// Copy each row entering the top k into its own single-row batch, and
+    /// `concat` these batches on emit.
+    ///
+    /// Inserting is more expensive, but no input batches are retained and
+    /// no compaction is needed, which can use less memory for a small k
+    /// with wide rows.
+    Rows,
+}
+
+/// How [`TopK`] allocates the space for the rows in the top k
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TopKHeapGrowth {
+    /// Start empty and grow as rows enter the top k, up to k rows. Uses
+    /// less memory when only a few rows are ever inserted, at the cost of
+    /// occasional reallocations.
+    #[default]
+    Lazy,
+    /// Allocate the space for all k rows up front, so it is never
+    /// reallocated while the top k fills up.
+    Eager,
+}
 
 /// Global TopK
 ///
@@ -80,16 +179,57 @@ pub struct TopK {
     metrics: TopKMetrics,
     /// Reservation
     reservation: MemoryReservation,
-    /// The target number of rows for output batches
+    /// The target number of rows for processing, and for output batches
+    /// unless `output_batch_size` is set
     batch_size: usize,
+    /// The target number of rows for output batches, if different from
+    /// `batch_size`
+    output_batch_size: Option<usize>,
+    /// The maximum number of rows of an output batch on emit, see
+    /// [`Self::with_emit_chunk_rows`]
+    emit_chunk_rows: Option<usize>,
     /// sort expressions
     expr: Arc<[PhysicalSortExpr]>,
+    /// The sort options of the fields of struct sort expressions, by
+    /// expression index, see [`Self::with_struct_field_options`]
+    struct_field_options: Vec<Option<Vec<SortOptions>>>,
     /// row converter, for sort keys
     row_converter: RowConverter,
     /// scratch space for converting rows
     scratch_rows: Rows,
+    /// The largest size of `scratch_rows` since it was allocated, in bytes.
+    /// [`Rows::size`] only reports the size of the current rows, but the
+    /// allocation keeps the peak size until `scratch_rows` is replaced
+    scratch_rows_peak_size: usize,
+    /// Number of consecutive batches that used much less than
+    /// `scratch_rows_peak_size` of `scratch_rows`
+    narrow_batches: usize,
     /// stores the top k values and their sort key values, in order
     heap: TopKHeap,
+    /// The ordering of the input, if known
+    input_ordering: Option<LexOrdering>,
+    /// Row converter for the leading sort expressions that the input is
+    /// already sorted by, if there are any
+    common_prefix_converter: Option<RowConverter>,
+    /// Number of leading sort expressions that the input is already sorted by
+    common_prefix_len: usize,
+    /// Set once no future input row can enter the top k
+    finished: bool,
+    /// How to order rows with NaN sort keys
+    nan_policy: TopKNanPolicy,
+    /// The column with the rank of each row appended to the output, if any
+    rank_column: Option<(FieldRef, TopKRankPolicy)>,
+    /// The column with the encoded sort key of each row appended to the
+    /// output, if any
+    sort_key_column: Option<FieldRef>,
+    /// Called once the heap first holds k rows, if set
+    on_saturated: Option<TopKSaturatedCallback>,
+    /// The projection evaluated against the top k rows on emit, if any
+    output_projection: Option<TopKOutputProjection>,
+    /// Minimum number of rows in a batch to select its candidate rows in
+    /// parallel, see [`Self::with_parallel_threshold`]
+    #[cfg(feature = "parallel_topk")]
+    parallel_threshold: Option<usize>,
 }
 
 impl TopK {
@@ -110,41 +250,481 @@ impl TopK {
 
         let expr: Arc<[PhysicalSortExpr]> = expr.inner.into();
 
-        let sort_fields: Vec<_> = expr
-            .iter()
-            .map(|e| {
-                Ok(SortField::new_with_options(
-                    e.expr.data_type(&schema)?,
-                    e.options,
-                ))
-            })
-            .collect::<Result<_>>()?;
+        let sort_fields = build_sort_fields(&expr, &schema)?;
 
         // TODO there is potential to add special cases for single column sort fields
         // to improve performance
         let row_converter = RowConverter::new(sort_fields)?;
-        let scratch_rows = row_converter.empty_rows(
-            batch_size,
-            20 * batch_size, // guesstimate 20 bytes per row
-        );
+        let scratch_rows = new_scratch_rows(&row_converter, batch_size);
 
         Ok(Self {
             schema: Arc::clone(&schema),
             metrics: TopKMetrics::new(metrics, partition_id),
             reservation,
             batch_size,
+            output_batch_size: None,
+            emit_chunk_rows: None,
             expr,
+            struct_field_options: vec![],
             row_converter,
             scratch_rows,
+            scratch_rows_peak_size: initial_scratch_rows_size(batch_size),
+            narrow_batches: 0,
             heap: TopKHeap::new(k, batch_size, schema),
+            input_ordering: None,
+            common_prefix_converter: None,
+            common_prefix_len: 0,
+            finished: false,
+            nan_policy: TopKNanPolicy::default(),
+            rank_column: None,
+            sort_key_column: None,
+            on_saturated: None,
+            output_projection: None,
+            #[cfg(feature = "parallel_topk")]
+            parallel_threshold: None,
         })
     }
 
+    /// Order rows with `comparator` instead of the byte-wise comparison of
+    /// their sort keys, for example to implement a domain specific ordering.
+    ///
+    /// `comparator` receives the sort keys encoded by the [`RowConverter`]
+    /// for the sort expressions, and should return [`Ordering::Less`] for
+    /// the row that belongs before the other in the output. As the encoded
+    /// prefix of a custom ordering can not be compared, a comparator
+    /// disables stopping early on sorted input.
+    pub fn with_comparator(mut self, comparator: TopKComparator) -> Self {
+        self.heap.comparator = Some(comparator);
+        self.common_prefix_converter = None;
+        self.common_prefix_len = 0;
+        self
+    }
+
+    /// Order the fields of the struct sort expression at `expr_index` by
+    /// `field_options`, one per field, instead of by the options of the
+    /// expression, for example to sort by `(s.a ASC, s.b DESC)`.
+    ///
+    /// The options of the expression still decide whether null structs
+    /// sort first or last. The rows are then ordered differently than
+    /// the sort expressions would order them, which disables stopping
+    /// early on sorted input. Must be called before any batch is inserted.
+    pub fn with_struct_field_options(
+        mut self,
+        expr_index: usize,
+        field_options: Vec<SortOptions>,
+    ) -> Result<Self> {
+        let Some(expr) = self.expr.get(expr_index) else {
+            return internal_err!("TopK has no sort expression {expr_index}");
+        };
+        let num_fields = match expr.expr.data_type(&self.schema)? {
+            DataType::Struct(fields) => fields.len(),
+            data_type => {
+                return internal_err!(
+                    "Cannot set field sort options for {} of type {data_type}",
+                    expr.expr
+                )
+            }
+        };
+        if field_options.len() != num_fields {
+            return internal_err!(
+                "Expected {num_fields} field sort options for {}, got {}",
+                expr.expr,
+                field_options.len()
+            );
+        }
+
+        self.struct_field_options.resize(self.expr.len(), None);
+        self.struct_field_options[expr_index] = Some(field_options);
+        self.row_converter = RowConverter::new(self.sort_fields(&self.expr)?)?;
+        self.scratch_rows = new_scratch_rows(&self.row_converter, self.batch_size);
+        self.scratch_rows_peak_size = initial_scratch_rows_size(self.batch_size);
+        self.common_prefix_converter = None;
+        self.common_prefix_len = 0;
+        Ok(self)
+    }
+
+    /// Returns true if any struct sort expression has field sort options,
+    /// see [`Self::with_struct_field_options`]
+    fn has_struct_field_options(&self) -> bool {
+        self.struct_field_options.iter().any(Option::is_some)
+    }
+
+    /// Returns the [`SortField`]s of the sort keys of `expr`, as expanded
+    /// by [`Self::expand_sort_keys`]
+    fn sort_fields(&self, expr: &[PhysicalSortExpr]) -> Result<Vec<SortField>> {
+        let mut sort_fields = build_sort_fields(expr, &self.schema)?;
+        let struct_field_options = self.struct_field_options.iter().enumerate().rev();
+        for (idx, field_options) in struct_field_options {
+            let Some(field_options) = field_options else {
+                continue;
+            };
+            let Some(DataType::Struct(fields)) = expr
+                .get(idx)
+                .map(|e| e.expr.data_type(&self.schema))
+                .transpose()?
+            else {
+                return internal_err!("TopK sort expression {idx} is not a struct");
+            };
+            // true for null structs, which sort first or last as the struct
+            let nulls = SortField::new_with_options(
+                DataType::Boolean,
+                SortOptions {
+                    descending: expr[idx].options.nulls_first,
+                    nulls_first: false,
+                },
+            );
+            let children = fields.iter().zip(field_options).map(|(field, options)| {
+                SortField::new_with_options(field.data_type().clone(), *options)
+            });
+            sort_fields.splice(idx..=idx, std::iter::once(nulls).chain(children));
+        }
+        Ok(sort_fields)
+    }
+
+    /// Replaces the keys of the struct sort expressions with field sort
+    /// options by a column that is true for null structs, followed by their
+    /// fields, which are null for null structs so that all null structs
+    /// compare equal
+    fn expand_sort_keys(&self, sort_keys: Vec<ArrayRef>) -> Result<Vec<ArrayRef>> {
+        if !self.has_struct_field_options() {
+            return Ok(sort_keys);
+        }
+        let mut expanded = Vec::with_capacity(sort_keys.len());
+        for (idx, keys) in sort_keys.into_iter().enumerate() {
+            if !matches!(self.struct_field_options.get(idx), Some(Some(_))) {
+                expanded.push(keys);
+                continue;
+            }
+            let nulls = is_null(&keys)?;
+            let fields = keys
+                .as_struct()
+                .columns()
+                .iter()
+                .map(|field| nullif(field, &nulls))
+                .collect::<Result<Vec<_>, _>>()?;
+            expanded.push(Arc::new(nulls));
+            expanded.extend(fields);
+        }
+        Ok(expanded)
+    }
+
+    /// Reverses [`Self::expand_sort_keys`], reassembling the keys of the
+    /// struct sort expressions with field sort options
+    fn collapse_sort_keys(&self, sort_keys: Vec<ArrayRef>) -> Result<Vec<ArrayRef>> {
+        if !self.has_struct_field_options() {
+            return Ok(sort_keys);
+        }
+        let mut sort_keys = sort_keys.into_iter();
+        let mut collapsed = Vec::with_capacity(self.expr.len());
+        for (idx, expr) in self.expr.iter().enumerate() {
+            let Some(keys) = sort_keys.next() else {
+                return internal_err!("Missing TopK sort keys for expression {idx}");
+            };
+            let (Some(Some(_)), DataType::Struct(fields)) = (
+                self.struct_field_options.get(idx),
+                expr.expr.data_type(&self.schema)?,
+            ) else {
+                collapsed.push(keys);
+                continue;
+            };
+            let valid = !keys.as_boolean().values();
+            let children = sort_keys.by_ref().take(fields.len()).collect();
+            collapsed.push(Arc::new(StructArray::try_new(
+                fields,
+                children,
+                Some(valid.into()),
+            )?));
+        }
+        Ok(collapsed)
+    }
+
+    /// Order rows whose sort keys are NaN according to `nan_policy`, see
+    /// [`TopKNanPolicy`].
+    ///
+    /// As moving NaN to [`TopKNanPolicy::First`] or [`TopKNanPolicy::Last`]
+    /// can reorder sorted input, these policies disable stopping early on
+    /// sorted input.
+    pub fn with_nan_policy(mut self, nan_policy: TopKNanPolicy) -> Self {
+        self.nan_policy = nan_policy;
+        if matches!(nan_policy, TopKNanPolicy::First | TopKNanPolicy::Last) {
+            self.common_prefix_converter = None;
+            self.common_prefix_len = 0;
+        }
+        self
+    }
+
+    /// Emit the top k in batches of `output_batch_size` rows instead of the
+    /// processing `batch_size` this [`TopK`] was created with.
+    ///
+    /// Only the output is affected: scratch space and compaction are still
+    /// sized by the processing `batch_size`. `output_batch_size` must be
+    /// greater than zero.
+    pub fn with_output_batch_size(mut self, output_batch_size: usize) -> Self {
+        self.output_batch_size = Some(output_batch_size);
+        self
+    }
+
+    /// Limit the output batches of [`Self::emit`] to at most `rows` rows,
+    /// even if the output batch size is larger. As the rows of each output
+    /// batch are gathered separately, this bounds the number of rows
+    /// gathered at once. `rows` must be greater than zero.
+    pub fn with_emit_chunk_rows(mut self, rows: usize) -> Self {
+        self.emit_chunk_rows = Some(rows);
+        self
+    }
+
+    /// Append a non null `UInt64` column named `name` to the output of
+    /// [`Self::emit`], holding the 1-based position of each row in the top
+    /// k numbered according to `policy`.
+    ///
+    /// This avoids evaluating a window function such as `ROW_NUMBER() OVER
+    /// (ORDER BY ..)` over the top k. Rows are tied if their sort keys
+    /// compare equal, using the comparator set by [`Self::with_comparator`]
+    /// if any.
+    pub fn with_rank_column(
+        mut self,
+        name: impl Into<String>,
+        policy: TopKRankPolicy,
+    ) -> Self {
+        let field = Arc::new(Field::new(name, DataType::UInt64, false));
+        self.rank_column = Some((field, policy));
+        self
+    }
+
+    /// Append a non null `Binary` column `name` to the output of
+    /// [`Self::emit`], holding the sort key of each row encoded in the
+    /// [arrow::row] format, after the rank column if any.
+    ///
+    /// The encoded sort keys compare byte-wise in the order of the output,
+    /// unless a comparator is set by [`Self::with_comparator`], and can be
+    /// decoded with a [`RowConverter`] for the sort expressions.
+    pub fn with_sort_key_column(mut self, name: impl Into<String>) -> Self {
+        self.sort_key_column = Some(Arc::new(Field::new(name, DataType::Binary, false)));
+        self
+    }
+
+    /// Evaluate `exprs` against the top k rows in [`Self::emit`], and output
+    /// their results named by the given names instead of the input columns.
+    ///
+    /// This avoids a separate [`ProjectionExec`] pass over the top k. The
+    /// rank and sort key columns, if set, are appended after the projected
+    /// columns. Returns an error if `exprs` can not be evaluated against the
+    /// input schema.
+    ///
+    /// [`ProjectionExec`]: crate::projection::ProjectionExec
+    pub fn with_output_projection(
+        mut self,
+        exprs: Vec<(Arc<dyn PhysicalExpr>, String)>,
+    ) -> Result<Self> {
+        self.output_projection =
+            Some(TopKOutputProjection::try_new(exprs, &self.schema)?);
+        Ok(self)
+    }
+
+    /// Select the rows of batches with at least `num_rows` rows that may
+    /// enter the top k in parallel, before adding them to the heap.
+    ///
+    /// The rows of such a batch are split into chunks, one per thread of the
+    /// rayon thread pool. Each chunk discards the rows that can not enter
+    /// the top k because at least k earlier rows of the same chunk (or the
+    /// current top k) sort before or equal to them. The remaining rows are
+    /// added as usual, so the result is identical to the serial path.
+    #[cfg(feature = "parallel_topk")]
+    pub fn with_parallel_threshold(mut self, num_rows: usize) -> Self {
+        self.parallel_threshold = Some(num_rows);
+        self
+    }
+
+    /// Declare that the input is sorted by `input_ordering`.
+    ///
+    /// If `input_ordering` shares a prefix with the sort expressions,
+    /// [`Self::insert_batch`] reports when the top k is complete: once the
+    /// heap is full and a batch ends with a prefix value greater than the
+    /// current k-th row, no later row can enter the top k.
+    pub fn with_input_ordering(mut self, input_ordering: LexOrdering) -> Result<Self> {
+        self.input_ordering = Some(input_ordering);
+        self.update_common_prefix()?;
+        Ok(self)
+    }
+
+    /// Recomputes the sort prefix shared by `expr` and `input_ordering`
+    fn update_common_prefix(&mut self) -> Result<()> {
+        let Some(input_ordering) = &self.input_ordering else {
+            return Ok(());
+        };
+        if self.heap.comparator.is_some()
+            || matches!(self.nan_policy, TopKNanPolicy::First | TopKNanPolicy::Last)
+            || self.has_struct_field_options()
+        {
+            return Ok(());
+        }
+        self.common_prefix_len = self
+            .expr
+            .iter()
+            .zip(input_ordering.iter())
+            .take_while(|(expr, input)| expr == input)
+            .count();
+        self.common_prefix_converter = if self.common_prefix_len > 0 {
+            let sort_fields =
+                build_sort_fields(&self.expr[..self.common_prefix_len], &self.schema)?;
+            Some(RowConverter::new(sort_fields)?)
+        } else {
+            None
+        };
+        Ok(())
+    }
+
+    /// Retain the values of the rows in the top k using `storage`, see
+    /// [`TopKStorage`]. Must be called before any batch is inserted.
+    pub fn with_storage(mut self, storage: TopKStorage) -> Self {
+        self.heap.storage = storage;
+        self
+    }
+
+    /// Allocate the space for the rows in the top k according to
+    /// `growth`, see [`TopKHeapGrowth`]. Must be called before any batch
+    /// is inserted.
+    pub fn with_heap_growth(mut self, growth: TopKHeapGrowth) -> Self {
+        self.heap.set_growth(growth);
+        self
+    }
+
+    /// Never allocate the space for all k rows up front if k is greater
+    /// than `limit`, even with [`TopKHeapGrowth::Eager`], but grow it as
+    /// rows enter the top k instead. This keeps a huge k from allocating
+    /// memory before any batch is inserted.
+    ///
+    /// Defaults to no limit. Must be called before any batch is inserted.
+    pub fn with_eager_allocation_limit(mut self, limit: usize) -> Self {
+        self.heap.set_eager_allocation_limit(limit);
+        self
+    }
+
+    /// Also compact the retained batches whenever their memory is at least
+    /// `threshold` times the memory attributable to the rows still in the
+    /// top k.
+    ///
+    /// By default compaction is only triggered by the number of unused
+    /// rows. `threshold` should be greater than `1.0`, otherwise the
+    /// batches are compacted on every call to [`Self::insert_batch`].
+    pub fn with_compaction_ratio_threshold(mut self, threshold: f64) -> Self {
+        self.heap.compaction_ratio_threshold = Some(threshold);
+        self
+    }
+
+    /// Also compact the retained batches whenever there are more than
+    /// `max` of them, into a single batch holding only the rows in the
+    /// top k.
+    ///
+    /// Each row in the top k can pin a different input batch, so a few
+    /// rows from large batches can retain much more memory than they use.
+    /// `max` should be at least `1`, otherwise the batches are compacted
+    /// on every call to [`Self::insert_batch`].
+    pub fn with_max_stored_batches(mut self, max: usize) -> Self {
+        self.heap.max_stored_batches = Some(max);
+        self
+    }
+
+    /// Call `callback` once, when the heap first holds `k` rows (see
+    /// [`Self::is_saturated`]), for example to start pushing down a filter
+    /// on the k-th value.
+    pub fn with_on_saturated(mut self, callback: TopKSaturatedCallback) -> Self {
+        self.on_saturated = Some(callback);
+        self
+    }
+
+    /// Returns true if the heap holds `k` rows, so that new rows must beat
+    /// the current k-th row (see [`Self::kth_value`]) to enter the top k
+    pub fn is_saturated(&self) -> bool {
+        self.heap.inner.is_full()
+    }
+
+    /// Returns the number of input rows examined so far, as counted by the
+    /// `input_rows` metric, and the number of rows currently in the top k,
+    /// for example to report the progress of a long running query without
+    /// emitting the top k.
+    ///
+    /// The rows in the top k grow up to `k` and then stay there, even as
+    /// more rows are examined.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.metrics.input_rows.value(), self.heap.inner.len())
+    }
+
+    /// Calls the callback set by [`Self::with_on_saturated`] if the heap
+    /// has become saturated
+    fn notify_saturated(&mut self) {
+        if self.is_saturated() {
+            if let Some(on_saturated) = self.on_saturated.take() {
+                on_saturated();
+            }
+        }
+    }
+
+    /// Discard all rows retained so far so this [`TopK`] can be reused
+    /// for new input sorted by `expr`.
+    ///
+    /// The cached [`RowConverter`] is kept, so `expr` must produce the
+    /// same sort fields (data types and [`SortOptions`]) as the
+    /// expressions this [`TopK`] was created with. Otherwise the
+    /// converter would silently produce rows in the wrong order, so an
+    /// error is returned instead.
+    pub fn reset(&mut self, expr: LexOrdering) -> Result<()> {
+        let expr: Arc<[PhysicalSortExpr]> = expr.inner.into();
+
+        let current_fields = self.sort_fields(&self.expr)?;
+        let new_fields = self.sort_fields(&expr)?;
+        if current_fields != new_fields {
+            return internal_err!(
+                "Cannot reset TopK with sort fields {new_fields:?}: \
+                 the existing row converter was created for {current_fields:?}"
+            );
+        }
+
+        self.expr = expr;
+        self.scratch_rows.clear();
+        let compaction_ratio_threshold = self.heap.compaction_ratio_threshold;
+        let max_stored_batches = self.heap.max_stored_batches;
+        let comparator = self.heap.comparator.take();
+        let storage = self.heap.storage;
+        let growth = self.heap.growth;
+        let eager_allocation_limit = self.heap.eager_allocation_limit;
+        self.heap.store.clear();
+        let evicted = self.heap.store.take_evicted();
+        self.heap = TopKHeap::new(
+            self.heap.inner.k(),
+            self.batch_size,
+            Arc::clone(&self.schema),
+        );
+        self.heap.eager_allocation_limit = eager_allocation_limit;
+        self.heap.compaction_ratio_threshold = compaction_ratio_threshold;
+        self.heap.max_stored_batches = max_stored_batches;
+        self.heap.comparator = comparator;
+        self.heap.storage = storage;
+        self.heap.set_growth(growth);
+        self.metrics.batches_evicted.add(evicted);
+        self.update_heap_metrics();
+        self.finished = false;
+        self.update_common_prefix()?;
+        self.reservation.try_resize(self.size())?;
+        Ok(())
+    }
+
     /// Insert `batch`, remembering if any of its values are among
     /// the top k seen so far.
-    pub fn insert_batch(&mut self, batch: RecordBatch) -> Result<()> {
+    ///
+    /// Returns `true` once the input ordering (see
+    /// [`Self::with_input_ordering`]) guarantees that no subsequent batch
+    /// can change the top k, in which case the caller can stop reading its
+    /// input. Batches inserted after that are ignored.
+    pub fn insert_batch(&mut self, batch: RecordBatch) -> Result<bool> {
+        if self.finished {
+            return Ok(true);
+        }
+
         // Updates on drop
-        let _timer = self.metrics.baseline.elapsed_compute().timer();
+        let elapsed_compute = self.metrics.baseline.elapsed_compute().clone();
+        let _timer = elapsed_compute.timer();
+        self.metrics.input_rows.add(batch.num_rows());
 
         let sort_keys: Vec<ArrayRef> = self
             .expr
@@ -154,132 +734,940 @@ impl TopK {
                 value.into_array(batch.num_rows())
             })
             .collect::<Result<Vec<_>>>()?;
+        let (batch, sort_keys) = self.apply_nan_policy(batch, sort_keys)?;
+        let sort_keys = self.expand_sort_keys(sort_keys)?;
+
+        // with a single primitive sort key, only the rows that can enter
+        // the heap are converted, see `primitive_candidates`
+        let primitive_candidates = self.primitive_candidates(&sort_keys)?;
 
         // reuse existing `Rows` to avoid reallocations
         let rows = &mut self.scratch_rows;
         rows.clear();
-        self.row_converter.append(rows, &sort_keys)?;
+        match &primitive_candidates {
+            Some(candidates) => {
+                self.metrics
+                    .skipped_rows
+                    .add(batch.num_rows() - candidates.len());
+                let indices = UInt32Array::from_iter_values(
+                    candidates.iter().map(|&idx| idx as u32),
+                );
+                let candidate_keys = take_arrays(&sort_keys, &indices, None)?;
+                self.row_converter.append(rows, &candidate_keys)?;
+            }
+            None => self.row_converter.append(rows, &sort_keys)?,
+        }
 
         // TODO make this algorithmically better?:
         // Idea: filter out rows >= self.heap.max() early (before passing to `RowConverter`)
         //       this avoids some work and also might be better vectorizable.
+        let rejected = self.heap.rejects_all(rows);
+        if rejected {
+            self.metrics.skipped_rows.add(rows.num_rows());
+        }
+        #[cfg(feature = "parallel_topk")]
+        let candidates = match self.parallel_threshold {
+            _ if rejected => Some(vec![]),
+            Some(threshold) if rows.num_rows() >= threshold => {
+                Some(self.heap.parallel_candidates(rows))
+            }
+            _ => None,
+        };
+        #[cfg(not(feature = "parallel_topk"))]
+        let candidates: Option<Vec<usize>> = rejected.then(Vec::new);
+        let indices: Box<dyn Iterator<Item = usize>> = match candidates {
+            Some(candidates) => Box::new(candidates.into_iter()),
+            None => Box::new(0..rows.num_rows()),
+        };
+
         let mut batch_entry = self.heap.register_batch(batch);
-        for (index, row) in rows.iter().enumerate() {
+        for index in indices {
+            let row = rows.row(index);
             match self.heap.max() {
                 // heap has k items, and the new row is greater than the
                 // current max in the heap ==> it is not a new topk
-                Some(max_row) if row.as_ref() >= max_row.row() => {}
+                Some(max_row)
+                    if self.heap.compare(row.as_ref(), max_row.row()).is_ge() => {}
                 // don't yet have k items or new item is lower than the currently k low values
                 None | Some(_) => {
-                    self.heap.add(&mut batch_entry, row, index);
+                    let index = match &primitive_candidates {
+                        Some(candidates) => candidates[index],
+                        None => index,
+                    };
+                    self.heap.add(&mut batch_entry, row, index)?;
                     self.metrics.row_replacements.add(1);
                 }
             }
         }
         self.heap.insert_batch_entry(batch_entry);
+        self.notify_saturated();
 
         // conserve memory
         self.heap.maybe_compact()?;
+        self.maybe_shrink_scratch_rows();
+        self.update_heap_metrics();
+
+        // update memory reservation
+        self.reservation.try_resize(self.size())?;
+
+        self.finished = self.common_prefix_exceeds_kth_row(&sort_keys)?;
+        Ok(self.finished)
+    }
+
+    /// Insert `batch` like [`Self::insert_batch`], but return `true` if
+    /// any of its rows entered the top k, for example to react to a new
+    /// k-th value.
+    ///
+    /// Returns `false` if no row could beat the current top k, including
+    /// once the top k is known to be complete.
+    pub fn insert_batch_changed(&mut self, batch: RecordBatch) -> Result<bool> {
+        let row_replacements = self.metrics.row_replacements.value();
+        self.insert_batch(batch)?;
+        Ok(self.metrics.row_replacements.value() > row_replacements)
+    }
+
+    /// Returns the indices of the rows that can enter the heap, in
+    /// ascending order, if the sort key is a single primitive column.
+    ///
+    /// These are the first k rows of the batch in sort order among those
+    /// that sort before the current max of the heap, if it is full. The
+    /// native values are compared directly, which is much cheaper than
+    /// converting all rows to the row format, as usually only few rows of
+    /// a large input can enter the heap.
+    fn primitive_candidates(&self, sort_keys: &[ArrayRef]) -> Result<Option<Vec<usize>>> {
+        let [keys] = sort_keys else {
+            return Ok(None);
+        };
+        if self.heap.comparator.is_some() || !is_native_sort_type(keys.data_type()) {
+            return Ok(None);
+        }
+
+        // decode the sort key of the max row back into an array
+        let max = match self.heap.max() {
+            Some(max_row) => {
+                let parser = self.row_converter.parser();
+                let mut max = self
+                    .row_converter
+                    .convert_rows([parser.parse(max_row.row())])?;
+                max.pop()
+            }
+            None => None,
+        };
+        let options = self.expr[0].options;
+        let k = self.heap.inner.k();
+        let candidates = downcast_primitive_array!(
+            keys => primitive_candidates(
+                keys,
+                max.as_ref().map(|max| max.as_primitive()),
+                options,
+                k,
+            ),
+            dt => return internal_err!("Unexpected primitive sort key type {dt}")
+        );
+        Ok(Some(candidates))
+    }
+
+    /// Updates the metrics of the rows and batches retained by the heap
+    fn update_heap_metrics(&mut self) {
+        self.metrics.heap_owned_bytes.set(self.heap.owned_bytes);
+        self.metrics.batches_pinned.set(self.heap.store.len());
+        self.metrics
+            .batches_evicted
+            .add(self.heap.store.take_evicted());
+    }
+
+    /// Reallocates `scratch_rows` if the last batches used much less of it
+    /// than its peak size, for example after a batch with unusually wide
+    /// sort keys, so the peak is not retained for the lifetime of the
+    /// operator
+    fn maybe_shrink_scratch_rows(&mut self) {
+        let used = self.scratch_rows.size();
+        self.scratch_rows_peak_size = self.scratch_rows_peak_size.max(used);
+        if used * SCRATCH_ROWS_SHRINK_RATIO > self.scratch_rows_peak_size {
+            self.narrow_batches = 0;
+            return;
+        }
+
+        self.narrow_batches += 1;
+        let initial_size = initial_scratch_rows_size(self.batch_size);
+        if self.narrow_batches >= SCRATCH_ROWS_SHRINK_BATCHES
+            && self.scratch_rows_peak_size > initial_size
+        {
+            self.scratch_rows = new_scratch_rows(&self.row_converter, self.batch_size);
+            self.scratch_rows_peak_size = initial_size;
+            self.narrow_batches = 0;
+        }
+    }
+
+    /// Merge the rows retained by `other`, for example the partial top k of
+    /// another partition, into this [`TopK`], keeping the overall top k.
+    ///
+    /// The rows of `other` are moved into a single batch, so no batch of
+    /// `other` is retained for rows that do not enter this top k. `other`
+    /// must have the same schema and sort fields (data types and
+    /// [`SortOptions`]) as this [`TopK`], otherwise an error is returned.
+    pub fn merge(&mut self, mut other: TopK) -> Result<()> {
+        let fields = self.sort_fields(&self.expr)?;
+        let other_fields = other.sort_fields(&other.expr)?;
+        if self.schema != other.schema || fields != other_fields {
+            return internal_err!(
+                "Cannot merge TopK with sort fields {other_fields:?} \
+                 into TopK with sort fields {fields:?}"
+            );
+        }
+
+        // Updates on drop
+        let elapsed_compute = self.metrics.baseline.elapsed_compute().clone();
+        let _timer = elapsed_compute.timer();
+
+        // the rows of `other` in sorted order, with the i-th row at
+        // index i of `other_batch`
+        let (other_batch, other_rows) = other.heap.emit_with_state()?;
+        let mut batch_entry = self.heap.register_batch(other_batch);
+        for (index, row) in other_rows.iter().enumerate() {
+            if let Some(max_row) = self.heap.max() {
+                // as `other_rows` are sorted, no later row can enter either
+                if self.heap.compare(row.row(), max_row.row()).is_ge() {
+                    break;
+                }
+            }
+            self.heap.add(&mut batch_entry, row.row(), index)?;
+            self.metrics.row_replacements.add(1);
+        }
+        self.heap.insert_batch_entry(batch_entry);
+        self.notify_saturated();
+
+        // conserve memory
+        self.heap.maybe_compact()?;
+        self.update_heap_metrics();
 
         // update memory reservation
         self.reservation.try_resize(self.size())?;
         Ok(())
     }
 
-    /// Returns the top k results broken into `batch_size` [`RecordBatch`]es, consuming the heap
+    /// Applies the [`TopKNanPolicy`] to the `sort_keys` of `batch`, removing
+    /// the rows with NaN sort keys or moving NaN to the requested end
+    fn apply_nan_policy(
+        &self,
+        batch: RecordBatch,
+        sort_keys: Vec<ArrayRef>,
+    ) -> Result<(RecordBatch, Vec<ArrayRef>)> {
+        match self.nan_policy {
+            TopKNanPolicy::RowFormat => Ok((batch, sort_keys)),
+            TopKNanPolicy::Exclude => {
+                let nans = sort_keys
+                    .iter()
+                    .filter_map(nan_mask)
+                    .reduce(|a, b| &a | &b)
+                    .filter(|nans| nans.count_set_bits() > 0);
+                let Some(nans) = nans else {
+                    return Ok((batch, sort_keys));
+                };
+                let keep = BooleanArray::new(!&nans, None);
+                let batch = filter_record_batch(&batch, &keep)?;
+                let sort_keys = sort_keys
+                    .iter()
+                    .map(|keys| filter(keys, &keep))
+                    .collect::<Result<_, _>>()?;
+                Ok((batch, sort_keys))
+            }
+            TopKNanPolicy::First | TopKNanPolicy::Last => {
+                let sort_keys = sort_keys
+                    .into_iter()
+                    .zip(self.expr.iter())
+                    .map(|(keys, expr)| {
+                        // the row format orders positive NaN after all values
+                        let last = self.nan_policy == TopKNanPolicy::Last;
+                        set_nan_sign(keys, last != expr.options.descending)
+                    })
+                    .collect();
+                Ok((batch, sort_keys))
+            }
+        }
+    }
+
+    /// Returns true if the heap is full and the sorted prefix of the last
+    /// row in `sort_keys` is greater than the prefix of the current k-th
+    /// row. As the input is sorted on that prefix, all later rows compare
+    /// greater than the k-th row too.
+    fn common_prefix_exceeds_kth_row(&self, sort_keys: &[ArrayRef]) -> Result<bool> {
+        let Some(prefix_converter) = &self.common_prefix_converter else {
+            return Ok(false);
+        };
+        let Some(max_row) = self.heap.max() else {
+            return Ok(false);
+        };
+        let num_rows = sort_keys[0].len();
+        if num_rows == 0 {
+            return Ok(false);
+        }
+
+        let parser = self.row_converter.parser();
+        let kth_keys = self
+            .row_converter
+            .convert_rows([parser.parse(max_row.row())])?;
+        let kth_prefix =
+            prefix_converter.convert_columns(&kth_keys[..self.common_prefix_len])?;
+
+        let last_keys: Vec<ArrayRef> = sort_keys[..self.common_prefix_len]
+            .iter()
+            .map(|keys| keys.slice(num_rows - 1, 1))
+            .collect();
+        let last_prefix = prefix_converter.convert_columns(&last_keys)?;
+
+        Ok(last_prefix.row(0) > kth_prefix.row(0))
+    }
+
+    /// Returns the value of the primary (first) sort expression for the
+    /// current k-th row, the boundary that new rows must beat to enter the
+    /// top k.
+    ///
+    /// Returns `None` if fewer than k rows have been inserted so far.
+    pub fn kth_value(&self) -> Result<Option<ScalarValue>> {
+        let Some(max_row) = self.heap.max() else {
+            return Ok(None);
+        };
+
+        // decode the sort key of the boundary row back into arrays
+        let parser = self.row_converter.parser();
+        let sort_keys = self
+            .row_converter
+            .convert_rows([parser.parse(max_row.row())])?;
+        let sort_keys = self.collapse_sort_keys(sort_keys)?;
+        ScalarValue::try_from_array(&sort_keys[0], 0).map(Some)
+    }
+
+    /// Returns the top k results broken into `batch_size` (or
+    /// `output_batch_size`, if set) [`RecordBatch`]es, consuming the heap.
+    ///
+    /// The rows of each batch are gathered when the stream is polled for
+    /// it, so the memory used for the output is bounded by the batch size
+    /// rather than by k.
     pub fn emit(self) -> Result<SendableRecordBatchStream> {
         let Self {
-            schema,
+            schema: _,
             metrics,
             reservation: _,
             batch_size,
+            output_batch_size,
+            emit_chunk_rows,
             expr: _,
+            struct_field_options: _,
             row_converter: _,
             scratch_rows: _,
+            scratch_rows_peak_size: _,
+            narrow_batches: _,
             mut heap,
+            input_ordering: _,
+            common_prefix_converter: _,
+            common_prefix_len: _,
+            finished: _,
+            nan_policy: _,
+            rank_column,
+            sort_key_column,
+            on_saturated: _,
+            output_projection,
+            #[cfg(feature = "parallel_topk")]
+                parallel_threshold: _,
         } = self;
-        let _timer = metrics.baseline.elapsed_compute().timer(); // time updated on drop
-
-        // break into record batches as needed
-        let mut batches = vec![];
-        if let Some(mut batch) = heap.emit()? {
-            metrics.baseline.output_rows().add(batch.num_rows());
-
-            loop {
-                if batch.num_rows() <= batch_size {
-                    batches.push(Ok(batch));
-                    break;
-                } else {
-                    batches.push(Ok(batch.slice(0, batch_size)));
-                    let remaining_length = batch.num_rows() - batch_size;
-                    batch = batch.slice(batch_size, remaining_length);
-                }
+        let batch_size = output_batch_size
+            .unwrap_or(batch_size)
+            .min(emit_chunk_rows.unwrap_or(usize::MAX));
+        let timer = metrics.baseline.elapsed_compute().timer();
+        let topk_rows = heap.take_sorted_rows();
+        // ranks depend on the previous rows, so are computed for all rows
+        let ranks = rank_column
+            .as_ref()
+            .map(|(_, policy)| heap.ranks(&topk_rows, *policy));
+
+        // the stored values of `topk_rows[offset..offset + num_rows]`, with
+        // the projection and the rank and sort key columns applied
+        let finish = move |heap: &TopKHeap,
+                           topk_rows: &[TopKRow],
+                           offset: usize,
+                           num_rows: usize|
+              -> Result<RecordBatch> {
+            let rows = &topk_rows[offset..offset + num_rows];
+            let batch = heap.stored_rows(rows)?;
+            let batch = match &output_projection {
+                Some(projection) => projection.project(&batch)?,
+                None => batch,
+            };
+            if rank_column.is_none() && sort_key_column.is_none() {
+                return Ok(batch);
             }
+            let schema = batch.schema();
+            let mut fields = schema.fields().to_vec();
+            let mut columns = batch.columns().to_vec();
+            if let (Some((field, _)), Some(ranks)) = (&rank_column, &ranks) {
+                fields.push(Arc::clone(field));
+                columns.push(Arc::new(ranks.slice(offset, num_rows)));
+            }
+            if let Some(field) = &sort_key_column {
+                fields.push(Arc::clone(field));
+                columns.push(Arc::new(BinaryArray::from_iter_values(
+                    rows.iter().map(|row| row.row()),
+                )));
+            }
+            let schema =
+                Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+            Ok(RecordBatch::try_new(schema, columns)?)
         };
+        let schema = finish(&heap, &topk_rows, 0, 0)?.schema();
+
+        let num_topk_rows = topk_rows.len();
+        timer.done();
+
+        // the values of each batch are only gathered when it is polled, so
+        // at most one output batch is built at a time, however large k is
+        let batches = (0..num_topk_rows).step_by(batch_size).map(move |offset| {
+            let num_rows = batch_size.min(num_topk_rows - offset);
+            let _timer = metrics.baseline.elapsed_compute().timer(); // time updated on drop
+            let batch = finish(&heap, &topk_rows, offset, num_rows)?;
+            metrics.baseline.output_rows().add(batch.num_rows());
+            Ok(batch)
+        });
         Ok(Box::pin(RecordBatchStreamAdapter::new(
             schema,
             futures::stream::iter(batches),
         )))
     }
 
+    /// Returns the sort expressions of this [`TopK`], which order the
+    /// output of [`Self::into_sorted_stream`]
+    pub fn ordering(&self) -> LexOrdering {
+        LexOrdering::new(self.expr.to_vec())
+    }
+
+    /// Returns the top k results like [`Self::emit`], as a stream sorted by
+    /// [`Self::ordering`].
+    ///
+    /// The outputs of several [`TopK`]s with the same ordering can thus be
+    /// the sorted runs of a [`StreamingMergeBuilder`] that finds their
+    /// overall top k, without sorting them again.
+    ///
+    /// Returns an error if the rows are ordered differently than the sort
+    /// expressions would order them, i.e. with a custom comparator, a
+    /// [`TopKNanPolicy`] of `First` or `Last` or struct field sort options,
+    /// or if the output is projected by [`Self::with_output_projection`].
+    ///
+    /// [`StreamingMergeBuilder`]: crate::sorts::streaming_merge::StreamingMergeBuilder
+    pub fn into_sorted_stream(self) -> Result<SendableRecordBatchStream> {
+        if self.heap.comparator.is_some() {
+            return not_impl_err!(
+                "TopK with a custom comparator can not be merged by its sort expressions"
+            );
+        }
+        if matches!(self.nan_policy, TopKNanPolicy::First | TopKNanPolicy::Last) {
+            return not_impl_err!(
+                "TopK with NaN policy {:?} can not be merged by its sort expressions",
+                self.nan_policy
+            );
+        }
+        if self.has_struct_field_options() {
+            return not_impl_err!(
+                "TopK with struct field sort options can not be merged by its sort expressions"
+            );
+        }
+        if self.output_projection.is_some() {
+            return not_impl_err!(
+                "TopK with an output projection can not be merged by its sort expressions"
+            );
+        }
+        self.emit()
+    }
+
     /// return the size of memory used by this operator, in bytes
     fn size(&self) -> usize {
         size_of::<Self>()
             + self.row_converter.size()
-            + self.scratch_rows.size()
+            + self.scratch_rows_peak_size
             + self.heap.size()
+            + self
+                .common_prefix_converter
+                .as_ref()
+                .map_or(0, |converter| converter.size())
     }
 }
 
-struct TopKMetrics {
-    /// metrics
-    pub baseline: BaselineMetrics,
-
-    /// count of how many rows were replaced in the heap
-    pub row_replacements: Count,
+/// The projection of the output of a [`TopK`], see
+/// [`TopK::with_output_projection`]
+struct TopKOutputProjection {
+    /// The expressions to evaluate against the top k rows
+    exprs: Vec<Arc<dyn PhysicalExpr>>,
+    /// The schema of the projected rows
+    schema: SchemaRef,
 }
 
-impl TopKMetrics {
-    fn new(metrics: &ExecutionPlanMetricsSet, partition: usize) -> Self {
-        Self {
-            baseline: BaselineMetrics::new(metrics, partition),
-            row_replacements: MetricBuilder::new(metrics)
-                .counter("row_replacements", partition),
-        }
+impl TopKOutputProjection {
+    fn try_new(
+        exprs: Vec<(Arc<dyn PhysicalExpr>, String)>,
+        input_schema: &Schema,
+    ) -> Result<Self> {
+        let fields = exprs
+            .iter()
+            .map(|(expr, name)| {
+                let field = Field::new(
+                    name,
+                    expr.data_type(input_schema)?,
+                    expr.nullable(input_schema)?,
+                );
+                Ok(field.with_metadata(
+                    get_field_metadata(expr, input_schema).unwrap_or_default(),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let schema = Arc::new(Schema::new_with_metadata(
+            fields,
+            input_schema.metadata().clone(),
+        ));
+        let exprs = exprs.into_iter().map(|(expr, _)| expr).collect();
+        Ok(Self { exprs, schema })
+    }
+
+    fn project(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let arrays = self
+            .exprs
+            .iter()
+            .map(|expr| {
+                expr.evaluate(batch)
+                    .and_then(|v| v.into_array(batch.num_rows()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let options = RecordBatchOptions::new().with_row_count(Some(batch.num_rows()));
+        Ok(RecordBatch::try_new_with_options(
+            Arc::clone(&self.schema),
+            arrays,
+            &options,
+        )?)
     }
 }
 
-/// This structure keeps at most the *smallest* k items, using the
-/// [arrow::row] format for sort keys. While it is called "topK" for
-/// values like `1, 2, 3, 4, 5` the "top 3" really means the
-/// *smallest* 3 , `1, 2, 3`, not the *largest* 3 `3, 4, 5`.
+/// Returns true if the row format orders values of `data_type` like
+/// [`ArrowNativeTypeOp::compare`] orders their native values
+fn is_native_sort_type(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float16
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Date32
+            | DataType::Date64
+            | DataType::Time32(_)
+            | DataType::Time64(_)
+            | DataType::Timestamp(_, _)
+            | DataType::Duration(_)
+            | DataType::Decimal128(_, _)
+            | DataType::Decimal256(_, _)
+    )
+}
+
+/// Returns the indices of the first `k` rows of `keys` in the order of
+/// `options` that sort before the single value of `max`, if any, in
+/// ascending order.
 ///
-/// Using the `Row` format handles things such as ascending vs
-/// descending and nulls first vs nulls last.
-struct TopKHeap {
-    /// The maximum number of elements to store in this heap.
+/// Rows after the first `k` can not enter a heap of `k` rows, as rows
+/// that sort equal do not replace the rows inserted before them.
+fn primitive_candidates<T: ArrowPrimitiveType>(
+    keys: &PrimitiveArray<T>,
+    max: Option<&PrimitiveArray<T>>,
+    options: SortOptions,
     k: usize,
-    /// The target number of rows for output batches
-    batch_size: usize,
-    /// Storage for up at most `k` items using a BinaryHeap. Reversed
-    /// so that the smallest k so far is on the top
-    inner: BinaryHeap<TopKRow>,
-    /// Storage the original row values (TopKRow only has the sort key)
-    store: RecordBatchStore,
-    /// The size of all owned data held by this heap
-    owned_bytes: usize,
+) -> Vec<usize> {
+    let value = |array: &PrimitiveArray<T>, idx: usize| {
+        array.is_valid(idx).then(|| array.value(idx))
+    };
+    let compare = |lhs: Option<T::Native>, rhs: Option<T::Native>| match (lhs, rhs) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) if options.nulls_first => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) if options.nulls_first => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(lhs), Some(rhs)) if options.descending => lhs.compare(rhs).reverse(),
+        (Some(lhs), Some(rhs)) => lhs.compare(rhs),
+    };
+
+    let mut candidates: Vec<usize> = match max {
+        Some(max) => {
+            let max = value(max, 0);
+            (0..keys.len())
+                .filter(|&idx| compare(value(keys, idx), max).is_lt())
+                .collect()
+        }
+        None => (0..keys.len()).collect(),
+    };
+    if candidates.len() > k {
+        candidates.select_nth_unstable_by(k - 1, |&lhs, &rhs| {
+            compare(value(keys, lhs), value(keys, rhs)).then(lhs.cmp(&rhs))
+        });
+        candidates.truncate(k);
+        candidates.sort_unstable();
+    }
+    candidates
 }
 
-impl TopKHeap {
-    fn new(k: usize, batch_size: usize, schema: SchemaRef) -> Self {
+/// Creates the scratch space used by [`TopK`] to convert the sort keys of
+/// batches of `batch_size` rows
+fn new_scratch_rows(row_converter: &RowConverter, batch_size: usize) -> Rows {
+    row_converter.empty_rows(
+        batch_size,
+        20 * batch_size, // guesstimate 20 bytes per row
+    )
+}
+
+/// Returns the size of [`new_scratch_rows`] for `batch_size`, in bytes,
+/// computed like [`Rows::size`]
+fn initial_scratch_rows_size(batch_size: usize) -> usize {
+    size_of::<Rows>() + 20 * batch_size + (batch_size + 1) * size_of::<usize>()
+}
+
+/// Builds the [`SortField`]s used to create the [`RowConverter`] for `expr`
+fn build_sort_fields(
+    expr: &[PhysicalSortExpr],
+    schema: &SchemaRef,
+) -> Result<Vec<SortField>> {
+    expr.iter()
+        .map(|e| {
+            let data_type = e.expr.data_type(schema)?;
+            if !is_orderable(&data_type) {
+                return not_impl_err!(
+                    "TopK can not sort by {}: values of type {data_type} are not orderable",
+                    e.expr
+                );
+            }
+            Ok(SortField::new_with_options(data_type, e.options))
+        })
+        .collect()
+}
+
+/// Returns true if values of `data_type` can be compared in the
+/// [arrow::row] format, which would otherwise only be reported by
+/// [`RowConverter::new`] without naming the sort expression
+fn is_orderable(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Dictionary(_, values) => is_orderable(values),
+        DataType::List(field) | DataType::LargeList(field) => {
+            is_orderable(field.data_type())
+        }
+        DataType::Struct(fields) => {
+            fields.iter().all(|field| is_orderable(field.data_type()))
+        }
+        _ => !data_type.is_nested(),
+    }
+}
+
+/// Returns the non null rows of the float `array` that are NaN, or `None`
+/// if `array` is not a float array
+fn nan_mask(array: &ArrayRef) -> Option<BooleanBuffer> {
+    fn mask<T: ArrowPrimitiveType>(
+        array: &ArrayRef,
+        is_nan: impl Fn(T::Native) -> bool,
+    ) -> BooleanBuffer {
+        let array = array.as_primitive::<T>();
+        let nans = BooleanBuffer::collect_bool(array.len(), |i| is_nan(array.value(i)));
+        match array.nulls() {
+            Some(nulls) => &nans & nulls.inner(),
+            None => nans,
+        }
+    }
+
+    match array.data_type() {
+        DataType::Float16 => Some(mask::<Float16Type>(array, |v| v.is_nan())),
+        DataType::Float32 => Some(mask::<Float32Type>(array, |v| v.is_nan())),
+        DataType::Float64 => Some(mask::<Float64Type>(array, |v| v.is_nan())),
+        _ => None,
+    }
+}
+
+/// Replaces all NaN in the float `array` by NaN with a positive sign if
+/// `positive`, or with a negative sign otherwise
+fn set_nan_sign(array: ArrayRef, positive: bool) -> ArrayRef {
+    fn set_sign<T: ArrowPrimitiveType>(
+        array: ArrayRef,
+        is_nan: impl Fn(T::Native) -> bool,
+        nan: T::Native,
+    ) -> ArrayRef {
+        let values = array.as_primitive::<T>();
+        if !values.values().iter().any(|v| is_nan(*v)) {
+            return array;
+        }
+        Arc::new(values.unary::<_, T>(|v| if is_nan(v) { nan } else { v }))
+    }
+
+    match array.data_type() {
+        DataType::Float16 => {
+            let nan = if positive { f16::NAN } else { -f16::NAN };
+            set_sign::<Float16Type>(array, |v| v.is_nan(), nan)
+        }
+        DataType::Float32 => {
+            let nan = if positive { f32::NAN } else { -f32::NAN };
+            set_sign::<Float32Type>(array, |v| v.is_nan(), nan)
+        }
+        DataType::Float64 => {
+            let nan = if positive { f64::NAN } else { -f64::NAN };
+            set_sign::<Float64Type>(array, |v| v.is_nan(), nan)
+        }
+        _ => array,
+    }
+}
+
+/// Returns the `(best, worst)` values of `array` when sorted with `options`:
+/// the values that would be placed first and last in the sorted output.
+///
+/// Nulls are represented by a null [`ScalarValue`] of the array's type, so
+/// for an array with nulls either the best (`nulls_first`) or worst bound is
+/// null, and both are null for an all-null array. Returns `None` for an empty
+/// array or a type not supported by arrow's min/max kernels.
+///
+/// This can be used to check if any value of a batch can enter the top k
+/// without converting it to the row format.
+pub fn sort_key_bounds(
+    array: &ArrayRef,
+    options: SortOptions,
+) -> Option<(ScalarValue, ScalarValue)> {
+    if array.is_empty() {
+        return None;
+    }
+    let null = ScalarValue::try_from(array.data_type()).ok()?;
+    if array.null_count() == array.len() {
+        return Some((null.clone(), null));
+    }
+
+    let (min, max) = min_max(array)?;
+    let (best, worst) = if options.descending {
+        (max, min)
+    } else {
+        (min, max)
+    };
+    match (array.null_count() > 0, options.nulls_first) {
+        (true, true) => Some((null, worst)),
+        (true, false) => Some((best, null)),
+        (false, _) => Some((best, worst)),
+    }
+}
+
+/// Returns the minimum and maximum non-null value of `array`
+fn min_max(array: &ArrayRef) -> Option<(ScalarValue, ScalarValue)> {
+    fn primitive_min_max<T: ArrowPrimitiveType>(
+        array: &PrimitiveArray<T>,
+    ) -> Option<(ScalarValue, ScalarValue)> {
+        let data_type = array.data_type();
+        Some((
+            ScalarValue::new_primitive::<T>(min(array), data_type).ok()?,
+            ScalarValue::new_primitive::<T>(max(array), data_type).ok()?,
+        ))
+    }
+
+    let min_max = downcast_primitive_array! {
+        array => primitive_min_max(array)?,
+        DataType::Boolean => {
+            let array = array.as_boolean();
+            (
+                ScalarValue::Boolean(min_boolean(array)),
+                ScalarValue::Boolean(max_boolean(array)),
+            )
+        }
+        DataType::Utf8 => {
+            let array = array.as_string::<i32>();
+            (
+                ScalarValue::Utf8(min_string(array).map(String::from)),
+                ScalarValue::Utf8(max_string(array).map(String::from)),
+            )
+        }
+        DataType::LargeUtf8 => {
+            let array = array.as_string::<i64>();
+            (
+                ScalarValue::LargeUtf8(min_string(array).map(String::from)),
+                ScalarValue::LargeUtf8(max_string(array).map(String::from)),
+            )
+        }
+        DataType::Utf8View => {
+            let array = array.as_string_view();
+            (
+                ScalarValue::Utf8View(min_string_view(array).map(String::from)),
+                ScalarValue::Utf8View(max_string_view(array).map(String::from)),
+            )
+        }
+        DataType::Binary => {
+            let array = array.as_binary::<i32>();
+            (
+                ScalarValue::Binary(min_binary(array).map(Vec::from)),
+                ScalarValue::Binary(max_binary(array).map(Vec::from)),
+            )
+        }
+        DataType::LargeBinary => {
+            let array = array.as_binary::<i64>();
+            (
+                ScalarValue::LargeBinary(min_binary(array).map(Vec::from)),
+                ScalarValue::LargeBinary(max_binary(array).map(Vec::from)),
+            )
+        }
+        DataType::BinaryView => {
+            let array = array.as_binary_view();
+            (
+                ScalarValue::BinaryView(min_binary_view(array).map(Vec::from)),
+                ScalarValue::BinaryView(max_binary_view(array).map(Vec::from)),
+            )
+        }
+        _ => return None,
+    };
+    Some(min_max)
+}
+
+struct TopKMetrics {
+    /// metrics
+    pub baseline: BaselineMetrics,
+
+    /// count of how many rows were inserted, see [`TopK::progress`]
+    pub input_rows: Count,
+
+    /// count of how many rows were replaced in the heap
+    pub row_replacements: Count,
+
+    /// count of how many rows were skipped without comparing them one by
+    /// one, as all rows of their batch had the same sort key that could not
+    /// enter the heap
+    pub skipped_rows: Count,
+
+    /// bytes owned by the sort keys of the rows in the heap
+    pub heap_owned_bytes: Gauge,
+
+    /// number of input batches retained for the rows in the heap
+    pub batches_pinned: Gauge,
+
+    /// count of how many retained batches were released, as none of their
+    /// rows remained in the heap or the heap was compacted or reset
+    pub batches_evicted: Count,
+}
+
+impl TopKMetrics {
+    fn new(metrics: &ExecutionPlanMetricsSet, partition: usize) -> Self {
+        Self {
+            baseline: BaselineMetrics::new(metrics, partition),
+            input_rows: MetricBuilder::new(metrics).counter("input_rows", partition),
+            row_replacements: MetricBuilder::new(metrics)
+                .counter("row_replacements", partition),
+            skipped_rows: MetricBuilder::new(metrics).counter("skipped_rows", partition),
+            heap_owned_bytes: MetricBuilder::new(metrics)
+                .gauge("heap_owned_bytes", partition),
+            batches_pinned: MetricBuilder::new(metrics)
+                .gauge("batches_pinned", partition),
+            batches_evicted: MetricBuilder::new(metrics)
+                .counter("batches_evicted", partition),
+        }
+    }
+}
+
+/// This structure keeps at most the *smallest* k items, using the
+/// [arrow::row] format for sort keys. While it is called "topK" for
+/// values like `1, 2, 3, 4, 5` the "top 3" really means the
+/// *smallest* 3 , `1, 2, 3`, not the *largest* 3 `3, 4, 5`.
+///
+/// Using the `Row` format handles things such as ascending vs
+/// descending and nulls first vs nulls last.
+///
+/// The rows themselves are kept in a [`BoundedMinKeeper`].
+struct TopKHeap {
+    /// The target number of rows for output batches
+    batch_size: usize,
+    /// The smallest k rows so far
+    inner: BoundedMinKeeper<TopKRow>,
+    /// Storage the original row values (TopKRow only has the sort key)
+    store: RecordBatchStore,
+    /// The size of all owned data held by this heap
+    owned_bytes: usize,
+    /// If set, compact the store once its compaction ratio reaches this
+    /// value, see [`TopK::with_compaction_ratio_threshold`]
+    compaction_ratio_threshold: Option<f64>,
+    /// If set, compact the store once it holds more than this many
+    /// batches, see [`TopK::with_max_stored_batches`]
+    max_stored_batches: Option<usize>,
+    /// If set, used to order the rows instead of comparing their bytes,
+    /// see [`TopK::with_comparator`]
+    comparator: Option<TopKComparator>,
+    /// How the values of the rows in the heap are retained, see
+    /// [`TopK::with_storage`]
+    storage: TopKStorage,
+    /// How the space for the rows in `inner` is allocated, see
+    /// [`TopK::with_heap_growth`]
+    growth: TopKHeapGrowth,
+    /// The largest k for which the space for all k rows in `inner` is
+    /// allocated up front, see [`TopK::with_eager_allocation_limit`]
+    eager_allocation_limit: usize,
+    /// The size of memory used by this heap, in bytes, updated as rows
+    /// are added and evicted and batches are inserted and unused, see
+    /// [`Self::size`]
+    size: usize,
+}
+
+impl TopKHeap {
+    fn new(k: usize, batch_size: usize, schema: SchemaRef) -> Self {
         assert!(k > 0);
         Self {
-            k,
             batch_size,
-            inner: BinaryHeap::new(),
+            inner: BoundedMinKeeper::new(k),
             store: RecordBatchStore::new(schema),
             owned_bytes: 0,
+            compaction_ratio_threshold: None,
+            max_stored_batches: None,
+            comparator: None,
+            storage: TopKStorage::default(),
+            growth: TopKHeapGrowth::default(),
+            eager_allocation_limit: usize::MAX,
+            size: 0,
         }
+        .with_computed_size()
+    }
+
+    /// Sets the tracked size of this heap to [`Self::compute_size`]
+    fn with_computed_size(mut self) -> Self {
+        self.size = self.compute_size();
+        self
+    }
+
+    /// Sets the growth strategy, reserving the space for all k rows if it
+    /// is [`TopKHeapGrowth::Eager`] and k is within the eager allocation
+    /// limit
+    fn set_growth(&mut self, growth: TopKHeapGrowth) {
+        self.growth = growth;
+        if growth == TopKHeapGrowth::Eager
+            && self.inner.k() <= self.eager_allocation_limit
+        {
+            let inner_size = self.inner_size();
+            self.inner.reserve_k();
+            self.update_size(inner_size, self.inner_size());
+        }
+    }
+
+    /// Sets the largest k for which the space for all k rows is allocated
+    /// up front, releasing the space already reserved for a greater k
+    fn set_eager_allocation_limit(&mut self, limit: usize) {
+        self.eager_allocation_limit = limit;
+        if self.inner.k() > limit {
+            let inner_size = self.inner_size();
+            self.inner.shrink_to_fit();
+            self.update_size(inner_size, self.inner_size());
+        }
+    }
+
+    /// Updates the tracked size after memory used by this heap changed
+    /// from `before` to `after` bytes
+    fn update_size(&mut self, before: usize, after: usize) {
+        self.size = self.size - before + after;
+    }
+
+    /// Compares the sort keys `a` and `b` in the order of this heap
+    fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        compare_rows(self.comparator.as_ref(), a, b)
     }
 
     /// Register a [`RecordBatch`] with the heap, returning the
@@ -291,18 +1679,100 @@ impl TopKHeap {
     /// Insert a [`RecordBatchEntry`] created by a previous call to
     /// [`Self::register_batch`] into storage.
     pub fn insert_batch_entry(&mut self, entry: RecordBatchEntry) {
-        self.store.insert(entry)
+        let store_size = self.store.size();
+        self.store.insert(entry);
+        self.update_size(store_size, self.store.size());
     }
 
     /// Returns the largest value stored by the heap if there are k
     /// items, otherwise returns None. Remember this structure is
     /// keeping the "smallest" k values
     fn max(&self) -> Option<&TopKRow> {
-        if self.inner.len() < self.k {
-            None
-        } else {
-            self.inner.peek()
+        self.inner.max()
+    }
+
+    /// Returns true if the heap is full and all `rows` have the same sort
+    /// key, which is not less than the current max, so that none of them
+    /// can enter the heap. This is the case for a constant sort key once
+    /// the heap holds the first k rows.
+    ///
+    /// The rows are compared for equality rather than with
+    /// [`Self::compare`], and the scan stops at the first row that differs.
+    fn rejects_all(&self, rows: &Rows) -> bool {
+        let Some(max_row) = self.max() else {
+            return false;
+        };
+        if rows.num_rows() == 0 {
+            return false;
         }
+        let first = rows.row(0);
+        self.compare(first.as_ref(), max_row.row()).is_ge()
+            && (1..rows.num_rows()).all(|idx| rows.row(idx) == first)
+    }
+
+    /// Returns the indices of the rows of `rows` that may enter this heap,
+    /// in ascending order, selecting them from chunks of `rows` in parallel.
+    ///
+    /// Each chunk runs the check of [`TopK::insert_batch`] on its own,
+    /// starting with the current heap: a row is discarded if at least k
+    /// rows, of the heap or earlier in the chunk, sort before or equal to
+    /// it. Those rows also precede it in the whole input, so the serial
+    /// path would discard it too.
+    #[cfg(feature = "parallel_topk")]
+    fn parallel_candidates(&self, rows: &Rows) -> Vec<usize> {
+        use rayon::prelude::*;
+
+        let num_rows = rows.num_rows();
+        let chunk_size = num_rows.div_ceil(rayon::current_num_threads()).max(1);
+        let max_row = self.max().map(|row| row.row());
+        let chunks: Vec<Vec<usize>> = (0..num_rows)
+            .step_by(chunk_size)
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(|start| {
+                let end = (start + chunk_size).min(num_rows);
+                self.chunk_candidates(rows, start..end, max_row)
+            })
+            .collect();
+        chunks.concat()
+    }
+
+    /// Returns the indices in `range` of the rows that may enter this heap,
+    /// considering only the rows of the heap, whose largest row is
+    /// `max_row` if the heap is full, and the earlier rows in `range`
+    #[cfg(feature = "parallel_topk")]
+    fn chunk_candidates(
+        &self,
+        rows: &Rows,
+        range: std::ops::Range<usize>,
+        max_row: Option<&[u8]>,
+    ) -> Vec<usize> {
+        use std::collections::BinaryHeap;
+
+        // the k smallest rows of the chunk so far
+        let mut local_rows: BinaryHeap<CandidateRow<'_>> = BinaryHeap::new();
+        let mut candidates = vec![];
+        for index in range {
+            let row = rows.row(index).data();
+            if max_row.is_some_and(|max_row| self.compare(row, max_row).is_ge()) {
+                continue;
+            }
+            if local_rows.len() == self.inner.k() {
+                if local_rows
+                    .peek()
+                    .is_some_and(|local_max| self.compare(row, local_max.row).is_ge())
+                {
+                    continue;
+                }
+                local_rows.pop();
+            }
+            local_rows.push(CandidateRow {
+                row,
+                comparator: self.comparator.as_ref(),
+            });
+            candidates.push(index);
+        }
+        candidates
     }
 
     /// Adds `row` to this heap. If inserting this new item would
@@ -313,16 +1783,33 @@ impl TopKHeap {
         batch_entry: &mut RecordBatchEntry,
         row: impl AsRef<[u8]>,
         index: usize,
-    ) {
-        let batch_id = batch_entry.id;
-        batch_entry.uses += 1;
+    ) -> Result<()> {
+        let store_size = self.store.size();
+        let (batch_id, index) = match self.storage {
+            TopKStorage::Batches => {
+                batch_entry.uses += 1;
+                (batch_entry.id, index)
+            }
+            TopKStorage::Rows => {
+                // copy the row so `batch_entry` is not retained
+                let indices = UInt32Array::from(vec![index as u32]);
+                let row_batch = take_record_batch(&batch_entry.batch, &indices)?;
+                let mut row_entry = self.store.register(row_batch);
+                row_entry.uses = 1;
+                let row_id = row_entry.id;
+                self.store.insert(row_entry);
+                (row_id, 0)
+            }
+        };
 
-        assert!(self.inner.len() <= self.k);
         let row = row.as_ref();
 
         // Reuse storage for evicted item if possible
-        let new_top_k = if self.inner.len() == self.k {
-            let prev_min = self.inner.pop().unwrap();
+        let new_top_k = if self.inner.is_full() {
+            let prev_min = self
+                .inner
+                .pop_max_by(topk_row_order(&self.comparator))
+                .unwrap();
 
             // Update batch use
             if prev_min.batch_id == batch_entry.id {
@@ -333,34 +1820,65 @@ impl TopKHeap {
 
             // update memory accounting
             self.owned_bytes -= prev_min.owned_size();
+            self.update_size(prev_min.owned_size(), 0);
             prev_min.with_new_row(row, batch_id, index)
         } else {
             TopKRow::new(row, batch_id, index)
         };
 
         self.owned_bytes += new_top_k.owned_size();
+        self.update_size(0, new_top_k.owned_size());
+        self.update_size(store_size, self.store.size());
 
         // put the new row into the heap
-        self.inner.push(new_top_k)
-    }
-
-    /// Returns the values stored in this heap, from values low to
-    /// high, as a single [`RecordBatch`], resetting the inner heap
-    pub fn emit(&mut self) -> Result<Option<RecordBatch>> {
-        Ok(self.emit_with_state()?.0)
+        let inner_size = self.inner_size();
+        let evicted = self
+            .inner
+            .push_by(new_top_k, topk_row_order(&self.comparator));
+        debug_assert!(evicted.is_none());
+        self.update_size(inner_size, self.inner_size());
+        Ok(())
     }
 
     /// Returns the values stored in this heap, from values low to
     /// high, as a single [`RecordBatch`], and a sorted vec of the
     /// current heap's contents
-    pub fn emit_with_state(&mut self) -> Result<(Option<RecordBatch>, Vec<TopKRow>)> {
+    pub fn emit_with_state(&mut self) -> Result<(RecordBatch, Vec<TopKRow>)> {
+        let topk_rows = self.take_sorted_rows();
+        let batch = self.stored_rows(&topk_rows)?;
+        Ok((batch, topk_rows))
+    }
+
+    /// Removes the rows from this heap, returning them in sorted order
+    fn take_sorted_rows(&mut self) -> Vec<TopKRow> {
+        let inner_size = self.inner_size();
+        let topk_rows = self.inner.take_sorted_by(topk_row_order(&self.comparator));
+        self.update_size(inner_size, self.inner_size());
+        topk_rows
+    }
+
+    /// Returns the stored values of `topk_rows`, in the same order, as a
+    /// single [`RecordBatch`]
+    fn stored_rows(&self, topk_rows: &[TopKRow]) -> Result<RecordBatch> {
         let schema = Arc::clone(self.store.schema());
 
-        // generate sorted rows
-        let topk_rows = std::mem::take(&mut self.inner).into_sorted_vec();
+        // avoid calling `interleave` with no input arrays
+        if topk_rows.is_empty() {
+            return Ok(RecordBatch::new_empty(schema));
+        }
+
+        let stored_batch = |k: &TopKRow| match self.store.get(k.batch_id) {
+            Some(entry) => Ok(&entry.batch),
+            None => internal_err!("Invalid stored batch id {} in TopK heap", k.batch_id),
+        };
 
-        if self.store.is_empty() {
-            return Ok((None, topk_rows));
+        // each row is stored in its own batch, so concatenate them in order
+        if self.storage == TopKStorage::Rows {
+            let row_batches = topk_rows
+                .iter()
+                .map(stored_batch)
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(concat_batches(&schema, row_batches)?);
         }
 
         // Indices for each row within its respective RecordBatch
@@ -378,12 +1896,8 @@ impl TopKHeap {
             .map(|col| {
                 let input_arrays: Vec<_> = topk_rows
                     .iter()
-                    .map(|k| {
-                        let entry =
-                            self.store.get(k.batch_id).expect("invalid stored batch id");
-                        entry.batch.column(col) as &dyn Array
-                    })
-                    .collect();
+                    .map(|k| Ok(stored_batch(k)?.column(col) as &dyn Array))
+                    .collect::<Result<_>>()?;
 
                 // at this point `indices` contains indexes within the
                 // rows and `input_arrays` contains a reference to the
@@ -393,23 +1907,66 @@ impl TopKHeap {
             })
             .collect::<Result<_>>()?;
 
-        let new_batch = RecordBatch::try_new(schema, output_columns)?;
-        Ok((Some(new_batch), topk_rows))
+        Ok(RecordBatch::try_new(schema, output_columns)?)
     }
 
-    /// Compact this heap, rewriting all stored batches into a single
-    /// input batch
-    pub fn maybe_compact(&mut self) -> Result<()> {
+    /// Returns the rank of each of the sorted `rows`, as emitted by
+    /// [`Self::emit_with_state`], numbered according to `policy`
+    fn ranks(&self, rows: &[TopKRow], policy: TopKRankPolicy) -> UInt64Array {
+        let mut rank = 0;
+        let ranks = rows.iter().enumerate().map(|(i, row)| {
+            let tied = i > 0 && self.compare(rows[i - 1].row(), row.row()).is_eq();
+            match policy {
+                TopKRankPolicy::RowNumber => rank = i as u64 + 1,
+                TopKRankPolicy::Rank if !tied => rank = i as u64 + 1,
+                TopKRankPolicy::DenseRank if !tied => rank += 1,
+                TopKRankPolicy::Rank | TopKRankPolicy::DenseRank => {}
+            }
+            rank
+        });
+        UInt64Array::from_iter_values(ranks)
+    }
+
+    /// Returns true if the stored batches hold enough unused data
+    /// that [`Self::maybe_compact`] should rewrite them
+    fn should_compact(&self) -> bool {
+        // only the rows in the heap are stored, and compacting would break
+        // the invariant of one row per batch
+        if self.storage == TopKStorage::Rows {
+            return false;
+        }
+
+        // compact if the rows in the heap are spread over too many batches
+        if let Some(max) = self.max_stored_batches {
+            if self.store.len() > max {
+                return true;
+            }
+        }
+
+        // compact if the retained batches are much larger than what is
+        // needed for the rows in the heap
+        if let Some(threshold) = self.compaction_ratio_threshold {
+            if self.store.compaction_ratio(self.inner.len()) >= threshold {
+                return true;
+            }
+        }
+
         // we compact if the number of "unused" rows in the store is
         // past some pre-defined threshold. Target holding up to
         // around 20 batches, but handle cases of large k where some
         // batches might be partially full
-        let max_unused_rows = (20 * self.batch_size) + self.k;
+        let max_unused_rows = (20 * self.batch_size) + self.inner.k();
         let unused_rows = self.store.unused_rows();
 
         // don't compact if the store has one extra batch or
         // unused rows is under the threshold
-        if self.store.len() <= 2 || unused_rows < max_unused_rows {
+        self.store.len() > 2 && unused_rows >= max_unused_rows
+    }
+
+    /// Compact this heap, rewriting all stored batches into a single
+    /// input batch
+    pub fn maybe_compact(&mut self) -> Result<()> {
+        if !self.should_compact() {
             return Ok(());
         }
         // at first, compact the entire thing always into a new batch
@@ -419,13 +1976,15 @@ impl TopKHeap {
         // Note: new batch is in the same order as inner
         let num_rows = self.inner.len();
         let (new_batch, mut topk_rows) = self.emit_with_state()?;
-        let Some(new_batch) = new_batch else {
+        if new_batch.num_rows() == 0 {
             return Ok(());
-        };
+        }
 
         // clear all old entries in store (this invalidates all
         // store_ids in `inner`)
+        let store_size = self.store.size();
         self.store.clear();
+        self.update_size(store_size, self.store.size());
 
         let mut batch_entry = self.register_batch(new_batch);
         batch_entry.uses = num_rows;
@@ -439,26 +1998,42 @@ impl TopKHeap {
         }
         self.insert_batch_entry(batch_entry);
         // restore the heap
-        self.inner = BinaryHeap::from(topk_rows);
+        let inner_size = self.inner_size();
+        self.inner = BoundedMinKeeper::from_vec_by(
+            self.inner.k(),
+            topk_rows,
+            topk_row_order(&self.comparator),
+        );
+        self.update_size(inner_size, self.inner_size());
 
         Ok(())
     }
 
-    /// return the size of memory used by this heap, in bytes
+    /// return the size of memory used by this heap, in bytes, as tracked
+    /// incrementally
     fn size(&self) -> usize {
-        size_of::<Self>()
-            + (self.inner.capacity() * size_of::<TopKRow>())
-            + self.store.size()
-            + self.owned_bytes
+        self.size
+    }
+
+    /// Computes the size of memory used by this heap from scratch, in
+    /// bytes. Should always be equal to [`Self::size`]
+    fn compute_size(&self) -> usize {
+        size_of::<Self>() + self.inner_size() + self.store.size() + self.owned_bytes
+    }
+
+    /// return the size of memory allocated by `inner`, not including
+    /// the owned bytes of the rows
+    fn inner_size(&self) -> usize {
+        self.inner.capacity() * size_of::<TopKRow>()
     }
 }
 
-/// Represents one of the top K rows held in this heap. Orders
-/// according to memcmp of row (e.g. the arrow Row format, but could
-/// also be primitive values)
+/// Represents one of the top K rows held in this heap. Ordered by
+/// [`topk_row_order`], according to memcmp of row (e.g. the arrow Row
+/// format, but could also be primitive values), or the custom comparator
+/// of the heap if set
 ///
 /// Reuses allocations to minimize runtime overhead of creating new Vecs
-#[derive(Debug, PartialEq)]
 struct TopKRow {
     /// the value of the sort key for this row. This contains the
     /// bytes that could be stored in `OwnedRow` but uses `Vec<u8>` to
@@ -514,17 +2089,61 @@ impl TopKRow {
     }
 }
 
-impl Eq for TopKRow {}
+impl Debug for TopKRow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TopKRow")
+            .field("row", &self.row)
+            .field("batch_id", &self.batch_id)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+/// Compares the sort keys `a` and `b` with `comparator`, or byte-wise if
+/// it is not set
+fn compare_rows(comparator: Option<&TopKComparator>, a: &[u8], b: &[u8]) -> Ordering {
+    match comparator {
+        Some(comparator) => comparator(a, b),
+        None => a.cmp(b),
+    }
+}
+
+/// Returns the order of the [`TopKRow`]s of a heap with `comparator`
+fn topk_row_order(
+    comparator: &Option<TopKComparator>,
+) -> impl Fn(&TopKRow, &TopKRow) -> Ordering + '_ {
+    move |a, b| compare_rows(comparator.as_ref(), a.row(), b.row())
+}
+
+/// The sort key of a row considered by [`TopKHeap::chunk_candidates`],
+/// ordered like [`TopKRow`] without copying the row
+#[cfg(feature = "parallel_topk")]
+struct CandidateRow<'a> {
+    row: &'a [u8],
+    comparator: Option<&'a TopKComparator>,
+}
+
+#[cfg(feature = "parallel_topk")]
+impl PartialEq for CandidateRow<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+#[cfg(feature = "parallel_topk")]
+impl Eq for CandidateRow<'_> {}
 
-impl PartialOrd for TopKRow {
+#[cfg(feature = "parallel_topk")]
+impl PartialOrd for CandidateRow<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for TopKRow {
+#[cfg(feature = "parallel_topk")]
+impl Ord for CandidateRow<'_> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.row.cmp(&other.row)
+        compare_rows(self.comparator, self.row, other.row)
     }
 }
 
@@ -548,6 +2167,9 @@ struct RecordBatchStore {
     batches: HashMap<u32, RecordBatchEntry>,
     /// total size of all record batches tracked by this store
     batches_size: usize,
+    /// number of batches removed since the last call to
+    /// [`Self::take_evicted`]
+    evicted: usize,
     /// schema of the batches
     schema: SchemaRef,
 }
@@ -558,6 +2180,7 @@ impl RecordBatchStore {
             next_id: 0,
             batches: HashMap::new(),
             batches_size: 0,
+            evicted: 0,
             schema,
         }
     }
@@ -582,10 +2205,17 @@ impl RecordBatchStore {
 
     /// Clear all values in this store, invalidating all previous batch ids
     fn clear(&mut self) {
+        self.evicted += self.batches.len();
         self.batches.clear();
         self.batches_size = 0;
     }
 
+    /// Returns the number of batches removed from this store since the
+    /// last call, resetting it
+    fn take_evicted(&mut self) -> usize {
+        std::mem::take(&mut self.evicted)
+    }
+
     fn get(&self, id: u32) -> Option<&RecordBatchEntry> {
         self.batches.get(&id)
     }
@@ -604,9 +2234,27 @@ impl RecordBatchStore {
             .sum()
     }
 
-    /// returns true if the store has nothing stored
-    fn is_empty(&self) -> bool {
-        self.batches.is_empty()
+    /// Returns the memory of all stored batches divided by the memory
+    /// attributable to the `live_rows` rows still referenced from them,
+    /// assuming all stored rows are the same size.
+    ///
+    /// A ratio of `1.0` means no memory is wasted, while a ratio of `10.0`
+    /// means compacting would free about 90% of the stored batch memory.
+    fn compaction_ratio(&self, live_rows: usize) -> f64 {
+        let retained_rows: usize = self
+            .batches
+            .values()
+            .map(|batch_entry| batch_entry.batch.num_rows())
+            .sum();
+        if retained_rows == 0 || self.batches_size == 0 {
+            return 1.0;
+        }
+        if live_rows == 0 {
+            return f64::INFINITY;
+        }
+
+        let bytes_per_row = self.batches_size as f64 / retained_rows as f64;
+        self.batches_size as f64 / (bytes_per_row * live_rows as f64)
     }
 
     /// return the schema of batches stored
@@ -627,6 +2275,7 @@ impl RecordBatchStore {
         };
 
         if remove {
+            self.evicted += 1;
             let old_entry = self.batches.remove(&id).unwrap();
             self.batches_size = self
                 .batches_size
@@ -647,10 +2296,25 @@ impl RecordBatchStore {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::collect;
+    use crate::memory::MemoryExec;
+    use crate::projection::ProjectionExec;
+    use crate::sorts::sort::SortExec;
+    use crate::sorts::streaming_merge::StreamingMergeBuilder;
+    use crate::ExecutionPlan;
     use arrow::array::Int32Array;
-    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::datatypes::{DataType, Field, Fields, Schema};
     use arrow::record_batch::RecordBatch;
-    use arrow_array::Float64Array;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Int32Type, UInt64Type};
+    use arrow_array::{Float64Array, Int64Array, StringArray};
+    use arrow_schema::SortOptions;
+    use datafusion_common::assert_batches_eq;
+    use datafusion_execution::TaskContext;
+    use datafusion_expr::Operator;
+    use datafusion_physical_expr::expressions::{binary, col, lit};
+    use futures::TryStreamExt;
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 
     /// This test ensures the size calculation is correct for RecordBatches with multiple columns.
     #[test]
@@ -683,4 +2347,1550 @@ mod tests {
         record_batch_store.unuse(0);
         assert_eq!(record_batch_store.batches_size, 0);
     }
+
+    /// Creates a [`TopK`] over `schema` sorting by column `a` with `options`
+    fn topk_on_a(schema: &SchemaRef, options: SortOptions, k: usize) -> Result<TopK> {
+        TopK::try_new(
+            0,
+            Arc::clone(schema),
+            sort_on_a(schema, options)?,
+            k,
+            2,
+            Arc::new(RuntimeEnv::default()),
+            &ExecutionPlanMetricsSet::new(),
+        )
+    }
+
+    fn sort_on_a(schema: &SchemaRef, options: SortOptions) -> Result<LexOrdering> {
+        Ok(LexOrdering::new(vec![PhysicalSortExpr {
+            expr: col("a", schema)?,
+            options,
+        }]))
+    }
+
+    #[tokio::test]
+    async fn test_topk_reset() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int32Array::from(vec![3, 1, 2]))],
+        )?;
+
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 2)?;
+        topk.insert_batch(batch.clone())?;
+
+        // same sort options: the previous rows are discarded
+        topk.reset(sort_on_a(&schema, SortOptions::default())?)?;
+        topk.insert_batch(RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int32Array::from(vec![5, 4, 6]))],
+        )?)?;
+        let results: Vec<_> = topk.emit()?.try_collect().await?;
+        assert_batches_eq!(
+            &["+---+", "| a |", "+---+", "| 4 |", "| 5 |", "+---+"],
+            &results
+        );
+
+        // changed sort direction can not reuse the row converter
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 2)?;
+        topk.insert_batch(batch)?;
+        let descending = SortOptions {
+            descending: true,
+            nulls_first: true,
+        };
+        let err = topk
+            .reset(sort_on_a(&schema, descending)?)
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(
+            err.contains("Cannot reset TopK with sort fields"),
+            "unexpected error: {err}"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_emit_empty() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+
+        let mut heap = TopKHeap::new(3, 2, Arc::clone(&schema));
+        let (batch, _) = heap.emit_with_state()?;
+        assert_eq!(batch.num_rows(), 0);
+        assert_eq!(batch.schema(), schema);
+
+        // a TopK that never saw any input produces no output
+        let topk = topk_on_a(&schema, SortOptions::default(), 3)?;
+        let results: Vec<_> = topk.emit()?.try_collect().await?;
+        assert!(results.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_topk_kth_value() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = |values: Vec<i32>| {
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![Arc::new(Int32Array::from(values))],
+            )
+        };
+
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 3)?;
+        topk.insert_batch(batch(vec![50, 10])?)?;
+        // fewer than k rows so far
+        assert_eq!(topk.kth_value()?, None);
+
+        topk.insert_batch(batch(vec![90, 30, 70])?)?;
+        assert_eq!(topk.kth_value()?, Some(ScalarValue::Int32(Some(50))));
+
+        topk.insert_batch(batch(vec![20, 80, 40, 60])?)?;
+        assert_eq!(topk.kth_value()?, Some(ScalarValue::Int32(Some(30))));
+
+        // for a descending sort the boundary is the k-th largest value
+        let descending = SortOptions {
+            descending: true,
+            nulls_first: false,
+        };
+        let mut topk = topk_on_a(&schema, descending, 3)?;
+        topk.insert_batch(batch((1..=100).collect())?)?;
+        assert_eq!(topk.kth_value()?, Some(ScalarValue::Int32(Some(98))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_batch_store_compaction_ratio() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let mut store = RecordBatchStore::new(Arc::clone(&schema));
+        // nothing stored, nothing to compact
+        assert_eq!(store.compaction_ratio(0), 1.0);
+
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from((0..10).collect::<Vec<_>>()))],
+        )?;
+        let mut entry = store.register(batch);
+        entry.uses = 10;
+        store.insert(entry);
+
+        // low ratio: every stored row is live
+        assert_eq!(store.compaction_ratio(10), 1.0);
+        // high ratio: only one row in ten is live
+        assert_eq!(store.compaction_ratio(1), 10.0);
+        assert_eq!(store.compaction_ratio(0), f64::INFINITY);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_sorted_input_early_exit() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        let batch = |a: Vec<i32>, b: Vec<i32>| {
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![Arc::new(Int32Array::from(a)), Arc::new(Int32Array::from(b))],
+            )
+        };
+        let sort_expr = |name: &str| -> Result<PhysicalSortExpr> {
+            Ok(PhysicalSortExpr {
+                expr: col(name, &schema)?,
+                options: SortOptions::default(),
+            })
+        };
+        let new_topk = || {
+            TopK::try_new(
+                0,
+                Arc::clone(&schema),
+                LexOrdering::new(vec![sort_expr("a")?, sort_expr("b")?]),
+                2,
+                2,
+                Arc::new(RuntimeEnv::default()),
+                &ExecutionPlanMetricsSet::new(),
+            )
+        };
+
+        // input sorted on `a`, a prefix of the sort expressions
+        let mut topk =
+            new_topk()?.with_input_ordering(LexOrdering::new(vec![sort_expr("a")?]))?;
+        // the heap is full, but later rows with `a = 1` can still enter
+        assert!(!topk.insert_batch(batch(vec![1, 1], vec![5, 4])?)?);
+        // every row after `a = 2` is greater than the k-th row (1, 4)
+        assert!(topk.insert_batch(batch(vec![1, 2], vec![3, 0])?)?);
+        // further input is not read
+        assert!(topk.insert_batch(batch(vec![3, 3], vec![0, 0])?)?);
+        let results: Vec<_> = topk.emit()?.try_collect().await?;
+        assert_batches_eq!(
+            &[
+                "+---+---+",
+                "| a | b |",
+                "+---+---+",
+                "| 1 | 3 |",
+                "| 1 | 4 |",
+                "+---+---+",
+            ],
+            &results
+        );
+
+        // input sorted on `b` only: no common prefix, never done
+        let mut topk =
+            new_topk()?.with_input_ordering(LexOrdering::new(vec![sort_expr("b")?]))?;
+        assert!(!topk.insert_batch(batch(vec![1, 1], vec![0, 1])?)?);
+        assert!(!topk.insert_batch(batch(vec![2, 3], vec![2, 3])?)?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_custom_comparator() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Utf8, true)]));
+        let batches = [
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![Arc::new(StringArray::from(vec!["10", "9", "100"]))],
+            )?,
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![Arc::new(StringArray::from(vec!["2", "33", "1"]))],
+            )?,
+        ];
+
+        // by default strings are ordered lexically
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 3)?;
+        for batch in &batches {
+            topk.insert_batch(batch.clone())?;
+        }
+        let results: Vec<_> = topk.emit()?.try_collect().await?;
+        assert_batches_eq!(
+            &[
+                "+-----+", "| a   |", "+-----+", "| 1   |", "| 10  |", "| 100 |",
+                "+-----+"
+            ],
+            &results
+        );
+
+        // decode the sort keys and compare them as numbers
+        let converter = RowConverter::new(vec![SortField::new(DataType::Utf8)])?;
+        let comparator: TopKComparator = Arc::new(move |a, b| {
+            let parser = converter.parser();
+            let keys = converter
+                .convert_rows([parser.parse(a), parser.parse(b)])
+                .unwrap();
+            let keys = keys[0].as_string::<i32>();
+            let a: i64 = keys.value(0).parse().unwrap();
+            let b: i64 = keys.value(1).parse().unwrap();
+            a.cmp(&b)
+        });
+        let mut topk =
+            topk_on_a(&schema, SortOptions::default(), 3)?.with_comparator(comparator);
+        for batch in &batches {
+            topk.insert_batch(batch.clone())?;
+        }
+        let results: Vec<_> = topk.emit()?.try_collect().await?;
+        assert_batches_eq!(
+            &["+---+", "| a |", "+---+", "| 1 |", "| 2 |", "| 9 |", "+---+"],
+            &results
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_topk_compaction_ratio_threshold() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int32Array::from((0..100).collect::<Vec<_>>()))],
+        )?;
+        let retained_rows = |topk: &TopK| {
+            topk.heap
+                .store
+                .batches
+                .values()
+                .map(|entry| entry.batch.num_rows())
+                .sum::<usize>()
+        };
+
+        // by default a single retained batch is never compacted
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 1)?;
+        topk.insert_batch(batch.clone())?;
+        assert_eq!(retained_rows(&topk), 100);
+
+        // the ratio (100) is below the threshold
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 1)?
+            .with_compaction_ratio_threshold(200.0);
+        topk.insert_batch(batch.clone())?;
+        assert_eq!(retained_rows(&topk), 100);
+
+        // the ratio (100) reaches the threshold, only the live row is kept
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 1)?
+            .with_compaction_ratio_threshold(50.0);
+        topk.insert_batch(batch)?;
+        assert_eq!(retained_rows(&topk), 1);
+        assert_eq!(topk.kth_value()?, Some(ScalarValue::Int32(Some(0))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_max_stored_batches() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        // single row batches with decreasing values, each entering the top k
+        let batches = (0..50)
+            .rev()
+            .map(|a| {
+                RecordBatch::try_new(
+                    Arc::clone(&schema),
+                    vec![Arc::new(Int32Array::from(vec![a]))],
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut outputs = vec![];
+        for max_stored_batches in [None, Some(4)] {
+            let mut topk = topk_on_a(&schema, SortOptions::default(), 10)?;
+            if let Some(max) = max_stored_batches {
+                topk = topk.with_max_stored_batches(max);
+            }
+            let mut stored_batches = vec![];
+            for batch in &batches {
+                topk.insert_batch(batch.clone())?;
+                assert_eq!(topk.heap.size(), topk.heap.compute_size());
+                stored_batches.push(topk.heap.store.len());
+            }
+            let max_stored = stored_batches.iter().max().copied();
+            match max_stored_batches {
+                // each row in the top k pins its own batch
+                None => assert_eq!(max_stored, Some(10)),
+                // the store collapses to a single batch past the limit
+                Some(max) => {
+                    assert_eq!(max_stored, Some(max));
+                    assert_eq!(&stored_batches[..6], &[1, 2, 3, 4, 1, 2]);
+                }
+            }
+            let results: Vec<_> = topk.emit()?.try_collect().await?;
+            outputs.push(concat_batches(&schema, &results)?);
+        }
+
+        assert_eq!(outputs[0], outputs[1]);
+        assert_eq!(
+            outputs[1].column(0).as_primitive::<Int32Type>().values(),
+            &(0..10).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_topk_output_batch_size() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int32Array::from(
+                (0..10).rev().collect::<Vec<_>>(),
+            ))],
+        )?;
+
+        // by default the output is chunked by the processing batch size (2)
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 7)?;
+        topk.insert_batch(batch.clone())?;
+        let results: Vec<_> = topk.emit()?.try_collect().await?;
+        let sizes: Vec<_> = results.iter().map(|b| b.num_rows()).collect();
+        assert_eq!(sizes, vec![2, 2, 2, 1]);
+
+        let mut topk =
+            topk_on_a(&schema, SortOptions::default(), 7)?.with_output_batch_size(3);
+        // internal sizing still uses the processing batch size
+        assert_eq!(topk.batch_size, 2);
+        assert_eq!(topk.heap.batch_size, 2);
+        topk.insert_batch(batch)?;
+        let results: Vec<_> = topk.emit()?.try_collect().await?;
+        let sizes: Vec<_> = results.iter().map(|b| b.num_rows()).collect();
+        assert_eq!(sizes, vec![3, 3, 1]);
+        assert_batches_eq!(
+            &[
+                "+---+", "| a |", "+---+", "| 0 |", "| 1 |", "| 2 |", "| 3 |", "| 4 |",
+                "| 5 |", "| 6 |", "+---+",
+            ],
+            &results
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_progress() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = |a: Vec<i32>| {
+            RecordBatch::try_new(Arc::clone(&schema), vec![Arc::new(Int32Array::from(a))])
+        };
+
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 5)?;
+        assert_eq!(topk.progress(), (0, 0));
+
+        let mut progress = vec![];
+        for a in [vec![9, 8], vec![7, 6, 5], vec![4, 3, 2, 1], vec![10]] {
+            topk.insert_batch(batch(a)?)?;
+            progress.push(topk.progress());
+        }
+        assert_eq!(progress, vec![(2, 2), (5, 5), (9, 5), (10, 5)]);
+
+        // the examined rows are also reported as a metric
+        let input_rows = topk.metrics.input_rows.clone();
+        let results: Vec<_> = topk.emit()?.try_collect().await?;
+        assert_eq!(input_rows.value(), 10);
+        assert_batches_eq!(
+            &[
+                "+---+", "| a |", "+---+", "| 1 |", "| 2 |", "| 3 |", "| 4 |", "| 5 |",
+                "+---+",
+            ],
+            &results
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_retention_metrics() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        // distinct values, so the top k is unambiguous
+        let batches = (0..12)
+            .map(|i| {
+                let a: Int32Array =
+                    (0..8).map(|j| ((i * 8 + j) * 7_919) % 1_009).collect();
+                RecordBatch::try_new(Arc::clone(&schema), vec![Arc::new(a)])
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let k = 10;
+        let mut topk = topk_on_a(&schema, SortOptions::default(), k)?;
+        for (i, batch) in batches.iter().enumerate() {
+            topk.insert_batch(batch.clone())?;
+
+            // the input batches holding one of the k smallest values so far
+            let mut values: Vec<_> = batches[..=i]
+                .iter()
+                .enumerate()
+                .flat_map(|(batch_idx, batch)| {
+                    let a = batch.column(0).as_primitive::<Int32Type>();
+                    a.values().iter().map(move |&v| (v, batch_idx))
+                })
+                .collect();
+            values.sort_unstable();
+            let contributing: std::collections::HashSet<_> = values
+                .iter()
+                .take(k)
+                .map(|(_, batch_idx)| batch_idx)
+                .collect();
+
+            let pinned = topk.metrics.batches_pinned.value();
+            assert!(pinned > 0 && pinned <= contributing.len(), "{pinned}");
+            assert_eq!(pinned, topk.heap.store.len());
+            assert_eq!(topk.metrics.heap_owned_bytes.value(), topk.heap.owned_bytes);
+        }
+        assert!(topk.metrics.batches_evicted.value() > 0);
+
+        // the metrics of a query are reported by the plan
+        let input = MemoryExec::try_new(&[batches], Arc::clone(&schema), None)?;
+        let sort =
+            SortExec::new(sort_on_a(&schema, SortOptions::default())?, Arc::new(input))
+                .with_fetch(Some(k));
+        let results = collect(sort.execute(0, Arc::new(TaskContext::default()))?).await?;
+        assert_eq!(results.iter().map(|b| b.num_rows()).sum::<usize>(), k);
+        let metrics = sort.metrics().unwrap();
+        for name in ["heap_owned_bytes", "batches_pinned", "batches_evicted"] {
+            let value = metrics.sum_by_name(name).unwrap().as_usize();
+            assert!(value > 0, "{name} is {value}");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_emit_chunk_rows() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        // every value three times, so ties cross the chunk boundaries
+        let batches = (0..5)
+            .map(|i| {
+                let a: Int32Array = (0..3_000).map(|j| (i * 3_000 + j) / 3).collect();
+                RecordBatch::try_new(Arc::clone(&schema), vec![Arc::new(a)])
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut outputs = vec![];
+        for emit_chunk_rows in [None, Some(2_999)] {
+            let mut topk = topk_on_a(&schema, SortOptions::default(), 10_000)?
+                .with_output_batch_size(4_096)
+                .with_rank_column("rank", TopKRankPolicy::Rank)
+                .with_sort_key_column("sort_key");
+            if let Some(rows) = emit_chunk_rows {
+                topk = topk.with_emit_chunk_rows(rows);
+            }
+            for batch in &batches {
+                topk.insert_batch(batch.clone())?;
+            }
+            let results: Vec<_> = topk.emit()?.try_collect().await?;
+            let sizes: Vec<_> = results.iter().map(|b| b.num_rows()).collect();
+            outputs.push((sizes, concat_batches(&results[0].schema(), &results)?));
+        }
+
+        // the output batches are limited to the smaller of the two sizes
+        assert_eq!(outputs[0].0, vec![4_096, 4_096, 1_808]);
+        assert_eq!(outputs[1].0, vec![2_999, 2_999, 2_999, 1_003]);
+        assert_eq!(outputs[0].1, outputs[1].1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_emit_streamed() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batches = (0..4)
+            .map(|i| {
+                let a: Int32Array = (0..5_000)
+                    .map(|j| (j % 7 != 0).then_some((i * 7_919 + j * 104_729) % 30_011))
+                    .collect();
+                let b: StringArray =
+                    a.iter().map(|a| a.map(|a| format!("value {a}"))).collect();
+                RecordBatch::try_new(Arc::clone(&schema), vec![Arc::new(a), Arc::new(b)])
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let new_topk = || -> Result<TopK> {
+            let mut topk = TopK::try_new(
+                0,
+                Arc::clone(&schema),
+                sort_on_a(&schema, SortOptions::default())?,
+                10_000,
+                512,
+                Arc::new(RuntimeEnv::default()),
+                &ExecutionPlanMetricsSet::new(),
+            )?;
+            for batch in &batches {
+                topk.insert_batch(batch.clone())?;
+            }
+            Ok(topk)
+        };
+
+        // the top k gathered into a single batch, then sliced
+        let (batch, _) = new_topk()?.heap.emit_with_state()?;
+        let expected: Vec<_> = (0..batch.num_rows())
+            .step_by(512)
+            .map(|offset| batch.slice(offset, 512.min(batch.num_rows() - offset)))
+            .collect();
+        assert_eq!(expected.len(), 20);
+
+        let results: Vec<_> = new_topk()?.emit()?.try_collect().await?;
+        assert_eq!(results, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_key_bounds() {
+        let asc = |nulls_first| SortOptions {
+            descending: false,
+            nulls_first,
+        };
+        let desc = |nulls_first| SortOptions {
+            descending: true,
+            nulls_first,
+        };
+        let int = |v: Option<i32>| ScalarValue::Int32(v);
+
+        // without nulls, null placement does not matter
+        let array: ArrayRef = Arc::new(Int32Array::from(vec![3, 1, 7, 5]));
+        for nulls_first in [true, false] {
+            assert_eq!(
+                sort_key_bounds(&array, asc(nulls_first)),
+                Some((int(Some(1)), int(Some(7))))
+            );
+            assert_eq!(
+                sort_key_bounds(&array, desc(nulls_first)),
+                Some((int(Some(7)), int(Some(1))))
+            );
+        }
+
+        // with nulls
+        let array: ArrayRef =
+            Arc::new(Int32Array::from(vec![Some(3), None, Some(1), Some(7)]));
+        assert_eq!(
+            sort_key_bounds(&array, asc(true)),
+            Some((int(None), int(Some(7))))
+        );
+        assert_eq!(
+            sort_key_bounds(&array, asc(false)),
+            Some((int(Some(1)), int(None)))
+        );
+        assert_eq!(
+            sort_key_bounds(&array, desc(true)),
+            Some((int(None), int(Some(1))))
+        );
+        assert_eq!(
+            sort_key_bounds(&array, desc(false)),
+            Some((int(Some(7)), int(None)))
+        );
+
+        // strings
+        let array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("b"),
+            None,
+            Some("a"),
+            Some("c"),
+        ]));
+        let utf8 = |v: Option<&str>| ScalarValue::Utf8(v.map(String::from));
+        assert_eq!(
+            sort_key_bounds(&array, desc(false)),
+            Some((utf8(Some("c")), utf8(None)))
+        );
+
+        // all nulls
+        let array: ArrayRef = Arc::new(Int32Array::from(vec![None, None]));
+        for options in [asc(true), asc(false), desc(true), desc(false)] {
+            assert_eq!(
+                sort_key_bounds(&array, options),
+                Some((int(None), int(None)))
+            );
+        }
+
+        // empty
+        let array: ArrayRef = Arc::new(Int32Array::from(Vec::<i32>::new()));
+        assert_eq!(sort_key_bounds(&array, asc(true)), None);
+    }
+    #[test]
+    fn test_topk_insert_batch_changed() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = |values: Vec<i32>| {
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![Arc::new(Int32Array::from(values))],
+            )
+        };
+
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 2)?;
+        assert!(topk.insert_batch_changed(batch(vec![3, 4])?)?);
+        // can not beat the current top 2
+        assert!(!topk.insert_batch_changed(batch(vec![5, 4])?)?);
+        assert!(!topk.insert_batch_changed(batch(vec![])?)?);
+        // 1 enters the top 2
+        assert!(topk.insert_batch_changed(batch(vec![5, 1])?)?);
+        assert_eq!(topk.kth_value()?, Some(ScalarValue::Int32(Some(3))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_row_storage() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batches = (0..5)
+            .map(|i| {
+                let a: Int32Array = (0..6)
+                    .map(|j| ((i * 7 + j * 5) % 11 != 3).then_some((i * 7 + j * 5) % 11))
+                    .collect();
+                let b: StringArray =
+                    (0..6).map(|j| Some(format!("batch {i} row {j}"))).collect();
+                RecordBatch::try_new(Arc::clone(&schema), vec![Arc::new(a), Arc::new(b)])
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for options in [
+            SortOptions::default(),
+            SortOptions {
+                descending: true,
+                nulls_first: false,
+            },
+        ] {
+            for k in [1, 4, 40] {
+                let mut topk = topk_on_a(&schema, options, k)?;
+                let mut row_topk =
+                    topk_on_a(&schema, options, k)?.with_storage(TopKStorage::Rows);
+                for batch in &batches {
+                    topk.insert_batch(batch.clone())?;
+                    row_topk.insert_batch(batch.clone())?;
+                    // only the rows in the top k are retained
+                    assert_eq!(row_topk.heap.store.len(), row_topk.heap.inner.len());
+                }
+
+                let expected: Vec<_> = topk.emit()?.try_collect().await?;
+                let results: Vec<_> = row_topk.emit()?.try_collect().await?;
+                assert_eq!(results, expected);
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_heap_growth() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        // a selective input: only a few rows for a large k
+        let batches = [vec![Some(4), None, Some(2)], vec![Some(7), Some(1)]]
+            .into_iter()
+            .map(|a| {
+                RecordBatch::try_new(
+                    Arc::clone(&schema),
+                    vec![Arc::new(Int32Array::from(a))],
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut peak_sizes = vec![];
+        let mut outputs = vec![];
+        for growth in [TopKHeapGrowth::Lazy, TopKHeapGrowth::Eager] {
+            let mut topk = topk_on_a(&schema, SortOptions::default(), 10_000)?
+                .with_heap_growth(growth);
+            let mut peak_size = 0;
+            for batch in &batches {
+                topk.insert_batch(batch.clone())?;
+                assert_eq!(topk.heap.size(), topk.heap.compute_size());
+                peak_size = peak_size.max(topk.reservation.size());
+            }
+            peak_sizes.push(peak_size);
+            let results: Vec<_> = topk.emit()?.try_collect().await?;
+            outputs.push(results);
+        }
+
+        assert_eq!(outputs[0], outputs[1]);
+        assert!(
+            peak_sizes[0] < peak_sizes[1],
+            "lazy growth should use less memory than eager: {peak_sizes:?}"
+        );
+        // eager growth reserves the space for all k rows
+        assert!(peak_sizes[1] > 10_000 * size_of::<TopKRow>());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_eager_allocation_limit() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int32Array::from(vec![3, 1, 2]))],
+        )?;
+
+        // a huge k over the limit does not allocate the space for all k rows
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 100_000_000)?
+            .with_eager_allocation_limit(1_000)
+            .with_heap_growth(TopKHeapGrowth::Eager);
+        assert_eq!(topk.heap.inner.capacity(), 0);
+        assert!(topk.size() < 1_000 * size_of::<TopKRow>());
+        topk.insert_batch(batch.clone())?;
+        let results: Vec<_> = topk.emit()?.try_collect().await?;
+        assert_batches_eq!(
+            &["+---+", "| a |", "+---+", "| 1 |", "| 2 |", "| 3 |", "+---+"],
+            &results
+        );
+
+        // setting the limit after the growth releases the space reserved
+        let topk = topk_on_a(&schema, SortOptions::default(), 10_000)?
+            .with_heap_growth(TopKHeapGrowth::Eager);
+        assert!(topk.heap.inner.capacity() >= 10_000);
+        let topk = topk.with_eager_allocation_limit(1_000);
+        assert_eq!(topk.heap.inner.capacity(), 0);
+        assert_eq!(topk.heap.size(), topk.heap.compute_size());
+
+        // a k within the limit is still allocated up front
+        let topk = topk_on_a(&schema, SortOptions::default(), 1_000)?
+            .with_eager_allocation_limit(1_000)
+            .with_heap_growth(TopKHeapGrowth::Eager);
+        assert!(topk.heap.inner.capacity() >= 1_000);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_constant_sort_key() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        let batch = |a: Vec<i32>, b: Vec<i32>| {
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![Arc::new(Int32Array::from(a)), Arc::new(Int32Array::from(b))],
+            )
+        };
+
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 3)?;
+        for i in 0..5 {
+            topk.insert_batch(batch(vec![1; 4], (i * 4..i * 4 + 4).collect())?)?;
+        }
+        // the row after the first k rows of the first batch, and once the
+        // heap is full, the batches of the same key are skipped
+        assert_eq!(topk.metrics.row_replacements.value(), 3);
+        assert_eq!(topk.metrics.skipped_rows.value(), 17);
+
+        // the rows of a batch that is not constant are compared to the max
+        // as native values, and only the rows with a smaller key are kept
+        topk.insert_batch(batch(vec![1, 1, 2], vec![20, 21, 22])?)?;
+        assert_eq!(topk.metrics.skipped_rows.value(), 20);
+        topk.insert_batch(batch(vec![0, 2, 0], vec![23, 25, 24])?)?;
+        assert_eq!(topk.metrics.skipped_rows.value(), 21);
+        assert_eq!(topk.metrics.row_replacements.value(), 5);
+
+        let results: Vec<_> = topk.emit()?.try_collect().await?;
+        let results = concat_batches(&schema, &results)?;
+        let a = results.column(0).as_primitive::<Int32Type>();
+        let b = results.column(1).as_primitive::<Int32Type>();
+        assert_eq!(a.values(), &[0, 0, 1]);
+        // the remaining row with a tied key is one of the first k rows
+        assert_eq!(&b.values()[..2], &[23, 24]);
+        assert!(b.value(2) < 3, "unexpected row {}", b.value(2));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_primitive_sort_key() -> Result<()> {
+        let ints: ArrayRef = Arc::new(Int64Array::from_iter(
+            (0..2_000).map(|i| (i % 11 != 0).then_some((i * 7_919) % 601 - 300)),
+        ));
+        let floats: ArrayRef =
+            Arc::new(Float64Array::from_iter((0..2_000).map(|i| match i % 13 {
+                0 => None,
+                1 => Some(f64::NAN),
+                2 => Some(-0.0),
+                3 => Some(0.0),
+                _ => Some(((i * 7_919) % 601 - 300) as f64 / 7.0),
+            })));
+
+        for keys in [ints, floats] {
+            let schema = Arc::new(Schema::new(vec![
+                Field::new("a", keys.data_type().clone(), true),
+                Field::new("b", DataType::Int32, false),
+            ]));
+            let batches = (0..10)
+                .map(|i| {
+                    RecordBatch::try_new(
+                        Arc::clone(&schema),
+                        vec![
+                            keys.slice(i * 200, 200),
+                            Arc::new(Int32Array::from_iter_values(
+                                (i as i32 * 200)..(i as i32 + 1) * 200,
+                            )),
+                        ],
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for descending in [false, true] {
+                for nulls_first in [false, true] {
+                    let options = SortOptions {
+                        descending,
+                        nulls_first,
+                    };
+                    // a second, constant, sort key uses the row format
+                    // for all rows
+                    let row_format = LexOrdering::new(vec![
+                        PhysicalSortExpr {
+                            expr: col("a", &schema)?,
+                            options,
+                        },
+                        PhysicalSortExpr {
+                            expr: lit(0),
+                            options,
+                        },
+                    ]);
+                    let mut outputs = vec![];
+                    for expr in [sort_on_a(&schema, options)?, row_format] {
+                        let mut topk = TopK::try_new(
+                            0,
+                            Arc::clone(&schema),
+                            expr,
+                            100,
+                            64,
+                            Arc::new(RuntimeEnv::default()),
+                            &ExecutionPlanMetricsSet::new(),
+                        )?;
+                        for batch in &batches {
+                            topk.insert_batch(batch.clone())?;
+                        }
+                        let skipped_rows = topk.metrics.skipped_rows.value();
+                        let results: Vec<_> = topk.emit()?.try_collect().await?;
+                        outputs.push((concat_batches(&schema, &results)?, skipped_rows));
+                    }
+                    // rows with tied keys may be kept in any order, but the
+                    // kept rows are rows of the input, identified by `b`
+                    let (output, row_format) = (&outputs[0].0, &outputs[1].0);
+                    assert_eq!(output.num_rows(), 100);
+                    assert_eq!(output.column(0), row_format.column(0), "{options:?}");
+                    let input = concat_batches(&schema, &batches)?;
+                    let indices = UInt32Array::from_iter_values(
+                        output
+                            .column(1)
+                            .as_primitive::<Int32Type>()
+                            .values()
+                            .iter()
+                            .map(|&b| b as u32),
+                    );
+                    assert_eq!(&take_record_batch(&input, &indices)?, output);
+                    // most rows are not converted to the row format
+                    assert!(outputs[0].1 > 1_000, "{options:?}");
+                    assert_eq!(outputs[1].1, 0, "{options:?}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_struct_field_options() -> Result<()> {
+        let fields = Fields::from(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("s", DataType::Struct(fields.clone()), true),
+        ]));
+        let s = StructArray::try_new(
+            fields,
+            vec![
+                Arc::new(Int32Array::from(vec![
+                    Some(1),
+                    Some(1),
+                    Some(2),
+                    Some(9),
+                    Some(1),
+                    Some(0),
+                    Some(2),
+                    None,
+                ])),
+                Arc::new(StringArray::from(vec![
+                    Some("x"),
+                    Some("z"),
+                    Some("a"),
+                    Some("c"),
+                    None,
+                    Some("m"),
+                    Some("b"),
+                    Some("q"),
+                ])),
+            ],
+            Some(vec![true, true, true, false, true, true, true, true].into()),
+        )?;
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int32Array::from_iter_values(0..8)), Arc::new(s)],
+        )?;
+
+        let sort_on_s = LexOrdering::new(vec![PhysicalSortExpr {
+            expr: col("s", &schema)?,
+            options: SortOptions {
+                descending: false,
+                nulls_first: false,
+            },
+        }]);
+        let new_topk = |k| {
+            TopK::try_new(
+                0,
+                Arc::clone(&schema),
+                sort_on_s.clone(),
+                k,
+                2,
+                Arc::new(RuntimeEnv::default()),
+                &ExecutionPlanMetricsSet::new(),
+            )
+        };
+        let field_options = vec![
+            SortOptions {
+                descending: false,
+                nulls_first: false,
+            },
+            SortOptions {
+                descending: true,
+                nulls_first: true,
+            },
+        ];
+        let ids = |results: &[RecordBatch]| -> Result<Vec<i32>> {
+            let results = concat_batches(&schema, results)?;
+            Ok(results
+                .column(0)
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec())
+        };
+
+        // (s.a ASC NULLS LAST, s.b DESC NULLS FIRST), with null structs last
+        assert!(new_topk(8)?
+            .with_struct_field_options(1, field_options.clone())
+            .is_err());
+        let mut topk =
+            new_topk(8)?.with_struct_field_options(0, field_options.clone())?;
+        topk.insert_batch(batch.slice(0, 4))?;
+        topk.insert_batch(batch.slice(4, 4))?;
+        let results: Vec<_> = topk.emit()?.try_collect().await?;
+        assert_eq!(ids(&results)?, vec![5, 4, 1, 0, 6, 2, 7, 3]);
+
+        // the k-th value is the struct
+        let mut topk = new_topk(6)?.with_struct_field_options(0, field_options)?;
+        topk.insert_batch(batch.clone())?;
+        let ScalarValue::Struct(kth) = topk.kth_value()?.unwrap() else {
+            panic!("expected a struct k-th value");
+        };
+        assert_eq!(
+            ScalarValue::try_from_array(kth.column(1), 0)?,
+            ScalarValue::from("a")
+        );
+
+        // the options of the struct apply to all fields by default
+        let mut topk = new_topk(8)?;
+        topk.insert_batch(batch)?;
+        let results: Vec<_> = topk.emit()?.try_collect().await?;
+        assert_eq!(ids(&results)?, vec![5, 0, 1, 4, 2, 6, 7, 3]);
+
+        let err = new_topk(8)?
+            .with_struct_field_options(0, vec![SortOptions::default()])
+            .err()
+            .unwrap()
+            .strip_backtrace();
+        assert!(
+            err.contains("Expected 2 field sort options"),
+            "unexpected error: {err}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_topk_is_saturated() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let saturated = Arc::new(AtomicUsize::new(0));
+        let callback_saturated = Arc::clone(&saturated);
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 3)?.with_on_saturated(
+            Box::new(move || {
+                callback_saturated.fetch_add(1, AtomicOrdering::Relaxed);
+            }),
+        );
+        assert!(!topk.is_saturated());
+
+        // every row enters the top k, as each is less than the previous
+        for (i, value) in [9, 8, 7, 6, 5].into_iter().enumerate() {
+            let batch = RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![Arc::new(Int32Array::from(vec![value]))],
+            )?;
+            assert!(topk.insert_batch_changed(batch)?);
+            // saturated at exactly the k-th row
+            assert_eq!(topk.is_saturated(), i + 1 >= 3, "after {} rows", i + 1);
+            assert_eq!(
+                saturated.load(AtomicOrdering::Relaxed),
+                usize::from(i + 1 >= 3)
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_nan_policy() -> Result<()> {
+        let schema =
+            Arc::new(Schema::new(vec![Field::new("a", DataType::Float64, true)]));
+        let batches = [vec![2.0, f64::NAN, -1.0], vec![-f64::NAN, 5.0]]
+            .map(|values| {
+                RecordBatch::try_new(
+                    Arc::clone(&schema),
+                    vec![Arc::new(Float64Array::from(values))],
+                )
+            })
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        let asc = SortOptions::default();
+        let desc = SortOptions {
+            descending: true,
+            nulls_first: true,
+        };
+
+        for (options, policy, expected) in [
+            (asc, TopKNanPolicy::RowFormat, "[NaN, -1.0, 2.0, 5.0]"),
+            (asc, TopKNanPolicy::Exclude, "[-1.0, 2.0, 5.0]"),
+            (asc, TopKNanPolicy::First, "[NaN, NaN, -1.0, 2.0]"),
+            (asc, TopKNanPolicy::Last, "[-1.0, 2.0, 5.0, NaN]"),
+            (desc, TopKNanPolicy::RowFormat, "[NaN, 5.0, 2.0, -1.0]"),
+            (desc, TopKNanPolicy::Exclude, "[5.0, 2.0, -1.0]"),
+            (desc, TopKNanPolicy::First, "[NaN, NaN, 5.0, 2.0]"),
+            (desc, TopKNanPolicy::Last, "[5.0, 2.0, -1.0, NaN]"),
+        ] {
+            let mut topk = topk_on_a(&schema, options, 4)?.with_nan_policy(policy);
+            for batch in &batches {
+                topk.insert_batch(batch.clone())?;
+            }
+            let results: Vec<_> = topk.emit()?.try_collect().await?;
+            let values: Vec<f64> = results
+                .iter()
+                .flat_map(|batch| batch.column(0).as_primitive::<Float64Type>().values())
+                .copied()
+                .collect();
+            assert_eq!(format!("{values:?}"), expected, "{options:?} {policy:?}");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_merge() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = |a: Vec<Option<i32>>| {
+            let b: StringArray = a.iter().map(|a| a.map(|a| format!("b{a}"))).collect();
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![Arc::new(Int32Array::from(a)), Arc::new(b)],
+            )
+        };
+        let left = [
+            batch(vec![Some(8), Some(3), None])?,
+            batch(vec![Some(11), Some(1)])?,
+        ];
+        let right = [
+            batch(vec![Some(4), Some(0), Some(9)])?,
+            batch(vec![Some(2), Some(6)])?,
+        ];
+
+        for options in [
+            SortOptions::default(),
+            SortOptions {
+                descending: true,
+                nulls_first: false,
+            },
+        ] {
+            let mut single = topk_on_a(&schema, options, 4)?;
+            let mut partial_left = topk_on_a(&schema, options, 4)?;
+            let mut partial_right = topk_on_a(&schema, options, 4)?;
+            for batch in &left {
+                single.insert_batch(batch.clone())?;
+                partial_left.insert_batch(batch.clone())?;
+            }
+            for batch in &right {
+                single.insert_batch(batch.clone())?;
+                partial_right.insert_batch(batch.clone())?;
+            }
+
+            partial_left.merge(partial_right)?;
+            let expected: Vec<_> = single.emit()?.try_collect().await?;
+            let results: Vec<_> = partial_left.emit()?.try_collect().await?;
+            assert_eq!(results, expected);
+        }
+
+        // can not merge a TopK sorting in a different direction
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 4)?;
+        let descending = SortOptions {
+            descending: true,
+            nulls_first: true,
+        };
+        let err = topk
+            .merge(topk_on_a(&schema, descending, 4)?)
+            .unwrap_err()
+            .strip_backtrace();
+        assert!(err.contains("Cannot merge TopK"), "unexpected error: {err}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_rank_column() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Int32Array::from(vec![3, 1, 2, 1, 5, 2, 7])),
+                Arc::new(StringArray::from(vec!["x", "y", "z", "y", "v", "z", "w"])),
+            ],
+        )?;
+
+        for (policy, expected) in [
+            (TopKRankPolicy::RowNumber, vec![1, 2, 3, 4, 5]),
+            (TopKRankPolicy::Rank, vec![1, 1, 3, 3, 5]),
+            (TopKRankPolicy::DenseRank, vec![1, 1, 2, 2, 3]),
+        ] {
+            let mut topk = topk_on_a(&schema, SortOptions::default(), 5)?
+                .with_rank_column("rank", policy);
+            topk.insert_batch(batch.clone())?;
+            let stream = topk.emit()?;
+            let output_schema = stream.schema();
+            assert_eq!(output_schema.field(2).name(), "rank");
+            assert_eq!(output_schema.field(2).data_type(), &DataType::UInt64);
+            let results: Vec<_> = stream.try_collect().await?;
+            let output = concat_batches(&output_schema, &results)?;
+
+            let a = output.column(0).as_primitive::<Int32Type>();
+            assert_eq!(a.values().to_vec(), vec![1, 1, 2, 2, 3]);
+            let ranks = output.column(2).as_primitive::<UInt64Type>();
+            assert_eq!(ranks.values().to_vec(), expected, "{policy:?}");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_output_projection() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Int32Array::from(vec![3, 1, 2, 1, 5, 2, 7])),
+                Arc::new(StringArray::from(vec!["x", "y", "z", "y", "v", "z", "w"])),
+            ],
+        )?;
+        let options = SortOptions {
+            descending: true,
+            nulls_first: false,
+        };
+        let exprs = || -> Result<Vec<(Arc<dyn PhysicalExpr>, String)>> {
+            Ok(vec![
+                (col("b", &schema)?, "b".to_string()),
+                (
+                    binary(col("a", &schema)?, Operator::Multiply, lit(10), &schema)?,
+                    "a_times_10".to_string(),
+                ),
+            ])
+        };
+
+        let mut topk = topk_on_a(&schema, options, 4)?.with_output_projection(exprs()?)?;
+        topk.insert_batch(batch.clone())?;
+        let projected: Vec<_> = topk.emit()?.try_collect().await?;
+        assert_batches_eq!(
+            &[
+                "+---+------------+",
+                "| b | a_times_10 |",
+                "+---+------------+",
+                "| w | 70         |",
+                "| v | 50         |",
+                "| x | 30         |",
+                "| z | 20         |",
+                "+---+------------+",
+            ],
+            &projected
+        );
+
+        // the same as projecting the output by a separate ProjectionExec
+        let mut topk = topk_on_a(&schema, options, 4)?;
+        topk.insert_batch(batch.clone())?;
+        let output: Vec<_> = topk.emit()?.try_collect().await?;
+        let input = MemoryExec::try_new(&[output], Arc::clone(&schema), None)?;
+        let projection = ProjectionExec::try_new(exprs()?, Arc::new(input))?;
+        let expected =
+            collect(projection.execute(0, Arc::new(TaskContext::default()))?).await?;
+        assert_eq!(projected, expected);
+
+        // the rank column is appended after the projected columns
+        let mut topk = topk_on_a(&schema, options, 4)?
+            .with_output_projection(exprs()?)?
+            .with_rank_column("rank", TopKRankPolicy::RowNumber);
+        topk.insert_batch(batch.clone())?;
+        let stream = topk.emit()?;
+        let output_schema = stream.schema();
+        let names: Vec<_> = output_schema.fields().iter().map(|f| f.name()).collect();
+        assert_eq!(names, ["b", "a_times_10", "rank"]);
+
+        let mut topk = topk_on_a(&schema, options, 4)?.with_output_projection(exprs()?)?;
+        topk.insert_batch(batch)?;
+        let err = topk.into_sorted_stream().err().unwrap().strip_backtrace();
+        assert_eq!(
+            err,
+            "This feature is not implemented: TopK with an output projection can not be merged by its sort expressions"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_sort_key_column() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Int32Array::from(vec![
+                    Some(3),
+                    None,
+                    Some(-2),
+                    Some(7),
+                    Some(3),
+                    Some(5),
+                ])),
+                Arc::new(StringArray::from(vec!["x", "y", "z", "y", "v", "w"])),
+            ],
+        )?;
+        let options = SortOptions {
+            descending: true,
+            nulls_first: false,
+        };
+
+        let mut topk = topk_on_a(&schema, options, 5)?
+            .with_rank_column("rank", TopKRankPolicy::Rank)
+            .with_sort_key_column("sort_key");
+        topk.insert_batch(batch)?;
+        let stream = topk.emit()?;
+        let output_schema = stream.schema();
+        assert_eq!(output_schema.field(3).name(), "sort_key");
+        assert_eq!(output_schema.field(3).data_type(), &DataType::Binary);
+        let results: Vec<_> = stream.try_collect().await?;
+        let output = concat_batches(&output_schema, &results)?;
+
+        let a = output.column(0).as_primitive::<Int32Type>();
+        assert_eq!(a.values().to_vec(), vec![7, 5, 3, 3, -2]);
+        let ranks = output.column(2).as_primitive::<UInt64Type>();
+        assert_eq!(ranks.values().to_vec(), vec![1, 2, 3, 3, 5]);
+
+        // comparing the sort keys reproduces the output order
+        let sort_keys = output.column(3).as_binary::<i32>();
+        assert_eq!(sort_keys.null_count(), 0);
+        let sort_keys: Vec<&[u8]> = sort_keys.iter().flatten().collect();
+        assert!(sort_keys.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(sort_keys[2], sort_keys[3]);
+
+        // and they decode back to the sort key values
+        let converter = RowConverter::new(vec![SortField::new_with_options(
+            DataType::Int32,
+            options,
+        )])?;
+        let parser = converter.parser();
+        let decoded =
+            converter.convert_rows(sort_keys.iter().map(|key| parser.parse(key)))?;
+        assert_eq!(&decoded[0], output.column(0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_topk_into_sorted_stream_merge() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let descending = SortOptions {
+            descending: true,
+            nulls_first: false,
+        };
+        let partitions = [
+            vec![Some(3), None, Some(17), Some(8), Some(1)],
+            vec![Some(12), Some(5), Some(20), Some(5)],
+            vec![Some(9), Some(14), None, Some(2), Some(11), Some(19)],
+        ];
+
+        // one TopK per partition, each keeping its own top 4
+        let mut streams = vec![];
+        let mut ordering = None;
+        for values in &partitions {
+            let mut topk = topk_on_a(&schema, descending, 4)?;
+            topk.insert_batch(RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![Arc::new(Int32Array::from(values.clone()))],
+            )?)?;
+            ordering = Some(topk.ordering());
+            streams.push(topk.into_sorted_stream()?);
+        }
+        let ordering = ordering.unwrap();
+        assert_eq!(ordering, sort_on_a(&schema, descending)?);
+
+        let metrics = ExecutionPlanMetricsSet::new();
+        let reservation =
+            MemoryConsumer::new("merge").register(&RuntimeEnv::default().memory_pool);
+        let merged = StreamingMergeBuilder::new()
+            .with_streams(streams)
+            .with_schema(Arc::clone(&schema))
+            .with_expressions(&ordering)
+            .with_metrics(BaselineMetrics::new(&metrics, 0))
+            .with_batch_size(8)
+            .with_fetch(Some(4))
+            .with_reservation(reservation)
+            .build()?;
+        let results: Vec<_> = merged.try_collect().await?;
+        assert_batches_eq!(
+            &[
+                "+----+", "| a  |", "+----+", "| 20 |", "| 19 |", "| 17 |", "| 14 |",
+                "+----+",
+            ],
+            &results
+        );
+
+        // a comparator orders the rows differently than the sort expressions
+        let topk = topk_on_a(&schema, descending, 4)?
+            .with_comparator(Arc::new(|a: &[u8], b: &[u8]| a.cmp(b)));
+        let err = topk.into_sorted_stream().err().unwrap().strip_backtrace();
+        assert!(
+            err.contains("TopK with a custom comparator can not be merged"),
+            "unexpected error: {err}"
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parallel_topk")]
+    #[tokio::test]
+    async fn test_topk_parallel_matches_serial() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        // many duplicate values of `a`, so ties at the k-th row must be
+        // resolved the same way by both paths
+        let batches = (0..3)
+            .map(|batch| {
+                let a: Int32Array = (0..5000)
+                    .map(|i| (i % 7 != 0).then_some((i * 7919 + batch * 31) % 500))
+                    .collect();
+                let b: StringArray =
+                    (0..5000).map(|i| Some(format!("{batch}-{i}"))).collect();
+                RecordBatch::try_new(Arc::clone(&schema), vec![Arc::new(a), Arc::new(b)])
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for options in [
+            SortOptions::default(),
+            SortOptions {
+                descending: true,
+                nulls_first: false,
+            },
+        ] {
+            for k in [1, 10, 300] {
+                let mut serial = topk_on_a(&schema, options, k)?;
+                let mut parallel =
+                    topk_on_a(&schema, options, k)?.with_parallel_threshold(100);
+                for batch in &batches {
+                    serial.insert_batch(batch.clone())?;
+                    parallel.insert_batch(batch.clone())?;
+                }
+                let expected: Vec<_> = serial.emit()?.try_collect().await?;
+                let results: Vec<_> = parallel.emit()?.try_collect().await?;
+                assert_eq!(results, expected, "k = {k}, options = {options:?}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_topk_non_orderable_sort_key() -> Result<()> {
+        let entries = Field::new_struct(
+            "entries",
+            vec![
+                Field::new("key", DataType::Utf8, false),
+                Field::new("value", DataType::Int32, true),
+            ],
+            false,
+        );
+        let map_type = DataType::Map(Arc::new(entries), false);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("m", map_type.clone(), true),
+            Field::new("l", DataType::new_list(map_type, true), true),
+        ]));
+
+        for (name, expected) in [
+            ("m", "TopK can not sort by m@1: values of type Map("),
+            ("l", "TopK can not sort by l@2: values of type List(Field"),
+        ] {
+            let sort_expr = LexOrdering::new(vec![
+                PhysicalSortExpr {
+                    expr: col("a", &schema)?,
+                    options: SortOptions::default(),
+                },
+                PhysicalSortExpr {
+                    expr: col(name, &schema)?,
+                    options: SortOptions::default(),
+                },
+            ]);
+            let err = TopK::try_new(
+                0,
+                Arc::clone(&schema),
+                sort_expr,
+                5,
+                2,
+                Arc::new(RuntimeEnv::default()),
+                &ExecutionPlanMetricsSet::new(),
+            )
+            .err()
+            .unwrap()
+            .strip_backtrace();
+            assert!(
+                err.starts_with("This feature is not implemented: "),
+                "{err}"
+            );
+            assert!(err.contains(expected), "unexpected error: {err}");
+            assert!(err.ends_with("are not orderable"), "{err}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_topk_tracked_size() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        // each batch has smaller values than the previous one, so rows of
+        // every batch enter the top k and earlier batches become unused
+        let batches = (0..30)
+            .rev()
+            .map(|i| {
+                let a: Int32Array = (0..10).map(|j| Some(i * 10 + j)).collect();
+                let b: StringArray =
+                    (0..10).map(|j| Some(format!("row {i} {j}"))).collect();
+                RecordBatch::try_new(Arc::clone(&schema), vec![Arc::new(a), Arc::new(b)])
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let assert_tracked_size = |topk: &TopK| {
+            assert_eq!(topk.heap.size(), topk.heap.compute_size());
+        };
+
+        for storage in [TopKStorage::Batches, TopKStorage::Rows] {
+            let mut topk =
+                topk_on_a(&schema, SortOptions::default(), 3)?.with_storage(storage);
+            assert_tracked_size(&topk);
+            for batch in &batches {
+                topk.insert_batch(batch.clone())?;
+                assert_tracked_size(&topk);
+            }
+
+            let mut other =
+                topk_on_a(&schema, SortOptions::default(), 3)?.with_storage(storage);
+            other.insert_batch(batches[0].clone())?;
+            topk.merge(other)?;
+            assert_tracked_size(&topk);
+
+            topk.reset(sort_on_a(&schema, SortOptions::default())?)?;
+            assert_tracked_size(&topk);
+        }
+
+        // compaction rewrites the retained batches
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 3)?
+            .with_compaction_ratio_threshold(2.0);
+        for batch in &batches {
+            topk.insert_batch(batch.clone())?;
+            assert_tracked_size(&topk);
+        }
+        assert_eq!(topk.heap.store.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_topk_shrink_scratch_rows() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Utf8, true)]));
+        let batch = |width: usize| {
+            let a: StringArray =
+                (0..2).map(|i| Some(i.to_string().repeat(width))).collect();
+            RecordBatch::try_new(Arc::clone(&schema), vec![Arc::new(a)])
+        };
+
+        let mut topk = topk_on_a(&schema, SortOptions::default(), 3)?;
+        let initial_peak_size = topk.scratch_rows_peak_size;
+        topk.insert_batch(batch(10)?)?;
+        assert_eq!(topk.scratch_rows_peak_size, initial_peak_size);
+
+        // a batch with wide sort keys grows the scratch space
+        topk.insert_batch(batch(10_000)?)?;
+        assert!(topk.scratch_rows_peak_size > 20_000);
+        let wide_size = topk.size();
+
+        // the peak is kept for a few narrow batches, in case they are
+        // followed by wide batches again
+        for _ in 1..SCRATCH_ROWS_SHRINK_BATCHES {
+            topk.insert_batch(batch(1)?)?;
+            assert!(topk.scratch_rows_peak_size > 20_000);
+        }
+
+        // but is eventually released
+        topk.insert_batch(batch(1)?)?;
+        assert_eq!(topk.scratch_rows_peak_size, initial_peak_size);
+        assert!(topk.size() < wide_size - 20_000);
+        assert_eq!(topk.reservation.size(), topk.size());
+        Ok(())
+    }
 }